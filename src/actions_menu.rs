@@ -0,0 +1,121 @@
+use crate::commands::{ActionTarget, CommandAction, CommandRegistry};
+use crate::ui::centered_fixed_rect;
+use crossterm::event::{KeyCode, KeyEvent};
+
+use ratatui::{
+    prelude::*,
+    widgets::*,
+};
+
+/// Result of a key press in the actions menu
+#[derive(Debug, PartialEq)]
+pub enum MenuOutcome {
+    /// Keep the menu open
+    Continue,
+    /// Close the menu without doing anything
+    Close,
+    /// Close the menu and run the chosen action
+    Run(CommandAction),
+}
+
+/// A single line in the actions menu
+#[derive(Debug)]
+pub struct MenuItem {
+    pub action: CommandAction,
+    pub label: &'static str,
+    pub key_hint: String,
+}
+
+/// Popup listing the operations available for the selected entry
+#[derive(Debug)]
+pub struct ActionsMenu {
+    pub title: String,
+    pub items: Vec<MenuItem>,
+    pub state: ListState,
+}
+
+impl ActionsMenu {
+    /// Build the menu from every registered command that applies to the target
+    pub fn new(title: String, target: &ActionTarget, registry: &CommandRegistry) -> Self {
+        let items: Vec<MenuItem> = registry
+            .commands()
+            .iter()
+            .filter(|cmd| cmd.action.is_available_for(target))
+            .map(|cmd| MenuItem {
+                action: cmd.action.clone(),
+                label: cmd.description,
+                key_hint: cmd.key_binding.display_text(),
+            })
+            .collect();
+
+        let mut state = ListState::default();
+        if !items.is_empty() {
+            state.select(Some(0));
+        }
+
+        Self { title, items, state }
+    }
+
+    /// Handle menu key input
+    pub fn handle_key(&mut self, key: KeyEvent) -> MenuOutcome {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => MenuOutcome::Close,
+            KeyCode::Up => {
+                self.state.select_previous();
+                MenuOutcome::Continue
+            }
+            KeyCode::Down => {
+                self.state.select_next();
+                MenuOutcome::Continue
+            }
+            KeyCode::Enter => match self.state.selected().and_then(|i| self.items.get(i)) {
+                Some(item) => MenuOutcome::Run(item.action.clone()),
+                None => MenuOutcome::Close,
+            },
+            _ => MenuOutcome::Continue,
+        }
+    }
+}
+
+/// Render the actions menu popup
+pub fn render_actions_menu(frame: &mut Frame, menu: &ActionsMenu) {
+    let label_width = menu.items.iter().map(|item| item.label.len()).max().unwrap_or(0);
+    let hint_width = menu.items.iter().map(|item| item.key_hint.len()).max().unwrap_or(0);
+    let width = (label_width + hint_width + 10).max(menu.title.len() + 6).max(30) as u16;
+    let height = menu.items.len().max(1) as u16 + 4;
+    let area = centered_fixed_rect(width, height, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(menu.title.as_str())
+        .title_bottom(Line::from(" Enter to run, Esc to close ").centered())
+        .border_style(Style::default().fg(Color::Cyan))
+        .padding(Padding::uniform(1));
+
+    if menu.items.is_empty() {
+        let empty = Paragraph::new("No actions available")
+            .style(Style::default().fg(Color::DarkGray))
+            .block(block);
+        frame.render_widget(empty, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = menu
+        .items
+        .iter()
+        .map(|item| {
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{:<width$}  ", item.label, width = label_width)),
+                Span::styled(item.key_hint.clone(), Style::default().fg(Color::DarkGray)),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut state = menu.state.clone();
+    frame.render_stateful_widget(list, area, &mut state);
+}
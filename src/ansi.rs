@@ -0,0 +1,138 @@
+//! ANSI escape sequences in previewed text
+//!
+//! Logs, captured command output and the like color their text with SGR
+//! sequences (`ESC [ … m`). These are turned into styled spans, with a style
+//! carrying over line breaks as it would in a terminal. Other escape sequences,
+//! such as cursor movement and window titles, are left out, so no raw escape
+//! bytes reach the screen.
+
+use ratatui::prelude::*;
+
+const ESC: char = '\x1b';
+const BEL: char = '\x07';
+
+/// Whether text has any escape sequences to interpret
+pub fn has_escapes(text: &str) -> bool {
+    text.contains(ESC)
+}
+
+/// Text with its SGR sequences applied as styles and other escape sequences removed
+pub fn ansi_text(text: &str) -> Text<'static> {
+    let mut style = Style::default();
+    let lines = text.lines().map(|line| {
+        let mut spans = Vec::new();
+        let mut run = String::new();
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != ESC {
+                run.push(c);
+                continue;
+            }
+            match chars.next() {
+                // Control sequence: parameters, then intermediate bytes, then a final byte
+                Some('[') => {
+                    let mut params = String::new();
+                    let mut last = None;
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            last = Some(c);
+                            break;
+                        }
+                        params.push(c);
+                    }
+                    if last == Some('m') {
+                        if !run.is_empty() {
+                            spans.push(Span::styled(std::mem::take(&mut run), style));
+                        }
+                        style = apply_sgr(style, &params);
+                    }
+                }
+                // Operating system command, such as a window title or hyperlink, ending in BEL or ESC \
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == BEL || (c == ESC && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !run.is_empty() {
+            spans.push(Span::styled(run, style));
+        }
+        Line::from(spans)
+    });
+    Text::from(lines.collect::<Vec<_>>())
+}
+
+/// Style after an SGR sequence with these parameters
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    // Parameters may also be split by colons, as in `38:5:208`
+    let codes: Vec<u16> = params.split([';', ':']).map(|code| code.parse().unwrap_or(0)).collect();
+    let mut codes = codes.into_iter();
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            8 => style.add_modifier(Modifier::HIDDEN),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            25 => style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            28 => style.remove_modifier(Modifier::HIDDEN),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(Color::Indexed((code - 30) as u8)),
+            39 => style.fg(Color::Reset),
+            40..=47 => style.bg(Color::Indexed((code - 40) as u8)),
+            49 => style.bg(Color::Reset),
+            90..=97 => style.fg(Color::Indexed((code - 90 + 8) as u8)),
+            100..=107 => style.bg(Color::Indexed((code - 100 + 8) as u8)),
+            38 | 48 => {
+                let color = match codes.next() {
+                    Some(5) => codes.next().map(|index| Color::Indexed(index as u8)),
+                    Some(2) => {
+                        let mut channel = || codes.next().unwrap_or(0) as u8;
+                        Some(Color::Rgb(channel(), channel(), channel()))
+                    }
+                    _ => None,
+                };
+                match color {
+                    Some(color) if code == 38 => style.fg(color),
+                    Some(color) => style.bg(color),
+                    None => style,
+                }
+            }
+            _ => style,
+        };
+    }
+    style
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colors_carry_over_lines_and_other_sequences_go() {
+        let text = ansi_text("\x1b[1;32mok\x1b[0m plain\x1b[2K\n\x1b[38;5;208mstill\x1b]0;title\x07 orange\nmore\x1b[39;48;2;1;2;3m!\x1b[m");
+        let green = Style::default().add_modifier(Modifier::BOLD).fg(Color::Indexed(2));
+        let orange = Style::default().fg(Color::Indexed(208));
+        assert_eq!(
+            text.lines,
+            [
+                Line::from(vec![Span::styled("ok", green), Span::raw(" plain")]),
+                Line::from(vec![Span::styled("still orange", orange)]),
+                Line::from(vec![Span::styled("more", orange), Span::styled("!", orange.fg(Color::Reset).bg(Color::Rgb(1, 2, 3)))]),
+            ]
+        );
+        assert!(!has_escapes("plain"));
+    }
+}
@@ -1,31 +1,123 @@
-use crate::browser::{DirColumn, Browser};
-use crate::commands::{CommandRegistry, CommandAction};
-use crate::config::{Settings, load_settings};
-use crate::error::ErrorLog;
-use crate::file_operations::{FileDetails};
+use crate::actions_menu::{ActionsMenu, MenuOutcome};
+use crate::bookmarks::{
+    export_bookmarks, import_bookmarks, is_bookmark_key, BookmarkManager, BookmarkOutcome, BookmarkPrompt, BOOKMARKS_FILE,
+};
+use crate::browser::{browser_layout, DirColumn, Browser};
+use crate::clipboard::copy_to_clipboard;
+use crate::commands::{ActionTarget, CommandRegistry, CommandAction, KeyBinding, KeyMatch, StartupCommand};
+use crate::config::{EnterAction, PickMode, Settings, WidthAdjustment, save_settings};
+use crate::dialog::{Dialog, DialogAction, DialogOutcome};
+use crate::dir_sizes::DirSizes;
+use crate::entry_counts::EntryCounts;
+use crate::mime_types::MimeTypes;
+use crate::disk_usage::{disk_usage, DiskUsagePanel, UsageEntry, UsageOutcome};
+use crate::error::{ErrorEntry, ErrorLog};
+use crate::file_preview::{content_height, linked_image_area, FullPreview};
+use crate::graphics::ImagePlacement;
+use crate::outline::OutlineMove;
+use crate::external::{context_env, expand_placeholders, is_executable, program_help, run_captured, ExternalCommand};
+use crate::finder::{find, FindQuery, FindTerm, ResultsOutcome, ResultsPanel};
+use crate::fuzzy::{walk, FuzzyFinder, FuzzyOutcome};
+use crate::git::{diff_against_head, GitStatus, RepoInfo, RepoWatcher};
+use crate::grep::{grep, GrepOptions, GrepPanel};
+use crate::file_operations::{
+    copy_into_with, count_tree_entries, delete_tree_with, disk_space, mime_type_for, move_into_with, rename_entry, touch_path, trash_path,
+    tree_size, ConflictPolicy, DiskSpace, FileDetails, PreviewContent, Progress, ProgressCallback,
+};
+use std::ops::ControlFlow;
+use crate::input::TextInput;
+use crate::jobs::{send_desktop_notification, JobId, JobKind, JobManager, JobOutcome};
+use crate::journal::{interrupted_operations, journal_dir, InterruptedOperation, Journal, StepState};
+use crate::project::{find_project, Project};
+use crate::session::{load_session, save_session, Autosave, Session};
 use crate::tabs::TabManager;
 use crate::ui::render_ui;
 use crate::settings::{SettingsManager, SettingsState};
+use crate::utils::{format_file_size, parse_timestamp, resolve_user_path, TIMESTAMP_FORMAT};
+use chrono::Local;
 use color_eyre::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent, MouseEventKind, MouseButton};
 use ratatui::widgets::ScrollDirection;
-use ratatui::{Frame, prelude::Rect};
+use ratatui::{Frame, prelude::{Position, Rect}};
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long a toast stays in the status bar
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+
+/// How often the free space of the active directory's filesystem is checked again
+const DISK_SPACE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How often a followed preview checks whether its file changed
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often a copy or move reports the bytes it has written, and a delete the entries it has removed
+const PROGRESS_REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Most failures a delete lists in its error before saying how many more there were
+const MAX_LISTED_FAILURES: usize = 20;
+
+/// Narrowest and widest the preview can be made, in percent of the browser's width
+const PREVIEW_PERCENT_RANGE: (u16, u16) = (10, 80);
+
+/// How much `[` and `]` change the preview's width, in percent
+const PREVIEW_PERCENT_STEP: u16 = 5;
+
+/// Lines a file preview scrolls for each step of the mouse wheel
+const PREVIEW_WHEEL_LINES: isize = 3;
 
 
 /// Preview content for the right panel
 #[derive(Debug)]
 pub enum Preview {
     Directory(DirColumn),
-    File(FileDetails),
+    /// A file's details, and how many lines of its content are scrolled past
+    File(FileDetails, u16),
 }
 
 /// UI layout tracking for mouse interactions
 #[derive(Debug, Default)]
 pub struct LayoutInfo {
     pub column_areas: Vec<Rect>,
+    /// Area of the tree, in tree view; there are no column areas then
+    pub tree_area: Option<Rect>,
+    /// Area of the preview, when one is shown; its left border can be dragged to resize it
+    pub preview_area: Option<Rect>,
+    /// The whole screen, which the full screen preview covers
+    pub screen_area: Rect,
     pub browser_area: Rect,
     pub tab_area: Rect,
     pub status_area: Rect,
+    /// Part of the status bar showing the current path, which opens the goto prompt when clicked
+    pub status_path_area: Rect,
+}
+
+/// A drag over the rows of a column, shown as a visual range until the button is released
+#[derive(Debug, Clone, Copy)]
+struct MouseDrag {
+    column: usize,
+    anchor: usize,
+    moved: bool,
+}
+
+/// Help for a program being looked up in the background, shown once it's found
+struct PendingHelp {
+    job: JobId,
+    path: PathBuf,
+    receiver: mpsc::Receiver<String>,
+}
+
+/// Short-lived message shown in the status bar
+#[derive(Debug)]
+pub struct Toast {
+    pub message: String,
+    pub shown_at: Instant,
 }
 
 /// Main application state
@@ -36,20 +128,123 @@ pub struct App {
     config: Settings,
     should_quit: bool,
     command_registry: CommandRegistry,
+    /// Keys pressed so far of a multi-key binding such as `g g`
+    pending_keys: Vec<KeyEvent>,
     layout_info: LayoutInfo,
+    job_manager: JobManager,
+    toast: Option<Toast>,
+    terminal_focused: bool,
+    actions_menu: Option<ActionsMenu>,
+    bookmark_manager: Option<BookmarkManager>,
+    /// Waiting for the key naming a bookmark, after `m` or `'`
+    bookmark_prompt: Option<BookmarkPrompt>,
+    dialog: Option<Dialog>,
+    results_panel: Option<ResultsPanel>,
+    fuzzy_finder: Option<FuzzyFinder>,
+    grep_panel: Option<GrepPanel>,
+    disk_usage_panel: Option<DiskUsagePanel>,
+    full_preview: Option<FullPreview>,
+    /// The preview's left border is being dragged to resize it
+    resizing_preview: bool,
+    /// Column and entry a left-button drag started on, to mark the rows it sweeps over
+    mouse_drag: Option<MouseDrag>,
+    /// Directories each running file job changes, refreshed when it finishes
+    job_dirs: HashMap<JobId, Vec<PathBuf>>,
+    pending_help: Option<PendingHelp>,
+    /// Paths chosen when running as a picker, printed on exit
+    chosen: Vec<PathBuf>,
+    /// Program waiting for the main loop to hand it the terminal
+    external_command: Option<ExternalCommand>,
+    /// Commands run since macro recording started, with the keys that ran them
+    macro_recording: Option<Vec<(CommandAction, KeyEvent)>>,
+    /// The last recorded macro, replayed with `@`
+    recorded_macro: Vec<(CommandAction, KeyEvent)>,
+    /// Branch and sync state of the active directory's git worktree
+    repo_watcher: RepoWatcher,
+    /// Sizes of the directories listed in the visible columns, when turned on
+    dir_sizes: DirSizes,
+    /// Entry counts of the directories listed in the visible columns, when turned on
+    entry_counts: EntryCounts,
+    mime_types: MimeTypes,
+    /// Free space of the active directory's filesystem, with the directory and when it was checked
+    disk_space: Option<(PathBuf, Instant, DiskSpace)>,
+    /// Project the active directory is in, with the directory it was looked up for
+    project: Option<(PathBuf, Option<Project>)>,
+    /// When a followed preview last checked its file
+    followed: Instant,
+    /// Files whose preview was refused, already in the error log, so selecting them again doesn't repeat it
+    unreadable_logged: HashSet<PathBuf>,
+    /// Saves the session as the browser runs, once turned on with `enable_autosave`
+    autosave: Option<Autosave>,
 }
 
 impl App {
-    /// Create a new application instance
-    pub fn new() -> Result<Self> {
+    /// Create a new application instance with a tab for each path
+    ///
+    /// Without paths, a single tab opens in the working directory.
+    pub fn new(paths: &[PathBuf], config: Settings) -> Result<Self> {
         let current_dir = std::env::current_dir()
             .map_err(|e| color_eyre::eyre::eyre!("Failed to get current directory: {}", e))?;
 
-        let config = load_settings()
-            .map_err(|e| color_eyre::eyre::eyre!("Failed to load settings: {}", e))?;
+        if paths.is_empty() {
+            let restore = config.restore_session;
+            let mut app = Self::with_config(current_dir.clone(), config)?;
+            // A session that can't be read is simply not offered, and a picker starts fresh
+            app.check_interrupted_operations();
+            if restore && app.config.pick.is_none() && app.dialog.is_none() && let Ok(Some(session)) = load_session(&app.config) && session.differs_from(&current_dir) {
+                app.offer_session(session);
+            }
+            return Ok(app);
+        }
+
+        let paths = paths
+            .iter()
+            .map(|path| {
+                let path = current_dir.join(path);
+                match fs::symlink_metadata(&path) {
+                    Ok(_) => Ok(path),
+                    Err(e) => Err(color_eyre::eyre::eyre!("Cannot open {}: {}", path.display(), e)),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::with_paths(&paths, config)
+    }
+
+    /// Create an application instance with one tab per path, the first one active
+    ///
+    /// A file opens its directory with the file selected.
+    pub fn with_paths(paths: &[PathBuf], config: Settings) -> Result<Self> {
+        let mut locations = paths.iter().map(|path| match (path.is_dir(), path.parent(), path.file_name()) {
+            (false, Some(parent), Some(name)) => (parent.to_path_buf(), Some(name)),
+            _ => (path.clone(), None),
+        });
+
+        let (dir, name) = locations.next().ok_or_else(|| color_eyre::eyre::eyre!("No paths to open"))?;
+        let mut app = Self::with_config(dir, config)?;
+        if let Some(name) = name {
+            app.tab_manager.active_tab_mut().browser.select_by_name(name, &app.config);
+        }
+
+        for (dir, name) in locations {
+            app.tab_manager.open_tab(dir, &app.config, Some(&mut app.error_log))?;
+            if let Some(name) = name {
+                app.tab_manager.active_tab_mut().browser.select_by_name(name, &app.config);
+            }
+        }
 
+        app.tab_manager.select_tab(0);
+        Ok(app)
+    }
+
+    /// Create an application instance for a start directory and configuration
+    ///
+    /// Unlike `new`, this does not read the working directory or the settings file,
+    /// which makes it suitable for tests and headless rendering.
+    pub fn with_config(current_dir: PathBuf, config: Settings) -> Result<Self> {
         let mut error_log = ErrorLog::new();
+        error_log.set_limits(config.error_log_limits.clone());
         let tab_manager = TabManager::new(current_dir, &config, Some(&mut error_log))?;
+        let command_registry = CommandRegistry::new(&config, Some(&mut error_log));
 
         let app = Self {
             tab_manager,
@@ -57,13 +252,166 @@ impl App {
             error_log,
             config,
             should_quit: false,
-            command_registry: CommandRegistry::new(),
+            command_registry,
+            pending_keys: Vec::new(),
             layout_info: LayoutInfo::default(),
+            job_manager: JobManager::new(),
+            toast: None,
+            terminal_focused: true,
+            actions_menu: None,
+            bookmark_manager: None,
+            bookmark_prompt: None,
+            dialog: None,
+            results_panel: None,
+            fuzzy_finder: None,
+            grep_panel: None,
+            disk_usage_panel: None,
+            full_preview: None,
+            resizing_preview: false,
+            mouse_drag: None,
+            job_dirs: HashMap::new(),
+            pending_help: None,
+            chosen: Vec::new(),
+            external_command: None,
+            macro_recording: None,
+            recorded_macro: Vec::new(),
+            repo_watcher: RepoWatcher::new(),
+            dir_sizes: DirSizes::new(),
+            entry_counts: EntryCounts::new(),
+            mime_types: MimeTypes::new(),
+            disk_space: None,
+            followed: Instant::now(),
+            unreadable_logged: HashSet::new(),
+            autosave: None,
+            project: None,
         };
 
         Ok(app)
     }
 
+    /// Run the `startup_commands` setting, logging any that fail and carrying on
+    pub fn run_startup_commands(&mut self) {
+        for line in self.config.startup_commands.clone() {
+            let result = StartupCommand::parse(&line)
+                .map_err(|e| color_eyre::eyre::eyre!(e))
+                .and_then(|command| self.run_startup_command(command));
+            if let Err(e) = result {
+                self.error_log.warning(format!("Startup command '{}' failed: {}", line, e), Some("Startup".to_string()));
+            }
+        }
+    }
+
+    fn run_startup_command(&mut self, command: StartupCommand) -> Result<()> {
+        let current_dir = self.browser().active_column().path.clone();
+        match command {
+            StartupCommand::Cd(path) => {
+                let browser = &mut self.tab_manager.active_tab_mut().browser;
+                browser.navigate_to(resolve_user_path(&path, &current_dir), &self.config)?;
+                self.tab_manager.update_active_tab_name(&self.config);
+            }
+            StartupCommand::Tab(path) => {
+                let path = resolve_user_path(&path, &current_dir);
+                self.tab_manager.open_tab(path, &self.config, Some(&mut self.error_log))?;
+            }
+            StartupCommand::Filter(text) => {
+                self.tab_manager.active_tab_mut().browser.set_filter(&text, &self.config)?;
+            }
+            StartupCommand::Select(name) => {
+                if !self.tab_manager.active_tab_mut().browser.select_by_name(OsStr::new(&name), &self.config) {
+                    return Err(color_eyre::eyre::eyre!("No entry named '{}'", name));
+                }
+            }
+            StartupCommand::Action(action) => {
+                self.execute_command(&action, KeyEvent::new(KeyCode::Null, KeyModifiers::NONE))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Branch and sync state of the git worktree the active directory is in, once known
+    pub fn repo_info(&self) -> Option<&RepoInfo> {
+        self.repo_watcher.info().filter(|_| self.config.git_status)
+    }
+
+    /// Free and total space of the filesystem holding the active directory
+    pub fn disk_space(&self) -> Option<DiskSpace> {
+        self.disk_space.as_ref().map(|(_, _, space)| *space)
+    }
+
+    /// Check the free space again when the active directory changed or the last check is stale
+    fn update_disk_space(&mut self) {
+        let dir = &self.browser().active_column().path;
+        let fresh = self.disk_space.as_ref()
+            .is_some_and(|(checked_dir, checked, _)| checked_dir == dir && checked.elapsed() < DISK_SPACE_INTERVAL);
+        if !fresh {
+            let dir = dir.clone();
+            self.disk_space = disk_space(&dir).ok().map(|space| (dir, Instant::now(), space));
+        }
+    }
+
+    /// Project the active directory is in, if any
+    pub fn project(&self) -> Option<&Project> {
+        self.project.as_ref().and_then(|(_, project)| project.as_ref())
+    }
+
+    /// Look the project up again when the active directory changed
+    fn update_project(&mut self) {
+        let dir = &self.browser().active_column().path;
+        if self.project.as_ref().is_none_or(|(checked_dir, _)| checked_dir != dir) {
+            let dir = dir.clone();
+            self.project = Some((dir.clone(), find_project(&dir)));
+        }
+    }
+
+    /// Set the preview's width from its current one, in percent of the browser's width
+    fn resize_preview(&mut self, change: impl FnOnce(u16) -> u16) {
+        let current = self.config.preview_percent.unwrap_or_else(|| {
+            let browser_width = self.layout_info.browser_area.width.max(1);
+            let preview_width = self.layout_info.preview_area.map_or(0, |area| area.width);
+            (u32::from(preview_width) * 100 / u32::from(browser_width)) as u16
+        });
+        let (min, max) = PREVIEW_PERCENT_RANGE;
+        self.config.preview_percent = Some(change(current).clamp(min, max));
+        self.config.show_preview = true;
+    }
+
+    /// Widen the active column and the preview by some cells, or narrow them with negative ones
+    ///
+    /// Cells the layout can't give are dropped rather than saved up, so turning
+    /// back starts right away.
+    fn adjust_widths(&mut self, column: i16, preview: i16) {
+        let area = self.layout_info.browser_area;
+        let before = browser_layout(area, self.browser(), &self.config);
+        let adjustment = &mut self.config.width_adjustment;
+        adjustment.column = adjustment.column.saturating_add(column);
+        adjustment.preview = adjustment.preview.saturating_add(preview);
+        if browser_layout(area, self.browser(), &self.config) == before {
+            let adjustment = &mut self.config.width_adjustment;
+            adjustment.column -= column;
+            adjustment.preview -= preview;
+        }
+    }
+
+    /// Directory sizes to show next to directories, when they are being calculated
+    pub fn dir_sizes(&self) -> Option<&DirSizes> {
+        Some(&self.dir_sizes).filter(|_| self.config.dir_sizes)
+    }
+
+    /// Entry counts to show next to directories, when they are being counted
+    pub fn entry_counts(&self) -> Option<&EntryCounts> {
+        Some(&self.entry_counts).filter(|_| self.config.entry_counts)
+    }
+
+    /// Sniffed MIME types of files, when icons go by their contents
+    pub fn mime_types(&self) -> Option<&MimeTypes> {
+        Some(&self.mime_types).filter(|_| self.config.show_icons && self.config.sniff_mime_types)
+    }
+
+    /// Check whether a macro is being recorded
+    pub fn is_recording_macro(&self) -> bool {
+        self.macro_recording.is_some()
+    }
+
     /// Check if the application should quit
     pub fn should_quit(&self) -> bool {
         self.should_quit
@@ -79,142 +427,1901 @@ impl App {
         &self.command_registry
     }
 
+    /// Periodic housekeeping: collect finished jobs and expire the toast
+    pub fn tick(&mut self) {
+        if self.config.git_status {
+            let dir = self.browser().active_column().path.clone();
+            self.repo_watcher.update(&dir);
+        }
+        let wanted = if self.config.dir_sizes { self.browser().listed_subdirectories() } else { Vec::new() };
+        self.dir_sizes.update(wanted);
+        let wanted = if self.config.entry_counts { self.browser().listed_subdirectories() } else { Vec::new() };
+        self.entry_counts.update(wanted);
+        let wanted = if self.mime_types().is_some() { self.browser().listed_files(&self.config) } else { Vec::new() };
+        self.mime_types.update(wanted);
+        self.update_disk_space();
+        self.update_project();
+        if self.followed.elapsed() >= FOLLOW_INTERVAL {
+            self.followed = Instant::now();
+            self.tab_manager.active_tab_mut().browser.follow_preview(&self.config);
+        }
+        if let Some(panel) = &mut self.results_panel {
+            panel.poll();
+        }
+        if let Some(finder) = &mut self.fuzzy_finder {
+            finder.poll(&self.config);
+        }
+        if let Some(panel) = &mut self.grep_panel {
+            panel.poll();
+        }
+        if let Some(panel) = &mut self.disk_usage_panel {
+            panel.poll();
+        }
+
+        for finished in self.job_manager.poll() {
+            if self.pending_help.as_ref().is_some_and(|help| help.job == finished.id) {
+                self.finish_program_help(finished.outcome);
+                continue;
+            }
+            if let Some(panel) = &mut self.results_panel
+                && panel.job == finished.id
+            {
+                panel.poll();
+                panel.finished = true;
+            }
+            if let Some(finder) = &mut self.fuzzy_finder
+                && finder.job == finished.id
+            {
+                finder.poll(&self.config);
+                finder.finished = true;
+            }
+            if let Some(panel) = &mut self.grep_panel
+                && panel.job == finished.id
+            {
+                panel.poll();
+                panel.finished = true;
+            }
+            if let Some(panel) = &mut self.disk_usage_panel
+                && panel.job == finished.id
+            {
+                panel.poll();
+                panel.finished = true;
+            }
+
+            // A failure's first line sums it up, and any further lines list what went wrong
+            let (message, causes) = match &finished.outcome {
+                JobOutcome::Succeeded(summary) => (format!("{} finished: {}", finished.label, summary), Vec::new()),
+                JobOutcome::Failed(error) => {
+                    let mut lines = error.lines();
+                    let summary = lines.next().unwrap_or_default();
+                    (format!("{} failed: {}", finished.label, summary), lines.map(str::to_string).collect())
+                }
+                JobOutcome::Cancelled(progress) => (format!("{} {}", finished.label, progress.lines().next().unwrap_or_default()), Vec::new()),
+            };
+
+            if let Some(dirs) = self.job_dirs.remove(&finished.id) {
+                self.dir_sizes.invalidate(&dirs);
+                self.entry_counts.invalidate(&dirs);
+                self.mime_types.invalidate(&dirs);
+                self.tab_manager.refresh_paths(&dirs, &self.config);
+            }
+
+            // Stopping a job was asked for, so it only shows as a toast
+            if let JobOutcome::Failed(_) = finished.outcome {
+                let mut entry = ErrorEntry::error(message.clone(), Some(finished.kind.display_name().to_string()));
+                entry.causes = causes;
+                self.error_log.add_entry(entry);
+            }
+
+            if !self.terminal_focused
+                && !matches!(finished.outcome, JobOutcome::Cancelled(_))
+                && self.config.notifications.should_notify(finished.kind, finished.elapsed)
+                && let Err(e) = send_desktop_notification("browse", &message)
+            {
+                self.error_log.warning(format!("Failed to send desktop notification: {}", e), Some("Notifications".to_string()));
+            }
+
+            self.show_toast(message);
+        }
+
+        if self.toast.as_ref().is_some_and(|toast| toast.shown_at.elapsed() > TOAST_DURATION) {
+            self.toast = None;
+        }
+
+        self.prompt_for_large_content();
+        self.log_unreadable_preview();
+        self.autosave_session();
+    }
+
+    /// Save the session as it changes, not just on quit
+    ///
+    /// Off until turned on, so tests and pickers don't touch the saved session.
+    pub fn enable_autosave(&mut self) {
+        self.autosave = Some(Autosave::default());
+    }
+
+    fn autosave_session(&mut self) {
+        // The saved session is still on offer, so it isn't replaced yet
+        if matches!(self.dialog.as_ref().map(|dialog| &dialog.action), Some(DialogAction::RestoreSession(_))) {
+            return;
+        }
+        let session = self.session();
+        let Some(session) = self.autosave.as_mut().and_then(|autosave| autosave.check(session, Instant::now())) else {
+            return;
+        };
+        if let Err(e) = save_session(&session, &self.config) {
+            self.error_log.warning(format!("Failed to save session: {}", e), Some("Session".to_string()));
+        }
+        // A failed save isn't tried again until the session changes
+        if let Some(autosave) = &mut self.autosave {
+            autosave.saved(session);
+        }
+    }
+
+    /// Note a previewed file this user can't read in the error log, once per file
+    fn log_unreadable_preview(&mut self) {
+        let Some(Preview::File(details, _)) = self.tab_manager.active_tab().browser.preview() else {
+            return;
+        };
+        let PreviewContent::PermissionDenied { owner, mode, .. } = &details.content_preview else {
+            return;
+        };
+        if self.unreadable_logged.insert(details.path.clone()) {
+            let message = format!("Cannot read {}: permission denied (owner {}, mode {:04o})", details.path.display(), owner, mode);
+            self.error_log.warning(message, Some("Preview".to_string()));
+        }
+    }
+
+    /// Ask about a large directory or file preview that hasn't been decided this session
+    fn prompt_for_large_content(&mut self) {
+        if self.dialog.is_some() || self.settings_manager.is_open() {
+            return;
+        }
+
+        let column = self.browser().active_column();
+        if column.truncated && !self.config.large_content.directories.contains_key(&column.path) {
+            let name = column.path.file_name().unwrap_or(column.path.as_os_str()).to_string_lossy();
+            let message = format!(
+                "{} has {} entries. Load them all? (n lists the first {})",
+                name, column.unfiltered_len, self.config.large_directory_entries
+            );
+            let path = column.path.clone();
+            self.dialog = Some(Dialog::confirm("Large directory", message, DialogAction::LoadLargeDirectory(path)));
+            return;
+        }
+
+        if let Some(Preview::File(details, _)) = self.browser().preview()
+            && details.content_preview == PreviewContent::TooLarge
+            && self.config.large_content.file_previews.is_none()
+        {
+            let name = details.path.file_name().unwrap_or(details.path.as_os_str()).to_string_lossy();
+            let message = format!("{} is {}. Preview the first {} of large files?", name, format_file_size(details.size), format_file_size(self.config.preview_bytes));
+            self.dialog = Some(Dialog::confirm("Large file", message, DialogAction::PreviewLargeFiles));
+        }
+    }
+
+    /// Ask whether to reopen the tabs of a saved session
+    pub fn offer_session(&mut self, session: Session) {
+        let count = session.tabs.len();
+        let message = format!("Reopen the {} {} from the last session?", count, if count == 1 { "tab" } else { "tabs" });
+        self.dialog = Some(Dialog::confirm("Restore session", message, DialogAction::RestoreSession(session)));
+    }
+
+    /// Report operations a previous run was killed in the middle of
+    ///
+    /// What became of each entry goes to the error log. When moves finished
+    /// entries, the user is asked whether to move them back; otherwise the
+    /// journals are done with.
+    pub fn check_interrupted_operations(&mut self) {
+        let operations = interrupted_operations(&journal_dir(&self.config));
+        for operation in &operations {
+            self.error_log.warning(format!("Interrupted: {}", operation.summary()), Some("Recovery".to_string()));
+            for line in operation.details() {
+                self.error_log.info(line, Some("Recovery".to_string()));
+            }
+        }
+
+        let (roll_back, done): (Vec<_>, Vec<_>) = operations
+            .into_iter()
+            .partition(|operation| operation.can_roll_back() && !self.config.read_only);
+        for operation in done {
+            self.discard_journal(&operation);
+        }
+        if !roll_back.is_empty() {
+            let count: usize = roll_back.iter()
+                .flat_map(|operation| &operation.steps)
+                .filter(|(_, state)| matches!(state, StepState::Finished(Some(_))))
+                .count();
+            let message = format!(
+                "An earlier move was interrupted. Move {} finished {} back?",
+                count,
+                if count == 1 { "entry" } else { "entries" },
+            );
+            self.dialog = Some(Dialog::confirm("Interrupted move", message, DialogAction::RollBack(roll_back)));
+        }
+    }
+
+    /// Move back the finished entries of interrupted moves, then forget the moves
+    fn roll_back_operations(&mut self, operations: Vec<InterruptedOperation>) {
+        let mut dirs = Vec::new();
+        for operation in operations {
+            for (path, error) in operation.roll_back() {
+                self.error_log.add_entry(
+                    ErrorEntry::error(format!("Failed to move {} back", path.display()), Some("Recovery".to_string()))
+                        .with_source("move back", &path, &error),
+                );
+            }
+            dirs.extend(parent_dirs(&operation.steps.iter().map(|(path, _)| path.clone()).collect::<Vec<_>>()));
+            dirs.extend(operation.destination.clone());
+            self.discard_journal(&operation);
+        }
+        self.tab_manager.refresh_paths(&dirs, &self.config);
+    }
+
+    fn discard_journal(&mut self, operation: &InterruptedOperation) {
+        if let Err(e) = operation.discard() {
+            self.error_log.add_entry(
+                ErrorEntry::warning("Failed to remove operation journal".to_string(), Some("Recovery".to_string()))
+                    .with_source("remove journal", &operation.journal, &e),
+            );
+        }
+    }
+
+    /// Replace the open tabs with those of a saved session
+    ///
+    /// Tabs whose directory no longer exists are left out.
+    fn restore_session(&mut self, session: Session) {
+        let mut restored: Option<TabManager> = None;
+        let mut active = 0;
+        for (index, tab) in session.tabs.into_iter().enumerate() {
+            if !tab.path.is_dir() {
+                continue;
+            }
+            let tabs = match &mut restored {
+                Some(tabs) => {
+                    if tabs.open_tab(tab.path, &self.config, Some(&mut self.error_log)).is_err() {
+                        continue;
+                    }
+                    tabs
+                }
+                None => match TabManager::new(tab.path, &self.config, Some(&mut self.error_log)) {
+                    Ok(tabs) => restored.insert(tabs),
+                    Err(_) => continue,
+                },
+            };
+            if let Some(name) = &tab.selected {
+                tabs.active_tab_mut().browser.select_by_name(OsStr::new(name), &self.config);
+            }
+            tabs.active_tab_mut().accent = tab.accent.and_then(|accent| accent.parse().ok());
+            // The active tab, or the one before it if it couldn't be reopened
+            if index <= session.active {
+                active = tabs.active_index();
+            }
+        }
+
+        match restored {
+            Some(mut tabs) => {
+                tabs.select_tab(active);
+                self.tab_manager = tabs;
+            }
+            None => self.show_toast("None of the session's directories exist any more".to_string()),
+        }
+    }
+
+    /// Capture the open tabs, to be saved on quit
+    pub fn session(&self) -> Session {
+        Session::capture(&self.tab_manager)
+    }
+
+    fn move_in_preview(&mut self, action: OutlineMove) {
+        let page = self.layout_info.preview_area.map_or(0, content_height);
+        self.tab_manager.active_tab_mut().browser.move_in_preview(action, page, &self.config);
+    }
+
+    /// List every entry of a directory over the entry limit, for the rest of the session
+    fn load_all_entries(&mut self, path: PathBuf) {
+        self.config.large_content.directories.insert(path.clone(), true);
+        self.tab_manager.refresh_paths(&[path], &self.config);
+    }
+
+    /// Remember a declined dialog whose answer lasts for the session
+    fn decline_dialog_action(&mut self, action: DialogAction) {
+        match action {
+            DialogAction::LoadLargeDirectory(path) => {
+                self.config.large_content.directories.insert(path, false);
+            }
+            DialogAction::PreviewLargeFiles => self.config.large_content.file_previews = Some(false),
+            DialogAction::RollBack(operations) => {
+                for operation in &operations {
+                    self.discard_journal(operation);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Show a short-lived message in the status bar
+    pub fn show_toast(&mut self, message: String) {
+        self.toast = Some(Toast { message, shown_at: Instant::now() });
+    }
+
+    /// Record whether the terminal window has focus
+    pub fn set_focused(&mut self, focused: bool) {
+        self.terminal_focused = focused;
+    }
+
     /// Render the application UI and return layout info for mouse handling
     pub fn render(&mut self, frame: &mut Frame) -> LayoutInfo {
         render_ui(frame, self)
     }
 
-    /// Handle keyboard input
-    pub fn handle_key(&mut self, key: KeyEvent) -> Result<()> {
-        if key.kind != KeyEventKind::Press {
-            return Ok(());
+    /// Handle keyboard input
+    pub fn handle_key(&mut self, key: KeyEvent) -> Result<()> {
+        if key.kind != KeyEventKind::Press {
+            return Ok(());
+        }
+
+        // Handle settings panel if open
+        if self.settings_manager.is_open() {
+            let changes = self.settings_manager.handle_key(key, &mut self.config, &self.command_registry)?;
+            if changes.reload_browser {
+                self.tab_manager.reload_all_tabs(&self.config);
+            }
+            if changes.keybindings {
+                self.command_registry = CommandRegistry::new(&self.config, Some(&mut self.error_log));
+                if let Err(e) = save_settings(&self.config) {
+                    self.error_log.error(format!("Failed to save key bindings: {}", e), Some("Settings".to_string()));
+                }
+            }
+            return Ok(());
+        }
+
+        // Handle confirmation or prompt dialog if open
+        if let Some(dialog) = &mut self.dialog {
+            match dialog.handle_key(key) {
+                DialogOutcome::Continue => {}
+                DialogOutcome::Cancel => {
+                    if let Some(dialog) = self.dialog.take() {
+                        self.decline_dialog_action(dialog.action);
+                    }
+                }
+                DialogOutcome::Accept { action, input } => {
+                    self.dialog = None;
+                    self.run_dialog_action(action, input);
+                }
+            }
+            return Ok(());
+        }
+
+        // Handle fuzzy finder if open
+        if let Some(finder) = &mut self.fuzzy_finder {
+            match finder.handle_key(key, &self.config) {
+                FuzzyOutcome::Continue => {}
+                FuzzyOutcome::Close => self.close_fuzzy_finder(),
+                FuzzyOutcome::Open(path) => {
+                    self.close_fuzzy_finder();
+                    let browser = &mut self.tab_manager.active_tab_mut().browser;
+                    if let Err(e) = browser.open_path(&path, &self.config) {
+                        self.log_open_error(&path, &e);
+                    }
+                    self.tab_manager.update_active_tab_name(&self.config);
+                }
+            }
+            return Ok(());
+        }
+
+        // Handle content search results if open
+        if let Some(panel) = &mut self.grep_panel {
+            match panel.handle_key(key) {
+                ResultsOutcome::Open(path) => {
+                    self.close_grep_panel();
+                    let browser = &mut self.tab_manager.active_tab_mut().browser;
+                    if let Err(e) = browser.open_path(&path, &self.config) {
+                        self.log_open_error(&path, &e);
+                    }
+                    self.tab_manager.update_active_tab_name(&self.config);
+                }
+                ResultsOutcome::Close => self.close_grep_panel(),
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        // Handle the full screen preview if open
+        if let Some(preview) = &mut self.full_preview {
+            if preview.handle_key(key, content_height(self.layout_info.screen_area), &self.config) {
+                self.full_preview = None;
+            }
+            return Ok(());
+        }
+
+        // Handle search results if open
+        if let Some(panel) = &mut self.results_panel {
+            match panel.handle_key(key) {
+                ResultsOutcome::Continue => {}
+                ResultsOutcome::Close => self.close_results_panel(),
+                ResultsOutcome::Open(path) => {
+                    self.close_results_panel();
+                    self.reveal_path(&path);
+                }
+                ResultsOutcome::Delete(paths) => self.confirm_delete_results(paths),
+            }
+            return Ok(());
+        }
+
+        // Handle the disk usage view if open
+        if let Some(panel) = &mut self.disk_usage_panel {
+            match panel.handle_key(key) {
+                UsageOutcome::Continue => {}
+                UsageOutcome::Close => self.close_disk_usage_panel(),
+                UsageOutcome::Enter(dir) => {
+                    if !panel.finished {
+                        self.job_manager.cancel(panel.job);
+                    }
+                    let (job, receiver) = self.spawn_disk_usage(&dir);
+                    if let Some(panel) = &mut self.disk_usage_panel {
+                        panel.enter(dir, job, receiver);
+                    }
+                }
+                UsageOutcome::Up => {
+                    if !panel.finished {
+                        self.job_manager.cancel(panel.job);
+                        panel.finished = true;
+                    }
+                    // Above where the view started, or left before it was worked out
+                    if !panel.back()
+                        && let Some(parent) = panel.root.parent().map(Path::to_path_buf)
+                    {
+                        // Going back up selects the directory we came from
+                        let select = Some(panel.root.clone());
+                        self.start_disk_usage(parent, select);
+                    }
+                }
+                UsageOutcome::Reveal(path) => {
+                    self.close_disk_usage_panel();
+                    self.reveal_path(&path);
+                }
+            }
+            return Ok(());
+        }
+
+        // Handle inline rename if active
+        if self.browser().active_column().rename_input.is_some() {
+            match key.code {
+                KeyCode::Esc => self.tab_manager.active_tab_mut().browser.active_column_mut().rename_input = None,
+                KeyCode::Enter => self.finish_rename(),
+                _ => {
+                    if let Some(input) = &mut self.tab_manager.active_tab_mut().browser.active_column_mut().rename_input {
+                        input.handle_key(key);
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        // Handle filter typing if active
+        if self.browser().active_column().filter_input.is_some() {
+            self.handle_filter_key(key)?;
+            return Ok(());
+        }
+
+        // Handle actions menu if open
+        if let Some(menu) = &mut self.actions_menu {
+            match menu.handle_key(key) {
+                MenuOutcome::Continue => {}
+                MenuOutcome::Close => self.actions_menu = None,
+                MenuOutcome::Run(action) => {
+                    self.actions_menu = None;
+                    return self.execute_command(&action, key);
+                }
+            }
+            return Ok(());
+        }
+
+        // Handle bookmark manager if open
+        if let Some(manager) = &mut self.bookmark_manager {
+            match manager.handle_key(key) {
+                BookmarkOutcome::Continue => {}
+                BookmarkOutcome::Close => self.bookmark_manager = None,
+                BookmarkOutcome::Jump(path) => {
+                    self.bookmark_manager = None;
+                    self.jump_to_directory(path);
+                }
+                BookmarkOutcome::Delete(key) => {
+                    self.config.bookmarks.remove(&key);
+                }
+                BookmarkOutcome::Export => {
+                    self.bookmark_manager = None;
+                    self.dialog = Some(Dialog::prompt(
+                        "Export bookmarks",
+                        "File (.json for JSON):",
+                        BOOKMARKS_FILE,
+                        DialogAction::ExportBookmarks,
+                    ));
+                }
+                BookmarkOutcome::Import => {
+                    self.bookmark_manager = None;
+                    self.dialog = Some(Dialog::prompt("Import bookmarks", "File:", BOOKMARKS_FILE, DialogAction::ImportBookmarks));
+                }
+            }
+            return Ok(());
+        }
+
+        // The key after `m` or `'` names the bookmark
+        if let Some(prompt) = self.bookmark_prompt.take() {
+            self.toast = None;
+            if let KeyCode::Char(c) = key.code
+                && is_bookmark_key(c)
+            {
+                self.run_bookmark_prompt(prompt, c);
+            }
+            return Ok(());
+        }
+
+        // Handle error log navigation if visible
+        if self.error_log.is_visible() {
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    self.error_log.hide();
+                    return Ok(());
+                }
+                KeyCode::Up => {
+                    self.error_log.select_previous();
+                    return Ok(());
+                }
+                KeyCode::Down => {
+                    self.error_log.select_next();
+                    return Ok(());
+                }
+                KeyCode::Home => {
+                    self.error_log.select_first();
+                    return Ok(());
+                }
+                KeyCode::End => {
+                    self.error_log.select_last();
+                    return Ok(());
+                }
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.error_log.clear();
+                    return Ok(());
+                }
+                KeyCode::Enter => {
+                    self.error_log.toggle_selected_wrap();
+                    return Ok(());
+                }
+                KeyCode::Char('a') => {
+                    self.error_log.toggle_selected_acknowledged();
+                    return Ok(());
+                }
+                KeyCode::Char('A') => {
+                    self.error_log.acknowledge_all();
+                    return Ok(());
+                }
+                KeyCode::Char('g') => {
+                    match self.error_log.selected_path().map(Path::to_path_buf) {
+                        Some(path) => {
+                            self.error_log.hide();
+                            self.reveal_path(&path);
+                        }
+                        None => self.show_toast("This entry isn't about a path".to_string()),
+                    }
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(mode) = self.config.pick
+            && key.code == KeyCode::Enter
+            && self.pending_keys.is_empty()
+        {
+            self.choose(mode);
+            return Ok(());
+        }
+
+        // Letters carry on a quick search under way, even those bound to commands like `r`
+        if self.pending_keys.is_empty()
+            && self.browser().is_quick_searching()
+            && KeyBinding::CharRange.matches(&key)
+        {
+            return self.execute_command(&CommandAction::SearchChar, key);
+        }
+
+        // Find matching command, possibly completing a multi-key binding
+        self.pending_keys.push(key);
+        let action = match self.command_registry.match_keys(&self.pending_keys) {
+            KeyMatch::Command(command) => Some(command.action.clone()),
+            KeyMatch::Pending => return Ok(()),
+            // A key that breaks off a sequence is looked up on its own
+            KeyMatch::None if self.pending_keys.len() > 1 => match self.command_registry.match_keys(&[key]) {
+                KeyMatch::Command(command) => Some(command.action.clone()),
+                KeyMatch::Pending => {
+                    self.pending_keys = vec![key];
+                    return Ok(());
+                }
+                KeyMatch::None => None,
+            },
+            KeyMatch::None => None,
+        };
+        self.pending_keys.clear();
+
+        match action {
+            Some(action) => self.execute_command(&action, key),
+            None => Ok(()),
+        }
+    }
+
+    /// Execute a command action
+    fn execute_command(&mut self, action: &CommandAction, key: KeyEvent) -> Result<()> {
+        if self.config.read_only && action.modifies_files() {
+            self.show_toast("Read-only: files can't be changed".to_string());
+            return Ok(());
+        }
+
+        if let Some(recording) = &mut self.macro_recording
+            && !matches!(action, CommandAction::RecordMacro | CommandAction::PlayMacro)
+        {
+            recording.push((action.clone(), key));
+        }
+
+        match action {
+            CommandAction::Quit => {
+                self.should_quit = true;
+            }
+            CommandAction::ShowSettings => {
+                self.settings_manager.open();
+            }
+            CommandAction::ShowErrorLog => {
+                self.error_log.toggle_visibility();
+            }
+            CommandAction::NewTab => {
+                self.tab_manager.create_tab(&self.config, Some(&mut self.error_log))?;
+            }
+            CommandAction::CloseTab => {
+                if !self.tab_manager.close_current_tab() {
+                    // Cannot close the last tab - add a message to error log
+                    self.error_log.warning("Cannot close the last tab".to_string(), None);
+                }
+            }
+            CommandAction::NextTab => {
+                self.tab_manager.next_tab();
+            }
+            CommandAction::PrevTab => {
+                self.tab_manager.prev_tab();
+            }
+            CommandAction::CycleTabAccent => {
+                self.tab_manager.cycle_accent(&self.config);
+            }
+            CommandAction::ClearSearch => {
+                let browser = &mut self.tab_manager.active_tab_mut().browser;
+                browser.clear_search();
+                // Esc ends visual mode by marking the range, as Enter does
+                if browser.active_column().visual_range().is_some() {
+                    browser.active_column_mut().toggle_visual();
+                }
+                if !browser.active_column().filter.is_empty() {
+                    browser.set_filter("", &self.config)?;
+                }
+            }
+            CommandAction::Filter => {
+                let column = self.tab_manager.active_tab_mut().browser.active_column_mut();
+                column.filter_input = Some(TextInput::new(column.filter.clone()));
+            }
+            CommandAction::NavigateUp => {
+                let active_tab = self.tab_manager.active_tab_mut();
+                active_tab.browser.select_previous();
+                _ = active_tab.browser.update_preview(&self.config);
+            }
+            CommandAction::NavigateDown => {
+                let active_tab = self.tab_manager.active_tab_mut();
+                active_tab.browser.select_next();
+                _ = active_tab.browser.update_preview(&self.config);
+            }
+            CommandAction::NavigateLeft => {
+                // In tree view Left collapses within the tree first, and the tree checks the lock itself
+                if self.browser().tree().is_none()
+                    && let Some(root) = self.browser().root_lock().filter(|_| self.browser().at_root_lock())
+                {
+                    self.show_toast(format!("Tab is locked to {}", root.display()));
+                    return Ok(());
+                }
+                let active_tab = self.tab_manager.active_tab_mut();
+                _ = active_tab.browser.navigate_left(&self.config);
+                self.tab_manager.update_active_tab_name(&self.config);
+            }
+            CommandAction::NavigateRight => {
+                let active_tab = self.tab_manager.active_tab_mut();
+                _ = active_tab.browser.navigate_right(&self.config);
+                self.tab_manager.update_active_tab_name(&self.config);
+            }
+            CommandAction::Open => {
+                let column = self.tab_manager.active_tab_mut().browser.active_column_mut();
+                if column.visual_range().is_some() {
+                    column.toggle_visual();
+                } else {
+                    self.open_selected();
+                }
+            }
+            CommandAction::EditSelected => match self.browser().active_column().selected_entry().map(|entry| entry.path()) {
+                Some(path) if !path.is_dir() => self.external_command = Some(ExternalCommand::edit(&path).with_env(self.context_env())),
+                Some(_) => self.show_toast("Can't edit a directory".to_string()),
+                None => {}
+            },
+            CommandAction::SetAnchor => {
+                self.tab_manager.active_tab_mut().browser.set_anchor(&self.config)?;
+            }
+            CommandAction::AlternateDirectory => match self.browser().alternate_directory() {
+                Some(path) => {
+                    let path = path.to_path_buf();
+                    let browser = &mut self.tab_manager.active_tab_mut().browser;
+                    if let Err(e) = browser.open_directory(&path, &self.config) {
+                        self.log_open_error(&path, &e);
+                    }
+                    self.tab_manager.update_active_tab_name(&self.config);
+                }
+                None => self.show_toast("No previous directory".to_string()),
+            },
+            CommandAction::SetBookmark => {
+                self.bookmark_prompt = Some(BookmarkPrompt::Set);
+                self.show_toast("Bookmark as: press a letter".to_string());
+            }
+            CommandAction::JumpToBookmark => {
+                self.bookmark_prompt = Some(BookmarkPrompt::Jump);
+                self.show_toast("Jump to bookmark: press its letter".to_string());
+            }
+            CommandAction::ShowBookmarks => {
+                self.bookmark_manager = Some(BookmarkManager::new(&self.config.bookmarks));
+            }
+            CommandAction::ToggleRootLock => {
+                self.tab_manager.active_tab_mut().browser.toggle_root_lock(&self.config)?;
+                let message = match self.browser().root_lock() {
+                    Some(root) => format!("Tab locked to {}", root.display()),
+                    None => "Tab unlocked".to_string(),
+                };
+                self.show_toast(message);
+            }
+            CommandAction::JumpToProjectRoot => {
+                self.update_project();
+                match self.project().map(|project| project.root.clone()) {
+                    Some(root) => {
+                        let browser = &mut self.tab_manager.active_tab_mut().browser;
+                        if let Err(e) = browser.open_directory(&root, &self.config) {
+                            self.log_open_error(&root, &e);
+                        }
+                        self.tab_manager.update_active_tab_name(&self.config);
+                    }
+                    None => self.show_toast("Not inside a project".to_string()),
+                }
+            }
+            CommandAction::JumpToScratch => {
+                if let Some(dir) = self.scratch_dir() {
+                    self.jump_to_directory(dir);
+                }
+            }
+            CommandAction::TogglePreview => {
+                self.config.show_preview = !self.config.show_preview;
+            }
+            CommandAction::PreviewUp => self.move_in_preview(OutlineMove::Up),
+            CommandAction::PreviewDown => self.move_in_preview(OutlineMove::Down),
+            CommandAction::PreviewLeft => self.move_in_preview(OutlineMove::Fold),
+            CommandAction::PreviewRight => self.move_in_preview(OutlineMove::Unfold),
+            CommandAction::FollowPreview => {
+                self.tab_manager.active_tab_mut().browser.toggle_follow(&self.config);
+            }
+            CommandAction::GitDiff => self.show_git_diff(),
+            CommandAction::ProgramHelp => self.show_program_help(),
+            CommandAction::ScrollPreviewUp => {
+                let page = self.layout_info.preview_area.map_or(0, content_height);
+                self.tab_manager.active_tab_mut().browser.scroll_preview(-(page as isize), page, &self.config);
+            }
+            CommandAction::ScrollPreviewDown => {
+                let page = self.layout_info.preview_area.map_or(0, content_height);
+                self.tab_manager.active_tab_mut().browser.scroll_preview(page as isize, page, &self.config);
+            }
+            CommandAction::ShrinkPreview => self.resize_preview(|percent| percent.saturating_sub(PREVIEW_PERCENT_STEP)),
+            CommandAction::GrowPreview => self.resize_preview(|percent| percent + PREVIEW_PERCENT_STEP),
+            CommandAction::NarrowColumn => self.adjust_widths(-1, 0),
+            CommandAction::WidenColumn => self.adjust_widths(1, 0),
+            CommandAction::NarrowPreviewByCell => self.adjust_widths(0, -1),
+            CommandAction::WidenPreviewByCell => self.adjust_widths(0, 1),
+            CommandAction::ResetWidths => self.config.width_adjustment = WidthAdjustment::default(),
+            CommandAction::ToggleTreeView => {
+                self.tab_manager.active_tab_mut().browser.toggle_tree(&self.config);
+            }
+            CommandAction::ToggleDetailView => {
+                self.config.detail_view = !self.config.detail_view;
+            }
+            CommandAction::JumpToFirst => {
+                self.tab_manager.active_tab_mut().browser.jump_to_first(&self.config)?;
+            }
+            CommandAction::JumpToLast => {
+                self.tab_manager.active_tab_mut().browser.jump_to_last(&self.config)?;
+            }
+            CommandAction::JumpUpBy10 => {
+                self.tab_manager.active_tab_mut().browser.jump_up_by_10(&self.config)?;
+            }
+            CommandAction::JumpDownBy10 => {
+                self.tab_manager.active_tab_mut().browser.jump_down_by_10(&self.config)?;
+            }
+            CommandAction::CopyPreview => {
+                self.copy_preview_to_clipboard();
+            }
+            CommandAction::CopyPath => {
+                self.copy_selected_path_to_clipboard();
+            }
+            CommandAction::ShowActions => {
+                self.open_actions_menu();
+            }
+            CommandAction::ReloadColumn => {
+                let paths = [self.browser().active_column().path.clone()];
+                self.dir_sizes.invalidate(&paths);
+                self.entry_counts.invalidate(&paths);
+                self.mime_types.invalidate(&paths);
+                let browser = &mut self.tab_manager.active_tab_mut().browser;
+                if let Err(e) = browser.reload_active_column(&self.config, Some(&mut self.error_log)) {
+                    let path = browser.active_column().path.clone();
+                    self.error_log.add_entry(
+                        ErrorEntry::error(format!("Failed to reload directory: {}", e), Some("Reload".to_string()))
+                            .with_source("reload directory", path, e.as_ref()),
+                    );
+                }
+            }
+            CommandAction::LoadAllEntries => {
+                let column = self.browser().active_column();
+                if column.truncated {
+                    let path = column.path.clone();
+                    self.load_all_entries(path);
+                } else {
+                    self.show_toast("Every entry is listed already".to_string());
+                }
+            }
+            CommandAction::CopySelected => {
+                self.prompt_for_destination(JobKind::Copy);
+            }
+            CommandAction::MoveSelected => {
+                self.prompt_for_destination(JobKind::Move);
+            }
+            CommandAction::MoveToScratch => {
+                self.move_to_scratch();
+            }
+            CommandAction::DeleteSelected if self.config.delete_to_trash => {
+                self.trash_selected();
+            }
+            CommandAction::DeleteSelected | CommandAction::DeletePermanently => {
+                self.confirm_delete();
+            }
+            CommandAction::ToggleMark => {
+                let browser = &mut self.tab_manager.active_tab_mut().browser;
+                browser.active_column_mut().toggle_mark();
+                _ = browser.update_preview(&self.config);
+            }
+            CommandAction::VisualMark => {
+                self.tab_manager.active_tab_mut().browser.active_column_mut().toggle_visual();
+            }
+            CommandAction::ClearMarks => {
+                self.tab_manager.active_tab_mut().browser.active_column_mut().clear_marks();
+            }
+            CommandAction::Find => {
+                self.prompt_for_find(String::new());
+            }
+            CommandAction::GotoPath => {
+                self.prompt_for_goto();
+            }
+            CommandAction::RecordMacro => match self.macro_recording.take() {
+                Some(recording) => {
+                    self.show_toast(format!("Recorded a macro of {} command{}", recording.len(), if recording.len() == 1 { "" } else { "s" }));
+                    self.recorded_macro = recording;
+                }
+                None => {
+                    self.macro_recording = Some(Vec::new());
+                    self.show_toast(format!("Recording a macro; {} to stop", self.command_registry.key_text(&CommandAction::RecordMacro)));
+                }
+            },
+            CommandAction::PlayMacro => {
+                if self.recorded_macro.is_empty() {
+                    self.show_toast("No macro recorded".to_string());
+                }
+                for (action, key) in self.recorded_macro.clone() {
+                    self.execute_command(&action, key)?;
+                }
+            }
+            CommandAction::ShellCommand => {
+                self.dialog = Some(Dialog::prompt("Shell command", "Run (%f file, %d directory, %s marked):", "", DialogAction::Shell));
+            }
+            CommandAction::FuzzyFind => {
+                self.open_fuzzy_finder();
+            }
+            CommandAction::Grep => {
+                self.prompt_for_grep();
+            }
+            CommandAction::FindEmpty => {
+                self.start_find("empty".to_string(), FindQuery { terms: vec![FindTerm::Empty] });
+            }
+            CommandAction::DiskUsage => {
+                let dir = self.browser().active_column().path.clone();
+                self.start_disk_usage(dir, None);
+            }
+            CommandAction::TouchSelected => {
+                self.prompt_for_touch();
+            }
+            CommandAction::RenameSelected => {
+                self.start_rename();
+            }
+            CommandAction::SearchChar => {
+                if let KeyCode::Char(c) = key.code {
+                    self.tab_manager.active_tab_mut().browser.handle_search_char(c, &self.config)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Describe the selected entry for deciding which actions apply
+    fn selection_target(&self) -> ActionTarget {
+        let browser = self.browser();
+        match browser.active_column().selected_entry() {
+            None => ActionTarget::Nothing,
+            Some(entry) if entry.path().is_dir() => ActionTarget::Directory,
+            Some(entry) => {
+                let column = browser.active_column();
+                let changed_in_git = column.git.as_ref()
+                    .and_then(|git| git.get(&entry.file_name()))
+                    .is_some_and(|status| matches!(status, GitStatus::Modified | GitStatus::Staged | GitStatus::Conflicted));
+                let is_program = is_executable(&entry.path());
+                match browser.preview() {
+                    Some(Preview::File(details, _)) => ActionTarget::File {
+                        mime_type: details.mime_type.clone(),
+                        has_text_preview: details.content_preview.text().is_some(),
+                        changed_in_git,
+                        is_program,
+                    },
+                    _ => ActionTarget::File {
+                        mime_type: mime_type_for(&entry.path(), &self.config),
+                        has_text_preview: false,
+                        changed_in_git,
+                        is_program,
+                    },
+                }
+            }
+        }
+    }
+
+    /// Show the selected file's changes against git HEAD over the whole screen
+    fn show_git_diff(&mut self) {
+        let Some(path) = self.browser().active_column().selected_entry().map(|entry| entry.path()) else {
+            return;
+        };
+        if path.is_dir() {
+            self.show_toast("Can't diff a directory".to_string());
+            return;
+        }
+        match diff_against_head(&path) {
+            Ok(diff) if diff.is_empty() => self.show_toast("No changes against HEAD".to_string()),
+            Ok(diff) => match FileDetails::from_path(&path, &self.config) {
+                Ok(details) => self.full_preview = Some(FullPreview::diff(details, diff)),
+                Err(e) => self.log_open_error(&path, &e.into()),
+            },
+            Err(e) => self.error_log.error(format!("Cannot diff {}: {}", path.display(), e), Some("Git".to_string())),
+        }
+    }
+
+    /// Show the selected program's man page, or what it prints for `--help`, over the whole screen
+    ///
+    /// Programs under an untrusted root are never run, so only their man page is looked for.
+    fn show_program_help(&mut self) {
+        let Some(path) = self.browser().active_column().selected_entry().map(|entry| entry.path()) else {
+            return;
+        };
+        if !is_executable(&path) {
+            self.show_toast(format!("{} isn't a program", path.file_name().unwrap_or_default().to_string_lossy()));
+            return;
+        }
+        if let Some(help) = self.pending_help.take() {
+            self.job_manager.cancel(help.job);
+        }
+
+        let width = self.layout_info.screen_area.width.saturating_sub(4).max(40);
+        let run_program = !self.config.is_untrusted(&path);
+        let (sender, receiver) = mpsc::channel();
+        let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let program = path.clone();
+        let job = self.job_manager.spawn(JobKind::Shell, format!("Help for {}", name), move |ctx| {
+            let help = program_help(&program, width, run_program, || ctx.is_cancelled())?;
+            let _ = sender.send(help);
+            Ok("found".to_string())
+        });
+        self.pending_help = Some(PendingHelp { job, path, receiver });
+    }
+
+    /// Show the help a lookup found, or why it found none
+    fn finish_program_help(&mut self, outcome: JobOutcome) {
+        let Some(help) = self.pending_help.take() else {
+            return;
+        };
+        match outcome {
+            JobOutcome::Succeeded(_) => {
+                let Ok(text) = help.receiver.try_recv() else {
+                    return;
+                };
+                match FileDetails::from_path(&help.path, &self.config) {
+                    Ok(details) => self.full_preview = Some(FullPreview::help(details, text)),
+                    Err(e) => self.log_open_error(&help.path, &e.into()),
+                }
+            }
+            JobOutcome::Failed(error) => self.show_toast(error),
+            JobOutcome::Cancelled(_) => {}
+        }
+    }
+
+    /// Open the actions menu for the selected entry
+    fn open_actions_menu(&mut self) {
+        let title = self.browser().active_column().selected_entry()
+            .map(|entry| format!(" {} ", entry.file_name().to_string_lossy()))
+            .unwrap_or_else(|| " Actions ".to_string());
+        let target = self.selection_target();
+        self.actions_menu = Some(ActionsMenu::new(title, &target, &self.command_registry));
+    }
+
+    /// Paths that file operations act on: the marked entries, or else the selected one
+    pub fn selected_paths(&self) -> Vec<PathBuf> {
+        let column = self.browser().active_column();
+        let marked = column.marked_paths();
+        if !marked.is_empty() {
+            return marked;
+        }
+        column.selected_entry()
+            .map(|entry| vec![entry.path()])
+            .unwrap_or_default()
+    }
+
+    /// Open a prompt asking where to copy or move the selected paths
+    fn prompt_for_destination(&mut self, kind: JobKind) {
+        let paths = self.selected_paths();
+        if paths.is_empty() {
+            self.show_toast("Nothing selected".to_string());
+            return;
+        }
+
+        let (title, action) = match kind {
+            JobKind::Move => ("Move", DialogAction::Move(paths.clone())),
+            _ => ("Copy", DialogAction::Copy(paths.clone())),
+        };
+        let message = format!("{} {} to:", title, describe_paths(&paths));
+        let current_dir = self.browser().active_column().path.to_string_lossy().to_string();
+        self.dialog = Some(Dialog::prompt(title, message, current_dir, action));
+    }
+
+    /// The scratch directory, created if it doesn't exist yet
+    fn scratch_dir(&mut self) -> Option<PathBuf> {
+        let dir = self.config.scratch_path();
+        match fs::create_dir_all(&dir) {
+            Ok(()) => Some(dir),
+            Err(e) => {
+                self.error_log.add_entry(
+                    ErrorEntry::error(format!("Cannot create scratch directory {}: {}", dir.display(), e), Some("File Operations".to_string()))
+                        .with_source("create", &dir, &e),
+                );
+                None
+            }
+        }
+    }
+
+    /// Move the selected paths to the scratch directory without asking where
+    ///
+    /// With every operation to be confirmed, asks whether to move them first.
+    fn move_to_scratch(&mut self) {
+        let paths = self.selected_paths();
+        if paths.is_empty() {
+            self.show_toast("Nothing selected".to_string());
+            return;
+        }
+        if self.config.confirm.asks_before_move() {
+            let message = format!("Move {} to the scratch directory?", describe_paths(&paths));
+            self.dialog = Some(Dialog::confirm("Move", message, DialogAction::MoveToScratch(paths)));
+            return;
+        }
+        self.move_paths_to_scratch(paths);
+    }
+
+    /// Move paths to the scratch directory, creating it first if need be
+    fn move_paths_to_scratch(&mut self, paths: Vec<PathBuf>) {
+        let Some(dir) = self.scratch_dir() else {
+            return;
+        };
+        let target = dir.clone();
+        self.spawn_file_job(JobKind::Move, paths, Some(&dir), move |path, progress| {
+            move_into_with(path, &target, ConflictPolicy::Fail, progress)
+        });
+    }
+
+    /// Prompt for a query to search the current directory's subtree with
+    fn prompt_for_find(&mut self, initial: String) {
+        let dir = self.browser().active_column().path.clone();
+        let name = dir.file_name().unwrap_or(dir.as_os_str()).to_string_lossy().to_string();
+        let message = format!("Find under {} (e.g. age<2d, size>100M):", name);
+        self.dialog = Some(Dialog::prompt("Find", message, initial, DialogAction::Find));
+    }
+
+    /// Start searching the current directory's subtree and show the results panel
+    fn start_find(&mut self, query_text: String, query: FindQuery) {
+        if let Some(panel) = &self.results_panel {
+            self.job_manager.cancel(panel.job);
+        }
+
+        let root = self.browser().active_column().path.clone();
+        let show_hidden = self.config.show_hidden_files;
+        let (sender, receiver) = mpsc::channel();
+
+        let search_root = root.clone();
+        let job = self.job_manager.spawn(JobKind::Search, format!("Find {}", query_text), move |ctx| {
+            find(&search_root, &query, show_hidden, ctx, &sender)
+                .map(|count| format!("{} found", count))
+        });
+        self.results_panel = Some(ResultsPanel::new(query_text, root, job, receiver));
+    }
+
+    /// Open the fuzzy finder and start indexing the current directory's subtree
+    fn open_fuzzy_finder(&mut self) {
+        let root = self.browser().active_column().path.clone();
+        let show_hidden = self.config.show_hidden_files;
+        let (sender, receiver) = mpsc::channel();
+
+        let walk_root = root.clone();
+        let name = root.file_name().unwrap_or(root.as_os_str()).to_string_lossy().to_string();
+        let job = self.job_manager.spawn(JobKind::Search, format!("Index {}", name), move |ctx| {
+            walk(&walk_root, show_hidden, ctx, &sender).map(|count| format!("{} entries", count))
+        });
+        self.fuzzy_finder = Some(FuzzyFinder::new(root, job, receiver));
+    }
+
+    /// Close the fuzzy finder, stopping its walk if it is still running
+    fn close_fuzzy_finder(&mut self) {
+        if let Some(finder) = self.fuzzy_finder.take()
+            && !finder.finished
+        {
+            self.job_manager.cancel(finder.job);
+        }
+    }
+
+    /// Prompt for text to search for in the files under the current directory
+    fn prompt_for_grep(&mut self) {
+        let dir = self.browser().active_column().path.clone();
+        let name = dir.file_name().unwrap_or(dir.as_os_str()).to_string_lossy().to_string();
+        let initial = self.grep_panel.as_ref().map(|panel| panel.query.clone()).unwrap_or_default();
+        let message = format!("Search file contents under {}:", name);
+        self.dialog = Some(Dialog::prompt("Search contents", message, initial, DialogAction::Grep));
+    }
+
+    /// Start searching file contents under the current directory and show the matches
+    fn start_grep(&mut self, text: String) {
+        self.close_grep_panel();
+
+        let root = self.browser().active_column().path.clone();
+        let options = GrepOptions {
+            ignore_case: self.config.search_ignore_case,
+            show_hidden: self.config.show_hidden_files,
+        };
+        let (sender, receiver) = mpsc::channel();
+
+        let search_root = root.clone();
+        let search_text = text.clone();
+        let job = self.job_manager.spawn(JobKind::Search, format!("Search \"{}\"", text), move |ctx| {
+            grep(&search_root, &search_text, &options, ctx, &sender)
+                .map(|count| format!("{} matches", count))
+        });
+        self.grep_panel = Some(GrepPanel::new(text, root, job, receiver));
+    }
+
+    /// Close the grep panel, stopping its search if it is still running
+    fn close_grep_panel(&mut self) {
+        if let Some(panel) = self.grep_panel.take()
+            && !panel.finished
+        {
+            self.job_manager.cancel(panel.job);
+        }
+    }
+
+    /// Start working out the disk usage of `root` and show it, selecting `select` once it shows up
+    fn start_disk_usage(&mut self, root: PathBuf, select: Option<PathBuf>) {
+        self.close_disk_usage_panel();
+        let (job, receiver) = self.spawn_disk_usage(&root);
+        self.disk_usage_panel = Some(DiskUsagePanel::new(root, job, receiver, select));
+    }
+
+    /// Start the job working out the disk usage of `root`
+    fn spawn_disk_usage(&mut self, root: &Path) -> (JobId, mpsc::Receiver<UsageEntry>) {
+        let (sender, receiver) = mpsc::channel();
+        let usage_root = root.to_path_buf();
+        let name = root.file_name().unwrap_or(root.as_os_str()).to_string_lossy().to_string();
+        let job = self.job_manager.spawn(JobKind::DiskUsage, format!("Disk usage {}", name), move |ctx| {
+            disk_usage(&usage_root, ctx, &sender).map(format_file_size)
+        });
+        (job, receiver)
+    }
+
+    /// Close the disk usage view, stopping its job if it is still running
+    fn close_disk_usage_panel(&mut self) {
+        if let Some(panel) = self.disk_usage_panel.take()
+            && !panel.finished
+        {
+            self.job_manager.cancel(panel.job);
+        }
+    }
+
+    /// Close the results panel, stopping its search if it is still running
+    fn close_results_panel(&mut self) {
+        if let Some(panel) = self.results_panel.take()
+            && !panel.finished
+        {
+            self.job_manager.cancel(panel.job);
+        }
+    }
+
+    /// Bookmark the current directory, or jump to a bookmark
+    fn run_bookmark_prompt(&mut self, prompt: BookmarkPrompt, key: char) {
+        match prompt {
+            BookmarkPrompt::Set => {
+                let dir = self.browser().active_column().path.clone();
+                self.show_toast(format!("Bookmarked {} as '{}'", dir.display(), key));
+                self.config.bookmarks.insert(key, dir);
+            }
+            BookmarkPrompt::Jump => match self.config.bookmarks.get(&key) {
+                Some(path) => self.jump_to_directory(path.clone()),
+                None => self.show_toast(format!("No bookmark '{}'", key)),
+            },
+        }
+    }
+
+    /// Choose the marked entries, or the selected one, and quit
+    ///
+    /// When picking files, Enter on a directory opens it instead.
+    fn choose(&mut self, mode: PickMode) {
+        let column = self.browser().active_column();
+        let marked = column.marked_paths();
+        let Some(selected) = column.selected_entry().map(|entry| entry.path()) else {
+            return;
+        };
+
+        if marked.is_empty() && mode == PickMode::Files && selected.is_dir() {
+            if let Err(e) = self.tab_manager.active_tab_mut().browser.open_directory(&selected, &self.config) {
+                self.log_open_error(&selected, &e);
+            }
+            self.tab_manager.update_active_tab_name(&self.config);
+            return;
+        }
+
+        let candidates = if marked.is_empty() { vec![selected] } else { marked };
+        let chosen: Vec<PathBuf> = candidates.into_iter().filter(|path| mode.accepts(path)).collect();
+        if chosen.is_empty() {
+            self.show_toast(format!("Choose a {}", mode.noun()));
+            return;
+        }
+        self.chosen = chosen;
+        self.should_quit = true;
+    }
+
+    /// Open the selected directory, or hand the selected file to its opener
+    fn open_selected(&mut self) {
+        let Some(path) = self.browser().active_column().selected_entry().map(|entry| entry.path()) else {
+            return;
+        };
+
+        if path.is_dir() {
+            let active_tab = self.tab_manager.active_tab_mut();
+            _ = active_tab.browser.navigate_right(&self.config);
+            self.tab_manager.update_active_tab_name(&self.config);
+            return;
+        }
+
+        let mime_type = mime_type_for(&path, &self.config);
+        let action = mime_type.as_deref().map(|mime_type| self.config.enter_action_for(mime_type)).unwrap_or_default();
+        match action {
+            EnterAction::Open => {
+                let command = mime_type.and_then(|mime_type| self.config.open_command_for(&mime_type));
+                self.external_command = Some(ExternalCommand::open(&path, command).with_env(self.context_env()));
+            }
+            EnterAction::Edit => self.external_command = Some(ExternalCommand::edit(&path).with_env(self.context_env())),
+            EnterAction::Preview => match FileDetails::from_path(&path, &self.config) {
+                Ok(details) => self.full_preview = Some(FullPreview::new(details)),
+                Err(e) => self.log_open_error(&path, &e.into()),
+            },
+            EnterAction::Nothing => {}
+        }
+    }
+
+    /// Environment telling a program started from here what is selected
+    fn context_env(&self) -> Vec<(&'static str, OsString)> {
+        let column = self.browser().active_column();
+        let selected = column.selected_entry().map(|entry| entry.path());
+        context_env(&column.path, selected.as_deref(), &column.marked_paths())
+    }
+
+    /// Take the program waiting to run with the terminal, if any
+    pub fn take_external_command(&mut self) -> Option<ExternalCommand> {
+        self.external_command.take()
+    }
+
+    /// Report how an external program went and show any files it changed
+    pub fn finish_external_command(&mut self, command: &ExternalCommand, result: io::Result<ExitStatus>) {
+        let error = match result {
+            Ok(status) if status.success() => None,
+            Ok(status) => Some(io::Error::other(format!("exited with {}", status))),
+            Err(e) => Some(e),
+        };
+        if let Some(e) = error {
+            self.error_log.add_entry(
+                ErrorEntry::error(format!("{} failed: {}", command.display(), e), Some("External".to_string()))
+                    .with_source(command.operation, &command.path, &e),
+            );
+        }
+
+        self.tab_manager.refresh_paths(std::slice::from_ref(&command.dir), &self.config);
+    }
+
+    /// Paths chosen with Enter when running as a picker
+    pub fn chosen_paths(&self) -> &[PathBuf] {
+        &self.chosen
+    }
+
+    /// Log that a path couldn't be opened, with where and why
+    fn log_open_error(&mut self, path: &Path, error: &color_eyre::Report) {
+        self.error_log.add_entry(
+            ErrorEntry::error(format!("Cannot open {}: {}", path.display(), error), Some("Navigation".to_string()))
+                .with_source("open", path, error.as_ref()),
+        );
+    }
+
+    /// Open a directory in the active tab, reporting why if it can't be opened
+    fn jump_to_directory(&mut self, path: PathBuf) {
+        let browser = &mut self.tab_manager.active_tab_mut().browser;
+        if let Err(e) = browser.navigate_to(path.clone(), &self.config) {
+            self.log_open_error(&path, &e);
+        }
+        self.tab_manager.update_active_tab_name(&self.config);
+    }
+
+    /// Navigate to the directory containing a path and select it
+    fn reveal_path(&mut self, path: &Path) {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return;
+        };
+
+        let browser = &mut self.tab_manager.active_tab_mut().browser;
+        if let Err(e) = browser.navigate_to(parent.to_path_buf(), &self.config) {
+            self.log_open_error(parent, &e);
+            return;
+        }
+        browser.select_by_name(name, &self.config);
+        self.tab_manager.update_active_tab_name(&self.config);
+    }
+
+    /// Run a shell command in the background, then refresh the directories it was given
+    ///
+    /// A failure is logged with the command's exit status and what it wrote to stderr.
+    fn start_shell_command(&mut self, command: String) {
+        let column = self.browser().active_column();
+        let dir = column.path.clone();
+        let file = column.selected_entry().map(|entry| entry.path());
+        let marked = column.marked_paths();
+        let expanded = expand_placeholders(&command, file.as_deref(), &dir, &marked);
+        let env = context_env(&dir, file.as_deref(), &marked);
+
+        let mut dirs = parent_dirs(&marked);
+        dirs.push(dir.clone());
+        let job = self.job_manager.spawn(JobKind::Shell, format!("`{}`", command), move |ctx| {
+            let output = run_captured(&expanded, &dir, &env, || ctx.is_cancelled()).map_err(|e| e.to_string())?;
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if output.status.success() {
+                // The last line of output usually sums up what happened
+                Ok(stdout.lines().rfind(|line| !line.trim().is_empty()).unwrap_or("done").to_string())
+            } else if stderr.trim().is_empty() {
+                Err(output.status.to_string())
+            } else {
+                Err(format!("{}: {}", output.status, stderr.trim()))
+            }
+        });
+        self.job_dirs.insert(job, dirs);
+    }
+
+    /// Prompt for a path to go to, starting from the current directory's path
+    fn prompt_for_goto(&mut self) {
+        let current_dir = self.browser().active_column().path.to_string_lossy().to_string();
+        self.dialog = Some(Dialog::prompt("Go to", "Path:", current_dir, DialogAction::Goto));
+    }
+
+    /// Show a directory, or the directory holding a file with the file selected
+    fn goto_path(&mut self, path: PathBuf) {
+        if path.is_dir() {
+            let browser = &mut self.tab_manager.active_tab_mut().browser;
+            if let Err(e) = browser.navigate_to(path.clone(), &self.config) {
+                self.log_open_error(&path, &e);
+                return;
+            }
+            self.tab_manager.update_active_tab_name(&self.config);
+        } else if path.exists() {
+            self.reveal_path(&path);
+        } else {
+            self.show_toast(format!("No such file or directory: {}", path.display()));
+            self.dialog = Some(Dialog::prompt("Go to", "Path:", path.to_string_lossy().to_string(), DialogAction::Goto));
+        }
+    }
+
+    /// Prompt for the modification time to give the selected paths
+    fn prompt_for_touch(&mut self) {
+        let paths = self.selected_paths();
+        if paths.is_empty() {
+            self.show_toast("Nothing selected".to_string());
+            return;
+        }
+        let now = Local::now().format(TIMESTAMP_FORMAT).to_string();
+        self.dialog = Some(touch_dialog(paths, now));
+    }
+
+    /// Move the selected paths to the trash, asking first if every operation is to be confirmed
+    fn trash_selected(&mut self) {
+        let paths = self.selected_paths();
+        if paths.is_empty() {
+            self.show_toast("Nothing selected".to_string());
+            return;
+        }
+        if self.config.confirm.asks_before_move() {
+            let message = format!("Move {} to the trash?", describe_paths(&paths));
+            self.dialog = Some(Dialog::confirm("Trash", message, DialogAction::Trash(paths)));
+            return;
+        }
+        self.spawn_file_job(JobKind::Trash, paths, None, |path, _| trash_path(path).map(Some));
+    }
+
+    /// Ask for confirmation before permanently deleting the selected paths, unless
+    /// nothing is to be confirmed
+    fn confirm_delete(&mut self) {
+        let paths = self.selected_paths();
+        if paths.is_empty() {
+            self.show_toast("Nothing selected".to_string());
+            return;
+        }
+        if !self.config.confirm.asks_before_delete() {
+            self.run_dialog_action(DialogAction::Delete(paths), None);
+            return;
+        }
+
+        let message = format!("Permanently delete {}?", describe_paths(&paths));
+        self.dialog = Some(Dialog::confirm("Delete", message, DialogAction::Delete(paths)));
+    }
+
+    /// Ask for confirmation before deleting search results in bulk, unless nothing is
+    /// to be confirmed
+    fn confirm_delete_results(&mut self, paths: Vec<PathBuf>) {
+        if self.config.read_only {
+            self.show_toast("Read-only: files can't be changed".to_string());
+            return;
+        }
+        let (title, message, action) = if self.config.delete_to_trash {
+            ("Trash", format!("Move {} to the trash?", describe_paths(&paths)), DialogAction::Trash(paths))
+        } else {
+            ("Delete", format!("Permanently delete {}?", describe_paths(&paths)), DialogAction::Delete(paths))
+        };
+        if self.config.confirm.asks_before_delete() {
+            self.dialog = Some(Dialog::confirm(title, message, action));
+        } else {
+            self.run_dialog_action(action, None);
+        }
+    }
+
+    /// Carry out an accepted dialog
+    fn run_dialog_action(&mut self, action: DialogAction, input: Option<String>) {
+        // Dialogs opened other than through a command, like the results panel's, are checked here too
+        if self.config.read_only && action.modifies_files() {
+            self.show_toast("Read-only: files can't be changed".to_string());
+            return;
         }
+        let current_dir = self.browser().active_column().path.clone();
+        let destination = input.as_deref().map(|input| resolve_user_path(input, &current_dir));
 
-        // Handle settings panel if open
-        if self.settings_manager.is_open() {
-            let needs_reload = self.settings_manager.handle_key(key, &mut self.config)?;
-            if needs_reload {
-                self.tab_manager.reload_all_tabs(&self.config);
+        match action {
+            DialogAction::Copy(paths) | DialogAction::Move(paths) if destination.as_ref().is_none_or(|dest| !dest.is_dir()) => {
+                let dest = destination.map(|dest| dest.display().to_string()).unwrap_or_default();
+                self.error_log.error(
+                    format!("Cannot transfer {}: {} is not a directory", describe_paths(&paths), dest),
+                    Some("File Operations".to_string()),
+                );
             }
-            return Ok(());
-        }
-
-        // Handle error log navigation if visible
-        if self.error_log.is_visible() {
-            match key.code {
-                KeyCode::Esc | KeyCode::Char('q') => {
-                    self.error_log.hide();
-                    return Ok(());
+            DialogAction::Copy(paths) => {
+                let dest = destination.unwrap_or(current_dir);
+                let target = dest.clone();
+                self.spawn_file_job(JobKind::Copy, paths, Some(&dest), move |path, progress| {
+                    copy_into_with(path, &target, ConflictPolicy::Fail, progress)
+                });
+            }
+            DialogAction::Move(paths) => {
+                let dest = destination.unwrap_or(current_dir);
+                let target = dest.clone();
+                self.spawn_file_job(JobKind::Move, paths, Some(&dest), move |path, progress| {
+                    move_into_with(path, &target, ConflictPolicy::Fail, progress)
+                });
+            }
+            DialogAction::Delete(paths) => {
+                if let Some(panel) = &mut self.results_panel {
+                    panel.remove_paths(&paths);
                 }
-                KeyCode::Up => {
-                    self.error_log.select_previous();
-                    return Ok(());
+                self.spawn_delete_job(paths);
+            }
+            DialogAction::Trash(paths) => {
+                if let Some(panel) = &mut self.results_panel {
+                    panel.remove_paths(&paths);
                 }
-                KeyCode::Down => {
-                    self.error_log.select_next();
-                    return Ok(());
+                self.spawn_file_job(JobKind::Trash, paths, None, |path, _| trash_path(path).map(Some));
+            }
+            DialogAction::MoveToScratch(paths) => self.move_paths_to_scratch(paths),
+            DialogAction::Find => {
+                let input = input.unwrap_or_default();
+                match FindQuery::parse(&input) {
+                    Ok(query) => self.start_find(input, query),
+                    Err(e) => {
+                        self.show_toast(e);
+                        self.prompt_for_find(input);
+                    }
                 }
-                KeyCode::Home => {
-                    self.error_log.select_first();
-                    return Ok(());
+            }
+            DialogAction::Grep => {
+                let text = input.unwrap_or_default();
+                if !text.is_empty() {
+                    self.start_grep(text);
                 }
-                KeyCode::End => {
-                    self.error_log.select_last();
-                    return Ok(());
+            }
+            DialogAction::Goto => {
+                if let Some(path) = destination {
+                    self.goto_path(path);
                 }
-                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                    self.error_log.clear();
-                    return Ok(());
+            }
+            DialogAction::Shell => {
+                if let Some(command) = input.filter(|command| !command.trim().is_empty()) {
+                    self.start_shell_command(command);
                 }
-                KeyCode::Enter => {
-                    self.error_log.toggle_selected_wrap();
-                    return Ok(());
+            }
+            DialogAction::LoadLargeDirectory(path) => self.load_all_entries(path),
+            DialogAction::PreviewLargeFiles => {
+                self.config.large_content.file_previews = Some(true);
+                self.tab_manager.refresh_paths(&[], &self.config);
+            }
+            DialogAction::RestoreSession(session) => self.restore_session(session),
+            DialogAction::RollBack(operations) => self.roll_back_operations(operations),
+            DialogAction::ExportBookmarks => {
+                let Some(path) = destination else { return };
+                let count = self.config.bookmarks.len();
+                match export_bookmarks(&self.config.bookmarks, &path) {
+                    Ok(()) => self.show_toast(format!(
+                        "Exported {} bookmark{} to {}",
+                        count,
+                        if count == 1 { "" } else { "s" },
+                        path.display()
+                    )),
+                    Err(e) => self.error_log.error(
+                        format!("Failed to export bookmarks to {}: {}", path.display(), e),
+                        Some("Bookmarks".to_string()),
+                    ),
+                }
+            }
+            DialogAction::ImportBookmarks => {
+                let Some(path) = destination else { return };
+                match import_bookmarks(&mut self.config.bookmarks, &path) {
+                    Ok(summary) => {
+                        let mut message =
+                            format!("Imported {} bookmark{}", summary.added, if summary.added == 1 { "" } else { "s" });
+                        if !summary.kept.is_empty() {
+                            let keys: Vec<String> = summary.kept.iter().map(|key| format!("'{}'", key)).collect();
+                            message.push_str(&format!("; kept the current ones for {}", keys.join(", ")));
+                        }
+                        self.show_toast(message);
+                    }
+                    Err(e) => self.error_log.error(
+                        format!("Failed to import bookmarks from {}: {}", path.display(), e),
+                        Some("Bookmarks".to_string()),
+                    ),
+                }
+            }
+            DialogAction::Touch(paths) => {
+                let input = input.unwrap_or_default();
+                match parse_timestamp(&input) {
+                    Some(time) => self.touch_paths(&paths, time.into()),
+                    None => {
+                        self.show_toast(format!("Invalid timestamp '{}', expected YYYY-MM-DD HH:MM:SS", input));
+                        self.dialog = Some(touch_dialog(paths, input));
+                    }
                 }
-                _ => {}
             }
         }
+    }
 
-        // Find matching command
-        if let Some(command) = self.command_registry.find_command(&key) {
-            let action = command.action.clone();
-            return self.execute_command(&action, key);
+    /// Set the modification time of the given paths and refresh the listing
+    fn touch_paths(&mut self, paths: &[PathBuf], time: SystemTime) {
+        let mut touched = 0;
+        for path in paths {
+            match touch_path(path, time) {
+                Ok(()) => touched += 1,
+                Err(e) => self.error_log.add_entry(
+                    ErrorEntry::error(format!("Failed to touch {}: {}", path.display(), e), Some("File Operations".to_string()))
+                        .with_source("touch", path.as_path(), &e),
+                ),
+            }
         }
 
-        Ok(())
+        self.tab_manager.active_tab_mut().browser.active_column_mut().clear_marks();
+        self.tab_manager.refresh_paths(&parent_dirs(paths), &self.config);
+        self.show_toast(format!("Touched {} of {} items", touched, paths.len()));
     }
 
-    /// Execute a command action
-    fn execute_command(&mut self, action: &CommandAction, key: KeyEvent) -> Result<()> {
-        match action {
-            CommandAction::Quit => {
-                self.should_quit = true;
+    /// Open the inline rename field on the selected entry
+    fn start_rename(&mut self) {
+        let column = self.tab_manager.active_tab_mut().browser.active_column_mut();
+        let Some(entry) = column.selected_entry() else {
+            self.show_toast("Nothing selected".to_string());
+            return;
+        };
+
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let mut input = TextInput::new(name);
+        // Put the cursor before the extension, which is rarely what needs changing
+        if !path.is_dir()
+            && let Some(extension) = path.extension().filter(|_| path.file_stem().is_some_and(|stem| !stem.is_empty()))
+        {
+            for _ in 0..=extension.to_string_lossy().chars().count() {
+                input.move_left();
             }
-            CommandAction::ShowSettings => {
-                self.settings_manager.open();
+        }
+        column.rename_input = Some(input);
+    }
+
+    /// Rename the selected entry to the name typed in the rename field
+    ///
+    /// Invalid or clashing names keep the field open so they can be corrected.
+    fn finish_rename(&mut self) {
+        let browser = &mut self.tab_manager.active_tab_mut().browser;
+        let column = browser.active_column_mut();
+        let Some(new_name) = column.rename_input.as_ref().map(|input| input.value().to_string()) else {
+            return;
+        };
+        let Some(path) = column.selected_entry().map(|entry| entry.path()) else {
+            column.rename_input = None;
+            return;
+        };
+
+        // The field only holds text, so a name that isn't UTF-8 left as it was keeps its bytes
+        let old_name = path.file_name().unwrap_or_default();
+        if old_name.to_str().is_none() && old_name.to_string_lossy() == new_name {
+            column.rename_input = None;
+            return;
+        }
+
+        let clashes = column.entries.iter()
+            .any(|entry| entry.file_name() == new_name.as_str() && entry.path() != path);
+        if clashes {
+            self.show_toast(format!("{} already exists", new_name));
+            return;
+        }
+
+        match rename_entry(&path, &new_name) {
+            Ok(_) => {
+                column.rename_input = None;
+                self.tab_manager.refresh_paths(&parent_dirs(&[path]), &self.config);
+                self.tab_manager.active_tab_mut().browser.select_by_name(std::ffi::OsStr::new(&new_name), &self.config);
             }
-            CommandAction::ShowErrorLog => {
-                self.error_log.toggle_visibility();
+            Err(e) if matches!(e.kind(), io::ErrorKind::InvalidInput | io::ErrorKind::AlreadyExists) => {
+                self.show_toast(e.to_string());
             }
-            CommandAction::NewTab => {
-                self.tab_manager.create_tab(&self.config, Some(&mut self.error_log))?;
+            Err(e) => {
+                column.rename_input = None;
+                self.error_log.add_entry(
+                    ErrorEntry::error(format!("Failed to rename {}: {}", path.display(), e), Some("File Operations".to_string()))
+                        .with_source(format!("rename to {}", new_name), path.as_path(), &e),
+                );
             }
-            CommandAction::CloseTab => {
-                if !self.tab_manager.close_current_tab() {
-                    // Cannot close the last tab - add a message to error log
-                    self.error_log.warning("Cannot close the last tab".to_string(), None);
+        }
+    }
+
+    /// Run a file operation over each path as a background job
+    ///
+    /// Every path is attempted; failures are collected into the job's error message,
+    /// which ends up in the error log when the job finishes. The directories holding
+    /// the paths, and `destination` if given, are refreshed once the job is done.
+    /// The operation returns where the path ended up, if anywhere, for the journal
+    /// that lets a later run report the job if it is killed halfway.
+    ///
+    /// Copies and moves also report the bytes they write through the operation's
+    /// progress callback, which the status bar shows the throughput and time left of.
+    fn spawn_file_job<F>(&mut self, kind: JobKind, paths: Vec<PathBuf>, destination: Option<&Path>, operation: F)
+    where
+        F: Fn(&Path, ProgressCallback) -> io::Result<Option<PathBuf>> + Send + 'static,
+    {
+        // The marks have served their purpose once the operation starts
+        self.tab_manager.active_tab_mut().browser.active_column_mut().clear_marks();
+
+        let mut dirs = parent_dirs(&paths);
+        if let Some(destination) = destination {
+            dirs.push(fs::canonicalize(destination).unwrap_or_else(|_| destination.to_path_buf()));
+        }
+
+        let mut journal = self.create_journal(kind, &paths, destination);
+        let label = format!("{} {}", kind.display_name(), describe_paths(&paths));
+        let job = self.job_manager.spawn(kind, label, move |ctx| {
+            let total = paths.len() as u64;
+            let mut failures = Vec::new();
+            let sizes: Option<Vec<u64>> = matches!(kind, JobKind::Copy | JobKind::Move)
+                .then(|| paths.iter().map(|path| tree_size(path).unwrap_or(0)).collect());
+            let bytes_total = sizes.iter().flatten().sum();
+            let mut bytes_before = 0;
+            let mut reported = Instant::now();
+
+            for (index, path) in paths.iter().enumerate() {
+                if ctx.is_cancelled() {
+                    if let Some(journal) = journal.take() {
+                        _ = journal.complete();
+                    }
+                    return Err(format!("cancelled after {} of {} items", index, total));
                 }
+                if let Some(journal) = &mut journal {
+                    _ = journal.started(index);
+                }
+                let mut progress = |progress: Progress| {
+                    // Stop a big file part way rather than at the next item
+                    if ctx.is_cancelled() {
+                        return ControlFlow::Break(());
+                    }
+                    if sizes.is_some() && (reported.elapsed() >= PROGRESS_REPORT_INTERVAL || progress.bytes_done == progress.bytes_total) {
+                        ctx.bytes(bytes_before + progress.bytes_done, bytes_total);
+                        reported = Instant::now();
+                    }
+                    ControlFlow::Continue(())
+                };
+                match operation(path, &mut progress) {
+                    Ok(target) => {
+                        if let Some(journal) = &mut journal {
+                            _ = journal.finished(index, target);
+                        }
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted && ctx.is_cancelled() => {
+                        if let Some(journal) = journal.take() {
+                            _ = journal.complete();
+                        }
+                        return Err(format!("cancelled during item {} of {}", index + 1, total));
+                    }
+                    Err(e) => failures.push(format!("{}: {}", path.display(), e)),
+                }
+                bytes_before += sizes.as_ref().map_or(0, |sizes| sizes[index]);
+                ctx.progress(index as u64 + 1, Some(total));
             }
-            CommandAction::NextTab => {
-                self.tab_manager.next_tab();
-            }
-            CommandAction::PrevTab => {
-                self.tab_manager.prev_tab();
-            }
-            CommandAction::ClearSearch => {
-                self.tab_manager.active_tab_mut().browser.clear_search();
-            }
-            CommandAction::NavigateUp => {
-                let active_tab = self.tab_manager.active_tab_mut();
-                active_tab.browser.select_previous();
-                _ = active_tab.browser.update_preview(&self.config);
-            }
-            CommandAction::NavigateDown => {
-                let active_tab = self.tab_manager.active_tab_mut();
-                active_tab.browser.select_next();
-                _ = active_tab.browser.update_preview(&self.config);
-            }
-            CommandAction::NavigateLeft => {
-                let active_tab = self.tab_manager.active_tab_mut();
-                _ = active_tab.browser.navigate_left(&self.config);
-                self.tab_manager.update_active_tab_name();
+
+            if let Some(journal) = journal {
+                _ = journal.complete();
             }
-            CommandAction::NavigateRight => {
-                let active_tab = self.tab_manager.active_tab_mut();
-                _ = active_tab.browser.navigate_right(&self.config);
-                self.tab_manager.update_active_tab_name();
+            if failures.is_empty() {
+                Ok(format!("{} item{}", total, if total == 1 { "" } else { "s" }))
+            } else {
+                Err(failures.join("; "))
             }
-            CommandAction::SetAnchor => {
-                self.tab_manager.active_tab_mut().browser.set_anchor(&self.config)?;
+        });
+        self.job_dirs.insert(job, dirs);
+    }
+
+    /// Permanently delete paths in the background, counting the entries removed
+    ///
+    /// Unlike the other file jobs, a delete goes on past the entries it can't
+    /// remove, and lists them all in the one error it ends with.
+    fn spawn_delete_job(&mut self, paths: Vec<PathBuf>) {
+        self.tab_manager.active_tab_mut().browser.active_column_mut().clear_marks();
+        let dirs = parent_dirs(&paths);
+        let mut journal = self.create_journal(JobKind::Delete, &paths, None);
+
+        let label = format!("{} {}", JobKind::Delete.display_name(), describe_paths(&paths));
+        let job = self.job_manager.spawn(JobKind::Delete, label, move |ctx| {
+            let total: u64 = paths.iter().map(|path| count_tree_entries(path)).sum();
+            let mut removed = 0;
+            let mut failures = Vec::new();
+            let mut reported = Instant::now();
+
+            for (index, path) in paths.iter().enumerate() {
+                if let Some(journal) = &mut journal {
+                    _ = journal.started(index);
+                }
+                let mut removed_here = 0;
+                let result = delete_tree_with(path, &mut |count| {
+                    removed_here = count;
+                    if ctx.is_cancelled() {
+                        return ControlFlow::Break(());
+                    }
+                    if reported.elapsed() >= PROGRESS_REPORT_INTERVAL {
+                        ctx.progress(removed + count, Some(total));
+                        reported = Instant::now();
+                    }
+                    ControlFlow::Continue(())
+                });
+                removed += removed_here;
+                match result {
+                    Ok(report) if report.failures.is_empty() => {
+                        if let Some(journal) = &mut journal {
+                            _ = journal.finished(index, None);
+                        }
+                    }
+                    Ok(report) => {
+                        failures.extend(report.failures.iter().map(|(path, e)| format!("{}: {}", path.display(), e)));
+                    }
+                    Err(_) => {
+                        if let Some(journal) = journal.take() {
+                            _ = journal.complete();
+                        }
+                        return Err(format!("cancelled after removing {} of {} entries", removed, total));
+                    }
+                }
+                ctx.progress(removed, Some(total));
             }
-            CommandAction::JumpToFirst => {
-                self.tab_manager.active_tab_mut().browser.jump_to_first(&self.config)?;
+
+            if let Some(journal) = journal {
+                _ = journal.complete();
             }
-            CommandAction::JumpToLast => {
-                self.tab_manager.active_tab_mut().browser.jump_to_last(&self.config)?;
+            if failures.is_empty() {
+                return Ok(format!("removed {} entr{}", removed, if removed == 1 { "y" } else { "ies" }));
             }
-            CommandAction::JumpUpBy10 => {
-                self.tab_manager.active_tab_mut().browser.jump_up_by_10(&self.config)?;
+            let mut message = format!("removed {} of {} entries, {} couldn't be removed", removed, total, failures.len());
+            for failure in failures.iter().take(MAX_LISTED_FAILURES) {
+                message.push('\n');
+                message.push_str(failure);
             }
-            CommandAction::JumpDownBy10 => {
-                self.tab_manager.active_tab_mut().browser.jump_down_by_10(&self.config)?;
+            if failures.len() > MAX_LISTED_FAILURES {
+                message.push_str(&format!("\nand {} more", failures.len() - MAX_LISTED_FAILURES));
             }
-            CommandAction::SearchChar => {
-                if let KeyCode::Char(c) = key.code {
-                    self.tab_manager.active_tab_mut().browser.handle_search_char(c)?;
-                }
+            Err(message)
+        });
+        self.job_dirs.insert(job, dirs);
+    }
+
+    /// Start the journal of a file operation
+    ///
+    /// Without a journal the operation still runs, it just can't be recovered.
+    fn create_journal(&mut self, kind: JobKind, paths: &[PathBuf], destination: Option<&Path>) -> Option<Journal> {
+        match Journal::create(&journal_dir(&self.config), kind, paths, destination) {
+            Ok(journal) => Some(journal),
+            Err(e) => {
+                self.error_log.add_entry(
+                    ErrorEntry::warning("Failed to create operation journal".to_string(), Some("File Operations".to_string()))
+                        .with_source("create journal", journal_dir(&self.config), &e),
+                );
+                None
             }
         }
-        Ok(())
+    }
+
+    /// Copy the absolute path of the selected entry to the clipboard
+    fn copy_selected_path_to_clipboard(&mut self) {
+        let Some(path) = self.browser().active_column().selected_entry().map(|entry| entry.path()) else {
+            self.show_toast("Nothing selected".to_string());
+            return;
+        };
+
+        let path = path.to_string_lossy().to_string();
+        match copy_to_clipboard(&path) {
+            Ok(()) => self.show_toast(format!("Copied {}", path)),
+            Err(e) => self.error_log.error(format!("Failed to copy to clipboard: {}", e), Some("Clipboard".to_string())),
+        }
+    }
+
+    /// Copy the text shown in the file preview to the clipboard
+    fn copy_preview_to_clipboard(&mut self) {
+        let text = match self.browser().preview() {
+            Some(Preview::File(details, _)) => details.content_preview.text().map(str::to_string),
+            _ => None,
+        };
+
+        let Some(text) = text else {
+            self.show_toast("Nothing to copy: no text preview".to_string());
+            return;
+        };
+
+        match copy_to_clipboard(&text) {
+            Ok(()) => self.show_toast(format!("Copied {} lines to clipboard", text.lines().count())),
+            Err(e) => self.error_log.error(format!("Failed to copy to clipboard: {}", e), Some("Clipboard".to_string())),
+        }
     }
 
     /// Handle mouse input
@@ -225,7 +2332,17 @@ impl App {
     pub fn handle_mouse(&mut self, mouse: MouseEvent) -> Result<()> {
         // Debug logging (can be enabled for troubleshooting)
         // Only handle mouse events if settings panel is not open
-        if self.settings_manager.is_open() {
+        if self.settings_manager.is_open()
+            || self.dialog.is_some()
+            || self.results_panel.is_some()
+            || self.fuzzy_finder.is_some()
+            || self.bookmark_manager.is_some()
+            || self.grep_panel.is_some()
+            || self.disk_usage_panel.is_some()
+            || self.full_preview.is_some()
+            || self.browser().active_column().rename_input.is_some()
+            || self.browser().active_column().filter_input.is_some()
+        {
             return Ok(());
         }
 
@@ -234,15 +2351,59 @@ impl App {
             // Could add specific mouse handling for error log here
 
         match mouse.kind {
+            // Shift+wheel and horizontal wheels shift the visible columns
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown if mouse.modifiers.contains(KeyModifiers::SHIFT) => {
+                self.handle_column_scroll(&mouse);
+            }
+            MouseEventKind::ScrollLeft | MouseEventKind::ScrollRight => {
+                self.handle_column_scroll(&mouse);
+            }
             MouseEventKind::ScrollUp => {
                 self.handle_mouse_scroll(&mouse)?;
             }
             MouseEventKind::ScrollDown => {
                 self.handle_mouse_scroll(&mouse)?;
             }
+            MouseEventKind::Down(MouseButton::Left) if self.layout_info.status_path_area.contains(Position::new(mouse.column, mouse.row)) => {
+                self.prompt_for_goto();
+            }
+            MouseEventKind::Down(MouseButton::Left)
+                if self.layout_info.preview_area.is_some_and(|area| mouse.column == area.x && area.contains(Position::new(mouse.column, mouse.row))) =>
+            {
+                self.resizing_preview = true;
+            }
+            MouseEventKind::Drag(MouseButton::Left) if self.resizing_preview => {
+                let browser_area = self.layout_info.browser_area;
+                let width = browser_area.right().saturating_sub(mouse.column);
+                let percent = (u32::from(width) * 100 / u32::from(browser_area.width.max(1))) as u16;
+                self.resize_preview(|_| percent);
+            }
+            MouseEventKind::Drag(MouseButton::Left) => self.handle_mouse_drag(mouse.column, mouse.row),
+            MouseEventKind::Up(MouseButton::Left) => {
+                self.resizing_preview = false;
+                self.finish_mouse_drag();
+            }
+            MouseEventKind::Down(MouseButton::Left) if mouse.modifiers.contains(KeyModifiers::SHIFT) => {
+                // Shift+click marks everything from the selection to the clicked row
+                let from = self.browser().active_column().selected.selected();
+                let active = self.browser().columns().len() - 1;
+                self.handle_mouse_click(mouse.column, mouse.row)?;
+                if let (Some(from), Some((column, to))) = (from, self.entry_under_mouse(mouse.column, mouse.row))
+                    && column == active
+                {
+                    self.tab_manager.active_tab_mut().browser.active_column_mut().mark_range(from.min(to)..=from.max(to));
+                }
+            }
             MouseEventKind::Down(MouseButton::Left) => {
                 // Handle left click - this will be used for row selection
                 self.handle_mouse_click(mouse.column, mouse.row)?;
+                self.mouse_drag = self.entry_under_mouse(mouse.column, mouse.row)
+                    .map(|(column, anchor)| MouseDrag { column, anchor, moved: false });
+            }
+            MouseEventKind::Down(MouseButton::Right) => {
+                // Select the row under the cursor, then offer actions for it
+                self.handle_mouse_click(mouse.column, mouse.row)?;
+                self.open_actions_menu();
             }
             _ => {
                 // Ignore other mouse events for now
@@ -260,18 +2421,67 @@ impl App {
                 MouseEventKind::ScrollUp => ScrollDirection::Backward,
                 _ => return Ok(())
             };
-            let area = self.layout_info.column_areas[column_index];
             let active_tab = self.tab_manager.active_tab_mut();
             let browser_columns_len = active_tab.browser.columns().len();
 
-            // Don't try to scroll preview columns, only actual directory columns
-            if column_index < browser_columns_len {
-                if let Some(column) = active_tab.browser.columns_mut().get_mut(column_index) {
-                    column.scroll(scroll_direction, usize::from(area.height));
-                    if std::env::var("BROWSE_DEBUG_MOUSE").is_ok() {
-                        let message = format!("Scrolled column {} down {}", column_index, column.selected.offset());
-                        self.error_log.info(message, Some("Mouse Event".to_string()));
-                    }
+            // A file preview scrolls its content; a directory preview doesn't scroll
+            if column_index == browser_columns_len {
+                let page = self.layout_info.preview_area.map_or(0, content_height);
+                let lines = if scroll_direction == ScrollDirection::Forward { PREVIEW_WHEEL_LINES } else { -PREVIEW_WHEEL_LINES };
+                active_tab.browser.scroll_preview(lines, page, &self.config);
+            } else if column_index < browser_columns_len
+                && let Some(column) = active_tab.browser.columns_mut().get_mut(column_index)
+            {
+                // Columns left of the active one hold the path to it, so their selection stays put
+                let moved = column.scroll(scroll_direction, column_index + 1 == browser_columns_len);
+                if std::env::var("BROWSE_DEBUG_MOUSE").is_ok() {
+                    let message = format!("Scrolled column {} down {}", column_index, column.selected.offset());
+                    self.error_log.info(message, Some("Mouse Event".to_string()));
+                }
+                if moved {
+                    _ = active_tab.browser.update_preview(&self.config);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Scroll the column chain horizontally in response to the mouse wheel
+    fn handle_column_scroll(&mut self, mouse: &MouseEvent) {
+        let direction = match mouse.kind {
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollLeft => ScrollDirection::Backward,
+            _ => ScrollDirection::Forward,
+        };
+        self.tab_manager.active_tab_mut().browser.scroll_columns(direction);
+    }
+
+    /// Handle a key while the filter is being typed
+    ///
+    /// The listing narrows as the filter changes. Enter keeps the filter and returns to
+    /// normal browsing; Esc drops it and restores the full listing.
+    fn handle_filter_key(&mut self, key: KeyEvent) -> Result<()> {
+        let browser = &mut self.tab_manager.active_tab_mut().browser;
+        match key.code {
+            KeyCode::Esc => {
+                browser.active_column_mut().filter_input = None;
+                browser.set_filter("", &self.config)?;
+            }
+            KeyCode::Enter => browser.active_column_mut().filter_input = None,
+            KeyCode::Up => {
+                browser.select_previous();
+                _ = browser.update_preview(&self.config);
+            }
+            KeyCode::Down => {
+                browser.select_next();
+                _ = browser.update_preview(&self.config);
+            }
+            _ => {
+                let Some(input) = &mut browser.active_column_mut().filter_input else {
+                    return Ok(());
+                };
+                if input.handle_key(key) {
+                    let filter = input.value().to_string();
+                    browser.set_filter(&filter, &self.config)?;
                 }
             }
         }
@@ -280,8 +2490,21 @@ impl App {
 
     /// Handle mouse clicks for row selection
     fn handle_mouse_click(&mut self, column: u16, row: u16) -> Result<()> {
+        if let Some(area) = self.layout_info.tree_area {
+            // Rows start below the top border and padding and stop above the bottom ones
+            if column >= area.x && column < area.x + area.width
+                && row >= area.y + 2 && row < (area.y + area.height).saturating_sub(2)
+                && let Some(tree) = self.browser().tree()
+            {
+                let index = tree.visible_offset(usize::from(area.height.saturating_sub(4))) + usize::from(row - area.y - 2);
+                self.tab_manager.active_tab_mut().browser.select_tree_row(index, &self.config);
+            }
+            return Ok(());
+        }
         // Check if click is within any column area
-        for (col_index, col_area) in self.layout_info.column_areas.iter().enumerate() {
+        let first_visible = self.browser().visible_columns().start;
+        for (area_index, col_area) in self.layout_info.column_areas.iter().enumerate() {
+            let col_index = first_visible + area_index;
             if column >= col_area.x && column < col_area.x + col_area.width
                 && row >= col_area.y && row < col_area.y + col_area.height
             {
@@ -303,13 +2526,13 @@ impl App {
                         // This is crucial: clicked_row_in_view is the visual row (0-based from top of visible area)
                         // but we need to account for how far the column has been scrolled down
                         if let Some(target_column) = browser.columns().get(col_index) {
-                            let scroll_offset = target_column.selected.offset();
+                            let scroll_offset = target_column.visible_offset(target_column.view_height());
                             let actual_item_index = clicked_row_in_view + scroll_offset;
 
                             // Now select the clicked row in the target column
                             if let Some(column_to_update) = browser.columns_mut().get_mut(col_index) {
                                 if actual_item_index < column_to_update.entries.len() {
-                                    column_to_update.selected.select(Some(actual_item_index));
+                                    column_to_update.select(Some(actual_item_index));
                                 }
                             }
                         }
@@ -327,6 +2550,52 @@ impl App {
         Ok(())
     }
 
+    /// Column and entry index of the row under the mouse, if it is over a listed entry
+    fn entry_under_mouse(&self, column: u16, row: u16) -> Option<(usize, usize)> {
+        let first_visible = self.browser().visible_columns().start;
+        let position = Position::new(column, row);
+        let area_index = self.layout_info.column_areas.iter().position(|area| area.contains(position))?;
+        let area = self.layout_info.column_areas[area_index];
+        let dir_column = self.browser().columns().get(first_visible + area_index)?;
+        // Rows start below the title and padding and stop above the footer
+        let content_start = area.y + 2;
+        if row < content_start || row >= (area.y + area.height).saturating_sub(3) {
+            return None;
+        }
+        let index = dir_column.visible_offset(dir_column.view_height()) + usize::from(row - content_start);
+        (index < dir_column.entries.len()).then_some((first_visible + area_index, index))
+    }
+
+    /// Extend the range being dragged over to the row under the mouse
+    fn handle_mouse_drag(&mut self, column: u16, row: u16) {
+        let Some(drag) = self.mouse_drag else {
+            return;
+        };
+        let Some((_, index)) = self.entry_under_mouse(column, row).filter(|(over, _)| *over == drag.column) else {
+            return;
+        };
+        self.mouse_drag = Some(MouseDrag { moved: true, ..drag });
+        let browser = &mut self.tab_manager.active_tab_mut().browser;
+        if let Some(dir_column) = browser.columns_mut().get_mut(drag.column) {
+            dir_column.visual_anchor = Some(drag.anchor);
+            dir_column.select(Some(index));
+        }
+        _ = browser.update_preview(&self.config);
+    }
+
+    /// Mark the rows a drag swept over when the button is released
+    fn finish_mouse_drag(&mut self) {
+        let Some(drag) = self.mouse_drag.take().filter(|drag| drag.moved) else {
+            return;
+        };
+        let browser = &mut self.tab_manager.active_tab_mut().browser;
+        if let Some(column) = browser.columns_mut().get_mut(drag.column)
+            && column.visual_anchor.is_some()
+        {
+            column.toggle_visual();
+        }
+    }
+
     /// Determine which column the mouse cursor is over
     ///
     /// This function iterates through all column areas (stored during UI rendering)
@@ -346,7 +2615,9 @@ impl App {
         }
 
         // Find which column the mouse is over
-        for (index, area) in self.layout_info.column_areas.iter().enumerate() {
+        let first_visible = self.browser().visible_columns().start;
+        for (area_index, area) in self.layout_info.column_areas.iter().enumerate() {
+            let index = first_visible + area_index;
             if mouse_column >= area.x && mouse_column < area.x + area.width &&
                mouse_row >= area.y && mouse_row < area.y + area.height {
                 // Additional check: make sure this column actually exists in the browser
@@ -386,9 +2657,93 @@ impl App {
         &self.error_log
     }
 
+    pub fn job_manager(&self) -> &JobManager {
+        &self.job_manager
+    }
+
+    pub fn actions_menu(&self) -> Option<&ActionsMenu> {
+        self.actions_menu.as_ref()
+    }
+
+    pub fn dialog(&self) -> Option<&Dialog> {
+        self.dialog.as_ref()
+    }
+
+    pub fn results_panel(&self) -> Option<&ResultsPanel> {
+        self.results_panel.as_ref()
+    }
+
+    pub fn bookmark_manager(&self) -> Option<&BookmarkManager> {
+        self.bookmark_manager.as_ref()
+    }
+
+    pub fn fuzzy_finder(&self) -> Option<&FuzzyFinder> {
+        self.fuzzy_finder.as_ref()
+    }
+
+    pub fn grep_panel(&self) -> Option<&GrepPanel> {
+        self.grep_panel.as_ref()
+    }
+
+    pub fn disk_usage_panel(&self) -> Option<&DiskUsagePanel> {
+        self.disk_usage_panel.as_ref()
+    }
+
+    pub fn full_preview(&self) -> Option<&FullPreview> {
+        self.full_preview.as_ref()
+    }
+
+    pub fn toast(&self) -> Option<&Toast> {
+        self.toast.as_ref()
+    }
+
+    /// Where the linked image of the previewed file goes, when nothing is open over the preview
+    pub fn image_placement(&self) -> Option<ImagePlacement> {
+        let popup_open = self.settings().is_some()
+            || self.actions_menu.is_some()
+            || self.bookmark_manager.is_some()
+            || self.results_panel.is_some()
+            || self.grep_panel.is_some()
+            || self.disk_usage_panel.is_some()
+            || self.full_preview.is_some()
+            || self.fuzzy_finder.is_some()
+            || self.dialog.is_some();
+        if popup_open {
+            return None;
+        }
+        let Some(Preview::File(details, _)) = self.browser().visible_preview() else {
+            return None;
+        };
+        let image = details.linked_image.as_ref()?;
+        let area = linked_image_area(details, self.layout_info.preview_area?)?;
+        Some(ImagePlacement { path: image.path.clone(), area })
+    }
+
     /// Update layout info for mouse interaction
     pub fn set_layout_info(&mut self, layout_info: LayoutInfo) {
         self.layout_info = layout_info;
     }
 
 }
+
+/// Prompt for a modification time, starting from `initial`
+fn touch_dialog(paths: Vec<PathBuf>, initial: String) -> Dialog {
+    let message = format!("Set modification time of {} to:", describe_paths(&paths));
+    Dialog::prompt("Touch", message, initial, DialogAction::Touch(paths))
+}
+
+/// Distinct directories containing the given paths
+fn parent_dirs(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = paths.iter().filter_map(|path| path.parent()).map(Path::to_path_buf).collect();
+    dirs.sort();
+    dirs.dedup();
+    dirs
+}
+
+/// Short description of a set of paths for prompts and job labels
+fn describe_paths(paths: &[PathBuf]) -> String {
+    match paths {
+        [path] => format!("'{}'", path.file_name().unwrap_or(path.as_os_str()).to_string_lossy()),
+        _ => format!("{} items", paths.len()),
+    }
+}
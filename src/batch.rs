@@ -0,0 +1,291 @@
+//! Non-interactive batch mode
+//!
+//! `browse --batch` reads one command per line from stdin and runs it against the
+//! same Browser and file operations the TUI uses, without drawing anything.
+//! Blank lines and lines starting with `#` are ignored. Execution stops at the
+//! first failing command.
+//!
+//! Commands:
+//! - `cd <path>` - change directory, relative to the current one or to `~`
+//! - `select <name>` - select an entry in the current directory
+//! - `mark <glob>` - mark entries in the current directory matching a glob
+//! - `copy to <dir>` - copy the marked entries (or the selection) into a directory
+//! - `move to <dir>` - move the marked entries (or the selection) into a directory
+//! - `delete` - permanently delete the marked entries (or the selection)
+//! - `print selection` - print the marked entries (or the selection), one per line
+
+use crate::browser::Browser;
+use crate::config::Settings;
+use crate::file_operations::{copy_into, delete_path, move_into};
+use crate::utils::{glob_match, resolve_user_path};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// A single batch mode command
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchCommand {
+    Cd(PathBuf),
+    Select(String),
+    Mark(String),
+    CopyTo(PathBuf),
+    MoveTo(PathBuf),
+    Delete,
+    PrintSelection,
+}
+
+impl BatchCommand {
+    /// Parse a command line; blank lines and comments yield `None`
+    pub fn parse(line: &str) -> Result<Option<Self>, String> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+
+        let (verb, rest) = match line.split_once(char::is_whitespace) {
+            Some((verb, rest)) => (verb, rest.trim()),
+            None => (line, ""),
+        };
+
+        let require_arg = |what: &str| {
+            if rest.is_empty() {
+                Err(format!("'{}' needs {}", verb, what))
+            } else {
+                Ok(rest.to_string())
+            }
+        };
+
+        let command = match verb {
+            "cd" => BatchCommand::Cd(PathBuf::from(require_arg("a path")?)),
+            "select" => BatchCommand::Select(require_arg("a name")?),
+            "mark" => BatchCommand::Mark(require_arg("a glob pattern")?),
+            "copy" | "move" => {
+                let dest = match rest.strip_prefix("to") {
+                    Some(after) if after.is_empty() || after.starts_with(char::is_whitespace) => after.trim(),
+                    _ => rest,
+                };
+                if dest.is_empty() {
+                    return Err(format!("'{}' needs a destination: {} to <dir>", verb, verb));
+                }
+                if verb == "copy" {
+                    BatchCommand::CopyTo(PathBuf::from(dest))
+                } else {
+                    BatchCommand::MoveTo(PathBuf::from(dest))
+                }
+            }
+            "delete" => BatchCommand::Delete,
+            "print" if rest.is_empty() || rest == "selection" => BatchCommand::PrintSelection,
+            _ => return Err(format!("Unknown command: {}", line)),
+        };
+
+        Ok(Some(command))
+    }
+}
+
+/// Browser state for a batch run
+pub struct BatchSession<'a> {
+    browser: Browser,
+    config: &'a Settings,
+}
+
+impl<'a> BatchSession<'a> {
+    /// Start a session in the given directory
+    pub fn new(start_dir: PathBuf, config: &'a Settings) -> Result<Self> {
+        Ok(Self {
+            browser: Browser::new_with_error_log(start_dir, config, None)?,
+            config,
+        })
+    }
+
+    /// Get the browser driven by this session
+    pub fn browser(&self) -> &Browser {
+        &self.browser
+    }
+
+    /// The paths commands operate on: marked entries, or else the selected entry
+    pub fn targets(&self) -> Vec<PathBuf> {
+        let column = self.browser.active_column();
+        let marked = column.marked_paths();
+        if !marked.is_empty() {
+            return marked;
+        }
+        column
+            .selected_entry()
+            .map(|entry| vec![entry.path()])
+            .unwrap_or_default()
+    }
+
+    /// Run a single command, writing any output
+    pub fn execute(&mut self, command: &BatchCommand, output: &mut impl Write) -> Result<()> {
+        let current_dir = self.browser.active_column().path.clone();
+
+        match command {
+            BatchCommand::CopyTo(_) | BatchCommand::MoveTo(_) | BatchCommand::Delete if self.config.read_only => {
+                return Err(eyre!("Can't change files with --read-only"));
+            }
+            BatchCommand::Cd(path) => {
+                let target = fs::canonicalize(resolve_path(path, &current_dir))
+                    .map_err(|e| eyre!("Cannot cd to {}: {}", path.display(), e))?;
+                self.browser.navigate_to(target, self.config)?;
+            }
+            BatchCommand::Select(name) => {
+                if !self.browser.select_by_name(OsStr::new(name), self.config) {
+                    return Err(eyre!("No entry named '{}' in {}", name, current_dir.display()));
+                }
+            }
+            BatchCommand::Mark(pattern) => {
+                let column = self.browser.active_column_mut();
+                let matches: Vec<OsString> = column.entries.iter()
+                    .map(|entry| entry.file_name())
+                    .filter(|name| glob_match(pattern, &name.to_string_lossy()))
+                    .collect();
+                if matches.is_empty() {
+                    return Err(eyre!("Nothing matches '{}' in {}", pattern, current_dir.display()));
+                }
+                column.marked.extend(matches);
+            }
+            BatchCommand::CopyTo(dest) => {
+                let dest_dir = resolve_path(dest, &current_dir);
+                for source in self.require_targets()? {
+                    copy_into(&source, &dest_dir)
+                        .map_err(|e| eyre!("Failed to copy {}: {}", source.display(), e))?;
+                }
+                self.browser.reload_all_columns(self.config)?;
+            }
+            BatchCommand::MoveTo(dest) => {
+                let dest_dir = resolve_path(dest, &current_dir);
+                for source in self.require_targets()? {
+                    move_into(&source, &dest_dir)
+                        .map_err(|e| eyre!("Failed to move {}: {}", source.display(), e))?;
+                }
+                self.browser.active_column_mut().clear_marks();
+                self.browser.reload_all_columns(self.config)?;
+            }
+            BatchCommand::Delete => {
+                for path in self.require_targets()? {
+                    delete_path(&path)
+                        .map_err(|e| eyre!("Failed to delete {}: {}", path.display(), e))?;
+                }
+                self.browser.active_column_mut().clear_marks();
+                self.browser.reload_all_columns(self.config)?;
+            }
+            BatchCommand::PrintSelection => {
+                for path in self.targets() {
+                    writeln!(output, "{}", path.display())?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn require_targets(&self) -> Result<Vec<PathBuf>> {
+        let targets = self.targets();
+        if targets.is_empty() {
+            return Err(eyre!("Nothing is selected or marked"));
+        }
+        Ok(targets)
+    }
+}
+
+/// Run commands from `input` until it ends or a command fails
+pub fn run_batch(input: impl BufRead, mut output: impl Write, start_dir: PathBuf, config: &Settings) -> Result<()> {
+    let mut session = BatchSession::new(start_dir, config)?;
+
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+        let line_number = index + 1;
+
+        let command = BatchCommand::parse(&line)
+            .map_err(|e| eyre!("line {}: {}", line_number, e))?;
+
+        if let Some(command) = command {
+            session.execute(&command, &mut output)
+                .map_err(|e| eyre!("line {}: {}", line_number, e))?;
+        }
+    }
+
+    output.flush()?;
+    Ok(())
+}
+
+/// A path from a command, relative to `base` or starting from the home directory with `~`
+fn resolve_path(path: &Path, base: &Path) -> PathBuf {
+    match path.to_str() {
+        Some(path) => resolve_user_path(path, base),
+        None => base.join(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(BatchCommand::parse("  "), Ok(None));
+        assert_eq!(BatchCommand::parse("# comment"), Ok(None));
+        assert_eq!(BatchCommand::parse("cd My Documents"), Ok(Some(BatchCommand::Cd(PathBuf::from("My Documents")))));
+        assert_eq!(BatchCommand::parse("mark *.txt"), Ok(Some(BatchCommand::Mark("*.txt".to_string()))));
+        assert_eq!(BatchCommand::parse("copy to ../out"), Ok(Some(BatchCommand::CopyTo(PathBuf::from("../out")))));
+        assert_eq!(BatchCommand::parse("print selection"), Ok(Some(BatchCommand::PrintSelection)));
+        assert_eq!(BatchCommand::parse("delete"), Ok(Some(BatchCommand::Delete)));
+        assert!(BatchCommand::parse("cd").is_err());
+        assert_eq!(BatchCommand::parse("move to /tmp"), Ok(Some(BatchCommand::MoveTo(PathBuf::from("/tmp")))));
+        assert!(BatchCommand::parse("copy to").is_err());
+        assert!(BatchCommand::parse("frobnicate").is_err());
+    }
+
+    #[test]
+    fn test_run_batch_copy_and_delete() {
+        let dir = std::env::temp_dir().join(format!("browse-batch-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("out")).unwrap();
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        fs::write(dir.join("b.txt"), "b").unwrap();
+        fs::write(dir.join("c.md"), "c").unwrap();
+
+        let script = "mark *.txt\ncopy to out\nprint selection\ndelete\ncd out\nselect b.txt\nprint\n";
+        let mut output = Vec::new();
+        run_batch(script.as_bytes(), &mut output, dir.clone(), &Settings::default()).unwrap();
+
+        let dir = fs::canonicalize(&dir).unwrap();
+        let expected = format!(
+            "{}\n{}\n{}\n",
+            dir.join("a.txt").display(),
+            dir.join("b.txt").display(),
+            dir.join("out").join("b.txt").display(),
+        );
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+        assert!(dir.join("out").join("a.txt").exists());
+        assert!(!dir.join("a.txt").exists());
+        assert!(dir.join("c.md").exists());
+
+        let error = run_batch("select missing\n".as_bytes(), Vec::new(), dir.clone(), &Settings::default());
+        assert!(error.unwrap_err().to_string().starts_with("line 1:"));
+
+        let read_only = Settings { read_only: true, ..Settings::default() };
+        let error = run_batch("select c.md\ndelete\n".as_bytes(), Vec::new(), dir.clone(), &read_only);
+        assert!(error.unwrap_err().to_string().contains("--read-only"));
+        assert!(dir.join("c.md").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cd_to_the_home_directory() {
+        let Some(home) = dirs::home_dir().and_then(|home| fs::canonicalize(home).ok()) else {
+            return;
+        };
+        let config = Settings::default();
+        let mut session = BatchSession::new(std::env::temp_dir(), &config).unwrap();
+        session.execute(&BatchCommand::Cd(PathBuf::from("~")), &mut Vec::new()).unwrap();
+        assert_eq!(session.browser().active_column().path, home);
+        session.execute(&BatchCommand::Cd(PathBuf::from("/")), &mut Vec::new()).unwrap();
+        session.execute(&BatchCommand::Cd(PathBuf::from("~/")), &mut Vec::new()).unwrap();
+        assert_eq!(session.browser().active_column().path, home);
+    }
+}
@@ -0,0 +1,249 @@
+//! Directory bookmarks
+//!
+//! `m` followed by a letter or digit bookmarks the current directory under that key,
+//! and `'` followed by the key jumps back to it. Bookmarks are kept in the settings
+//! file and can be listed and deleted in the bookmark manager.
+//!
+//! The manager also exports the bookmarks to a file and merges them in from one,
+//! so a set can travel with the dotfiles. The file holds a line per bookmark, its
+//! key then its path, or the same map as the settings file when it ends in
+//! `.json`. Paths under the home directory are written with `~`, so they still
+//! fit on a machine where home is elsewhere.
+
+use crate::ui::centered_fixed_rect;
+use crate::utils::{home_relative, resolve_user_path};
+use crossterm::event::{KeyCode, KeyEvent};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use ratatui::{
+    prelude::*,
+    widgets::*,
+};
+
+/// File the export and import prompts start with
+pub const BOOKMARKS_FILE: &str = "~/browse-bookmarks.txt";
+
+/// Check whether a character can name a bookmark
+pub fn is_bookmark_key(c: char) -> bool {
+    c.is_ascii_alphanumeric()
+}
+
+/// What the next key names a bookmark for, after `m` or `'`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BookmarkPrompt {
+    Set,
+    Jump,
+}
+
+/// Result of a key press in the bookmark manager
+#[derive(Debug, PartialEq)]
+pub enum BookmarkOutcome {
+    Continue,
+    Close,
+    /// Close the manager and go to this directory
+    Jump(PathBuf),
+    /// Remove the bookmark with this key
+    Delete(char),
+    /// Close the manager and ask where to export the bookmarks to
+    Export,
+    /// Close the manager and ask which file to import bookmarks from
+    Import,
+}
+
+/// Popup listing the bookmarks
+#[derive(Debug)]
+pub struct BookmarkManager {
+    pub items: Vec<(char, PathBuf)>,
+    pub state: ListState,
+}
+
+impl BookmarkManager {
+    pub fn new(bookmarks: &BTreeMap<char, PathBuf>) -> Self {
+        let items: Vec<(char, PathBuf)> = bookmarks.iter().map(|(&key, path)| (key, path.clone())).collect();
+        let mut state = ListState::default();
+        if !items.is_empty() {
+            state.select(Some(0));
+        }
+        Self { items, state }
+    }
+
+    /// Handle bookmark manager key input
+    pub fn handle_key(&mut self, key: KeyEvent) -> BookmarkOutcome {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return BookmarkOutcome::Close,
+            KeyCode::Enter => {
+                return match self.state.selected().and_then(|i| self.items.get(i)) {
+                    Some((_, path)) => BookmarkOutcome::Jump(path.clone()),
+                    None => BookmarkOutcome::Continue,
+                };
+            }
+            KeyCode::Delete | KeyCode::Char('d') => {
+                if let Some(index) = self.state.selected().filter(|&i| i < self.items.len()) {
+                    let (key, _) = self.items.remove(index);
+                    self.state.select(if self.items.is_empty() { None } else { Some(index.min(self.items.len() - 1)) });
+                    return BookmarkOutcome::Delete(key);
+                }
+            }
+            KeyCode::Char('e') => return BookmarkOutcome::Export,
+            KeyCode::Char('i') => return BookmarkOutcome::Import,
+            KeyCode::Up => self.state.select_previous(),
+            KeyCode::Down => self.state.select_next(),
+            _ => {}
+        }
+
+        // ListState doesn't know the list length, so keep the selection on a bookmark
+        if let Some(selected) = self.state.selected() {
+            self.state.select(Some(selected.min(self.items.len().saturating_sub(1))));
+        }
+        BookmarkOutcome::Continue
+    }
+}
+
+/// What merging in a bookmarks file did
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportSummary {
+    pub added: usize,
+    /// Keys already bookmarked elsewhere, which keep their directory
+    pub kept: Vec<char>,
+}
+
+/// Write the bookmarks to a file, as JSON if its name ends in `.json`
+pub fn export_bookmarks(bookmarks: &BTreeMap<char, PathBuf>, path: &Path) -> io::Result<()> {
+    let is_json = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    let contents = if is_json {
+        let map: BTreeMap<String, String> =
+            bookmarks.iter().map(|(key, dir)| (key.to_string(), home_relative(dir))).collect();
+        serde_json::to_string_pretty(&map)? + "\n"
+    } else {
+        bookmarks.iter().map(|(key, dir)| format!("{} {}\n", key, home_relative(dir))).collect()
+    };
+    fs::write(path, contents)
+}
+
+/// Read the bookmarks in a file written by `export_bookmarks`
+///
+/// Blank lines and lines starting with `#` are skipped in the plain format.
+pub fn read_bookmarks(path: &Path) -> io::Result<BTreeMap<char, PathBuf>> {
+    let contents = fs::read_to_string(path)?;
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let entries: Vec<(String, String)> = if contents.trim_start().starts_with('{') {
+        let map: BTreeMap<String, String> = serde_json::from_str(&contents)?;
+        map.into_iter().collect()
+    } else {
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+            .map(|(number, line)| match line.trim().split_once(char::is_whitespace) {
+                Some((key, dir)) => Ok((key.to_string(), dir.trim().to_string())),
+                None => Err(invalid(format!("line {}: expected a key and a path", number + 1))),
+            })
+            .collect::<io::Result<_>>()?
+    };
+
+    entries
+        .into_iter()
+        .map(|(key, dir)| {
+            let mut chars = key.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if is_bookmark_key(c) => Ok((c, resolve_user_path(&dir, Path::new("/")))),
+                _ => Err(invalid(format!("'{}' is not a bookmark key (a letter or digit)", key))),
+            }
+        })
+        .collect()
+}
+
+/// Add the bookmarks in a file to `bookmarks`, leaving the ones already set alone
+pub fn import_bookmarks(bookmarks: &mut BTreeMap<char, PathBuf>, path: &Path) -> io::Result<ImportSummary> {
+    let mut summary = ImportSummary::default();
+    for (key, dir) in read_bookmarks(path)? {
+        match bookmarks.get(&key) {
+            Some(existing) if *existing == dir => {}
+            Some(_) => summary.kept.push(key),
+            None => {
+                bookmarks.insert(key, dir);
+                summary.added += 1;
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// Render the bookmark manager popup
+pub fn render_bookmark_manager(frame: &mut Frame, manager: &BookmarkManager) {
+    let path_width = manager.items.iter().map(|(_, path)| path.to_string_lossy().chars().count()).max().unwrap_or(0);
+    let width = (path_width + 10).clamp(72, 100) as u16;
+    let height = manager.items.len().max(1) as u16 + 4;
+    let area = centered_fixed_rect(width, height, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Bookmarks ")
+        .title_bottom(Line::from(" Enter to go, d to delete, e to export, i to import, Esc to close ").centered())
+        .border_style(Style::default().fg(Color::Cyan))
+        .padding(Padding::uniform(1));
+
+    if manager.items.is_empty() {
+        let empty = Paragraph::new("No bookmarks; press m and a letter to add one, or i to import some")
+            .style(Style::default().fg(Color::DarkGray))
+            .block(block);
+        frame.render_widget(empty, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = manager
+        .items
+        .iter()
+        .map(|(key, path)| {
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{}  ", key), Style::default().fg(Color::Yellow)),
+                Span::raw(path.to_string_lossy().to_string()),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut state = manager.state.clone();
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bookmark_files_round_trip() {
+        let dir = std::env::temp_dir().join(format!("browse-bookmark-files-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let bookmarks = BTreeMap::from([('a', PathBuf::from("/srv/a b")), ('7', PathBuf::from("/tmp"))]);
+        if let Some(home) = dirs::home_dir() {
+            let mut with_home = bookmarks.clone();
+            with_home.insert('h', home.join("notes"));
+            export_bookmarks(&with_home, &dir.join("home.txt")).unwrap();
+            assert!(fs::read_to_string(dir.join("home.txt")).unwrap().contains("h ~/notes\n"));
+            assert_eq!(read_bookmarks(&dir.join("home.txt")).unwrap(), with_home);
+        }
+
+        for name in ["bookmarks.txt", "bookmarks.JSON"] {
+            export_bookmarks(&bookmarks, &dir.join(name)).unwrap();
+            assert_eq!(read_bookmarks(&dir.join(name)).unwrap(), bookmarks);
+        }
+        assert!(fs::read_to_string(dir.join("bookmarks.JSON")).unwrap().starts_with('{'));
+
+        fs::write(dir.join("comments.txt"), "# shared\n\n  x   /srv/x  \n").unwrap();
+        assert_eq!(read_bookmarks(&dir.join("comments.txt")).unwrap(), BTreeMap::from([('x', PathBuf::from("/srv/x"))]));
+        fs::write(dir.join("bad.txt"), "ab /srv\n").unwrap();
+        assert_eq!(read_bookmarks(&dir.join("bad.txt")).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        fs::write(dir.join("bad.txt"), "a\n").unwrap();
+        assert!(read_bookmarks(&dir.join("bad.txt")).unwrap_err().to_string().contains("line 1"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,17 +1,29 @@
 use crate::app::{App, Preview};
-use crate::config::{Settings, SEARCH_TIMEOUT_SECONDS, MAX_COLUMNS_DISPLAY};
+use crate::config::{Settings, WidthAdjustment, SEARCH_TIMEOUT_SECONDS, MAX_COLUMNS_DISPLAY, MAX_HISTORY};
 use crate::settings::render_settings_panel;
-use crate::utils::{truncate_text};
-use crate::file_operations::{get_icon_with_error_log, read_directory_with_error_log, is_safe_path, FileDetails};
-use crate::file_preview::render_file_preview;
+use crate::utils::{format_date_compact, format_file_size, format_permissions, pad_text, scroll_offset, search_key, text_width, SCROLL_MARGIN, truncate_text, user_name};
+use crate::file_operations::{entry_icon, is_sniffable, read_directory_with_error_log, is_safe_path, FileDetails, PreviewContent};
+use crate::file_preview::{content_text, render_file_preview, scroll_to_cursor};
+use crate::dir_sizes::{allocated_size, DirSize};
+use crate::entry_counts::EntryCount;
 use crate::error::ErrorLog;
+use crate::git::{dir_status, DirGitStatus, GitStatus};
+use crate::input::{render_text_input, TextInput};
+use crate::mime_types::MimeTypes;
+use crate::outline::OutlineMove;
+use crate::trace;
+use crate::tree::TreeView;
 use color_eyre::Result;
-use std::collections::{HashMap, VecDeque};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::ffi::{OsStr, OsString};
 use std::fs::DirEntry;
 use std::io;
-use std::path::PathBuf;
-use std::time::Instant;
-use std::cmp;
+use std::path::{Path, PathBuf};
+use std::ops::Range;
+use std::sync::Arc;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::time::{Instant, SystemTime};
 
 use ratatui::{
     prelude::*,
@@ -25,6 +37,42 @@ pub struct DirColumn {
     pub path: PathBuf,
     pub entries: Vec<DirEntry>,
     pub selected: ListState,
+    /// Names of marked entries; kept by name so marks survive reloads
+    pub marked: BTreeSet<OsString>,
+    /// Start of a range being marked with `v`, as an entry index
+    pub visual_anchor: Option<usize>,
+    /// New name being typed for the selected entry, while renaming
+    pub rename_input: Option<TextInput>,
+    /// Only entries whose names contain this are listed; empty lists everything
+    pub filter: String,
+    /// Filter being typed, while in filter mode
+    pub filter_input: Option<TextInput>,
+    /// Number of entries in the directory before filtering
+    pub unfiltered_len: usize,
+    /// Only the first entries of a large directory are listed
+    pub truncated: bool,
+    /// Git status of the entries, when the directory is in a worktree
+    pub git: Option<Arc<DirGitStatus>>,
+    /// The directory didn't answer within the read timeout, so nothing is listed until it is reloaded
+    pub slow: bool,
+    /// Metadata shown in the detail view, by entry name, read as entries are first drawn
+    details: RefCell<HashMap<OsString, EntryDetails>>,
+    /// Icons by entry name, worked out as entries are first drawn
+    icons: RefCell<HashMap<OsString, String>>,
+    /// Rows the column showed when last drawn, which selection changes scroll by
+    view_height: Cell<usize>,
+}
+
+/// What the detail view shows about an entry besides its name
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryDetails {
+    pub size: u64,
+    /// Space taken on disk
+    pub allocated: u64,
+    pub is_dir: bool,
+    pub modified: Option<SystemTime>,
+    pub mode: u32,
+    pub owner: String,
 }
 
 impl DirColumn {
@@ -33,13 +81,34 @@ impl DirColumn {
         Self::new_with_error_log(path, initial_selection, config, None)
     }
 
-    pub fn scroll(&mut self, direction: ScrollDirection, view_height: usize) {
-        // TODO: ListState might not let us scroll such that the selected item becomes invisible.
-        // Not sure how that's working, but it leads to some unexpected scrolling behavior.
-        match direction {
-            ScrollDirection::Backward => *self.selected.offset_mut() = cmp::max(0, self.selected.offset().saturating_sub(1)),
-            ScrollDirection::Forward => *self.selected.offset_mut() = cmp::min(self.entries.len().saturating_sub(view_height), self.selected.offset().saturating_add(1)),
+    /// Scroll the view by a row, returning whether the selection moved
+    ///
+    /// The list always keeps the selection in view, with the scroll margin around it.
+    /// With `carry_selection` the selection is carried along once it reaches the
+    /// margin; without it the view stops there.
+    pub fn scroll(&mut self, direction: ScrollDirection, carry_selection: bool) -> bool {
+        let view_height = self.view_height.get();
+        let offset = self.visible_offset(view_height);
+        let scrolled = match direction {
+            ScrollDirection::Backward => offset.saturating_sub(1),
+            ScrollDirection::Forward => (offset + 1).min(self.entries.len().saturating_sub(view_height)),
+        };
+        let Some(selected) = self.selected.selected() else {
+            *self.selected.offset_mut() = scrolled;
+            return false;
+        };
+        if !carry_selection {
+            *self.selected.offset_mut() = scroll_offset(scrolled, Some(selected), self.entries.len(), view_height);
+            return false;
         }
+        let offset = scrolled;
+        *self.selected.offset_mut() = offset;
+        let margin = SCROLL_MARGIN.min(view_height.saturating_sub(1) / 2);
+        let top = if offset == 0 { 0 } else { offset + margin };
+        let bottom = if offset + view_height >= self.entries.len() { self.entries.len() } else { offset + view_height - margin };
+        let moved = selected.clamp(top, bottom.saturating_sub(1).max(top));
+        self.selected.select(Some(moved));
+        moved != selected
     }
 
     /// Create a new directory column with error logging
@@ -55,17 +124,36 @@ impl DirColumn {
             ));
         }
 
-        let entries = read_directory_with_error_log(&path, config, error_log)?;
+        // A directory that doesn't answer still gets a column, which says so and can be reloaded
+        let (entries, total, slow) = match read_directory_with_error_log(&path, config, error_log) {
+            Ok((entries, total)) => (entries, total, false),
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => (Vec::new(), 0, true),
+            Err(e) => return Err(e),
+        };
         let mut selected = ListState::default();
 
         if !entries.is_empty() {
             selected.select(Some(initial_selection.min(entries.len() - 1)));
         }
 
+        let git = if config.git_status { dir_status(&path, false) } else { None };
+
         Ok(Self {
             path,
+            git,
+            unfiltered_len: total,
+            truncated: entries.len() < total,
             entries,
             selected,
+            marked: BTreeSet::new(),
+            visual_anchor: None,
+            rename_input: None,
+            filter: String::new(),
+            filter_input: None,
+            slow,
+            details: RefCell::new(HashMap::new()),
+            icons: RefCell::new(HashMap::new()),
+            view_height: Cell::new(0),
         })
     }
 
@@ -75,25 +163,214 @@ impl DirColumn {
     }
 
     /// Reload the directory contents with error logging
+    ///
+    /// The selection stays on the same file name, falling back to clamping the old
+    /// index when that entry is gone. The scroll offset is kept so the view doesn't jump.
+    /// The column's filter is applied to the fresh listing.
     pub fn reload_with_error_log(&mut self, config: &Settings, error_log: Option<&mut ErrorLog>) -> io::Result<()> {
-        self.entries = read_directory_with_error_log(&self.path, config, error_log)?;
-
-        // Adjust selection if it's out of bounds
-        if let Some(current_selection) = self.selected.selected() {
-            if current_selection >= self.entries.len() {
-                let new_selection = self.entries.len().saturating_sub(1);
-                self.selected.select(if self.entries.is_empty() { None } else { Some(new_selection) });
+        let selected_name = self.selected_entry().map(|entry| entry.file_name());
+        let view_height = self.view_height.get();
+        let offset = self.visible_offset(view_height);
+
+        let (mut entries, total) = match read_directory_with_error_log(&self.path, config, error_log) {
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => {
+                self.slow = true;
+                self.entries.clear();
+                self.selected.select(None);
+                return Err(e);
             }
+            result => result?,
+        };
+        self.slow = false;
+
+        // Forget marks on entries that no longer exist; filtered out entries keep theirs
+        let names: BTreeSet<OsString> = entries.iter().map(|entry| entry.file_name()).collect();
+        self.marked.retain(|name| names.contains(name));
+
+        self.unfiltered_len = total;
+        self.truncated = entries.len() < total;
+        if !self.filter.is_empty() {
+            let filter = search_key(&self.filter, config);
+            entries.retain(|entry| search_key(&entry.file_name().to_string_lossy(), config).contains(&filter));
         }
+        self.entries = entries;
+
+        let found = selected_name.and_then(|name| self.position_by_name(&name));
+        let selection = found.or_else(|| {
+            let current_selection = self.selected.selected()?;
+            (!self.entries.is_empty()).then(|| current_selection.min(self.entries.len() - 1))
+        });
+        self.selected.select(selection);
+        *self.selected.offset_mut() = scroll_offset(offset, selection, self.entries.len(), view_height);
+
+        self.visual_anchor = self.visual_anchor.filter(|&anchor| anchor < self.entries.len());
+        self.git = if config.git_status { dir_status(&self.path, true) } else { None };
+        self.details.borrow_mut().clear();
+        self.icons.borrow_mut().clear();
 
         Ok(())
     }
 
+    /// Metadata of an entry for the detail view
+    ///
+    /// Read on first use and kept until the next reload, so scrolling doesn't read it
+    /// again. Symlinks are described themselves rather than their targets, like `ls -l`.
+    pub fn entry_details(&self, entry: &DirEntry) -> Option<EntryDetails> {
+        let name = entry.file_name();
+        if let Some(details) = self.details.borrow().get(&name) {
+            return Some(details.clone());
+        }
+        let metadata = entry.metadata().ok()?;
+        let details = EntryDetails {
+            size: metadata.len(),
+            allocated: allocated_size(&metadata),
+            is_dir: metadata.is_dir(),
+            modified: metadata.modified().ok(),
+            mode: metadata.permissions().mode(),
+            owner: user_name(metadata.uid()),
+        };
+        self.details.borrow_mut().insert(name, details.clone());
+        Some(details)
+    }
+
+    /// Icon of an entry, empty when icons are turned off
+    ///
+    /// Worked out on first use and kept until the next reload, like the entry's
+    /// details. A file whose contents are still being sniffed goes by its name
+    /// meanwhile, and its icon is kept only once its type is in `mime_types`.
+    pub fn entry_icon(&self, entry: &DirEntry, config: &Settings, mime_types: Option<&MimeTypes>) -> String {
+        if !config.show_icons {
+            return String::new();
+        }
+        if let Some(icon) = self.icons.borrow().get(&entry.file_name()) {
+            return icon.clone();
+        }
+        let sniffed = match mime_types.filter(|_| is_sniffable(entry, config)) {
+            Some(mime_types) => match mime_types.get(&entry.path()) {
+                Some(mime_type) => mime_type,
+                None => return entry_icon(entry, config, None),
+            },
+            None => None,
+        };
+        let icon = entry_icon(entry, config, sniffed);
+        self.icons.borrow_mut().insert(entry.file_name(), icon.clone());
+        icon
+    }
+
+    /// Compute the first visible row for a view of the given height
+    ///
+    /// Starts from the stored offset and shifts only as far as needed to keep the
+    /// selection in view, with the scroll margin around it.
+    pub fn visible_offset(&self, view_height: usize) -> usize {
+        scroll_offset(self.selected.offset(), self.selected.selected(), self.entries.len(), view_height)
+    }
+
+    /// Rows the column showed when last drawn, 0 before it is drawn
+    pub fn view_height(&self) -> usize {
+        self.view_height.get()
+    }
+
+    /// Remember the rows the column showed as it is drawn
+    pub fn set_view_height(&self, view_height: usize) {
+        self.view_height.set(view_height);
+    }
+
+    /// Select an entry, scrolling from where the view was last drawn to keep it in view
+    ///
+    /// Every change of the selection goes through here, so a jump lands with the
+    /// scroll margin around the selection and the stored offset is the one drawn.
+    pub fn select(&mut self, index: Option<usize>) {
+        let view_height = self.view_height.get();
+        let offset = self.visible_offset(view_height);
+        self.selected.select(index);
+        *self.selected.offset_mut() = scroll_offset(offset, index, self.entries.len(), view_height);
+    }
+
+    /// Select the entry with the given file name, returning false if there is none
+    ///
+    /// Failing an exact match, an entry whose name reads as `name` once bytes that
+    /// aren't UTF-8 become `�` is selected, as names saved as text read.
+    pub fn select_by_name(&mut self, name: &OsStr) -> bool {
+        match self.position_by_name(name) {
+            Some(index) => {
+                self.select(Some(index));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn position_by_name(&self, name: &OsStr) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.file_name() == name).or_else(|| {
+            let name = name.to_string_lossy();
+            self.entries.iter().position(|entry| entry.file_name().to_string_lossy() == name)
+        })
+    }
+
     /// Get the currently selected entry
     pub fn selected_entry(&self) -> Option<&DirEntry> {
         self.selected.selected().and_then(|i| self.entries.get(i))
     }
 
+    /// Check whether an entry is marked
+    pub fn is_marked(&self, entry: &DirEntry) -> bool {
+        self.marked.contains(&entry.file_name())
+    }
+
+    /// Toggle the mark on the selected entry and move to the next one
+    pub fn toggle_mark(&mut self) {
+        let Some(name) = self.selected_entry().map(|entry| entry.file_name()) else {
+            return;
+        };
+        if !self.marked.remove(&name) {
+            self.marked.insert(name);
+        }
+        if let Some(index) = self.selected.selected()
+            && index + 1 < self.entries.len()
+        {
+            self.select(Some(index + 1));
+        }
+    }
+
+    /// Start a visual range at the selection, or mark the range if one is started
+    pub fn toggle_visual(&mut self) {
+        match self.visual_range() {
+            Some(range) => {
+                self.mark_range(range);
+                self.visual_anchor = None;
+            }
+            None => self.visual_anchor = self.selected.selected(),
+        }
+    }
+
+    /// Mark the entries with indices in `range`
+    pub fn mark_range(&mut self, range: std::ops::RangeInclusive<usize>) {
+        let names: Vec<OsString> = self.entries.get(range).unwrap_or_default().iter().map(|entry| entry.file_name()).collect();
+        self.marked.extend(names);
+    }
+
+    /// Entry indices covered by the visual range, from the anchor to the selection
+    pub fn visual_range(&self) -> Option<std::ops::RangeInclusive<usize>> {
+        let anchor = self.visual_anchor?;
+        let selected = self.selected.selected()?;
+        Some(anchor.min(selected)..=anchor.max(selected).min(self.entries.len().saturating_sub(1)))
+    }
+
+    /// Paths of the marked entries, in listing order
+    pub fn marked_paths(&self) -> Vec<PathBuf> {
+        self.entries
+            .iter()
+            .filter(|entry| self.is_marked(entry))
+            .map(|entry| entry.path())
+            .collect()
+    }
+
+    /// Remove all marks and cancel any visual range
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+        self.visual_anchor = None;
+    }
+
     /// Navigate to previous item
     pub fn select_previous(&mut self) {
         let new_index = match self.selected.selected() {
@@ -101,7 +378,7 @@ impl DirColumn {
             Some(_) => self.entries.len().saturating_sub(1),
             None => 0,
         };
-        self.selected.select(if self.entries.is_empty() { None } else { Some(new_index) });
+        self.select(if self.entries.is_empty() { None } else { Some(new_index) });
     }
 
     /// Navigate to next item
@@ -111,7 +388,7 @@ impl DirColumn {
             Some(_) => 0,
             None => 0,
         };
-        self.selected.select(if self.entries.is_empty() { None } else { Some(new_index) });
+        self.select(if self.entries.is_empty() { None } else { Some(new_index) });
     }
 }
 
@@ -120,9 +397,22 @@ impl DirColumn {
 pub struct Browser {
     columns: VecDeque<DirColumn>,
     preview: Option<Preview>,
-    selection_cache: HashMap<PathBuf, usize>,
+    /// File name last selected in each visited directory
+    selection_cache: HashMap<PathBuf, OsString>,
     search_string: String,
     last_key_time: Instant,
+    /// Number of columns the view is shifted left of the active column
+    column_scroll: usize,
+    /// Directory the browser may not leave, while the tab is locked to it
+    root_lock: Option<PathBuf>,
+    /// Directories visited, least recent first and ending with the active one
+    history: Vec<PathBuf>,
+    /// Tree shown instead of the columns, while in tree view
+    tree: Option<TreeView>,
+    /// The tree's selection moved, so the columns should follow it rather than the other way around
+    tree_moved: bool,
+    /// The previewed file's end is shown and read again as it changes, like `tail -f`
+    following: bool,
 }
 
 impl Browser {
@@ -141,27 +431,273 @@ impl Browser {
             selection_cache: HashMap::new(),
             search_string: String::new(),
             last_key_time: Instant::now(),
+            column_scroll: 0,
+            root_lock: None,
+            history: Vec::new(),
+            tree: None,
+            tree_moved: false,
+            following: false,
         };
 
         _ = browser.update_preview(config);
         Ok(browser)
     }
 
+    /// Tree shown instead of the columns, while in tree view
+    pub fn tree(&self) -> Option<&TreeView> {
+        self.tree.as_ref()
+    }
+
+    /// Switch between the columns and a tree of the first column's directory
+    ///
+    /// The tree opens with the directories down to the selection expanded, and the
+    /// columns come back showing the tree's selection.
+    pub fn toggle_tree(&mut self, config: &Settings) {
+        self.tree = match self.tree.take() {
+            Some(_) => None,
+            None => Some(TreeView::new(self.columns[0].path.clone())),
+        };
+        self.tree_moved = false;
+        _ = self.update_preview(config);
+    }
+
+    /// Select a row of the tree, as a click does
+    pub fn select_tree_row(&mut self, index: usize, config: &Settings) {
+        if let Some(tree) = &mut self.tree
+            && index < tree.rows().len()
+        {
+            tree.select(index);
+            self.tree_moved = true;
+            _ = self.update_preview(config);
+        }
+    }
+
+    /// Bring the tree and the columns back in step
+    ///
+    /// After a move in the tree, the columns are opened down to its selection.
+    /// Otherwise the tree follows the columns: it is rooted at the first column,
+    /// the directories of the others are expanded, and the active selection is selected.
+    fn sync_tree(&mut self, config: &Settings) {
+        if self.tree.is_none() {
+            return;
+        }
+        if std::mem::take(&mut self.tree_moved) {
+            if let Some(path) = self.tree.as_ref().and_then(TreeView::selected_path) {
+                self.show_columns_to(&path, config);
+            }
+            return;
+        }
+
+        let target = self.active_column().selected_entry().map(|entry| entry.path())
+            .unwrap_or_else(|| self.active_column().path.clone());
+        let Some(tree) = &mut self.tree else {
+            return;
+        };
+        tree.set_root(self.columns[0].path.clone());
+        for column in self.columns.iter().skip(1) {
+            tree.expand(column.path.clone());
+        }
+        tree.refresh(config);
+        tree.select_path(&target);
+    }
+
+    /// Open columns down to `path` and select it, leaving the preview alone
+    fn show_columns_to(&mut self, path: &Path, config: &Settings) {
+        let keep = self.columns.iter().rposition(|column| path.starts_with(&column.path) && path != column.path).unwrap_or(0);
+        self.cache_active_selection();
+        self.columns.truncate(keep + 1);
+        let Ok(relative) = path.strip_prefix(&self.columns[keep].path) else {
+            return;
+        };
+        let mut components = relative.components().peekable();
+        while let Some(component) = components.next() {
+            if !self.active_column_mut().select_by_name(component.as_os_str()) {
+                break;
+            }
+            if components.peek().is_some() {
+                let dir = self.active_column().path.join(component);
+                match self.column_with_cached_selection(dir, config) {
+                    Ok(column) => self.columns.push_back(column),
+                    Err(_) => break,
+                }
+            }
+        }
+    }
+
+    /// Go into the selected directory of the tree: expand it, or select its first entry
+    fn tree_right(&mut self, config: &Settings) {
+        let Some(tree) = &mut self.tree else {
+            return;
+        };
+        let Some(row) = tree.selected_row().filter(|row| row.is_dir) else {
+            return;
+        };
+        let path = row.entry.path();
+        if tree.is_expanded(&path) {
+            let next = tree.selected.selected().unwrap_or(0) + 1;
+            if tree.rows().get(next).is_some_and(|row| row.entry.path().parent() == Some(path.as_path())) {
+                tree.select(next);
+            }
+        } else {
+            tree.expand(path);
+            tree.refresh(config);
+        }
+        self.tree_moved = true;
+        _ = self.update_preview(config);
+    }
+
+    /// Go up in the tree: collapse the selected directory, select the parent, or show the root's parent
+    fn tree_left(&mut self, config: &Settings) -> Result<(), ()> {
+        let Some(tree) = &mut self.tree else {
+            return Ok(());
+        };
+        let selected = tree.selected_path();
+        if let Some(path) = selected.as_ref().filter(|path| tree.is_expanded(path)) {
+            tree.collapse(path);
+            tree.refresh(config);
+        } else if let Some(parent) = tree.parent_row() {
+            tree.select(parent);
+        } else {
+            let root = tree.root.clone();
+            let (Some(parent), Some(name)) = (root.parent(), root.file_name()) else {
+                return Ok(());
+            };
+            if self.root_lock.as_ref() == Some(&root) {
+                return Ok(());
+            }
+            // The old root stays open under its parent
+            tree.expand(root.clone());
+            self.navigate_to(parent.to_path_buf(), config).map_err(|_| ())?;
+            self.select_by_name(name, config);
+            return Ok(());
+        }
+        self.tree_moved = true;
+        self.update_preview(config)
+    }
+
+    /// Move the tree's selection and make the columns follow
+    fn tree_select(&mut self, config: &Settings, index: impl FnOnce(&TreeView) -> usize) {
+        if let Some(tree) = &mut self.tree {
+            let index = index(tree);
+            tree.select(index);
+            self.tree_moved = true;
+            _ = self.update_preview(config);
+        }
+    }
+
     /// Get reference to columns
     pub fn columns(&self) -> &VecDeque<DirColumn> {
         &self.columns
     }
 
+    /// Subdirectories listed in the visible columns and the previewed directory
+    ///
+    /// The active column's come first, then the preview's, then those of the
+    /// columns further left. Symlinks to directories are left out. In tree view
+    /// they are the directories listed in the tree.
+    pub fn listed_subdirectories(&self) -> Vec<PathBuf> {
+        if let Some(tree) = &self.tree {
+            return tree.rows().iter().filter(|row| row.is_dir).map(|row| row.entry.path()).collect();
+        }
+        let preview = match self.visible_preview() {
+            Some(Preview::Directory(column)) => Some(column),
+            _ => None,
+        };
+        let mut columns: Vec<&DirColumn> = self.columns.range(self.visible_columns()).rev().collect();
+        columns.splice(1..1, preview);
+
+        columns
+            .into_iter()
+            .flat_map(|column| &column.entries)
+            .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+            .map(|entry| entry.path())
+            .collect()
+    }
+
+    /// Files in view whose contents tell their type, for `MimeTypes` to sniff
+    ///
+    /// Only the rows each column showed when last drawn are listed, as a large
+    /// directory would take long to sniff in full.
+    pub fn listed_files(&self, config: &Settings) -> Vec<PathBuf> {
+        if self.tree.is_some() {
+            return Vec::new();
+        }
+        let preview = match self.visible_preview() {
+            Some(Preview::Directory(column)) => Some(column),
+            _ => None,
+        };
+        self.columns
+            .range(self.visible_columns())
+            .chain(preview)
+            .flat_map(|column| {
+                let view_height = column.view_height();
+                column.entries.iter().skip(column.visible_offset(view_height)).take(view_height)
+            })
+            .filter(|entry| is_sniffable(entry, config))
+            .map(|entry| entry.path())
+            .collect()
+    }
+
     /// Get mutable reference to columns
     pub fn columns_mut(&mut self) -> &mut VecDeque<DirColumn> {
         &mut self.columns
     }
 
+    /// Directory the browser is locked to, if any
+    pub fn root_lock(&self) -> Option<&Path> {
+        self.root_lock.as_deref()
+    }
+
+    /// Lock the browser to the active directory, or unlock it
+    ///
+    /// Locking drops the columns to the left, so the locked directory becomes the
+    /// first column and Left can't go above it.
+    pub fn toggle_root_lock(&mut self, config: &Settings) -> Result<()> {
+        if self.root_lock.take().is_none() {
+            self.set_anchor(config)?;
+            self.root_lock = Some(self.active_column().path.clone());
+        }
+        Ok(())
+    }
+
+    /// Check whether a path is inside the locked root, or there is no lock
+    pub fn is_within_root(&self, path: &Path) -> bool {
+        self.root_lock.as_ref().is_none_or(|root| path.starts_with(root))
+    }
+
+    /// Check whether Left is blocked because the only column is the locked root
+    pub fn at_root_lock(&self) -> bool {
+        self.columns.len() == 1 && self.root_lock.as_ref() == Some(&self.active_column().path)
+    }
+
     /// Get reference to preview
     pub fn preview(&self) -> &Option<Preview> {
         &self.preview
     }
 
+    /// Range of columns on screen
+    ///
+    /// At most `MAX_COLUMNS_DISPLAY` columns are shown, ending with the active column
+    /// unless the view has been scrolled left with `scroll_columns`.
+    pub fn visible_columns(&self) -> Range<usize> {
+        let end = self.columns.len() - self.column_scroll.min(self.columns.len() - 1);
+        end.saturating_sub(MAX_COLUMNS_DISPLAY)..end
+    }
+
+    /// Preview shown next to the columns, hidden while the view is scrolled left
+    pub fn visible_preview(&self) -> Option<&Preview> {
+        if self.column_scroll == 0 { self.preview.as_ref() } else { None }
+    }
+
+    /// Shift the visible columns toward the root (`Backward`) or the active column (`Forward`)
+    pub fn scroll_columns(&mut self, direction: ScrollDirection) {
+        let max_scroll = self.columns.len().saturating_sub(MAX_COLUMNS_DISPLAY);
+        self.column_scroll = match direction {
+            ScrollDirection::Backward => (self.column_scroll + 1).min(max_scroll),
+            ScrollDirection::Forward => self.column_scroll.saturating_sub(1),
+        };
+    }
+
     /// Get the search string
     pub fn search_string(&self) -> &str {
         &self.search_string
@@ -191,18 +727,36 @@ impl Browser {
         Ok(())
     }
 
+    /// Remember the selected file name of the active column for when it is revisited
+    fn cache_active_selection(&mut self) {
+        if let Some(column) = self.columns.back()
+            && let Some(entry) = column.selected_entry()
+        {
+            self.selection_cache.insert(column.path.clone(), entry.file_name());
+        }
+    }
+
+    /// Create a column for a directory, selecting the entry last selected there
+    fn column_with_cached_selection(&self, path: PathBuf, config: &Settings) -> io::Result<DirColumn> {
+        let cached_name = self.selection_cache.get(&path).cloned();
+        let mut column = DirColumn::new(path, 0, config)?;
+        if let Some(name) = cached_name {
+            column.select_by_name(&name);
+        }
+        Ok(column)
+    }
+
     /// Navigate left (parent directory)
     pub fn navigate_left(&mut self, config: &Settings) -> Result<(), ()> {
-        if self.columns.is_empty() {
+        if self.tree.is_some() {
+            return self.tree_left(config);
+        }
+        if self.columns.is_empty() || self.at_root_lock() {
             return Ok(());
         }
 
         // Cache current selection
-        if let Some(column) = self.columns.back() {
-            if let Some(selected_idx) = column.selected.selected() {
-                self.selection_cache.insert(column.path.clone(), selected_idx);
-            }
-        }
+        self.cache_active_selection();
 
         // If we have more than one column, just remove the rightmost
         if self.columns.len() > 1 {
@@ -238,25 +792,19 @@ impl Browser {
 
     /// Navigate right (enter directory)
     pub fn navigate_right(&mut self, config: &Settings) -> Result<(), ()> {
+        if self.tree.is_some() {
+            self.tree_right(config);
+            return Ok(());
+        }
         if let Some(entry) = self.active_column().selected_entry() {
             let path = entry.path();
 
             if path.is_dir() {
-                // Cache current selection
-                if let Some(selected_idx) = self.active_column().selected.selected() {
-                    self.selection_cache.insert(self.active_column().path.clone(), selected_idx);
-                }
-
-                let cached_selection = self.selection_cache.get(&path).copied().unwrap_or(0);
+                self.cache_active_selection();
 
                 // Try to create new column, but don't fail the whole operation if it fails
-                match DirColumn::new(path, cached_selection, config) {
+                match self.column_with_cached_selection(path, config) {
                     Ok(new_column) => {
-                        // Limit the number of columns displayed
-                        if self.columns.len() >= MAX_COLUMNS_DISPLAY {
-                            self.columns.pop_front();
-                        }
-
                         self.columns.push_back(new_column);
                         return self.update_preview(config);
                     }
@@ -270,6 +818,47 @@ impl Browser {
         Ok(())
     }
 
+    /// Replace all columns with a single column for the given directory
+    pub fn navigate_to(&mut self, path: PathBuf, config: &Settings) -> Result<()> {
+        if let Some(root) = self.root_lock.as_ref().filter(|root| !path.starts_with(root)) {
+            return Err(color_eyre::eyre::eyre!("{} is outside the tab's locked directory {}", path.display(), root.display()));
+        }
+        let column = self.column_with_cached_selection(path, config)?;
+
+        self.columns.clear();
+        self.columns.push_back(column);
+        _ = self.update_preview(config);
+        Ok(())
+    }
+
+    /// Show a path by opening a column for each directory down to it and selecting it
+    ///
+    /// Columns for the path's ancestors are kept. A path outside all of them starts
+    /// over from its parent. Stops early if an entry on the way isn't listed.
+    pub fn open_path(&mut self, path: &Path, config: &Settings) -> Result<()> {
+        let Some(keep) = self.columns.iter().rposition(|column| path.starts_with(&column.path) && path != column.path) else {
+            let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+                return Ok(());
+            };
+            self.navigate_to(parent.to_path_buf(), config)?;
+            self.select_by_name(name, config);
+            return Ok(());
+        };
+
+        self.columns.truncate(keep + 1);
+        let relative = path.strip_prefix(&self.columns[keep].path)?.to_path_buf();
+        let mut components = relative.components().peekable();
+        while let Some(component) = components.next() {
+            if !self.select_by_name(component.as_os_str(), config) {
+                break;
+            }
+            if components.peek().is_some() {
+                _ = self.navigate_right(config);
+            }
+        }
+        Ok(())
+    }
+
     /// Set the current directory as anchor (clear all columns to the left)
     pub fn set_anchor(&mut self, config: &Settings) -> Result<()> {
         if let Some(current_column) = self.columns.back() {
@@ -285,7 +874,7 @@ impl Browser {
     }
 
     /// Handle search character input
-    pub fn handle_search_char(&mut self, c: char) -> Result<()> {
+    pub fn handle_search_char(&mut self, c: char, config: &Settings) -> Result<()> {
         let now = Instant::now();
 
         // Reset search string if too much time has passed
@@ -297,11 +886,19 @@ impl Browser {
         self.last_key_time = now;
 
         // Find matching entry
+        let search = search_key(&self.search_string, config);
+        if let Some(tree) = &self.tree {
+            let found = tree.rows().iter().position(|row| search_key(&row.entry.file_name().to_string_lossy(), config).starts_with(&search));
+            if let Some(index) = found {
+                self.tree_select(config, |_| index);
+            }
+            return Ok(());
+        }
         if let Some(column) = self.columns.back_mut() {
             for (i, entry) in column.entries.iter().enumerate() {
-                let name = entry.file_name().to_string_lossy().to_lowercase();
-                if name.starts_with(&self.search_string.to_lowercase()) {
-                    column.selected.select(Some(i));
+                let name = search_key(&entry.file_name().to_string_lossy(), config);
+                if name.starts_with(&search) {
+                    column.select(Some(i));
                     break;
                 }
             }
@@ -310,8 +907,22 @@ impl Browser {
         Ok(())
     }
 
+    /// Select an entry in the current column by file name
+    pub fn select_by_name(&mut self, name: &OsStr, config: &Settings) -> bool {
+        let found = self.columns.back_mut().is_some_and(|column| column.select_by_name(name));
+        if found {
+            _ = self.update_preview(config);
+        }
+        found
+    }
+
     /// Navigate to previous item in current column
     pub fn select_previous(&mut self) {
+        if let Some(tree) = &mut self.tree {
+            tree.select_previous();
+            self.tree_moved = true;
+            return;
+        }
         if let Some(column) = self.columns.back_mut() {
             column.select_previous();
         }
@@ -319,54 +930,248 @@ impl Browser {
 
     /// Navigate to next item in current column
     pub fn select_next(&mut self) {
+        if let Some(tree) = &mut self.tree {
+            tree.select_next();
+            self.tree_moved = true;
+            return;
+        }
         if let Some(column) = self.columns.back_mut() {
             column.select_next();
         }
     }
 
+    /// Scroll the previewed file's content by `lines`, stopping once its last `page` lines are in view
+    ///
+    /// More of the file is read as the end of what was read comes near.
+    pub fn scroll_preview(&mut self, lines: isize, page: usize, config: &Settings) {
+        if let Some(Preview::File(details, scroll)) = &mut self.preview {
+            // A followed preview is scrolled past its end to keep the last lines in view
+            let current = (*scroll as usize).min(content_text(details).lines().count().saturating_sub(page));
+            let wanted = current.saturating_add_signed(lines);
+            while details.content_preview.text().is_some_and(|text| text.lines().count() < wanted + 2 * page)
+                && details.load_more(config)
+            {}
+            let max = content_text(details).lines().count().saturating_sub(page);
+            *scroll = wanted.min(max) as u16;
+        }
+    }
+
+    /// Move the cursor of an outline preview or fold its nodes, keeping the cursor among the `page` lines in view
+    ///
+    /// A table preview scrolls a row or a column instead.
+    pub fn move_in_preview(&mut self, action: OutlineMove, page: usize, config: &Settings) {
+        let Some(Preview::File(details, scroll)) = &mut self.preview else {
+            return;
+        };
+        match (&mut details.content_preview, action) {
+            (PreviewContent::Outline(outline), _) => {
+                outline.apply(action);
+                *scroll = scroll_to_cursor(outline, *scroll, page);
+            }
+            (PreviewContent::Table(_), OutlineMove::Up) => self.scroll_preview(-1, page, config),
+            (PreviewContent::Table(_), OutlineMove::Down) => self.scroll_preview(1, page, config),
+            (PreviewContent::Table(table), OutlineMove::Fold) => table.scroll_columns(-1),
+            (PreviewContent::Table(table), OutlineMove::Unfold) => table.scroll_columns(1),
+            _ => {}
+        }
+    }
+
+    pub fn is_following(&self) -> bool {
+        self.following
+    }
+
+    /// Start or stop following the previewed file
+    pub fn toggle_follow(&mut self, config: &Settings) {
+        self.following = !self.following;
+        // Going back to the start of the file
+        _ = self.update_preview(config);
+    }
+
+    /// Show the end of the previewed file if it changed, while following it
+    ///
+    /// Returns whether the preview changed.
+    pub fn follow_preview(&mut self, config: &Settings) -> bool {
+        if !self.following {
+            return false;
+        }
+        let Some(Preview::File(details, scroll)) = &mut self.preview else {
+            return false;
+        };
+        if !details.read_tail(config) {
+            return false;
+        }
+        *scroll = u16::MAX;
+        true
+    }
+
     /// Update the preview panel
     pub fn update_preview(&mut self, config: &Settings) -> Result<(),()> {
+        let _span = trace::span("preview build");
+        // Any navigation brings the active column back into view
+        self.column_scroll = 0;
+        self.sync_tree(config);
+        self.record_visit();
         self.preview = if let Some(entry) = self.active_column().selected_entry() {
             let path = entry.path();
 
             if path.is_dir() {
-                let cached_selection = self.selection_cache.get(&path).copied().unwrap_or(0);
-                match DirColumn::new(path, cached_selection, config) {
+                match self.column_with_cached_selection(path, config) {
                     Ok(preview_column) => Some(Preview::Directory(preview_column)),
                     Err(_) => None,
                 }
             } else {
                 match FileDetails::from_path(&path, config) {
-                    Ok(details) => Some(Preview::File(details)),
+                    Ok(details) => Some(Preview::File(details, 0)),
                     Err(_) => None,
                 }
             }
         } else {
             None
         };
+        self.follow_preview(config);
 
         Ok(())
     }
 
+    /// Move the active directory to the end of the history if it isn't already there
+    fn record_visit(&mut self) {
+        let path = &self.active_column().path;
+        if self.history.last() == Some(path) {
+            return;
+        }
+        let path = path.clone();
+        self.history.retain(|visited| *visited != path);
+        self.history.push(path);
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+    }
+
+    /// Directories visited in this browser, least recent first and ending with the active one
+    pub fn history(&self) -> &[PathBuf] {
+        &self.history
+    }
+
+    /// Directory visited before the active one, like `cd -`
+    pub fn alternate_directory(&self) -> Option<&Path> {
+        self.history.len().checked_sub(2).map(|i| self.history[i].as_path())
+    }
+
+    /// Make a directory the active column, keeping the columns of its ancestors
+    pub fn open_directory(&mut self, path: &Path, config: &Settings) -> Result<()> {
+        if let Some(index) = self.columns.iter().position(|column| column.path == path) {
+            self.cache_active_selection();
+            self.columns.truncate(index + 1);
+            _ = self.update_preview(config);
+            return Ok(());
+        }
+
+        if self.columns.iter().any(|column| path.starts_with(&column.path)) {
+            self.open_path(path, config)?;
+            if self.active_column().selected_entry().is_some_and(|entry| entry.path() == path) {
+                _ = self.navigate_right(config);
+            }
+            return Ok(());
+        }
+
+        self.navigate_to(path.to_path_buf(), config)
+    }
+
+    /// Whether a quick search is under way, so the next letter carries it on
+    pub fn is_quick_searching(&self) -> bool {
+        !self.search_string.is_empty() && self.last_key_time.elapsed().as_secs() <= SEARCH_TIMEOUT_SECONDS
+    }
+
     /// Clear the search string
     pub fn clear_search(&mut self) {
         self.search_string.clear();
     }
 
+    /// Get mutable reference to the active (rightmost) column
+    pub fn active_column_mut(&mut self) -> &mut DirColumn {
+        self.columns.back_mut().expect("At least one column should always exist")
+    }
+
+    /// Reload only the active column
+    pub fn reload_active_column(&mut self, config: &Settings, error_log: Option<&mut ErrorLog>) -> Result<()> {
+        if let Some(column) = self.columns.back_mut() {
+            column.reload_with_error_log(config, error_log)?;
+        }
+        if let Some(tree) = &mut self.tree {
+            tree.invalidate();
+        }
+        _ = self.update_preview(config);
+        Ok(())
+    }
+
+    /// Show only the active column's entries whose names contain `filter`
+    ///
+    /// Matching follows the quick search settings for case and accents. An empty
+    /// filter restores the full listing.
+    pub fn set_filter(&mut self, filter: &str, config: &Settings) -> Result<()> {
+        if let Some(column) = self.columns.back_mut() {
+            if column.filter == filter {
+                return Ok(());
+            }
+            column.filter = filter.to_string();
+            column.reload(config)?;
+            // Start at the top of the matches, keeping the selection visible
+            if column.selected.selected().is_none() && !column.entries.is_empty() {
+                column.select(Some(0));
+            }
+        }
+        _ = self.update_preview(config);
+        Ok(())
+    }
+
+    /// Reload the columns showing any of `dirs` after their contents changed
+    ///
+    /// Columns whose directory no longer exists are dropped; if even the first one is
+    /// gone, the browser moves to its nearest existing ancestor.
+    pub fn refresh_paths(&mut self, dirs: &[PathBuf], config: &Settings) {
+        if let Some(gone) = self.columns.iter().position(|column| !column.path.is_dir()) {
+            if gone == 0 {
+                let ancestor = self.columns[0].path.ancestors().skip(1).find(|path| path.is_dir()).map(Path::to_path_buf);
+                if let Some(ancestor) = ancestor {
+                    let _ = self.navigate_to(ancestor, config);
+                }
+                return;
+            }
+            self.columns.truncate(gone);
+        }
+
+        for column in &mut self.columns {
+            if dirs.contains(&column.path) {
+                let _ = column.reload(config);
+            }
+        }
+        if let Some(tree) = &mut self.tree {
+            tree.invalidate();
+        }
+        _ = self.update_preview(config);
+    }
+
     /// Reload all columns
     pub fn reload_all_columns(&mut self, config: &Settings) -> Result<()> {
         for column in &mut self.columns {
             let _ = column.reload(config);
         }
+        if let Some(tree) = &mut self.tree {
+            tree.invalidate();
+        }
         _ = self.update_preview(config);
         Ok(())
     }
 
     /// Jump to first item in current column
     pub fn jump_to_first(&mut self, config: &Settings) -> Result<()> {
+        if self.tree.is_some() {
+            self.tree_select(config, |_| 0);
+            return Ok(());
+        }
         if let Some(column) = self.columns.back_mut() {
             if !column.entries.is_empty() {
-                column.selected.select(Some(0));
+                column.select(Some(0));
                 _ = self.update_preview(config);
             }
         }
@@ -375,9 +1180,13 @@ impl Browser {
 
     /// Jump to last item in current column
     pub fn jump_to_last(&mut self, config: &Settings) -> Result<()> {
+        if self.tree.is_some() {
+            self.tree_select(config, |tree| tree.rows().len().saturating_sub(1));
+            return Ok(());
+        }
         if let Some(column) = self.columns.back_mut() {
             if !column.entries.is_empty() {
-                column.selected.select(Some(column.entries.len() - 1));
+                column.select(Some(column.entries.len() - 1));
                 _ = self.update_preview(config);
             }
         }
@@ -386,10 +1195,14 @@ impl Browser {
 
     /// Jump up by 10 items in current column
     pub fn jump_up_by_10(&mut self, config: &Settings) -> Result<()> {
+        if self.tree.is_some() {
+            self.tree_select(config, |tree| tree.selected.selected().unwrap_or(0).saturating_sub(10));
+            return Ok(());
+        }
         if let Some(column) = self.columns.back_mut() {
             if let Some(current) = column.selected.selected() {
                 let new_index = current.saturating_sub(10);
-                column.selected.select(Some(new_index));
+                column.select(Some(new_index));
                 _ = self.update_preview(config);
             }
         }
@@ -398,10 +1211,14 @@ impl Browser {
 
     /// Jump down by 10 items in current column
     pub fn jump_down_by_10(&mut self, config: &Settings) -> Result<()> {
+        if self.tree.is_some() {
+            self.tree_select(config, |tree| tree.selected.selected().unwrap_or(0) + 10);
+            return Ok(());
+        }
         if let Some(column) = self.columns.back_mut() {
             if let Some(current) = column.selected.selected() {
                 let new_index = (current + 10).min(column.entries.len().saturating_sub(1));
-                column.selected.select(Some(new_index));
+                column.select(Some(new_index));
                 _ = self.update_preview(config);
             }
         }
@@ -411,6 +1228,46 @@ impl Browser {
 
 const BORDER_AND_PADDING_WIDTH: u16 = 4; // 2 for borders + 2 for padding
 const ICON_SPACE_WIDTH: usize = 3; // icon + space + buffer
+const RENDER_WINDOW_MARGIN: usize = 8; // extra rows materialized around the visible window
+const DIR_SIZE_WIDTH: usize = 11; // " 1023.9 KB+"
+const MIN_DETAIL_NAME_WIDTH: usize = 12; // names keep this much room before detail fields are dropped
+const OWNER_WIDTH: usize = 8;
+const ENTRY_COUNT_WIDTH: usize = 6; // " 12345"
+const MIN_ADJUSTED_WIDTH: u16 = 8; // narrowest a column or the preview is made from the keyboard
+
+/// The detail view fields after an entry's name, as many as fit in `room`
+///
+/// Fields are dropped from the end: owner first, then permissions, date and size.
+/// A directory's size is `dir_size` when it is known, and `-` otherwise. With
+/// `show_allocated`, a file's size is followed by the space it takes on disk.
+fn detail_text(details: &EntryDetails, dir_size: Option<String>, show_allocated: bool, room: usize) -> String {
+    let size = match dir_size {
+        Some(size) => size,
+        None if details.is_dir => "-".to_string(),
+        None => format_file_size(details.size),
+    };
+    let date = details.modified.map(|modified| format_date_compact(&modified.into())).unwrap_or_default();
+    let allocated = match show_allocated {
+        true if details.is_dir => format!(" {:>12}", ""),
+        true => format!(" {:>12}", format!("({})", format_file_size(details.allocated))),
+        false => String::new(),
+    };
+    let fields = [
+        format!(" {:>10}{}", size, allocated),
+        format!(" {:>6}", date),
+        format!(" {}", format_permissions(details.mode)),
+        format!(" {}", pad_text(&truncate_text(&details.owner, OWNER_WIDTH), OWNER_WIDTH)),
+    ];
+
+    let mut text = String::new();
+    for field in fields {
+        if text_width(&text) + text_width(&field) > room {
+            break;
+        }
+        text.push_str(&field);
+    }
+    text
+}
 
 /// Calculate available width for content within a bordered area
 pub fn content_width(area: Rect) -> usize {
@@ -427,31 +1284,38 @@ fn filename_width(area: Rect, show_icons: bool) -> usize {
     }
 }
 
+/// A directory's entry count ahead of its size or detail fields, lined up in a field of its own
+fn with_entry_count(count: Option<String>, rest: Option<String>) -> Option<String> {
+    match (count, rest) {
+        (Some(count), Some(rest)) => Some(format!("{:>width$}{}", count, rest, width = ENTRY_COUNT_WIDTH)),
+        (count, rest) => count.or(rest),
+    }
+}
+
 /// Render the main content area (columns and preview)
 pub fn render_browser(frame: &mut Frame, app: &mut App, area: Rect) {
     let browser = app.browser();
-    let num_cols = browser.columns().len() + if browser.preview().is_some() { 1 } else { 0 };
-    let constraints = (0..num_cols)
-        .map(|_| Constraint::Ratio(1, num_cols as u32))
-        .collect::<Vec<_>>();
-    let layout = Layout::horizontal(constraints).split(area);
-
-    // Render columns
-    let active_column_index = browser.columns().len() - 1;
-    for (i, column) in browser.columns().iter().enumerate() {
-        let is_active = i == active_column_index;
-        render_dir_column(frame, column, layout[i], is_active, false, app.config());
+    let (areas, preview_area) = browser_layout(area, browser, app.config());
+    if let Some(tree) = browser.tree() {
+        render_tree_view(frame, tree, areas[0], app);
+    } else {
+        let active_column_index = browser.columns().len() - 1;
+        for (slot, i) in browser.visible_columns().enumerate() {
+            let is_active = i == active_column_index;
+            render_dir_column(frame, &browser.columns()[i], areas[slot], is_active, false, app);
+        }
     }
 
     // Render preview
-    if let Some(preview) = browser.preview() {
-        let preview_area = layout[browser.columns().len()];
+    if let Some(preview_area) = preview_area
+        && let Some(preview) = browser.visible_preview()
+    {
         match preview {
             Preview::Directory(dir_column) => {
-                render_dir_column(frame, dir_column, preview_area, false, true, app.config());
+                render_dir_column(frame, dir_column, preview_area, false, true, app);
             }
-            Preview::File(details) => {
-                render_file_preview(frame, details, preview_area);
+            Preview::File(details, scroll) => {
+                render_file_preview(frame, details, preview_area, *scroll);
             }
         }
     }
@@ -462,6 +1326,125 @@ pub fn render_browser(frame: &mut Frame, app: &mut App, area: Rect) {
     }
 }
 
+/// Areas of the visible columns, or of the tree in tree view, and of the preview
+///
+/// The preview takes `preview_percent` of the width when it is set, and otherwise a
+/// column's share, or a third next to the tree. It has no area when turned off.
+pub fn browser_layout(area: Rect, browser: &Browser, config: &Settings) -> (Vec<Rect>, Option<Rect>) {
+    let slots = if browser.tree().is_some() { 1 } else { browser.visible_columns().len() as u32 };
+    let with_preview = config.show_preview && browser.visible_preview().is_some();
+    let mut constraints = vec![Constraint::Ratio(1, slots); slots as usize];
+    if with_preview {
+        match config.preview_percent {
+            Some(percent) => {
+                constraints = vec![Constraint::Fill(1); slots as usize];
+                constraints.push(Constraint::Percentage(percent));
+            }
+            None => {
+                let (column, preview) = if browser.tree().is_some() {
+                    (Constraint::Ratio(2, 3), Constraint::Ratio(1, 3))
+                } else {
+                    (Constraint::Ratio(1, slots + 1), Constraint::Ratio(1, slots + 1))
+                };
+                constraints = vec![column; slots as usize];
+                constraints.push(preview);
+            }
+        }
+    }
+    let mut areas = Layout::horizontal(constraints).split(area).to_vec();
+
+    // Widths set from the keyboard: the last column, the one nearest the active one,
+    // and the preview get cells added to what the layout gave them, taken from the other columns
+    let adjustment = config.width_adjustment;
+    if adjustment != WidthAdjustment::default() {
+        let min = MIN_ADJUSTED_WIDTH;
+        let column_index = areas.len() - 1 - usize::from(with_preview);
+        // The columns giving up cells keep at least the minimum
+        let room = area.width.saturating_sub(min * column_index as u16);
+        let widen = |width: u16, cells: i16, max: u16| {
+            (i32::from(width) + i32::from(cells)).clamp(i32::from(min), i32::from(max.max(min))) as u16
+        };
+        // With no other columns, the column's cells come out of the preview instead
+        let preview_cells = if column_index == 0 { adjustment.preview.saturating_sub(adjustment.column) } else { adjustment.preview };
+        let preview_width = with_preview.then(|| widen(areas[column_index + 1].width, preview_cells, room.saturating_sub(min)));
+        let column_width = widen(areas[column_index].width, adjustment.column, room.saturating_sub(preview_width.unwrap_or(0)));
+        let mut constraints = vec![Constraint::Fill(1); column_index];
+        constraints.push(if column_index == 0 { Constraint::Fill(1) } else { Constraint::Length(column_width) });
+        constraints.extend(preview_width.map(Constraint::Length));
+        areas = Layout::horizontal(constraints).split(area).to_vec();
+    }
+
+    let preview = if with_preview { areas.pop() } else { None };
+    (areas, preview)
+}
+
+/// Render the tree of the tree view
+fn render_tree_view(frame: &mut Frame, tree: &TreeView, area: Rect, app: &App) {
+    let (browser, config, dir_sizes, entry_counts) = (app.browser(), app.config(), app.dir_sizes(), app.entry_counts());
+    let title = tree.root.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(truncate_text(&title, content_width(area)))
+        .border_style(Style::default().fg(app.tab_manager().active_accent(config).unwrap_or(Color::Cyan)))
+        .padding(Padding::uniform(1));
+
+    let list_area = block.inner(area);
+    let view_height = list_area.height as usize;
+    let offset = tree.visible_offset(view_height);
+    tree.set_view_height(view_height);
+    let window_start = offset.saturating_sub(RENDER_WINDOW_MARGIN);
+    let window_end = (offset + view_height + RENDER_WINDOW_MARGIN).min(tree.rows().len());
+
+    let items: Vec<ListItem> = tree
+        .rows()
+        .iter()
+        .skip(window_start)
+        .take(window_end.saturating_sub(window_start))
+        .map(|row| {
+            let path = row.entry.path();
+            let marker = match (row.is_dir, tree.is_expanded(&path)) {
+                (false, _) => ' ',
+                (true, true) => '▾',
+                (true, false) => '▸',
+            };
+            let indent = "  ".repeat(row.depth);
+            let icon = if config.show_icons { row.icon.as_str() } else { "" };
+            let size = dir_sizes.filter(|_| row.is_dir).and_then(|sizes| sizes.get(&path)).map(|size| match size {
+                DirSize::Pending => "…".to_string(),
+                DirSize::Known { bytes, complete: true } => format_file_size(bytes),
+                DirSize::Known { bytes, complete: false } => format!("{}+", format_file_size(bytes)),
+            });
+
+            let count = entry_counts.filter(|_| row.is_dir).and_then(|counts| counts.get(&path)).map(EntryCount::text);
+            let extra_width = if size.is_some() { DIR_SIZE_WIDTH } else { 0 } + if count.is_some() { ENTRY_COUNT_WIDTH } else { 0 };
+            let extra = with_entry_count(count, size);
+
+            let prefix = if icon.is_empty() { format!("{indent}{marker} ") } else { format!("{indent}{marker} {icon} ") };
+            let room = content_width(area).saturating_sub(Span::raw(&prefix).width() + extra_width);
+            let name = truncate_text(&row.entry.file_name().to_string_lossy(), room);
+            let mut spans = vec![Span::raw(format!("{prefix}{name}"))];
+            if let Some(extra) = extra {
+                let padding = content_width(area).saturating_sub(spans[0].width());
+                spans.push(Span::styled(format!("{:>width$}", extra, width = padding), Style::default().fg(Color::DarkGray)));
+            }
+
+            // Marks live on the columns, so they show for the directories the columns have open
+            let marked = browser
+                .columns()
+                .iter()
+                .any(|column| path.parent() == Some(column.path.as_path()) && column.is_marked(&row.entry));
+            let style = if marked { Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD) } else { Style::default() };
+            ListItem::new(Line::from(spans)).style(style)
+        })
+        .collect();
+
+    let list = List::new(items).block(block).highlight_style(Style::default().bg(Color::Cyan));
+    let mut list_state = ListState::default()
+        .with_offset(offset - window_start)
+        .with_selected(tree.selected.selected().map(|i| i.saturating_sub(window_start)));
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
 /// Render a directory column
 fn render_dir_column(
     frame: &mut Frame,
@@ -469,8 +1452,10 @@ fn render_dir_column(
     area: Rect,
     is_active: bool,
     _is_preview: bool,
-    config: &Settings,
+    app: &App,
 ) {
+    let (config, dir_sizes, entry_counts) = (app.config(), app.dir_sizes(), app.entry_counts());
+    let mime_types = app.mime_types();
     use crate::utils::get_path_info;
     use ratatui::layout::{Constraint, Layout, Direction};
     use ratatui::widgets::{Paragraph, Wrap};
@@ -486,7 +1471,7 @@ fn render_dir_column(
     let truncated_title = truncate_text(&title, content_width(area));
 
     let border_style = if is_active {
-        Style::default().fg(Color::Cyan)
+        Style::default().fg(app.tab_manager().active_accent(app.config()).unwrap_or(Color::Cyan))
     } else {
         Style::default()
     };
@@ -501,31 +1486,86 @@ fn render_dir_column(
         .split(area);
 
     let max_filename_width = filename_width(chunks[0], config.show_icons);
-
+    let git_badge_width = if column.git.is_some() { 2 } else { 0 };
+    // The detail view shows directory sizes in its own size field
+    let size_width = if dir_sizes.is_some() && !config.detail_view { DIR_SIZE_WIDTH } else { 0 };
+    let count_width = if entry_counts.is_some() { ENTRY_COUNT_WIDTH } else { 0 };
+
+    let list_block = Block::default()
+        .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
+        .title(truncated_title)
+        .border_style(border_style)
+        .padding(Padding::uniform(1));
+
+    // Only build items for the visible window (plus a margin) so large
+    // directories cost the same to draw as small ones
+    let list_area = list_block.inner(chunks[0]);
+    let view_height = list_area.height as usize;
+    let offset = column.visible_offset(view_height);
+    column.set_view_height(view_height);
+    let window_start = offset.saturating_sub(RENDER_WINDOW_MARGIN);
+    let window_end = (offset + view_height + RENDER_WINDOW_MARGIN).min(column.entries.len());
+
+    let visual_range = column.visual_range();
     let items: Vec<ListItem> = column
         .entries
         .iter()
-        .map(|entry| {
+        .enumerate()
+        .skip(window_start)
+        .take(window_end.saturating_sub(window_start))
+        .map(|(index, entry)| {
+            // Directory sizes line up at the right edge, before any git badge
+            let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+            let size = dir_sizes.filter(|_| is_dir).and_then(|sizes| sizes.get(&entry.path())).map(|size| match size {
+                DirSize::Pending => "…".to_string(),
+                DirSize::Known { bytes, complete: true } => format_file_size(bytes),
+                DirSize::Known { bytes, complete: false } => format!("{}+", format_file_size(bytes)),
+            });
+            let count = entry_counts.filter(|_| is_dir).and_then(|counts| counts.get(&entry.path())).map(EntryCount::text);
+            let details = if config.detail_view { column.entry_details(entry) } else { None };
+            let (size, details) = match details {
+                Some(details) => {
+                    let room = max_filename_width.saturating_sub(git_badge_width + count_width + MIN_DETAIL_NAME_WIDTH);
+                    (None, Some(detail_text(&details, size, config.allocated_sizes, room)))
+                }
+                None => (size, None),
+            };
+            let details_width = details.as_ref().map_or(0, |details| text_width(details));
+
             let name = entry.file_name().to_string_lossy().to_string();
-            let truncated_name = truncate_text(&name, max_filename_width);
-            let icon = get_icon_with_error_log(entry, config, None);
+            let truncated_name = truncate_text(&name, max_filename_width.saturating_sub(git_badge_width + count_width + size_width + details_width));
+            let icon = column.entry_icon(entry, config, mime_types);
             let display_text = if icon.is_empty() {
                 truncated_name
             } else {
                 format!("{} {}", icon, truncated_name)
             };
-            ListItem::new(display_text)
+
+            let mut spans = vec![Span::raw(display_text)];
+            if let Some(extra) = with_entry_count(count, size.or(details)) {
+                let name_width = spans[0].width();
+                let padding = content_width(chunks[0]).saturating_sub(git_badge_width + name_width);
+                spans.push(Span::styled(format!("{:>width$}", extra, width = padding), Style::default().fg(Color::DarkGray)));
+            }
+
+            let in_visual_range = visual_range.as_ref().is_some_and(|range| range.contains(&index));
+            let mut style = Style::default();
+            if column.is_marked(entry) || in_visual_range {
+                style = style.fg(Color::Yellow).add_modifier(Modifier::BOLD);
+            }
+
+            if let Some(status) = column.git.as_ref().and_then(|git| git.get(&entry.file_name())) {
+                if status == GitStatus::Ignored && config.dim_git_ignored {
+                    style = style.add_modifier(Modifier::DIM);
+                }
+                spans.push(Span::styled(format!(" {}", status.badge()), Style::default().fg(status.color())));
+            }
+            ListItem::new(Line::from(spans)).style(style)
         })
         .collect();
 
     let list = List::new(items)
-        .block(
-            Block::default()
-                .borders(Borders::TOP | Borders::LEFT | Borders::RIGHT)
-                .title(truncated_title)
-                .border_style(border_style)
-                .padding(Padding::uniform(1)),
-        )
+        .block(list_block)
         .highlight_style(
             if _is_preview {
                 Style::default()
@@ -535,16 +1575,47 @@ fn render_dir_column(
             }
         );
 
-    // Create a mutable state for rendering
-    let mut list_state = column.selected.clone();
+    // Create a mutable state for rendering, relative to the materialized window
+    let mut list_state = ListState::default()
+        .with_offset(offset - window_start)
+        .with_selected(column.selected.selected().map(|i| i.saturating_sub(window_start)));
     frame.render_stateful_widget(list, chunks[0], &mut list_state);
 
+    if column.slow {
+        let placeholder = Paragraph::new("Slow filesystem: no answer yet\nCtrl+R to retry")
+            .style(Style::default().fg(Color::Yellow))
+            .wrap(Wrap { trim: true });
+        frame.render_widget(placeholder, list_area);
+    }
+
+    // Draw the rename field over the selected row
+    if is_active
+        && let Some(input) = &column.rename_input
+        && let Some(selected) = column.selected.selected()
+        && selected >= offset
+        && selected < offset + view_height
+    {
+        let row = Rect::new(list_area.x, list_area.y + (selected - offset) as u16, list_area.width, 1);
+        frame.render_widget(Clear, row);
+        let block = Block::default().style(Style::default().fg(Color::Black).bg(Color::Yellow));
+        render_text_input(frame, input, block, row, true);
+    }
+
     // Render directory info at the bottom
-    let entry_count = column.entries.len();
+    let entry_count = if column.filter.is_empty() && !column.truncated {
+        column.entries.len().to_string()
+    } else {
+        format!("{} of {}", column.entries.len(), column.unfiltered_len)
+    };
+    let marked_text = if column.marked.is_empty() {
+        String::new()
+    } else {
+        format!(", {} marked", column.marked.len())
+    };
     let info_text = if let Some((permissions, date)) = get_path_info(&column.path) {
-        format!("{} {} ({} items)", permissions, date, entry_count)
+        format!("{} {} ({} items{})", permissions, date, entry_count, marked_text)
     } else {
-        format!("--------- ???? ({} items)", entry_count)
+        format!("--------- ???? ({} items{})", entry_count, marked_text)
     };
 
     let info_paragraph = Paragraph::new(info_text)
@@ -558,4 +1629,19 @@ fn render_dir_column(
         .wrap(Wrap { trim: true });
 
     frame.render_widget(info_paragraph, chunks[1]);
+
+    // Draw the filter field over the footer while it is being typed
+    if is_active && let Some(input) = &column.filter_input {
+        let row = Rect::new(chunks[1].x + 1, chunks[1].y, chunks[1].width.saturating_sub(2), 1);
+        let style = Style::default().fg(Color::Black).bg(Color::Yellow);
+        let label = "Filter: ";
+        frame.render_widget(Clear, row);
+        frame.render_widget(Paragraph::new(label).style(style), row);
+        let input_area = Rect {
+            x: row.x + label.len() as u16,
+            width: row.width.saturating_sub(label.len() as u16),
+            ..row
+        };
+        render_text_input(frame, input, Block::default().style(style), input_area, true);
+    }
 }
@@ -0,0 +1,234 @@
+//! Command-line interface definition
+
+use crate::config::{settings_path, PickMode, Settings};
+use crate::trace::TraceFormat;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// A TUI file browser
+#[derive(Parser, Debug)]
+#[command(name = "browse", version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+
+    /// Directories to open, one tab each; a file opens its directory with it selected
+    #[arg(value_name = "PATH")]
+    pub paths: Vec<PathBuf>,
+
+    /// Show hidden files for this run, whatever the settings say
+    #[arg(long)]
+    pub show_hidden: bool,
+
+    /// Read settings from FILE instead of ~/.browse, and save them back there
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Refuse to copy, move, delete, rename or touch anything
+    #[arg(long)]
+    pub read_only: bool,
+
+    /// Pick files: Enter quits and prints the marked files, or the selected one
+    #[arg(long, conflicts_with_all = ["choose_dir", "batch"])]
+    pub choose_file: bool,
+
+    /// Pick directories: Enter quits and prints the marked directories, or the selected one
+    #[arg(long, conflicts_with = "batch")]
+    pub choose_dir: bool,
+
+    /// Print the directory browsed last to stdout on quit
+    #[arg(long)]
+    pub print_cwd_on_exit: bool,
+
+    /// Write the directory browsed last to FILE on quit, for a shell wrapper to cd to
+    #[arg(long, value_name = "FILE")]
+    pub cwd_file: Option<PathBuf>,
+
+    /// Read commands from stdin and run them without the TUI
+    /// (cd, select, mark, copy to, delete, print selection)
+    #[arg(long)]
+    pub batch: bool,
+
+    /// Record performance trace spans to FILE
+    #[arg(long, value_name = "FILE")]
+    pub trace: Option<PathBuf>,
+
+    /// Format of the trace file
+    #[arg(long, value_enum, default_value_t = TraceFormat::Chrome, requires = "trace")]
+    pub trace_format: TraceFormat,
+}
+
+impl Cli {
+    /// Settings file to load and save
+    pub fn settings_path(&self) -> PathBuf {
+        self.config.clone().unwrap_or_else(settings_path)
+    }
+
+    /// Apply the flags that override the loaded settings for this run
+    pub fn apply_overrides(&self, config: &mut Settings) {
+        if self.show_hidden {
+            config.saved_show_hidden_files = Some(config.show_hidden_files);
+            config.show_hidden_files = true;
+        }
+        config.read_only = self.read_only;
+        config.pick = self.pick_mode();
+    }
+
+    /// What Enter chooses, if browse runs as a picker
+    pub fn pick_mode(&self) -> Option<PickMode> {
+        if self.choose_file {
+            Some(PickMode::Files)
+        } else if self.choose_dir {
+            Some(PickMode::Directories)
+        } else {
+            None
+        }
+    }
+}
+
+/// Utility subcommands that run instead of the browser
+#[derive(Subcommand, Debug)]
+pub enum CliCommand {
+    /// Print a shell completion script
+    Completions {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print a man page in roff format
+    Man,
+    /// Print a `bcd` shell function that runs browse and changes to the directory browsed last
+    ShellInit {
+        /// Shell to write the function for
+        #[arg(value_enum)]
+        shell: InitShell,
+    },
+}
+
+/// Shells `shell-init` can write a cd-on-exit function for
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum InitShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Write a completion script for `shell` generated from the CLI definition
+pub fn write_completions(shell: Shell, out: &mut impl Write) {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, out);
+}
+
+/// Write a shell function that runs browse and then changes to the directory browsed last
+pub fn write_shell_init(shell: InitShell, out: &mut impl Write) -> io::Result<()> {
+    let function = match shell {
+        InitShell::Bash | InitShell::Zsh => POSIX_SHELL_INIT,
+        InitShell::Fish => FISH_SHELL_INIT,
+    };
+    out.write_all(function.as_bytes())
+}
+
+const POSIX_SHELL_INIT: &str = r#"bcd() {
+    local cwd_file dir browse_status
+    cwd_file="$(mktemp)" || return
+    command browse --cwd-file "$cwd_file" "$@"
+    browse_status=$?
+    dir="$(cat -- "$cwd_file")"
+    rm -f -- "$cwd_file"
+    if [ -n "$dir" ] && [ "$dir" != "$PWD" ]; then
+        cd -- "$dir" || return
+    fi
+    return $browse_status
+}
+"#;
+
+const FISH_SHELL_INIT: &str = r#"function bcd
+    set -l cwd_file (mktemp); or return
+    command browse --cwd-file $cwd_file $argv
+    set -l browse_status $status
+    set -l dir (cat -- $cwd_file)
+    rm -f -- $cwd_file
+    if test -n "$dir"; and test "$dir" != "$PWD"
+        cd -- $dir
+    end
+    return $browse_status
+end
+"#;
+
+/// Write a roff man page generated from the CLI definition
+pub fn write_man_page(out: &mut impl Write) -> io::Result<()> {
+    clap_mangen::Man::new(Cli::command()).render(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_paths_and_subcommands() {
+        let cli = Cli::try_parse_from(["browse", "old", "new"]).unwrap();
+        assert_eq!(cli.paths, [PathBuf::from("old"), PathBuf::from("new")]);
+        assert!(cli.command.is_none());
+
+        let cli = Cli::try_parse_from(["browse", "man"]).unwrap();
+        assert!(matches!(cli.command, Some(CliCommand::Man)));
+        assert!(cli.paths.is_empty());
+    }
+
+    #[test]
+    fn test_flags_override_settings() {
+        let cli = Cli::try_parse_from(["browse", "--show-hidden", "--read-only", "--config", "alt.json", "src"]).unwrap();
+        assert_eq!(cli.paths, [PathBuf::from("src")]);
+        assert_eq!(cli.settings_path(), PathBuf::from("alt.json"));
+
+        let mut config = Settings::default();
+        cli.apply_overrides(&mut config);
+        assert!(config.show_hidden_files);
+        assert_eq!(config.saved_show_hidden_files, Some(false));
+        assert!(config.read_only);
+
+        let cli = Cli::try_parse_from(["browse"]).unwrap();
+        assert_eq!(cli.settings_path(), settings_path());
+        assert_eq!(cli.pick_mode(), None);
+
+        let cli = Cli::try_parse_from(["browse", "--choose-dir"]).unwrap();
+        assert_eq!(cli.pick_mode(), Some(PickMode::Directories));
+        assert!(Cli::try_parse_from(["browse", "--choose-file", "--choose-dir"]).is_err());
+    }
+
+    #[test]
+    fn test_shell_init() {
+        let cli = Cli::try_parse_from(["browse", "shell-init", "zsh"]).unwrap();
+        assert!(matches!(cli.command, Some(CliCommand::ShellInit { shell: InitShell::Zsh })));
+
+        for shell in [InitShell::Bash, InitShell::Fish] {
+            let mut function = Vec::new();
+            write_shell_init(shell, &mut function).unwrap();
+            let function = String::from_utf8(function).unwrap();
+            assert!(function.contains("command browse --cwd-file"));
+        }
+    }
+
+    #[test]
+    fn test_generated_docs_mention_flags() {
+        let mut completions = Vec::new();
+        write_completions(Shell::Bash, &mut completions);
+        let completions = String::from_utf8(completions).unwrap();
+        assert!(completions.contains("--batch"));
+        assert!(completions.contains("--trace"));
+
+        let mut man = Vec::new();
+        write_man_page(&mut man).unwrap();
+        let man = String::from_utf8(man).unwrap();
+        assert!(man.contains(".TH browse"));
+        assert!(man.contains("trace"));
+    }
+}
@@ -1,4 +1,8 @@
+use crate::config::Settings;
+use crate::error::ErrorLog;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::de::{value, IntoDeserializer};
+use serde::{Deserialize, Serialize};
 
 /// Represents a key binding for a command
 #[derive(Debug, Clone, PartialEq)]
@@ -9,40 +13,120 @@ pub enum KeyBinding {
     ModifiedKey(KeyCode, KeyModifiers),
     /// Character range for search functionality
     CharRange,
+    /// Keys pressed one after another (e.g., g g)
+    Sequence(Vec<KeyBinding>),
 }
 
 impl KeyBinding {
     /// Check if this key binding matches the given key event
     pub fn matches(&self, key: &KeyEvent) -> bool {
         match self {
+            // Shifted characters arrive with SHIFT set on some terminals, so tolerate it
+            KeyBinding::Key(code @ KeyCode::Char(_)) => {
+                key.code == *code && key.modifiers.difference(KeyModifiers::SHIFT).is_empty()
+            }
             KeyBinding::Key(code) => key.code == *code && key.modifiers.is_empty(),
             KeyBinding::ModifiedKey(code, modifiers) => {
                 key.code == *code && key.modifiers.contains(*modifiers)
             }
             KeyBinding::CharRange => {
                 matches!(key.code, KeyCode::Char(c) if c.is_ascii_lowercase())
+                    && !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+            }
+            KeyBinding::Sequence(_) => false,
+        }
+    }
+
+    /// Check if the keys pressed so far match this binding, or the start of it
+    fn matches_keys(&self, keys: &[KeyEvent], allow_prefix: bool) -> bool {
+        let bindings = match self {
+            KeyBinding::Sequence(bindings) => bindings.as_slice(),
+            binding => std::slice::from_ref(binding),
+        };
+        let length_ok = if allow_prefix { bindings.len() > keys.len() } else { bindings.len() == keys.len() };
+        length_ok && bindings.iter().zip(keys).all(|(binding, key)| binding.matches(key))
+    }
+
+    /// Parse a binding such as `ctrl+n`, `shift+delete`, `F5`, `?` or `g g`
+    ///
+    /// Whitespace separates the keys of a sequence; within a key, `+` separates
+    /// modifiers (`ctrl`, `alt`, `shift`) from the key name.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let chords = spec.split_whitespace().map(parse_chord).collect::<Result<Vec<_>, _>>()?;
+        match chords.len() {
+            0 => Err("empty key binding".to_string()),
+            1 => Ok(chords.into_iter().next().unwrap()),
+            _ => Ok(KeyBinding::Sequence(chords)),
+        }
+    }
+
+    /// Binding for a single key press, as captured by the key binding editor
+    pub fn from_key_event(key: &KeyEvent) -> Self {
+        // Shift is implied by an uppercase or symbol character
+        let modifiers = match key.code {
+            KeyCode::Char(_) => key.modifiers.difference(KeyModifiers::SHIFT),
+            _ => key.modifiers,
+        };
+        if modifiers.is_empty() {
+            KeyBinding::Key(key.code)
+        } else {
+            KeyBinding::ModifiedKey(key.code, modifiers)
+        }
+    }
+
+    /// The binding written the way `parse` reads it, for saving to the settings file
+    pub fn spec(&self) -> String {
+        match self {
+            KeyBinding::Key(code) => key_spec(code),
+            KeyBinding::ModifiedKey(code, modifiers) => {
+                let mut spec = String::new();
+                for (modifier, name) in [
+                    (KeyModifiers::CONTROL, "ctrl+"),
+                    (KeyModifiers::ALT, "alt+"),
+                    (KeyModifiers::SHIFT, "shift+"),
+                ] {
+                    if modifiers.contains(modifier) {
+                        spec.push_str(name);
+                    }
+                }
+                spec + &key_spec(code)
             }
+            KeyBinding::CharRange => "a-z".to_string(),
+            KeyBinding::Sequence(bindings) => bindings
+                .iter()
+                .map(KeyBinding::spec)
+                .collect::<Vec<_>>()
+                .join(" "),
         }
     }
 
     /// Get the display text for this key binding
     pub fn display_text(&self) -> String {
         match self {
-            KeyBinding::Key(KeyCode::Up) => "Up".to_string(),
-            KeyBinding::Key(KeyCode::Down) => "Down".to_string(),
-            KeyBinding::Key(KeyCode::Left) => "Left".to_string(),
-            KeyBinding::Key(KeyCode::Right) => "Right".to_string(),
-            KeyBinding::Key(KeyCode::Home) => "Home".to_string(),
-            KeyBinding::Key(KeyCode::End) => "End".to_string(),
-            KeyBinding::Key(KeyCode::PageUp) => "PgUp".to_string(),
-            KeyBinding::Key(KeyCode::PageDown) => "PgDn".to_string(),
-            KeyBinding::Key(KeyCode::Esc) => "Esc".to_string(),
-            KeyBinding::Key(KeyCode::Char(c)) => c.to_string(),
-            KeyBinding::ModifiedKey(KeyCode::Char(c), KeyModifiers::CONTROL) => {
-                format!("Ctrl+{}", c.to_uppercase())
+            KeyBinding::Key(code) => key_name(code),
+            KeyBinding::ModifiedKey(code, modifiers) => {
+                let mut text = String::new();
+                for (modifier, name) in [
+                    (KeyModifiers::CONTROL, "Ctrl+"),
+                    (KeyModifiers::ALT, "Alt+"),
+                    (KeyModifiers::SHIFT, "Shift+"),
+                ] {
+                    if modifiers.contains(modifier) {
+                        text.push_str(name);
+                    }
+                }
+                match code {
+                    KeyCode::Char(c) if *c != ' ' => text.extend(c.to_uppercase()),
+                    code => text.push_str(&key_name(code)),
+                }
+                text
             }
             KeyBinding::CharRange => "a-z".to_string(),
-            _ => "Unknown".to_string(),
+            KeyBinding::Sequence(bindings) => bindings
+                .iter()
+                .map(KeyBinding::display_text)
+                .collect::<Vec<_>>()
+                .join(" "),
         }
     }
 
@@ -62,6 +146,114 @@ impl KeyBinding {
     }
 }
 
+/// Display name of a key without modifiers
+fn key_name(code: &KeyCode) -> String {
+    match code {
+        KeyCode::Up => "Up".to_string(),
+        KeyCode::Down => "Down".to_string(),
+        KeyCode::Left => "Left".to_string(),
+        KeyCode::Right => "Right".to_string(),
+        KeyCode::Home => "Home".to_string(),
+        KeyCode::End => "End".to_string(),
+        KeyCode::PageUp => "PgUp".to_string(),
+        KeyCode::PageDown => "PgDn".to_string(),
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Enter => "Enter".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        KeyCode::Backspace => "Backspace".to_string(),
+        KeyCode::Insert => "Ins".to_string(),
+        KeyCode::Delete => "Del".to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        _ => "Unknown".to_string(),
+    }
+}
+
+/// Name of a key in the settings file syntax
+fn key_spec(code: &KeyCode) -> String {
+    match code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        code => key_name(code).to_lowercase(),
+    }
+}
+
+/// Parse a single key with optional modifiers, e.g. `ctrl+n`
+fn parse_chord(chord: &str) -> Result<KeyBinding, String> {
+    if chord == "a-z" {
+        return Ok(KeyBinding::CharRange);
+    }
+
+    // A trailing "+" is the plus key itself, as in "ctrl++"
+    let (modifier_part, key_part) = match chord.strip_suffix("++") {
+        Some(modifiers) => (modifiers, "+"),
+        None => chord.rsplit_once('+').filter(|(_, key)| !key.is_empty()).unwrap_or(("", chord)),
+    };
+
+    let mut modifiers = KeyModifiers::NONE;
+    for modifier in modifier_part.split('+').filter(|m| !m.is_empty()) {
+        modifiers |= match modifier.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => KeyModifiers::CONTROL,
+            "alt" | "meta" | "option" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return Err(format!("unknown modifier '{}' in '{}'", modifier, chord)),
+        };
+    }
+
+    let mut chars = key_part.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match key_part.to_ascii_lowercase().as_str() {
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pgup" | "pageup" => KeyCode::PageUp,
+            "pgdn" | "pagedown" => KeyCode::PageDown,
+            "esc" | "escape" => KeyCode::Esc,
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "ins" | "insert" => KeyCode::Insert,
+            "del" | "delete" => KeyCode::Delete,
+            "space" => KeyCode::Char(' '),
+            name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                Some(n) if (1..=24).contains(&n) => KeyCode::F(n),
+                _ => return Err(format!("unknown key '{}' in '{}'", key_part, chord)),
+            },
+        },
+    };
+
+    // Shifted characters are written as the character itself, and Ctrl+letter arrives lowercase
+    let code = match code {
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) && c.is_ascii_lowercase() => {
+            modifiers.remove(KeyModifiers::SHIFT);
+            KeyCode::Char(c.to_ascii_uppercase())
+        }
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => KeyCode::Char(c.to_ascii_lowercase()),
+        code => code,
+    };
+
+    if modifiers.is_empty() {
+        Ok(KeyBinding::Key(code))
+    } else {
+        Ok(KeyBinding::ModifiedKey(code, modifiers))
+    }
+}
+
+/// Result of looking up the keys pressed so far
+pub enum KeyMatch<'a> {
+    /// The keys complete a binding
+    Command(&'a Command),
+    /// The keys start a longer binding; wait for the next key
+    Pending,
+    /// No binding uses these keys
+    None,
+}
+
 /// Represents a command that can be executed
 pub struct Command {
     pub key_binding: KeyBinding,
@@ -69,8 +261,26 @@ pub struct Command {
     pub action: CommandAction,
 }
 
+/// What the selection refers to, used to decide which actions apply to it
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionTarget {
+    Directory,
+    File {
+        mime_type: Option<String>,
+        has_text_preview: bool,
+        /// The file has changes git knows about, staged or not
+        changed_in_git: bool,
+        /// The file is an executable program or script
+        is_program: bool,
+    },
+    Nothing,
+}
+
 /// The action to be performed when a command is executed
-#[derive(Clone)]
+///
+/// The snake_case variant names are used in the `keybindings` section of the settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum CommandAction {
     Quit,
     ShowSettings,
@@ -79,17 +289,242 @@ pub enum CommandAction {
     NavigateDown,
     NavigateLeft,
     NavigateRight,
+    Open,
+    EditSelected,
     JumpToFirst,
     JumpToLast,
     JumpUpBy10,
     JumpDownBy10,
     SetAnchor,
+    ToggleRootLock,
+    ToggleDetailView,
+    ToggleTreeView,
+    JumpToProjectRoot,
+    JumpToScratch,
+    TogglePreview,
+    ScrollPreviewUp,
+    ScrollPreviewDown,
+    FollowPreview,
+    PreviewUp,
+    PreviewDown,
+    PreviewLeft,
+    PreviewRight,
+    GitDiff,
+    ProgramHelp,
+    ShrinkPreview,
+    GrowPreview,
+    NarrowColumn,
+    WidenColumn,
+    NarrowPreviewByCell,
+    WidenPreviewByCell,
+    ResetWidths,
+    AlternateDirectory,
+    SetBookmark,
+    JumpToBookmark,
+    ShowBookmarks,
     SearchChar,
     ShowErrorLog,
     NewTab,
     CloseTab,
     NextTab,
     PrevTab,
+    CycleTabAccent,
+    CopyPreview,
+    CopyPath,
+    ShowActions,
+    ReloadColumn,
+    LoadAllEntries,
+    CopySelected,
+    MoveSelected,
+    MoveToScratch,
+    DeleteSelected,
+    DeletePermanently,
+    ToggleMark,
+    VisualMark,
+    ClearMarks,
+    RenameSelected,
+    TouchSelected,
+    Find,
+    FuzzyFind,
+    Grep,
+    FindEmpty,
+    DiskUsage,
+    GotoPath,
+    Filter,
+    ShellCommand,
+    RecordMacro,
+    PlayMacro,
+}
+
+/// A step of the `startup_commands` setting, run in order when the browser starts
+#[derive(Debug, Clone, PartialEq)]
+pub enum StartupCommand {
+    /// `cd <path>` - go to a directory in the active tab
+    Cd(String),
+    /// `tab <path>` - open a tab at a directory
+    Tab(String),
+    /// `filter <text>` - filter the active directory
+    Filter(String),
+    /// `select <name>` - select an entry of the active directory
+    Select(String),
+    /// An action name such as `next_tab`, run as if its key was pressed
+    Action(CommandAction),
+}
+
+impl StartupCommand {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (verb, rest) = match line.split_once(char::is_whitespace) {
+            Some((verb, rest)) => (verb, rest.trim()),
+            None => (line, ""),
+        };
+
+        let argument = || {
+            if rest.is_empty() {
+                Err(format!("'{}' needs an argument", verb))
+            } else {
+                Ok(rest.to_string())
+            }
+        };
+
+        match verb {
+            "cd" => argument().map(StartupCommand::Cd),
+            "tab" => argument().map(StartupCommand::Tab),
+            "filter" => argument().map(StartupCommand::Filter),
+            "select" => argument().map(StartupCommand::Select),
+            _ if rest.is_empty() => CommandAction::from_name(verb)
+                .map(StartupCommand::Action)
+                .ok_or_else(|| format!("Unknown command '{}'", verb)),
+            _ => Err(format!("'{}' takes no argument", verb)),
+        }
+    }
+}
+
+impl CommandAction {
+    /// Look up an action by its settings name, e.g. `copy_selected`
+    pub fn from_name(name: &str) -> Option<Self> {
+        let deserializer: value::StrDeserializer<value::Error> = name.into_deserializer();
+        CommandAction::deserialize(deserializer).ok()
+    }
+
+    /// Name of the action in the settings file
+    pub fn name(&self) -> String {
+        serde_json::to_value(self)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+
+    /// Whether this action changes files, and so is refused with --read-only
+    pub fn modifies_files(&self) -> bool {
+        matches!(
+            self,
+            CommandAction::CopySelected
+                | CommandAction::MoveSelected
+                | CommandAction::MoveToScratch
+                | CommandAction::DeleteSelected
+                | CommandAction::DeletePermanently
+                | CommandAction::RenameSelected
+                | CommandAction::TouchSelected
+                | CommandAction::EditSelected
+                | CommandAction::ShellCommand
+        )
+    }
+
+    /// Whether this action is offered in the actions menu for the given selection
+    ///
+    /// Every action is listed, so a new one has to be placed in or out of the menu.
+    pub fn is_available_for(&self, target: &ActionTarget) -> bool {
+        match self {
+            CommandAction::NavigateRight => matches!(target, ActionTarget::Directory),
+            CommandAction::Open => matches!(target, ActionTarget::File { .. }),
+            // A file of an unknown type may well be text, but images, archives and the like aren't
+            CommandAction::EditSelected => match target {
+                ActionTarget::File { mime_type, .. } => mime_type.as_deref().is_none_or(is_text_type),
+                _ => false,
+            },
+            CommandAction::CopyPath
+            | CommandAction::CopySelected
+            | CommandAction::MoveSelected
+            | CommandAction::MoveToScratch
+            | CommandAction::DeleteSelected
+            | CommandAction::DeletePermanently => !matches!(target, ActionTarget::Nothing),
+            CommandAction::CopyPreview => {
+                matches!(target, ActionTarget::File { has_text_preview: true, .. })
+            }
+            CommandAction::GitDiff => matches!(target, ActionTarget::File { changed_in_git: true, .. }),
+            CommandAction::ProgramHelp => matches!(target, ActionTarget::File { is_program: true, .. }),
+            CommandAction::ReloadColumn | CommandAction::Find | CommandAction::FindEmpty | CommandAction::GotoPath => true,
+            CommandAction::FuzzyFind | CommandAction::Grep | CommandAction::DiskUsage => true,
+            CommandAction::Filter | CommandAction::ShellCommand => true,
+            CommandAction::ToggleMark
+            | CommandAction::RenameSelected
+            | CommandAction::TouchSelected => !matches!(target, ActionTarget::Nothing),
+            // Navigation, layout, tabs and the like concern the view rather than the selection
+            CommandAction::Quit
+            | CommandAction::ShowSettings
+            | CommandAction::ClearSearch
+            | CommandAction::NavigateUp
+            | CommandAction::NavigateDown
+            | CommandAction::NavigateLeft
+            | CommandAction::JumpToFirst
+            | CommandAction::JumpToLast
+            | CommandAction::JumpUpBy10
+            | CommandAction::JumpDownBy10
+            | CommandAction::SetAnchor
+            | CommandAction::ToggleRootLock
+            | CommandAction::ToggleDetailView
+            | CommandAction::ToggleTreeView
+            | CommandAction::JumpToProjectRoot
+            | CommandAction::JumpToScratch
+            | CommandAction::TogglePreview
+            | CommandAction::ScrollPreviewUp
+            | CommandAction::ScrollPreviewDown
+            | CommandAction::FollowPreview
+            | CommandAction::PreviewUp
+            | CommandAction::PreviewDown
+            | CommandAction::PreviewLeft
+            | CommandAction::PreviewRight
+            | CommandAction::ShrinkPreview
+            | CommandAction::GrowPreview
+            | CommandAction::NarrowColumn
+            | CommandAction::WidenColumn
+            | CommandAction::NarrowPreviewByCell
+            | CommandAction::WidenPreviewByCell
+            | CommandAction::ResetWidths
+            | CommandAction::AlternateDirectory
+            | CommandAction::SetBookmark
+            | CommandAction::JumpToBookmark
+            | CommandAction::ShowBookmarks
+            | CommandAction::SearchChar
+            | CommandAction::ShowErrorLog
+            | CommandAction::NewTab
+            | CommandAction::CloseTab
+            | CommandAction::NextTab
+            | CommandAction::PrevTab
+            | CommandAction::CycleTabAccent
+            | CommandAction::ShowActions
+            | CommandAction::LoadAllEntries
+            | CommandAction::VisualMark
+            | CommandAction::ClearMarks
+            | CommandAction::RecordMacro
+            | CommandAction::PlayMacro => false,
+        }
+    }
+}
+
+/// Whether files of a MIME type are text, and so worth opening in an editor
+fn is_text_type(mime_type: &str) -> bool {
+    const TEXT_APPLICATION_TYPES: [&str; 7] = [
+        "application/json",
+        "application/xml",
+        "application/javascript",
+        "application/typescript",
+        "application/toml",
+        "application/x-yaml",
+        "application/x-sh",
+    ];
+    mime_type.starts_with("text/") || TEXT_APPLICATION_TYPES.contains(&mime_type)
 }
 
 impl Command {
@@ -108,8 +543,53 @@ pub struct CommandRegistry {
 }
 
 impl CommandRegistry {
-    pub fn new() -> Self {
-        let commands = vec![
+    /// Build the registry from the defaults, with the settings' key binding overrides applied
+    ///
+    /// Overridden commands are matched first, so a remapped key takes precedence over
+    /// a default binding of the same key. Invalid entries are reported and skipped.
+    pub fn new(config: &Settings, mut error_log: Option<&mut ErrorLog>) -> Self {
+        let mut overrides = Vec::new();
+        for (name, spec) in &config.keybindings {
+            let Some(action) = CommandAction::from_name(name) else {
+                if let Some(log) = error_log.as_deref_mut() {
+                    log.warning(format!("Unknown action '{}' in keybindings", name), Some("Settings".to_string()));
+                }
+                continue;
+            };
+            // An empty binding unbinds the action
+            if spec.trim().is_empty() {
+                overrides.push((action, None));
+                continue;
+            }
+            match KeyBinding::parse(spec) {
+                Ok(binding) => overrides.push((action, Some(binding))),
+                Err(e) => {
+                    if let Some(log) = error_log.as_deref_mut() {
+                        log.warning(format!("Invalid key binding for '{}': {}", name, e), Some("Settings".to_string()));
+                    }
+                }
+            }
+        }
+
+        let mut commands = Vec::new();
+        let mut defaults = Vec::new();
+        for command in Self::default_commands() {
+            match overrides.iter().find(|(action, _)| *action == command.action) {
+                // An action with several default keys gets just the one configured
+                Some((_, Some(_))) if commands.iter().any(|cmd: &Command| cmd.action == command.action) => {}
+                Some((_, Some(binding))) => commands.push(Command { key_binding: binding.clone(), ..command }),
+                Some((_, None)) => {}
+                None => defaults.push(command),
+            }
+        }
+        commands.extend(defaults);
+
+        Self { commands }
+    }
+
+    /// The built-in commands and their default key bindings
+    fn default_commands() -> Vec<Command> {
+        vec![
             Command::new(
                 KeyBinding::ctrl('c'),
                 "Quit the application",
@@ -127,7 +607,7 @@ impl CommandRegistry {
             ),
             Command::new(
                 KeyBinding::key(KeyCode::Esc),
-                "Clear search string",
+                "Clear search string and range",
                 CommandAction::ClearSearch,
             ),
             Command::new(
@@ -150,6 +630,136 @@ impl CommandRegistry {
                 "Previous tab",
                 CommandAction::PrevTab,
             ),
+            Command::new(
+                KeyBinding::char('C'),
+                "Cycle the tab's accent color",
+                CommandAction::CycleTabAccent,
+            ),
+            Command::new(
+                KeyBinding::ctrl('y'),
+                "Copy previewed text to clipboard",
+                CommandAction::CopyPreview,
+            ),
+            Command::new(
+                KeyBinding::char('Y'),
+                "Copy path of selected entry",
+                CommandAction::CopyPath,
+            ),
+            Command::new(
+                KeyBinding::ctrl('a'),
+                "Show actions for selected entry",
+                CommandAction::ShowActions,
+            ),
+            Command::new(
+                KeyBinding::ctrl('f'),
+                "Find files under current directory",
+                CommandAction::Find,
+            ),
+            Command::new(
+                KeyBinding::ctrl('p'),
+                "Fuzzy find a file by path",
+                CommandAction::FuzzyFind,
+            ),
+            Command::new(
+                KeyBinding::ctrl('g'),
+                "Search file contents under current directory",
+                CommandAction::Grep,
+            ),
+            Command::new(
+                KeyBinding::ctrl('l'),
+                "Go to a path",
+                CommandAction::GotoPath,
+            ),
+            Command::new(
+                KeyBinding::char('q'),
+                "Start/stop recording a macro",
+                CommandAction::RecordMacro,
+            ),
+            Command::new(
+                KeyBinding::char('@'),
+                "Replay the recorded macro",
+                CommandAction::PlayMacro,
+            ),
+            Command::new(
+                KeyBinding::char('!'),
+                "Run a shell command (%f file, %d directory, %s marked)",
+                CommandAction::ShellCommand,
+            ),
+            Command::new(
+                KeyBinding::ctrl('r'),
+                "Reload current directory",
+                CommandAction::ReloadColumn,
+            ),
+            Command::new(
+                KeyBinding::char('A'),
+                "List every entry of a large directory",
+                CommandAction::LoadAllEntries,
+            ),
+            Command::new(
+                KeyBinding::key(KeyCode::F(5)),
+                "Copy selected entry to a directory",
+                CommandAction::CopySelected,
+            ),
+            Command::new(
+                KeyBinding::key(KeyCode::F(6)),
+                "Move selected entry to a directory",
+                CommandAction::MoveSelected,
+            ),
+            Command::new(
+                KeyBinding::char('M'),
+                "Move selected entry to the scratch directory",
+                CommandAction::MoveToScratch,
+            ),
+            Command::new(
+                KeyBinding::key(KeyCode::Delete),
+                "Delete selected entry",
+                CommandAction::DeleteSelected,
+            ),
+            Command::new(
+                KeyBinding::ModifiedKey(KeyCode::Delete, KeyModifiers::SHIFT),
+                "Delete selected entry permanently",
+                CommandAction::DeletePermanently,
+            ),
+            Command::new(
+                KeyBinding::char(' '),
+                "Mark/unmark selected entry",
+                CommandAction::ToggleMark,
+            ),
+            Command::new(
+                KeyBinding::char('v'),
+                "Start/finish marking a range",
+                CommandAction::VisualMark,
+            ),
+            Command::new(
+                KeyBinding::char('/'),
+                "Filter current directory",
+                CommandAction::Filter,
+            ),
+            Command::new(
+                KeyBinding::char('r'),
+                "Rename selected entry",
+                CommandAction::RenameSelected,
+            ),
+            Command::new(
+                KeyBinding::char('E'),
+                "Find empty folders and files",
+                CommandAction::FindEmpty,
+            ),
+            Command::new(
+                KeyBinding::char('D'),
+                "Show disk usage of current directory",
+                CommandAction::DiskUsage,
+            ),
+            Command::new(
+                KeyBinding::char('T'),
+                "Set modification time (touch)",
+                CommandAction::TouchSelected,
+            ),
+            Command::new(
+                KeyBinding::char('U'),
+                "Clear all marks",
+                CommandAction::ClearMarks,
+            ),
             Command::new(
                 KeyBinding::key(KeyCode::Up),
                 "Navigate up",
@@ -170,6 +780,21 @@ impl CommandRegistry {
                 "Navigate to selected directory",
                 CommandAction::NavigateRight,
             ),
+            Command::new(
+                KeyBinding::key(KeyCode::Enter),
+                "Open selected file or directory",
+                CommandAction::Open,
+            ),
+            Command::new(
+                KeyBinding::char('o'),
+                "Open selected file or directory",
+                CommandAction::Open,
+            ),
+            Command::new(
+                KeyBinding::char('e'),
+                "Edit selected file in $EDITOR",
+                CommandAction::EditSelected,
+            ),
             Command::new(
                 KeyBinding::key(KeyCode::Home),
                 "Jump to first item",
@@ -195,14 +820,142 @@ impl CommandRegistry {
                 "Set selected directory as anchor",
                 CommandAction::SetAnchor,
             ),
+            Command::new(
+                KeyBinding::char('L'),
+                "Lock/unlock tab to current directory",
+                CommandAction::ToggleRootLock,
+            ),
+            Command::new(
+                KeyBinding::char('I'),
+                "Toggle detail view (size, date, permissions, owner)",
+                CommandAction::ToggleDetailView,
+            ),
+            Command::new(
+                KeyBinding::char('W'),
+                "Toggle tree view",
+                CommandAction::ToggleTreeView,
+            ),
+            Command::new(
+                KeyBinding::char('P'),
+                "Jump to project root",
+                CommandAction::JumpToProjectRoot,
+            ),
+            Command::new(
+                KeyBinding::char('S'),
+                "Jump to the scratch directory",
+                CommandAction::JumpToScratch,
+            ),
+            Command::new(
+                KeyBinding::char('|'),
+                "Show/hide the preview",
+                CommandAction::TogglePreview,
+            ),
+            Command::new(
+                KeyBinding::char('['),
+                "Narrow the preview",
+                CommandAction::ShrinkPreview,
+            ),
+            Command::new(
+                KeyBinding::char(']'),
+                "Widen the preview",
+                CommandAction::GrowPreview,
+            ),
+            Command::new(
+                KeyBinding::char('<'),
+                "Narrow the active column by a cell",
+                CommandAction::NarrowColumn,
+            ),
+            Command::new(
+                KeyBinding::char('>'),
+                "Widen the active column by a cell",
+                CommandAction::WidenColumn,
+            ),
+            Command::new(
+                KeyBinding::char('('),
+                "Narrow the preview by a cell",
+                CommandAction::NarrowPreviewByCell,
+            ),
+            Command::new(
+                KeyBinding::char(')'),
+                "Widen the preview by a cell",
+                CommandAction::WidenPreviewByCell,
+            ),
+            Command::new(
+                KeyBinding::char('='),
+                "Reset the widths set by a cell",
+                CommandAction::ResetWidths,
+            ),
+            Command::new(
+                KeyBinding::ModifiedKey(KeyCode::PageUp, KeyModifiers::SHIFT),
+                "Scroll the preview up a page",
+                CommandAction::ScrollPreviewUp,
+            ),
+            Command::new(
+                KeyBinding::ModifiedKey(KeyCode::PageDown, KeyModifiers::SHIFT),
+                "Scroll the preview down a page",
+                CommandAction::ScrollPreviewDown,
+            ),
+            Command::new(
+                KeyBinding::ModifiedKey(KeyCode::Up, KeyModifiers::SHIFT),
+                "Move up a JSON or YAML preview, or scroll a table preview up",
+                CommandAction::PreviewUp,
+            ),
+            Command::new(
+                KeyBinding::ModifiedKey(KeyCode::Down, KeyModifiers::SHIFT),
+                "Move down a JSON or YAML preview, or scroll a table preview down",
+                CommandAction::PreviewDown,
+            ),
+            Command::new(
+                KeyBinding::ModifiedKey(KeyCode::Left, KeyModifiers::SHIFT),
+                "Fold a node of a JSON or YAML preview, or scroll a table preview left",
+                CommandAction::PreviewLeft,
+            ),
+            Command::new(
+                KeyBinding::ModifiedKey(KeyCode::Right, KeyModifiers::SHIFT),
+                "Unfold a node of a JSON or YAML preview, or scroll a table preview right",
+                CommandAction::PreviewRight,
+            ),
+            Command::new(
+                KeyBinding::char('F'),
+                "Follow the previewed file as it grows",
+                CommandAction::FollowPreview,
+            ),
+            Command::new(
+                KeyBinding::ctrl('d'),
+                "Show the diff against git HEAD",
+                CommandAction::GitDiff,
+            ),
+            Command::new(
+                KeyBinding::char('K'),
+                "Show the man page or --help of the selected program",
+                CommandAction::ProgramHelp,
+            ),
+            Command::new(
+                KeyBinding::char('-'),
+                "Switch to previous directory",
+                CommandAction::AlternateDirectory,
+            ),
+            Command::new(
+                KeyBinding::char('m'),
+                "Bookmark current directory (then a letter)",
+                CommandAction::SetBookmark,
+            ),
+            Command::new(
+                KeyBinding::char('\''),
+                "Jump to a bookmark (then its letter)",
+                CommandAction::JumpToBookmark,
+            ),
+            Command::new(
+                KeyBinding::char('B'),
+                "Manage bookmarks",
+                CommandAction::ShowBookmarks,
+            ),
             Command::new(
                 KeyBinding::CharRange,
                 "Quick search by typing",
                 CommandAction::SearchChar,
             ),
-        ];
-
-        Self { commands }
+        ]
     }
 
     /// Find a command that matches the given key event
@@ -210,20 +963,77 @@ impl CommandRegistry {
         self.commands.iter().find(|cmd| cmd.key_binding.matches(key))
     }
 
+    /// Look up the keys pressed so far, which may be the start of a multi-key binding
+    ///
+    /// A key that starts a longer binding waits for the rest of it, even if it is
+    /// also bound on its own.
+    pub fn match_keys(&self, keys: &[KeyEvent]) -> KeyMatch<'_> {
+        if self.commands.iter().any(|cmd| cmd.key_binding.matches_keys(keys, true)) {
+            return KeyMatch::Pending;
+        }
+        match self.commands.iter().find(|cmd| cmd.key_binding.matches_keys(keys, false)) {
+            Some(command) => KeyMatch::Command(command),
+            None => KeyMatch::None,
+        }
+    }
+
+    /// Get all commands, in matching order
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+
+    /// Commands that can be remapped, in help order, with their current bindings
+    ///
+    /// Quick search is left out since it is bound to a range of keys.
+    pub fn remappable_commands(&self) -> Vec<(CommandAction, &'static str, Option<&KeyBinding>)> {
+        Self::default_commands()
+            .into_iter()
+            .filter(|command| command.action != CommandAction::SearchChar)
+            .map(|command| {
+                let binding = self.commands.iter()
+                    .find(|cmd| cmd.action == command.action)
+                    .map(|cmd| &cmd.key_binding);
+                (command.action, command.description, binding)
+            })
+            .collect()
+    }
+
+    /// Another command already bound to exactly this binding, if any
+    pub fn conflicting_command(&self, binding: &KeyBinding, action: &CommandAction) -> Option<&Command> {
+        self.commands.iter().find(|cmd| cmd.action != *action && cmd.key_binding == *binding)
+    }
+
+    /// Display text of the key bound to an action, or "-" if it is unbound
+    pub fn key_text(&self, action: &CommandAction) -> String {
+        self.commands
+            .iter()
+            .find(|cmd| cmd.action == *action)
+            .map(|cmd| cmd.key_binding.display_text())
+            .unwrap_or_else(|| "-".to_string())
+    }
+
     /// Get all commands for display in help
     pub fn get_display_commands(&self) -> Vec<(String, &str)> {
         let mut display_commands = Vec::new();
 
         // Group some commands for better display
-        display_commands.push(("Up/Down".to_string(), "Navigate list"));
-        display_commands.push(("Left/Right".to_string(), "Navigate directories"));
-        display_commands.push(("Home/End".to_string(), "Jump to first/last item"));
-        display_commands.push(("PgUp/PgDn".to_string(), "Jump by 10 items"));
+        let groups = [
+            (CommandAction::NavigateUp, CommandAction::NavigateDown, "Navigate list"),
+            (CommandAction::NavigateLeft, CommandAction::NavigateRight, "Navigate directories"),
+            (CommandAction::JumpToFirst, CommandAction::JumpToLast, "Jump to first/last item"),
+            (CommandAction::JumpUpBy10, CommandAction::JumpDownBy10, "Jump by 10 items"),
+        ];
+        for (first, second, description) in groups {
+            display_commands.push((format!("{}/{}", self.key_text(&first), self.key_text(&second)), description));
+        }
 
         // Add tab commands
-        display_commands.push(("Ctrl+T".to_string(), "New tab"));
-        display_commands.push(("Ctrl+W".to_string(), "Close tab"));
-        display_commands.push(("}/{".to_string(), "Next/Previous tab"));
+        display_commands.push((self.key_text(&CommandAction::NewTab), "New tab"));
+        display_commands.push((self.key_text(&CommandAction::CloseTab), "Close tab"));
+        display_commands.push((
+            format!("{}/{}", self.key_text(&CommandAction::NextTab), self.key_text(&CommandAction::PrevTab)),
+            "Next/Previous tab",
+        ));
 
         // Add individual commands that don't need grouping
         for cmd in &self.commands {
@@ -246,3 +1056,109 @@ impl CommandRegistry {
         display_commands
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_startup_commands() {
+        assert_eq!(StartupCommand::parse("tab ~/Downloads"), Ok(StartupCommand::Tab("~/Downloads".to_string())));
+        assert_eq!(StartupCommand::parse(" filter .rs "), Ok(StartupCommand::Filter(".rs".to_string())));
+        assert_eq!(StartupCommand::parse("prev_tab"), Ok(StartupCommand::Action(CommandAction::PrevTab)));
+        assert!(StartupCommand::parse("cd").is_err());
+        assert!(StartupCommand::parse("prev_tab 2").is_err());
+        assert!(StartupCommand::parse("frobnicate").is_err());
+    }
+
+    #[test]
+    fn test_parse_key_binding() {
+        assert_eq!(KeyBinding::parse("ctrl+n"), Ok(KeyBinding::ctrl('n')));
+        assert_eq!(KeyBinding::parse("Ctrl+N"), Ok(KeyBinding::ctrl('n')));
+        assert_eq!(KeyBinding::parse("?"), Ok(KeyBinding::char('?')));
+        assert_eq!(KeyBinding::parse("shift+t"), Ok(KeyBinding::char('T')));
+        assert_eq!(KeyBinding::parse("F5"), Ok(KeyBinding::key(KeyCode::F(5))));
+        assert_eq!(KeyBinding::parse("space"), Ok(KeyBinding::char(' ')));
+        assert_eq!(
+            KeyBinding::parse("shift+delete"),
+            Ok(KeyBinding::ModifiedKey(KeyCode::Delete, KeyModifiers::SHIFT))
+        );
+        assert_eq!(
+            KeyBinding::parse("ctrl++"),
+            Ok(KeyBinding::ModifiedKey(KeyCode::Char('+'), KeyModifiers::CONTROL))
+        );
+        assert_eq!(
+            KeyBinding::parse("g g"),
+            Ok(KeyBinding::Sequence(vec![KeyBinding::char('g'), KeyBinding::char('g')]))
+        );
+        assert!(KeyBinding::parse("hyper+x").is_err());
+        assert!(KeyBinding::parse("").is_err());
+
+        assert_eq!(KeyBinding::parse("shift+del").unwrap().display_text(), "Shift+Del");
+        assert_eq!(KeyBinding::parse("ctrl+f").unwrap().display_text(), "Ctrl+F");
+        assert_eq!(KeyBinding::parse("g g").unwrap().display_text(), "g g");
+    }
+
+    #[test]
+    fn test_key_binding_spec_round_trip() {
+        for spec in ["ctrl+p", "T", "?", "space", "f5", "shift+del", "alt+x", "g g", "pgdn"] {
+            let binding = KeyBinding::parse(spec).unwrap();
+            assert_eq!(KeyBinding::parse(&binding.spec()), Ok(binding), "{}", spec);
+        }
+
+        let captured = KeyBinding::from_key_event(&KeyEvent::new(KeyCode::Char('T'), KeyModifiers::SHIFT));
+        assert_eq!(captured, KeyBinding::char('T'));
+        assert_eq!(CommandAction::CopySelected.name(), "copy_selected");
+    }
+
+    #[test]
+    fn test_actions_filtered_by_file_type() {
+        let file = |mime_type: Option<&str>| ActionTarget::File {
+            mime_type: mime_type.map(str::to_string),
+            has_text_preview: false,
+            changed_in_git: false,
+            is_program: false,
+        };
+        for mime_type in [Some("text/x-rust"), Some("application/json"), None] {
+            assert!(CommandAction::EditSelected.is_available_for(&file(mime_type)), "{:?}", mime_type);
+        }
+        for mime_type in ["image/png", "application/zip", "application/pdf"] {
+            assert!(!CommandAction::EditSelected.is_available_for(&file(Some(mime_type))), "{}", mime_type);
+            assert!(CommandAction::Open.is_available_for(&file(Some(mime_type))), "{}", mime_type);
+        }
+        assert!(!CommandAction::EditSelected.is_available_for(&ActionTarget::Directory));
+        assert!(!CommandAction::GitDiff.is_available_for(&file(Some("text/plain"))));
+    }
+
+    #[test]
+    fn test_registry_applies_overrides() {
+        let mut config = Settings::default();
+        config.keybindings.insert("find".to_string(), "ctrl+p".to_string());
+        config.keybindings.insert("jump_to_first".to_string(), "g g".to_string());
+        config.keybindings.insert("touch_selected".to_string(), "".to_string());
+        config.keybindings.insert("open".to_string(), "l".to_string());
+        let registry = CommandRegistry::new(&config, None);
+
+        let key = |c: char, modifiers: KeyModifiers| KeyEvent::new(KeyCode::Char(c), modifiers);
+        let action = |keys: &[KeyEvent]| match registry.match_keys(keys) {
+            KeyMatch::Command(command) => Some(command.action.clone()),
+            _ => None,
+        };
+
+        assert_eq!(action(&[key('p', KeyModifiers::CONTROL)]), Some(CommandAction::Find));
+        assert_eq!(action(&[key('f', KeyModifiers::CONTROL)]), None);
+        assert!(matches!(registry.match_keys(&[key('g', KeyModifiers::NONE)]), KeyMatch::Pending));
+        assert_eq!(
+            action(&[key('g', KeyModifiers::NONE), key('g', KeyModifiers::NONE)]),
+            Some(CommandAction::JumpToFirst)
+        );
+        // Unbound, so T falls through to nothing rather than touching
+        assert_eq!(action(&[key('T', KeyModifiers::SHIFT)]), None);
+        // Open has two default keys, both replaced by the one configured
+        assert_eq!(action(&[key('l', KeyModifiers::NONE)]), Some(CommandAction::Open));
+        assert_ne!(action(&[key('o', KeyModifiers::NONE)]), Some(CommandAction::Open));
+        assert_eq!(action(&[KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE)]), None);
+        // Other defaults are kept
+        assert_eq!(action(&[key('?', KeyModifiers::NONE)]), Some(CommandAction::ShowSettings));
+    }
+}
@@ -1,18 +1,196 @@
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use crate::icons::{default_icon, IconTheme};
+use crate::jobs::JobKind;
+use crate::utils::resolve_user_path;
+use serde::de::{value, IntoDeserializer};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 // Configuration constants for better flexibility
 pub const DEFAULT_POLL_INTERVAL_MS: u64 = 100;
 pub const SEARCH_TIMEOUT_SECONDS: u64 = 1;
 pub const MAX_COLUMNS_DISPLAY: usize = 5; // Prevent UI from becoming too cluttered
+pub const MAX_HISTORY: usize = 100; // Directories remembered per tab
+
+/// Built-in entry categories and their default icons
+///
+/// They are looked up among the MIME subtypes, so each can be overridden in the
+/// File Types settings tab like any MIME type.
+pub const ENTRY_CATEGORIES: [(&str, &str); 7] = [
+    ("directory", "📁"),
+    ("empty-directory", "📂"),
+    ("git-repo", "🌱"),
+    ("mount-point", "💽"),
+    ("executable", "🚀"),
+    ("symlink", "🔗"),
+    ("broken-symlink", "💔"),
+];
+
+/// Top-level MIME types, offered when typing a file type rule
+pub const KNOWN_PRIMARY_TYPES: [&str; 9] = [
+    "application", "audio", "font", "image", "message", "model", "multipart", "text", "video",
+];
+
+/// Common full MIME types, offered when typing a file type rule
+pub const COMMON_MIME_TYPES: [&str; 36] = [
+    "application/gzip",
+    "application/json",
+    "application/pdf",
+    "application/toml",
+    "application/vnd.sqlite3",
+    "application/wasm",
+    "application/x-7z-compressed",
+    "application/x-bzip2",
+    "application/x-executable",
+    "application/x-sh",
+    "application/x-tar",
+    "application/xml",
+    "application/yaml",
+    "application/zip",
+    "audio/flac",
+    "audio/mpeg",
+    "audio/ogg",
+    "audio/wav",
+    "font/otf",
+    "font/ttf",
+    "font/woff2",
+    "image/gif",
+    "image/jpeg",
+    "image/png",
+    "image/svg+xml",
+    "image/webp",
+    "text/css",
+    "text/csv",
+    "text/html",
+    "text/javascript",
+    "text/markdown",
+    "text/plain",
+    "text/x-python",
+    "text/x-rust",
+    "video/mp4",
+    "video/webm",
+];
+
+/// Characters allowed in a MIME type or subtype name besides letters and digits
+const MIME_NAME_SYMBOLS: &str = "!#$&-^_.+";
+
+/// Check that a file type rule name is a primary type such as `text`, a full MIME
+/// type such as `text/markdown`, or one of the `ENTRY_CATEGORIES`
+pub fn validate_mime_type(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Enter a MIME type".to_string());
+    }
+    if ENTRY_CATEGORIES.iter().any(|(category, _)| *category == name) {
+        return Ok(());
+    }
+
+    let mut parts = name.split('/');
+    let primary = parts.next().unwrap_or_default();
+    let subtype = parts.next();
+    if parts.next().is_some() {
+        return Err(format!("\"{}\" has more than one /", name));
+    }
+
+    for part in std::iter::once(primary).chain(subtype) {
+        if part.is_empty() {
+            return Err(format!("\"{}\" needs a name on both sides of the /", name));
+        }
+        if !part.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+            return Err(format!("\"{}\" must start with a letter or digit", part));
+        }
+        if let Some(c) = part.chars().find(|&c| !c.is_ascii_alphanumeric() && !MIME_NAME_SYMBOLS.contains(c)) {
+            return Err(format!("'{}' isn't allowed in a MIME type", c));
+        }
+    }
+    Ok(())
+}
+
+/// Suggest a known name that `prefix` is the start of, primary types first
+pub fn complete_mime_type(prefix: &str) -> Option<&'static str> {
+    if prefix.is_empty() {
+        return None;
+    }
+    let prefix = prefix.to_ascii_lowercase();
+    KNOWN_PRIMARY_TYPES
+        .into_iter()
+        .chain(COMMON_MIME_TYPES)
+        .chain(ENTRY_CATEGORIES.iter().map(|(category, _)| *category))
+        .find(|name| name.len() > prefix.len() && name.starts_with(&prefix))
+}
 
 /// Configuration for file type rules including icon and preview settings
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct FileTypeRule {
     pub icon: String,
     pub preview: bool,
+    /// Shell command that opens files of this type, instead of the system opener
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_with: Option<String>,
+    /// What Enter does with files of this type, instead of opening them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_enter: Option<EnterAction>,
+}
+
+/// What Enter does with a file
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EnterAction {
+    /// Open it with the rule's `open_with` command, or the system opener
+    #[default]
+    Open,
+    /// Edit it in `$VISUAL` or `$EDITOR`
+    Edit,
+    /// Show its preview over the whole screen
+    Preview,
+    /// Leave it alone
+    Nothing,
+}
+
+/// Which file operations ask for confirmation before they run
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfirmLevel {
+    /// Nothing asks, not even permanent deletes
+    Never,
+    /// Permanent deletes ask, as does removing search results in bulk
+    #[default]
+    Destructive,
+    /// Moving entries to the trash or the scratch directory asks too
+    Everything,
+}
+
+impl ConfirmLevel {
+    /// Whether deleting permanently, or removing search results in bulk, asks first
+    pub fn asks_before_delete(self) -> bool {
+        self != ConfirmLevel::Never
+    }
+
+    /// Whether moving the selection to the trash or the scratch directory asks first
+    ///
+    /// Moves into a typed destination always ask where to, which is confirmation enough.
+    pub fn asks_before_move(self) -> bool {
+        self == ConfirmLevel::Everything
+    }
+
+    /// The level after this one, for cycling through them in the settings
+    pub fn next(self) -> Self {
+        match self {
+            ConfirmLevel::Never => ConfirmLevel::Destructive,
+            ConfirmLevel::Destructive => ConfirmLevel::Everything,
+            ConfirmLevel::Everything => ConfirmLevel::Never,
+        }
+    }
+
+    /// What asks at this level, for the settings
+    pub fn label(self) -> &'static str {
+        match self {
+            ConfirmLevel::Never => "nothing",
+            ConfirmLevel::Destructive => "permanent deletes",
+            ConfirmLevel::Everything => "deletes, trashing and moves",
+        }
+    }
 }
 
 /// Configuration for MIME type handling with primary types and subtypes
@@ -22,12 +200,284 @@ pub struct MimeTypeConfig {
     pub subtypes: HashMap<String, FileTypeRule>,
 }
 
+impl MimeTypeConfig {
+    /// Check whether a rule name covers a whole primary type, rather than one MIME type
+    /// or entry category
+    pub fn is_primary(name: &str) -> bool {
+        !name.contains('/') && !ENTRY_CATEGORIES.iter().any(|(category, _)| *category == name)
+    }
+
+    /// Check whether there is a rule with this name
+    pub fn contains(&self, name: &str) -> bool {
+        self.primary.contains_key(name) || self.subtypes.contains_key(name)
+    }
+
+    /// Add or replace a rule, in the map its name belongs to
+    pub fn insert(&mut self, name: String, rule: FileTypeRule) {
+        if Self::is_primary(&name) {
+            self.primary.insert(name, rule);
+        } else {
+            self.subtypes.insert(name, rule);
+        }
+    }
+
+    /// Remove a rule, wherever it is
+    pub fn remove(&mut self, name: &str) {
+        self.primary.remove(name);
+        self.subtypes.remove(name);
+    }
+}
+
+/// Desktop notification preferences for finished background jobs
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub enabled: bool,
+    /// Only notify for jobs that ran at least this long
+    pub min_duration_secs: u64,
+    /// Job kinds that trigger a notification
+    #[serde(deserialize_with = "known_job_kinds")]
+    pub job_kinds: Vec<JobKind>,
+}
+
+/// Job kinds by name, skipping any this version doesn't have, like the former `checksum`
+fn known_job_kinds<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<JobKind>, D::Error> {
+    let names = Vec::<String>::deserialize(deserializer)?;
+    Ok(names
+        .into_iter()
+        .filter_map(|name| {
+            let deserializer: value::StrDeserializer<value::Error> = name.as_str().into_deserializer();
+            JobKind::deserialize(deserializer).ok()
+        })
+        .collect())
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_duration_secs: 5,
+            job_kinds: JobKind::ALL.to_vec(),
+        }
+    }
+}
+
+/// Most entries of each severity the error log keeps, dropping the oldest beyond that
+///
+/// Errors are kept longest, so a flood of info messages can't push them out.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct ErrorLogLimits {
+    pub errors: usize,
+    pub warnings: usize,
+    pub info: usize,
+}
+
+impl Default for ErrorLogLimits {
+    fn default() -> Self {
+        Self {
+            errors: 1000,
+            warnings: 500,
+            info: 200,
+        }
+    }
+}
+
+impl NotificationSettings {
+    /// Check if a finished job of this kind and duration should notify
+    pub fn should_notify(&self, kind: JobKind, elapsed: Duration) -> bool {
+        self.enabled
+            && elapsed.as_secs() >= self.min_duration_secs
+            && self.job_kinds.contains(&kind)
+    }
+}
+
 /// Main application settings
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Settings {
     pub show_hidden_files: bool,
+    /// Off exactly when `icon_theme` is `none`, which settings from before icon themes lack
     pub show_icons: bool,
+    /// Set of icons entries are drawn with
+    #[serde(default)]
+    pub icon_theme: IconTheme,
     pub mime_types: MimeTypeConfig,
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    #[serde(default)]
+    pub error_log_limits: ErrorLogLimits,
+    /// Delete moves entries to the trash; Shift+Delete always deletes permanently
+    #[serde(default = "default_true")]
+    pub delete_to_trash: bool,
+    /// Which file operations ask before they run
+    #[serde(default)]
+    pub confirm: ConfirmLevel,
+    /// Quick search ignores upper/lower case
+    #[serde(default = "default_true")]
+    pub search_ignore_case: bool,
+    /// Quick search ignores accents, so "e" finds "é"
+    #[serde(default)]
+    pub search_ignore_diacritics: bool,
+    /// Key binding overrides from action name to keys, e.g. `"find": "ctrl+p"` or
+    /// `"jump_to_first": "g g"`; an empty string unbinds the action
+    #[serde(default)]
+    pub keybindings: BTreeMap<String, String>,
+    /// Run in order at startup, e.g. `["tab ~/Downloads", "filter .pdf", "prev_tab"]`;
+    /// besides `cd`, `tab`, `filter` and `select`, any key binding action name works
+    #[serde(default)]
+    pub startup_commands: Vec<String>,
+    /// Tab name for a directory; `{name}`, `{parent}`, `{path}` and `{path:-N}` are filled in
+    #[serde(default = "default_tab_name_template")]
+    pub tab_name_template: String,
+    /// Give each tab an accent color by its position, for the tab bar and the active column's border
+    #[serde(default)]
+    pub tab_accents: bool,
+    /// Bookmarked directories by their key, set with `m` and jumped to with `'`
+    #[serde(default)]
+    pub bookmarks: BTreeMap<char, PathBuf>,
+    /// Directories with more entries than this ask before listing them all
+    #[serde(default = "default_large_directory_entries")]
+    pub large_directory_entries: usize,
+    /// Directories that take longer than this many milliseconds to list are shown as slow; 0 waits for ever
+    #[serde(default = "default_read_timeout_ms")]
+    pub read_timeout_ms: u64,
+    /// Files larger than this many bytes ask before being previewed
+    #[serde(default = "default_large_file_bytes")]
+    pub large_file_bytes: u64,
+    /// How much of a file a preview reads at first, and reads more of at a time when scrolled to the end
+    #[serde(default = "default_preview_bytes")]
+    pub preview_bytes: u64,
+    /// Tell file types from their first bytes as well as their extension; off, the extension alone is
+    /// used, which saves a read per file on slow filesystems
+    #[serde(default = "default_true")]
+    pub sniff_mime_types: bool,
+    /// Offer to reopen the tabs of the previous session when started without paths
+    #[serde(default = "default_true")]
+    pub restore_session: bool,
+    /// Show the git status of entries in directories inside a git worktree
+    #[serde(default = "default_true")]
+    pub git_status: bool,
+    /// Dim entries git ignores
+    #[serde(default = "default_true")]
+    pub dim_git_ignored: bool,
+    /// Walk the subdirectories of the visible columns in the background and show their sizes
+    #[serde(default)]
+    pub dir_sizes: bool,
+    /// Count the entries of the subdirectories in the visible columns and show the count next to them
+    #[serde(default)]
+    pub entry_counts: bool,
+    /// List entries with their size, modification date, permissions and owner, like `ls -l`
+    #[serde(default)]
+    pub detail_view: bool,
+    /// Show the name of the project the active directory is in on the status bar
+    #[serde(default = "default_true")]
+    pub project_in_status: bool,
+    /// Show the space files take on disk next to their apparent size in the detail and disk usage views
+    #[serde(default)]
+    pub allocated_sizes: bool,
+    /// Show the first local image a markdown or HTML file links to under its preview
+    #[serde(default)]
+    pub linked_images: bool,
+    /// Show the preview of the selection next to the columns
+    #[serde(default = "default_true")]
+    pub show_preview: bool,
+    /// Share of the browser's width the preview takes, in percent; unset, it takes a column's share
+    #[serde(default)]
+    pub preview_percent: Option<u16>,
+    /// Directories whose files are previewed from their metadata alone, such as `~/Downloads`
+    #[serde(default)]
+    pub untrusted_roots: Vec<String>,
+    /// Directory to put things to deal with later, created when first used
+    #[serde(default = "default_scratch_dir")]
+    pub scratch_dir: String,
+    /// Refuse operations that change files, set with --read-only for one run
+    #[serde(skip)]
+    pub read_only: bool,
+    /// What Enter chooses when browse runs as a picker with --choose-file or --choose-dir
+    #[serde(skip)]
+    pub pick: Option<PickMode>,
+    /// Value from the settings file while --show-hidden overrides it, saved in its place
+    #[serde(skip)]
+    pub saved_show_hidden_files: Option<bool>,
+    /// File the settings were loaded from and are saved back to, if not `settings_path()`
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+    /// Answers to the large directory and file prompts, kept for this session only
+    #[serde(skip)]
+    pub large_content: LargeContentChoices,
+    /// Cells the active column and the preview were widened by from the keyboard, kept for this session only
+    #[serde(skip)]
+    pub width_adjustment: WidthAdjustment,
+}
+
+/// Kind of entry chosen when browse runs as a picker
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PickMode {
+    Files,
+    Directories,
+}
+
+impl PickMode {
+    /// Check whether a path is the kind of entry being chosen
+    pub fn accepts(self, path: &Path) -> bool {
+        match self {
+            PickMode::Files => !path.is_dir(),
+            PickMode::Directories => path.is_dir(),
+        }
+    }
+
+    /// Name of the kind of entry, for messages
+    pub fn noun(self) -> &'static str {
+        match self {
+            PickMode::Files => "file",
+            PickMode::Directories => "directory",
+        }
+    }
+}
+
+/// Cells added to the widths the layout gives the active column and the preview;
+/// negative to take them away
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct WidthAdjustment {
+    pub column: i16,
+    pub preview: i16,
+}
+
+/// Answers given when asked about loading a large directory or previewing a large file
+#[derive(Debug, Default)]
+pub struct LargeContentChoices {
+    /// Whether to list every entry, by directory
+    pub directories: HashMap<PathBuf, bool>,
+    /// Whether to preview large files, once asked
+    pub file_previews: Option<bool>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_tab_name_template() -> String {
+    "{name}".to_string()
+}
+
+fn default_scratch_dir() -> String {
+    "~/scratch".to_string()
+}
+
+fn default_large_directory_entries() -> usize {
+    1000
+}
+
+fn default_read_timeout_ms() -> u64 {
+    3000
+}
+
+fn default_large_file_bytes() -> u64 {
+    1024 * 1024 * 1024
+}
+
+fn default_preview_bytes() -> u64 {
+    4096
 }
 
 impl Default for Settings {
@@ -35,56 +485,183 @@ impl Default for Settings {
         let mut primary = HashMap::new();
         primary.insert("text".to_string(), FileTypeRule { 
             icon: "📄".to_string(), 
-            preview: true 
+            preview: true,
+            open_with: None,
+            on_enter: None,
         });
         primary.insert("image".to_string(), FileTypeRule { 
             icon: "🖼️".to_string(), 
-            preview: false 
+            preview: false,
+            open_with: None,
+            on_enter: None,
         });
         primary.insert("video".to_string(), FileTypeRule { 
             icon: "🎬".to_string(), 
-            preview: false 
+            preview: false,
+            open_with: None,
+            on_enter: None,
         });
         primary.insert("audio".to_string(), FileTypeRule { 
             icon: "🎵".to_string(), 
-            preview: false 
+            preview: false,
+            open_with: None,
+            on_enter: None,
         });
         primary.insert("application".to_string(), FileTypeRule { 
             icon: "📦".to_string(), 
-            preview: false 
+            preview: false,
+            open_with: None,
+            on_enter: None,
         });
 
         let mut subtypes = HashMap::new();
         subtypes.insert("text/markdown".to_string(), FileTypeRule { 
             icon: "📝".to_string(), 
-            preview: true 
+            preview: true,
+            open_with: None,
+            on_enter: None,
         });
         subtypes.insert("text/x-rust".to_string(), FileTypeRule { 
             icon: "🦀".to_string(), 
-            preview: true 
+            preview: true,
+            open_with: None,
+            on_enter: None,
         });
         subtypes.insert("application/toml".to_string(), FileTypeRule { 
             icon: "🦀".to_string(), 
-            preview: true 
+            preview: true,
+            open_with: None,
+            on_enter: None,
+        });
+        for structured in ["application/json", "application/yaml", "application/x-yaml"] {
+            subtypes.insert(structured.to_string(), FileTypeRule {
+                icon: "📋".to_string(),
+                preview: true,
+                open_with: None,
+                on_enter: None,
+            });
+        }
+        subtypes.insert("application/pdf".to_string(), FileTypeRule {
+            icon: "📕".to_string(),
+            preview: true,
+            open_with: None,
+            on_enter: None,
         });
         subtypes.insert("application/x-sh".to_string(), FileTypeRule { 
             icon: "🚀".to_string(), 
-            preview: true 
-        });
-        subtypes.insert("symlink".to_string(), FileTypeRule { 
-            icon: "🔗".to_string(), 
-            preview: false 
+            preview: true,
+            open_with: None,
+            on_enter: None,
         });
+        for (category, icon) in ENTRY_CATEGORIES {
+            subtypes.insert(category.to_string(), FileTypeRule {
+                icon: icon.to_string(),
+                preview: false,
+                open_with: None,
+                on_enter: None,
+            });
+        }
         
         Self {
             show_hidden_files: false,
             show_icons: true,
+            icon_theme: IconTheme::default(),
             mime_types: MimeTypeConfig { primary, subtypes },
+            notifications: NotificationSettings::default(),
+            error_log_limits: ErrorLogLimits::default(),
+            delete_to_trash: true,
+            confirm: ConfirmLevel::default(),
+            search_ignore_case: true,
+            search_ignore_diacritics: false,
+            keybindings: BTreeMap::new(),
+            startup_commands: Vec::new(),
+            bookmarks: BTreeMap::new(),
+            tab_name_template: default_tab_name_template(),
+            tab_accents: false,
+            large_directory_entries: default_large_directory_entries(),
+            read_timeout_ms: default_read_timeout_ms(),
+            large_file_bytes: default_large_file_bytes(),
+            preview_bytes: default_preview_bytes(),
+            sniff_mime_types: true,
+            restore_session: true,
+            git_status: true,
+            dim_git_ignored: true,
+            dir_sizes: false,
+            entry_counts: false,
+            detail_view: false,
+            project_in_status: true,
+            allocated_sizes: false,
+            linked_images: false,
+            show_preview: true,
+            preview_percent: None,
+            untrusted_roots: Vec::new(),
+            scratch_dir: default_scratch_dir(),
+            read_only: false,
+            pick: None,
+            saved_show_hidden_files: None,
+            source: None,
+            large_content: LargeContentChoices::default(),
+            width_adjustment: WidthAdjustment::default(),
         }
     }
 }
 
 impl Settings {
+    /// Number of entries to list in a directory, or None to list them all
+    pub fn directory_entry_limit(&self, path: &Path) -> Option<usize> {
+        match self.large_content.directories.get(path) {
+            Some(true) => None,
+            _ => Some(self.large_directory_entries),
+        }
+    }
+
+    /// How long to wait for a directory listing, or None to wait as long as it takes
+    pub fn read_timeout(&self) -> Option<Duration> {
+        (self.read_timeout_ms > 0).then(|| Duration::from_millis(self.read_timeout_ms))
+    }
+
+    /// Check whether a file of this size may be previewed without asking first
+    pub fn allows_preview_of(&self, size: u64) -> bool {
+        size <= self.large_file_bytes || self.large_content.file_previews == Some(true)
+    }
+
+    /// Check whether a path is under one of the `untrusted_roots`
+    ///
+    /// Files there are never opened for a preview, nor sniffed for their MIME type.
+    pub fn is_untrusted(&self, path: &Path) -> bool {
+        self.untrusted_roots
+            .iter()
+            .any(|root| path.starts_with(resolve_user_path(root, Path::new("/"))))
+    }
+
+    /// The scratch directory, with `~` expanded
+    pub fn scratch_path(&self) -> PathBuf {
+        resolve_user_path(&self.scratch_dir, Path::new("/"))
+    }
+
+    /// Get the command configured to open files of a MIME type
+    ///
+    /// A subtype rule without a command falls back to its primary type's.
+    pub fn open_command_for(&self, mime_type: &str) -> Option<&str> {
+        let primary_type = mime_type.split('/').next().unwrap_or(mime_type);
+        [self.mime_types.subtypes.get(mime_type), self.mime_types.primary.get(primary_type)]
+            .into_iter()
+            .flatten()
+            .find_map(|rule| rule.open_with.as_deref())
+    }
+
+    /// Get what Enter does with files of a MIME type
+    ///
+    /// A subtype rule without an action falls back to its primary type's.
+    pub fn enter_action_for(&self, mime_type: &str) -> EnterAction {
+        let primary_type = mime_type.split('/').next().unwrap_or(mime_type);
+        [self.mime_types.subtypes.get(mime_type), self.mime_types.primary.get(primary_type)]
+            .into_iter()
+            .flatten()
+            .find_map(|rule| rule.on_enter)
+            .unwrap_or_default()
+    }
+
     /// Get the file type rule for a given MIME type
     pub fn get_rule(&self, mime_type: &str) -> Option<&FileTypeRule> {
         // First check subtypes for exact match
@@ -102,8 +679,42 @@ impl Settings {
         None
     }
 
+    /// Icon given to a file type rule or category in the settings, if it isn't the default one
+    ///
+    /// Like `get_rule`, a subtype's rule is looked at before its primary type's.
+    /// Rules left at their default icon follow the icon theme instead.
+    pub fn custom_icon(&self, mime_type: &str) -> Option<&str> {
+        let primary_type = mime_type.split('/').next().unwrap_or(mime_type);
+        let (name, rule) = [(mime_type, &self.mime_types.subtypes), (primary_type, &self.mime_types.primary)]
+            .into_iter()
+            .find_map(|(name, rules)| Some((name, rules.get(name)?)))?;
+        (default_icon(name) != Some(rule.icon.as_str())).then_some(rule.icon.as_str())
+    }
+
+    /// Cycle to the next icon theme, turning icons off and on with it
+    pub fn cycle_icon_theme(&mut self) {
+        self.icon_theme = self.icon_theme.next();
+        self.show_icons = self.icon_theme != IconTheme::None;
+    }
+
     /// Validate settings and fix any inconsistencies
     pub fn validate_and_fix(&mut self) -> Result<(), String> {
+        // Icons turned off before there were themes stay off
+        if !self.show_icons {
+            self.icon_theme = IconTheme::None;
+        }
+        self.show_icons = self.icon_theme != IconTheme::None;
+
+        // Settings saved before a category existed get its default, so it shows up for editing
+        for (category, icon) in ENTRY_CATEGORIES {
+            self.mime_types.subtypes.entry(category.to_string()).or_insert_with(|| FileTypeRule {
+                icon: icon.to_string(),
+                preview: false,
+                open_with: None,
+                on_enter: None,
+            });
+        }
+
         // Ensure all icons are valid UTF-8 and not empty
         for rule in self.mime_types.primary.values_mut() {
             if rule.icon.is_empty() {
@@ -130,13 +741,19 @@ pub fn settings_path() -> PathBuf {
 
 /// Load settings from file with proper error handling
 pub fn load_settings() -> Result<Settings, Box<dyn std::error::Error>> {
-    let path = settings_path();
-    
+    load_settings_from(&settings_path())
+}
+
+/// Load settings from a given file; a missing file gives the defaults
+pub fn load_settings_from(path: &Path) -> Result<Settings, Box<dyn std::error::Error>> {
     if !path.exists() {
-        return Ok(Settings::default());
+        return Ok(Settings {
+            source: Some(path.to_path_buf()),
+            ..Settings::default()
+        });
     }
     
-    let file = fs::File::open(&path)
+    let file = fs::File::open(path)
         .map_err(|e| format!("Failed to open settings file {:?}: {}", path, e))?;
     
     let mut settings: Settings = serde_json::from_reader(file)
@@ -145,24 +762,108 @@ pub fn load_settings() -> Result<Settings, Box<dyn std::error::Error>> {
     settings.validate_and_fix()
         .map_err(|e| format!("Settings validation failed: {}", e))?;
     
+    settings.source = Some(path.to_path_buf());
     Ok(settings)
 }
 
 /// Save settings to file with proper error handling
+///
+/// They go back to the file they were loaded from, or `settings_path()`.
 pub fn save_settings(settings: &Settings) -> Result<(), Box<dyn std::error::Error>> {
-    let path = settings_path();
-    
+    match &settings.source {
+        Some(path) => save_settings_to(settings, path),
+        None => save_settings_to(settings, &settings_path()),
+    }
+}
+
+/// Save settings to a given file
+pub fn save_settings_to(settings: &Settings, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
     // Create parent directories if they don't exist
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)
             .map_err(|e| format!("Failed to create settings directory: {}", e))?;
     }
     
-    let file = fs::File::create(&path)
+    let file = fs::File::create(path)
         .map_err(|e| format!("Failed to create settings file {:?}: {}", path, e))?;
     
-    serde_json::to_writer_pretty(file, settings)
+    let mut value = serde_json::to_value(settings)
+        .map_err(|e| format!("Failed to write settings: {}", e))?;
+    // --show-hidden lasts for one run, unless hidden files were toggled off since
+    if let Some(saved) = settings.saved_show_hidden_files
+        && settings.show_hidden_files
+    {
+        value["show_hidden_files"] = saved.into();
+    }
+
+    serde_json::to_writer_pretty(file, &value)
         .map_err(|e| format!("Failed to write settings: {}", e))?;
     
     Ok(())
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_mime_type() {
+        assert!(validate_mime_type("text").is_ok());
+        assert!(validate_mime_type("image/svg+xml").is_ok());
+        assert!(validate_mime_type("application/vnd.ms-excel").is_ok());
+        assert!(validate_mime_type("executable").is_ok());
+
+        assert!(validate_mime_type("").is_err());
+        assert!(validate_mime_type("text/").is_err());
+        assert!(validate_mime_type("/plain").is_err());
+        assert!(validate_mime_type("text/plain/extra").is_err());
+        assert!(validate_mime_type("text/pl ain").is_err());
+        assert!(validate_mime_type(".hidden").is_err());
+    }
+
+    #[test]
+    fn test_complete_mime_type() {
+        assert_eq!(complete_mime_type("te"), Some("text"));
+        assert_eq!(complete_mime_type("text/m"), Some("text/markdown"));
+        assert_eq!(complete_mime_type("Image/P"), Some("image/png"));
+        assert_eq!(complete_mime_type("exec"), Some("executable"));
+        assert_eq!(complete_mime_type("text/markdown"), None);
+        assert_eq!(complete_mime_type(""), None);
+    }
+
+    #[test]
+    fn test_enter_action_falls_back_to_primary_type() {
+        let mut config = Settings::default();
+        assert_eq!(config.enter_action_for("text/plain"), EnterAction::Open);
+
+        config.mime_types.primary.get_mut("text").unwrap().on_enter = Some(EnterAction::Edit);
+        config.mime_types.subtypes.get_mut("text/markdown").unwrap().on_enter = Some(EnterAction::Preview);
+        assert_eq!(config.enter_action_for("text/plain"), EnterAction::Edit);
+        assert_eq!(config.enter_action_for("text/markdown"), EnterAction::Preview);
+        // A subtype rule without an action goes by its primary type
+        assert_eq!(config.enter_action_for("text/x-rust"), EnterAction::Edit);
+        assert_eq!(config.enter_action_for("video/mp4"), EnterAction::Open);
+
+        let rule: FileTypeRule = serde_json::from_str(r#"{"icon": "🎬", "preview": false, "on_enter": "nothing"}"#).unwrap();
+        assert_eq!(rule.on_enter, Some(EnterAction::Nothing));
+    }
+
+    #[test]
+    fn test_notifications_skip_job_kinds_since_dropped() {
+        let notifications: NotificationSettings = serde_json::from_str(r#"{"job_kinds": ["copy", "checksum", "shell"]}"#).unwrap();
+        assert_eq!(notifications.job_kinds, [JobKind::Copy, JobKind::Shell]);
+    }
+
+    #[test]
+    fn test_subtype_rules_go_to_subtypes() {
+        let mut config = Settings::default();
+        let rule = FileTypeRule { icon: "📊".to_string(), preview: true, open_with: None, on_enter: None };
+        config.mime_types.insert("text/csv".to_string(), rule.clone());
+        config.mime_types.insert("font".to_string(), rule.clone());
+        config.mime_types.insert("symlink".to_string(), rule);
+
+        assert!(config.mime_types.subtypes.contains_key("text/csv"));
+        assert!(config.mime_types.primary.contains_key("font"));
+        assert!(!config.mime_types.primary.contains_key("symlink"));
+        assert_eq!(config.get_rule("text/csv").unwrap().icon, "📊");
+    }
+}
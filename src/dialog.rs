@@ -0,0 +1,174 @@
+use crate::input::{render_text_input, TextInput};
+use crate::journal::InterruptedOperation;
+use crate::session::Session;
+use crate::ui::centered_fixed_rect;
+use crate::utils::text_width;
+use crossterm::event::{KeyCode, KeyEvent};
+use std::path::PathBuf;
+
+use ratatui::{
+    prelude::*,
+    widgets::*,
+};
+
+/// Operation carried out when a dialog is accepted
+#[derive(Debug, Clone, PartialEq)]
+pub enum DialogAction {
+    /// Copy the paths into the directory typed in the prompt
+    Copy(Vec<PathBuf>),
+    /// Move the paths into the directory typed in the prompt
+    Move(Vec<PathBuf>),
+    /// Permanently delete the paths
+    Delete(Vec<PathBuf>),
+    /// Move the paths to the trash
+    Trash(Vec<PathBuf>),
+    /// Move the paths to the scratch directory
+    MoveToScratch(Vec<PathBuf>),
+    /// Set the modification time of the paths to the timestamp typed in the prompt
+    Touch(Vec<PathBuf>),
+    /// Search the current directory's subtree with the query typed in the prompt
+    Find,
+    /// Search the contents of the files in the current directory's subtree
+    Grep,
+    /// Open the directory typed in the prompt, or reveal the file
+    Goto,
+    /// Run the shell command typed in the prompt in the background
+    Shell,
+    /// List every entry of a directory over the large directory threshold
+    LoadLargeDirectory(PathBuf),
+    /// Preview files over the large file threshold
+    PreviewLargeFiles,
+    /// Reopen the tabs of the previous session
+    RestoreSession(Session),
+    /// Move back what interrupted moves finished; declining keeps things as they are
+    RollBack(Vec<InterruptedOperation>),
+    /// Write the bookmarks to the file typed in the prompt
+    ExportBookmarks,
+    /// Merge in the bookmarks of the file typed in the prompt
+    ImportBookmarks,
+}
+
+impl DialogAction {
+    /// Whether carrying out the action changes files, which a read-only session refuses
+    pub fn modifies_files(&self) -> bool {
+        matches!(
+            self,
+            DialogAction::Copy(_)
+                | DialogAction::Move(_)
+                | DialogAction::Delete(_)
+                | DialogAction::Trash(_)
+                | DialogAction::MoveToScratch(_)
+                | DialogAction::Touch(_)
+                | DialogAction::Shell
+                | DialogAction::RollBack(_)
+        )
+    }
+}
+
+/// Result of a key press in a dialog
+#[derive(Debug, PartialEq)]
+pub enum DialogOutcome {
+    /// Keep the dialog open
+    Continue,
+    /// Close the dialog without doing anything
+    Cancel,
+    /// Close the dialog and run its action, with the prompt text if it has one
+    Accept {
+        action: DialogAction,
+        input: Option<String>,
+    },
+}
+
+/// Modal popup asking the user to confirm an operation or type a value for it
+#[derive(Debug)]
+pub struct Dialog {
+    pub title: String,
+    pub message: String,
+    pub input: Option<TextInput>,
+    pub action: DialogAction,
+}
+
+impl Dialog {
+    /// Create a yes/no confirmation
+    pub fn confirm(title: impl Into<String>, message: impl Into<String>, action: DialogAction) -> Self {
+        Self {
+            title: title.into(),
+            message: message.into(),
+            input: None,
+            action,
+        }
+    }
+
+    /// Create a prompt with an editable value
+    pub fn prompt(title: impl Into<String>, message: impl Into<String>, initial: impl Into<String>, action: DialogAction) -> Self {
+        Self {
+            title: title.into(),
+            message: message.into(),
+            input: Some(TextInput::new(initial)),
+            action,
+        }
+    }
+
+    /// Handle dialog key input
+    pub fn handle_key(&mut self, key: KeyEvent) -> DialogOutcome {
+        match &mut self.input {
+            Some(input) => match key.code {
+                KeyCode::Esc => DialogOutcome::Cancel,
+                KeyCode::Enter => DialogOutcome::Accept {
+                    action: self.action.clone(),
+                    input: Some(input.value().to_string()),
+                },
+                _ => {
+                    input.handle_key(key);
+                    DialogOutcome::Continue
+                }
+            },
+            None => match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => DialogOutcome::Accept {
+                    action: self.action.clone(),
+                    input: None,
+                },
+                KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Char('q') | KeyCode::Esc => DialogOutcome::Cancel,
+                _ => DialogOutcome::Continue,
+            },
+        }
+    }
+}
+
+/// Render a dialog popup
+pub fn render_dialog(frame: &mut Frame, dialog: &Dialog) {
+    let (hint, color) = match (&dialog.input, &dialog.action) {
+        (Some(_), _) => (" Enter to accept, Esc to cancel ", Color::Cyan),
+        (None, DialogAction::Delete(_)) => (" y to confirm, n to cancel ", Color::Red),
+        (None, _) => (" y to confirm, n to cancel ", Color::Cyan),
+    };
+
+    let input_height = if dialog.input.is_some() { 3 } else { 0 };
+    let width = (text_width(&dialog.message) + 6).clamp(50, 90) as u16;
+    let height = 5 + input_height;
+    let area = centered_fixed_rect(width, height, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", dialog.title))
+        .title_bottom(Line::from(hint).centered())
+        .border_style(Style::default().fg(color))
+        .padding(Padding::new(2, 2, 1, 0));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Length(input_height)])
+        .split(inner);
+
+    frame.render_widget(Paragraph::new(dialog.message.as_str()), rows[0]);
+
+    if let Some(input) = &dialog.input {
+        let input_block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::DarkGray));
+        render_text_input(frame, input, input_block, rows[1], true);
+    }
+}
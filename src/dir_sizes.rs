@@ -0,0 +1,273 @@
+//! Directory sizes computed in the background
+//!
+//! With `dir_sizes` turned on, the subdirectories listed in the visible columns
+//! are walked on a worker thread, and the total size of the files under each is
+//! shown next to it once known, as ncdu does. Navigation never waits for it: the
+//! App hands over the directories it wants on every tick, and the worker drops a
+//! directory it is walking when the directory is no longer wanted. Symlinks are
+//! not followed. Sizes are remembered for a while, then walked again.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a computed size is shown before the directory is walked again
+const DIR_SIZE_TTL: Duration = Duration::from_secs(60);
+
+/// How many entries the worker walks between checks that it is still wanted
+const CANCEL_CHECK_INTERVAL: u32 = 256;
+
+/// Size of a directory as far as it is known
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DirSize {
+    /// Waiting for the worker
+    Pending,
+    /// Total size of the files under the directory; `complete` is false when
+    /// some of it couldn't be read
+    Known { bytes: u64, complete: bool },
+}
+
+/// What the App and the worker share
+#[derive(Debug, Default)]
+struct Queue {
+    /// Directories to walk, most wanted first; the first is being walked
+    wanted: Vec<PathBuf>,
+    /// The App is gone, so the worker should stop
+    closed: bool,
+}
+
+/// Hands directories to a worker thread and collects their sizes
+#[derive(Debug)]
+pub struct DirSizes {
+    sizes: HashMap<PathBuf, (Instant, u64, bool)>,
+    queue: Arc<(Mutex<Queue>, Condvar)>,
+    results: Receiver<(PathBuf, u64, bool)>,
+    sender: Option<Sender<(PathBuf, u64, bool)>>,
+}
+
+impl Default for DirSizes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DirSizes {
+    pub fn new() -> Self {
+        let (sender, results) = mpsc::channel();
+        Self {
+            sizes: HashMap::new(),
+            queue: Arc::new((Mutex::new(Queue::default()), Condvar::new())),
+            results,
+            sender: Some(sender),
+        }
+    }
+
+    /// Size of a directory, or None if it hasn't been asked for
+    pub fn get(&self, dir: &Path) -> Option<DirSize> {
+        if let Some(&(_, bytes, complete)) = self.sizes.get(dir) {
+            return Some(DirSize::Known { bytes, complete });
+        }
+        let (queue, _) = &*self.queue;
+        let queue = queue.lock().ok()?;
+        queue.wanted.iter().any(|wanted| wanted == dir).then_some(DirSize::Pending)
+    }
+
+    /// Collect finished sizes, then ask for the directories in `dirs` that need walking
+    ///
+    /// Anything asked for before and not in `dirs` is dropped, including the
+    /// directory being walked.
+    pub fn update(&mut self, dirs: Vec<PathBuf>) {
+        let (queue, ready) = &*self.queue;
+        let Ok(mut queue) = queue.lock() else {
+            return;
+        };
+        // The worker reports while holding the lock, so nothing finished is asked for again
+        for (dir, bytes, complete) in self.results.try_iter() {
+            self.sizes.insert(dir, (Instant::now(), bytes, complete));
+        }
+
+        let wanted: Vec<PathBuf> = dirs
+            .into_iter()
+            .filter(|dir| self.sizes.get(dir).is_none_or(|(computed, _, _)| computed.elapsed() >= DIR_SIZE_TTL))
+            .collect();
+        if queue.wanted == wanted {
+            return;
+        }
+        queue.wanted = wanted;
+        if queue.wanted.is_empty() {
+            return;
+        }
+        ready.notify_one();
+
+        // The worker starts with the first request
+        if let Some(sender) = self.sender.take() {
+            let queue = Arc::clone(&self.queue);
+            thread::spawn(move || work(queue, sender));
+        }
+    }
+
+    /// Forget the sizes of directories under any of `paths`, after files changed there
+    pub fn invalidate(&mut self, paths: &[PathBuf]) {
+        // A change inside a directory changes the size of every directory above it
+        self.sizes.retain(|dir, _| !paths.iter().any(|path| path.starts_with(dir) || dir.starts_with(path)));
+    }
+}
+
+impl Drop for DirSizes {
+    fn drop(&mut self) {
+        let (queue, ready) = &*self.queue;
+        if let Ok(mut queue) = queue.lock() {
+            queue.closed = true;
+        }
+        ready.notify_one();
+    }
+}
+
+/// Worker loop: walk the most wanted directory, report its size, repeat
+fn work(shared: Arc<(Mutex<Queue>, Condvar)>, results: Sender<(PathBuf, u64, bool)>) {
+    let (queue, ready) = &*shared;
+    loop {
+        let dir = {
+            let Ok(mut queue) = queue.lock() else {
+                return;
+            };
+            loop {
+                if queue.closed {
+                    return;
+                }
+                if !queue.wanted.is_empty() {
+                    break;
+                }
+                queue = match ready.wait(queue) {
+                    Ok(queue) => queue,
+                    Err(_) => return,
+                };
+            }
+            queue.wanted[0].clone()
+        };
+
+        let still_wanted = || queue.lock().is_ok_and(|queue| !queue.closed && queue.wanted.contains(&dir));
+        let size = walk(&dir, &still_wanted);
+
+        let Ok(mut queue) = queue.lock() else {
+            return;
+        };
+        if let Some(Walked { bytes, complete, .. }) = size
+            && queue.wanted.contains(&dir)
+            && results.send((dir.clone(), bytes, complete)).is_err()
+        {
+            return;
+        }
+        queue.wanted.retain(|wanted| *wanted != dir);
+    }
+}
+
+/// Sizes of the files under a directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Walked {
+    /// Apparent size, the sum of the file lengths
+    pub bytes: u64,
+    /// Space the files take on disk, which sparse and compressed files make smaller
+    pub allocated: u64,
+    /// False when part of the directory couldn't be read
+    pub complete: bool,
+}
+
+/// Total size of the files under `dir`, and whether all of it could be read
+///
+/// Gives up with None as soon as `still_wanted` says so.
+pub fn walk(dir: &Path, still_wanted: &dyn Fn() -> bool) -> Option<Walked> {
+    let mut bytes = 0;
+    let mut allocated = 0;
+    let mut complete = true;
+    let mut walked = 0;
+    let mut pending = vec![dir.to_path_buf()];
+    let mut seen = HashSet::new();
+
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            complete = false;
+            continue;
+        };
+        for entry in entries {
+            walked += 1;
+            if walked % CANCEL_CHECK_INTERVAL == 0 && !still_wanted() {
+                return None;
+            }
+            let Ok(metadata) = entry.and_then(|entry| {
+                let metadata = entry.metadata()?;
+                if metadata.is_dir() {
+                    pending.push(entry.path());
+                }
+                Ok(metadata)
+            }) else {
+                complete = false;
+                continue;
+            };
+            // Hard links are counted once
+            if metadata.is_file() && seen_once(&mut seen, &metadata) {
+                bytes += metadata.len();
+                allocated += allocated_size(&metadata);
+            }
+        }
+    }
+    Some(Walked { bytes, allocated, complete })
+}
+
+/// Space a file takes on disk; st_blocks counts 512-byte units whatever the filesystem's block size
+pub fn allocated_size(metadata: &fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.blocks() * 512
+}
+
+fn seen_once(seen: &mut HashSet<(u64, u64)>, metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink() <= 1 || seen.insert((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_walk_and_update() {
+        let dir = std::env::temp_dir().join(format!("browse-dir-sizes-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("a/b")).unwrap();
+        fs::write(dir.join("a/one"), [0; 100]).unwrap();
+        fs::write(dir.join("a/b/two"), [0; 20]).unwrap();
+        fs::hard_link(dir.join("a/one"), dir.join("a/b/same")).unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("a/loop")).unwrap();
+
+        let walked = walk(&dir.join("a"), &|| true).unwrap();
+        assert_eq!((walked.bytes, walked.complete), (120, true));
+
+        // A sparse file takes less space on disk than its length
+        fs::create_dir_all(dir.join("sparse")).unwrap();
+        fs::File::create(dir.join("sparse/holes")).unwrap().set_len(1 << 20).unwrap();
+        let walked = walk(&dir.join("sparse"), &|| true).unwrap();
+        assert_eq!(walked.bytes, 1 << 20);
+        assert!(walked.allocated < walked.bytes, "allocated {}", walked.allocated);
+
+        let mut sizes = DirSizes::new();
+        assert_eq!(sizes.get(&dir.join("a")), None);
+        sizes.update(vec![dir.join("a"), dir.join("a/b")]);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while sizes.get(&dir.join("a/b")) != Some(DirSize::Known { bytes: 120, complete: true }) {
+            assert!(Instant::now() < deadline, "sizes were {:?}", sizes.sizes);
+            thread::sleep(Duration::from_millis(10));
+            sizes.update(vec![dir.join("a"), dir.join("a/b")]);
+        }
+        assert_eq!(sizes.get(&dir.join("a")), Some(DirSize::Known { bytes: 120, complete: true }));
+
+        // A change deep down makes the directories above it stale
+        sizes.invalidate(&[dir.join("a/b/two")]);
+        assert_eq!(sizes.get(&dir.join("a/b")), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,366 @@
+//! Disk usage view
+//!
+//! Lists the entries of a directory by the total size of everything under them,
+//! largest first, each with its share of the directory drawn as a bar. The sizes
+//! are worked out on a background job and fill in as each entry is done. Like
+//! `du`, it counts hidden entries too and doesn't follow symlinks.
+//!
+//! The view is browsed like a column: Right or Enter goes into a directory, Left
+//! goes back up, and `g` closes the view with the selected entry revealed. As in
+//! ncdu, the directories gone into are remembered, so going back up to one shows
+//! it again straight away, and the title tells what share of the directory the
+//! view started in the current one takes. Sizes are colored by order of magnitude.
+//!
+//! With allocated sizes turned on, each entry also shows the space it takes on
+//! disk in parentheses, which sparse files and compressing filesystems make smaller
+//! than its apparent size.
+
+use crate::dir_sizes::{allocated_size, walk, Walked};
+use crate::jobs::{JobContext, JobId};
+use crate::ui::centered_rect;
+use crate::utils::{format_file_size, truncate_text};
+use crossterm::event::{KeyCode, KeyEvent};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender};
+
+use ratatui::{
+    prelude::*,
+    widgets::*,
+};
+
+/// Width of the share bars, in cells
+const BAR_WIDTH: usize = 20;
+
+/// Width of the allocated size, as in " (1023.9 KB)"
+const ALLOCATED_WIDTH: usize = 13;
+
+/// Color for a size, by order of magnitude, so the big entries stand out
+pub fn size_color(bytes: u64) -> Color {
+    match bytes {
+        bytes if bytes >= 1 << 30 => Color::Red,
+        bytes if bytes >= 100 << 20 => Color::Yellow,
+        bytes if bytes >= 1 << 20 => Color::Cyan,
+        _ => Color::DarkGray,
+    }
+}
+
+/// An entry of the directory and the size of everything under it
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageEntry {
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub bytes: u64,
+    /// Space taken on disk
+    pub allocated: u64,
+    /// False when part of the entry couldn't be read, so it may be larger
+    pub complete: bool,
+}
+
+/// Work out the size of each entry of `root`, sending them to `results` as they are done
+///
+/// Returns the total size, or an error if the job was cancelled.
+pub fn disk_usage(root: &Path, ctx: &JobContext, results: &Sender<UsageEntry>) -> Result<u64, String> {
+    let entries: Vec<fs::DirEntry> = fs::read_dir(root)
+        .map_err(|e| format!("Cannot read {}: {}", root.display(), e))?
+        .flatten()
+        .collect();
+
+    let mut total = 0;
+    for (index, entry) in entries.iter().enumerate() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let usage = if metadata.is_dir() {
+            let Walked { bytes, allocated, complete } = walk(&entry.path(), &|| !ctx.is_cancelled()).ok_or("cancelled")?;
+            UsageEntry { path: entry.path(), is_dir: true, bytes, allocated, complete }
+        } else {
+            UsageEntry { path: entry.path(), is_dir: false, bytes: metadata.len(), allocated: allocated_size(&metadata), complete: true }
+        };
+        total += usage.bytes;
+        if results.send(usage).is_err() {
+            // Nobody is listening any more
+            return Err("cancelled".to_string());
+        }
+        ctx.progress(index as u64 + 1, Some(entries.len() as u64));
+    }
+    Ok(total)
+}
+
+/// Result of a key press in the disk usage view
+#[derive(Debug, PartialEq)]
+pub enum UsageOutcome {
+    Continue,
+    Close,
+    /// Go into this subdirectory
+    Enter(PathBuf),
+    /// Go back up to the parent directory
+    Up,
+    /// Close the view and reveal this path in the browser
+    Reveal(PathBuf),
+}
+
+/// A directory gone into from the view, kept to show again on the way back up
+#[derive(Debug)]
+struct Level {
+    root: PathBuf,
+    entries: Vec<UsageEntry>,
+    selected: Option<usize>,
+    /// False when the level was left before all of it was worked out
+    finished: bool,
+}
+
+/// Disk usage of one directory, being worked out or done
+#[derive(Debug)]
+pub struct DiskUsagePanel {
+    pub root: PathBuf,
+    /// Largest first
+    pub entries: Vec<UsageEntry>,
+    pub state: ListState,
+    pub job: JobId,
+    receiver: Receiver<UsageEntry>,
+    pub finished: bool,
+    /// Entry to select once it shows up, after going back up from it
+    select: Option<PathBuf>,
+    /// Directories gone into to get here, outermost first
+    parents: Vec<Level>,
+}
+
+impl DiskUsagePanel {
+    pub fn new(root: PathBuf, job: JobId, receiver: Receiver<UsageEntry>, select: Option<PathBuf>) -> Self {
+        Self {
+            root,
+            entries: Vec::new(),
+            state: ListState::default(),
+            job,
+            receiver,
+            finished: false,
+            select,
+            parents: Vec::new(),
+        }
+    }
+
+    /// Go into a subdirectory whose usage `job` is working out, remembering this directory
+    pub fn enter(&mut self, dir: PathBuf, job: JobId, receiver: Receiver<UsageEntry>) {
+        let root = std::mem::replace(&mut self.root, dir);
+        self.parents.push(Level {
+            root,
+            entries: std::mem::take(&mut self.entries),
+            selected: self.state.selected(),
+            finished: self.finished,
+        });
+        self.state = ListState::default();
+        self.job = job;
+        self.receiver = receiver;
+        self.finished = false;
+        self.select = None;
+    }
+
+    /// Show the directory this one was gone into from again
+    ///
+    /// Returns false, leaving the view alone, when there is no such directory or
+    /// it wasn't fully worked out, so it needs working out again.
+    pub fn back(&mut self) -> bool {
+        if !self.parents.last().is_some_and(|level| level.finished) {
+            return false;
+        }
+        let Some(level) = self.parents.pop() else {
+            return false;
+        };
+        self.root = level.root;
+        self.entries = level.entries;
+        self.state.select(level.selected);
+        self.finished = true;
+        self.select = None;
+        // Nothing more is coming for this level
+        self.receiver = std::sync::mpsc::channel().1;
+        true
+    }
+
+    /// The directory the view started in and its size, once this one was gone into from it
+    pub fn outermost(&self) -> Option<(&Path, u64)> {
+        let level = self.parents.first()?;
+        Some((&level.root, level.entries.iter().map(|entry| entry.bytes).sum()))
+    }
+
+    /// Total size of the entries done so far
+    pub fn total(&self) -> u64 {
+        self.entries.iter().map(|entry| entry.bytes).sum()
+    }
+
+    pub fn selected(&self) -> Option<&UsageEntry> {
+        self.state.selected().and_then(|index| self.entries.get(index))
+    }
+
+    /// Collect the entries done since the last poll, keeping the same entry selected
+    pub fn poll(&mut self) {
+        let selected = self.selected().map(|entry| entry.path.clone());
+        let count = self.entries.len();
+        self.entries.extend(self.receiver.try_iter());
+        if self.entries.len() == count {
+            return;
+        }
+        self.entries.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+
+        let position = |path: &PathBuf| self.entries.iter().position(|entry| entry.path == *path);
+        let index = match self.select.as_ref().and_then(position) {
+            Some(index) => {
+                self.select = None;
+                Some(index)
+            }
+            None => selected.as_ref().and_then(|path| self.entries.iter().position(|entry| entry.path == *path)),
+        };
+        self.state.select(index.or(Some(0)));
+    }
+
+    /// Handle disk usage view key input
+    pub fn handle_key(&mut self, key: KeyEvent) -> UsageOutcome {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return UsageOutcome::Close,
+            KeyCode::Right | KeyCode::Enter => {
+                if let Some(entry) = self.selected().filter(|entry| entry.is_dir) {
+                    return UsageOutcome::Enter(entry.path.clone());
+                }
+            }
+            KeyCode::Left | KeyCode::Backspace if self.root.parent().is_some() => return UsageOutcome::Up,
+            KeyCode::Char('g') => {
+                if let Some(entry) = self.selected() {
+                    return UsageOutcome::Reveal(entry.path.clone());
+                }
+            }
+            KeyCode::Up => self.state.select_previous(),
+            KeyCode::Down => self.state.select_next(),
+            KeyCode::PageUp => self.state.scroll_up_by(10),
+            KeyCode::PageDown => self.state.scroll_down_by(10),
+            KeyCode::Home => self.state.select_first(),
+            KeyCode::End => self.state.select_last(),
+            _ => {}
+        }
+
+        // ListState doesn't know the list length, so keep the selection on an entry
+        if let Some(selected) = self.state.selected() {
+            self.state.select(Some(selected.min(self.entries.len().saturating_sub(1))));
+        }
+        UsageOutcome::Continue
+    }
+}
+
+/// A bar `width` cells wide, filled in proportion to `fraction`, with eighths for the last cell
+pub fn share_bar(fraction: f64, width: usize) -> String {
+    const PARTIAL: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+    let eighths = (fraction.clamp(0.0, 1.0) * (width * 8) as f64).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    let partial = PARTIAL[eighths % 8];
+    if partial != ' ' {
+        bar.push(partial);
+    }
+    format!("{:<width$}", bar, width = width)
+}
+
+/// Render the disk usage view popup
+pub fn render_disk_usage_panel(frame: &mut Frame, panel: &DiskUsagePanel, show_allocated: bool) {
+    let area = centered_rect(80, 80, frame.area());
+    frame.render_widget(Clear, area);
+
+    let total = panel.total();
+    let status = if panel.finished { "" } else { ", scanning..." };
+    let share = match panel.outermost() {
+        Some((outermost, outermost_total)) if outermost_total > 0 => {
+            format!(", {:.1}% of {}", total as f64 * 100.0 / outermost_total as f64, outermost.display())
+        }
+        _ => String::new(),
+    };
+    let title = format!(" Disk usage of {} ({}{}{}) ", panel.root.display(), format_file_size(total), share, status);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_bottom(Line::from(" Right/Enter open directory, Left up, g go to, Esc close ").centered())
+        .border_style(Style::default().fg(Color::Cyan))
+        .padding(Padding::horizontal(1));
+
+    // Sizes, percentage and bar take a fixed width; names get the rest
+    let allocated_width = if show_allocated { ALLOCATED_WIDTH } else { 0 };
+    let name_width = (block.inner(area).width as usize).saturating_sub(10 + allocated_width + 7 + BAR_WIDTH + 4);
+    let items: Vec<ListItem> = panel
+        .entries
+        .iter()
+        .map(|entry| {
+            let fraction = if total == 0 { 0.0 } else { entry.bytes as f64 / total as f64 };
+            let mut size = format_file_size(entry.bytes);
+            if !entry.complete {
+                size.push('+');
+            }
+            let mut name = entry.path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            if entry.is_dir {
+                name.push('/');
+            }
+            let color = size_color(entry.bytes);
+            let allocated = if show_allocated {
+                format!(" {:>12}", format!("({})", format_file_size(entry.allocated)))
+            } else {
+                String::new()
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:>10}", size), Style::default().fg(color)),
+                Span::styled(allocated, Style::default().fg(Color::DarkGray)),
+                Span::styled(format!(" {:>5.1}% ", fraction * 100.0), Style::default().fg(Color::DarkGray)),
+                Span::styled(share_bar(fraction, BAR_WIDTH), Style::default().fg(color)),
+                Span::raw(format!("  {}", truncate_text(&name, name_width))),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut state = panel.state.clone();
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_bar() {
+        assert_eq!(share_bar(0.0, 4), "    ");
+        assert_eq!(share_bar(0.5, 4), "██  ");
+        assert_eq!(share_bar(1.0, 4), "████");
+        assert_eq!(share_bar(0.25 + 1.0 / 32.0, 4), "█▏  ");
+        assert_eq!(share_bar(2.0, 2), "██");
+    }
+
+    #[test]
+    fn test_size_color() {
+        assert_eq!(size_color(1000), Color::DarkGray);
+        assert_eq!(size_color(5 << 20), Color::Cyan);
+        assert_eq!(size_color(200 << 20), Color::Yellow);
+        assert_eq!(size_color(3 << 30), Color::Red);
+    }
+
+    #[test]
+    fn test_enter_and_back() {
+        let entry = |name: &str, bytes| UsageEntry { path: PathBuf::from("/top").join(name), is_dir: true, bytes, allocated: bytes, complete: true };
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut panel = DiskUsagePanel::new(PathBuf::from("/top"), 1, receiver, None);
+        sender.send(entry("small", 10)).unwrap();
+        sender.send(entry("big", 30)).unwrap();
+        panel.poll();
+        panel.finished = true;
+        panel.state.select(Some(1));
+        assert_eq!(panel.selected().unwrap().bytes, 10);
+
+        let (_sender, receiver) = std::sync::mpsc::channel();
+        panel.enter(PathBuf::from("/top/small"), 2, receiver);
+        assert!(panel.entries.is_empty());
+        assert_eq!(panel.outermost(), Some((Path::new("/top"), 40)));
+
+        // The finished parent comes back as it was, without working it out again
+        assert!(panel.back());
+        assert_eq!(panel.root, PathBuf::from("/top"));
+        assert_eq!(panel.selected().unwrap().bytes, 10);
+        assert!(!panel.back());
+    }
+}
@@ -0,0 +1,146 @@
+//! Entry counts of directories, read in the background
+//!
+//! With `entry_counts` turned on, the subdirectories listed in the visible
+//! columns have their entries counted on a worker thread, and the count is shown
+//! next to each name, so empty and huge directories stand out before they are
+//! entered. Hidden entries are counted too. A count takes one read of the
+//! directory, so the worker counts the directories it is handed in one batch and
+//! the next batch is sent once it is done. Counts are remembered for a while, or
+//! until files change in the directory.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a count is shown before the directory is read again
+const ENTRY_COUNT_TTL: Duration = Duration::from_secs(10);
+
+/// Number of entries in a directory as far as it is known
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryCount {
+    /// Waiting for the worker
+    Pending,
+    Known(usize),
+    /// The directory couldn't be read
+    Unreadable,
+}
+
+impl EntryCount {
+    /// The count as shown next to a directory's name
+    pub fn text(self) -> String {
+        match self {
+            EntryCount::Pending => "…".to_string(),
+            EntryCount::Known(count) => count.to_string(),
+            EntryCount::Unreadable => "?".to_string(),
+        }
+    }
+}
+
+/// Hands directories to a worker thread and collects their entry counts
+#[derive(Debug, Default)]
+pub struct EntryCounts {
+    counts: HashMap<PathBuf, (Instant, Option<usize>)>,
+    /// Directories being counted
+    pending: Vec<PathBuf>,
+    /// Where the counts of the pending directories arrive
+    results: Option<Receiver<(PathBuf, Option<usize>)>>,
+}
+
+impl EntryCounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Entry count of a directory, or None if it hasn't been asked for
+    pub fn get(&self, dir: &Path) -> Option<EntryCount> {
+        if let Some(&(_, count)) = self.counts.get(dir) {
+            return Some(count.map_or(EntryCount::Unreadable, EntryCount::Known));
+        }
+        self.pending.iter().any(|pending| pending == dir).then_some(EntryCount::Pending)
+    }
+
+    /// Collect finished counts, then, once the last batch is done, count the
+    /// directories in `dirs` that haven't been counted lately
+    pub fn update(&mut self, dirs: Vec<PathBuf>) {
+        if let Some(results) = &self.results {
+            loop {
+                match results.try_recv() {
+                    Ok((dir, count)) => {
+                        self.counts.insert(dir, (Instant::now(), count));
+                    }
+                    Err(TryRecvError::Empty) => return,
+                    Err(TryRecvError::Disconnected) => break,
+                }
+            }
+            self.pending.clear();
+            self.results = None;
+        }
+
+        let wanted: Vec<PathBuf> = dirs
+            .into_iter()
+            .filter(|dir| self.counts.get(dir).is_none_or(|(counted, _)| counted.elapsed() >= ENTRY_COUNT_TTL))
+            .collect();
+        if wanted.is_empty() {
+            return;
+        }
+        let (sender, receiver) = mpsc::channel();
+        let dirs = wanted.clone();
+        thread::spawn(move || {
+            for dir in dirs {
+                let count = count_entries(&dir);
+                if sender.send((dir, count)).is_err() {
+                    return;
+                }
+            }
+        });
+        self.pending = wanted;
+        self.results = Some(receiver);
+    }
+
+    /// Forget the counts of directories at or under any of `paths`, after files changed there
+    pub fn invalidate(&mut self, paths: &[PathBuf]) {
+        self.counts.retain(|dir, _| !paths.iter().any(|path| dir.starts_with(path)));
+    }
+}
+
+/// Number of entries in a directory, hidden ones included, or None if it can't be read
+pub fn count_entries(dir: &Path) -> Option<usize> {
+    fs::read_dir(dir).ok().map(Iterator::count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_arrive_and_are_forgotten_after_changes() {
+        let dir = std::env::temp_dir().join(format!("browse-entry-counts-{}", std::process::id()));
+        fs::create_dir_all(dir.join("full/nested")).unwrap();
+        fs::create_dir_all(dir.join("empty")).unwrap();
+        fs::write(dir.join("full/.hidden"), "").unwrap();
+        fs::write(dir.join("full/a.txt"), "").unwrap();
+
+        let mut counts = EntryCounts::new();
+        let dirs = vec![dir.join("full"), dir.join("empty"), dir.join("missing")];
+        counts.update(dirs.clone());
+        assert_eq!(counts.get(&dir.join("empty")), Some(EntryCount::Pending));
+        assert_eq!(counts.get(&dir), None);
+
+        let started = Instant::now();
+        while counts.get(&dir.join("missing")) == Some(EntryCount::Pending) && started.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+            counts.update(dirs.clone());
+        }
+        assert_eq!(counts.get(&dir.join("full")), Some(EntryCount::Known(3)));
+        assert_eq!(counts.get(&dir.join("empty")), Some(EntryCount::Known(0)));
+        assert_eq!(counts.get(&dir.join("missing")), Some(EntryCount::Unreadable));
+
+        counts.invalidate(&[dir.join("full")]);
+        assert_eq!(counts.get(&dir.join("full")), None);
+        assert_eq!(counts.get(&dir.join("empty")), Some(EntryCount::Known(0)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,14 +1,15 @@
 use chrono::{DateTime, Local};
 use std::collections::VecDeque;
+use std::error::Error;
+use std::io;
+use std::path::{Path, PathBuf};
 use ratatui::{
     prelude::*,
     widgets::*,
 };
+use crate::config::ErrorLogLimits;
 use crate::utils::truncate_text;
 
-/// Maximum number of error entries to keep in memory
-const MAX_ERROR_ENTRIES: usize = 1000;
-
 /// Represents a single error entry in the log
 #[derive(Debug, Clone)]
 pub struct ErrorEntry {
@@ -16,6 +17,22 @@ pub struct ErrorEntry {
     pub message: String,
     pub context: Option<String>,
     pub severity: ErrorSeverity,
+    /// The file operation that failed, if the error came from one
+    pub source: Option<ErrorSource>,
+    /// Underlying errors, outermost first, that the message doesn't already say
+    pub causes: Vec<String>,
+    /// Seen and dismissed, so it no longer counts towards the status bar badge
+    pub acknowledged: bool,
+}
+
+/// The file operation an error came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorSource {
+    /// What was being done, such as "open directory"
+    pub operation: String,
+    pub path: PathBuf,
+    /// Kind of the I/O error behind it, if any
+    pub kind: Option<io::ErrorKind>,
 }
 
 /// Error severity levels
@@ -51,7 +68,47 @@ impl ErrorEntry {
             message,
             context,
             severity,
+            source: None,
+            causes: Vec::new(),
+            acknowledged: false,
+        }
+    }
+
+    /// Record the operation and path an error came from, along with its causes
+    pub fn with_source(mut self, operation: impl Into<String>, path: impl Into<PathBuf>, error: &(dyn Error + 'static)) -> Self {
+        let kind = chain(error).find_map(|cause| cause.downcast_ref::<io::Error>()).map(io::Error::kind);
+        self.source = Some(ErrorSource {
+            operation: operation.into(),
+            path: path.into(),
+            kind,
+        });
+        self.with_causes(error)
+    }
+
+    /// Record the chain of errors behind this one
+    pub fn with_causes(mut self, error: &(dyn Error + 'static)) -> Self {
+        self.causes = chain(error)
+            .map(|cause| cause.to_string())
+            .filter(|cause| !self.message.contains(cause.as_str()))
+            .collect();
+        self
+    }
+
+    /// Lines shown when the entry is expanded: the entry, where it came from and why
+    pub fn details(&self) -> Vec<String> {
+        let mut lines = vec![self.format_for_display()];
+        if let Some(source) = &self.source {
+            lines.push(format!("Operation: {}", source.operation));
+            lines.push(format!("Path: {}", source.path.display()));
+            if let Some(kind) = source.kind {
+                lines.push(format!("Kind: {}", kind));
+            }
+        }
+        for (index, cause) in self.causes.iter().enumerate() {
+            let label = if index == 0 { "Caused by:" } else { "          " };
+            lines.push(format!("{} {}", label, cause));
         }
+        lines
     }
 
     pub fn error(message: String, context: Option<String>) -> Self {
@@ -66,6 +123,11 @@ impl ErrorEntry {
         Self::new(message, context, ErrorSeverity::Info)
     }
 
+    /// Check whether another entry reports the same thing
+    fn repeats(&self, other: &ErrorEntry) -> bool {
+        self.severity == other.severity && self.message == other.message && self.context == other.context
+    }
+
     /// Format the error entry for display
     pub fn format_for_display(&self) -> String {
         let timestamp = self.timestamp.format("%H:%M:%S");
@@ -84,35 +146,80 @@ impl ErrorEntry {
     }
 }
 
+/// An error followed by each of its sources in turn
+fn chain<'a>(error: &'a (dyn Error + 'static)) -> impl Iterator<Item = &'a (dyn Error + 'static)> {
+    std::iter::successors(Some(error), |&error| error.source())
+}
+
 /// Error log manager
 #[derive(Debug)]
 pub struct ErrorLog {
     entries: VecDeque<ErrorEntry>,
-    unread_count: usize,
     selected_index: usize,
     is_visible: bool,
     expanded_entries: std::collections::HashSet<usize>,
+    limits: ErrorLogLimits,
 }
 
 impl ErrorLog {
     pub fn new() -> Self {
         Self {
             entries: VecDeque::new(),
-            unread_count: 0,
             selected_index: 0,
             is_visible: false,
             expanded_entries: std::collections::HashSet::new(),
+            limits: ErrorLogLimits::default(),
         }
     }
 
-    /// Add an error entry to the log
-    pub fn add_entry(&mut self, entry: ErrorEntry) {
-        if self.entries.len() >= MAX_ERROR_ENTRIES {
-            self.entries.pop_front();
+    /// Change how many entries of each severity are kept, dropping any now over the limit
+    pub fn set_limits(&mut self, limits: ErrorLogLimits) {
+        self.limits = limits;
+        for severity in [ErrorSeverity::Error, ErrorSeverity::Warning, ErrorSeverity::Info] {
+            while self.entries.iter().filter(|entry| entry.severity == severity).count() > self.limit_for(&severity) {
+                self.remove_oldest(&severity);
+            }
         }
+    }
 
+    /// Most entries of a severity to keep
+    fn limit_for(&self, severity: &ErrorSeverity) -> usize {
+        match severity {
+            ErrorSeverity::Error => self.limits.errors,
+            ErrorSeverity::Warning => self.limits.warnings,
+            ErrorSeverity::Info => self.limits.info,
+        }
+    }
+
+    /// Add an error entry to the log
+    ///
+    /// Past the limit for its severity, the oldest entry of that severity is dropped.
+    /// An entry repeating one that was acknowledged is acknowledged too.
+    pub fn add_entry(&mut self, mut entry: ErrorEntry) {
+        entry.acknowledged = self.entries.iter().any(|known| known.acknowledged && known.repeats(&entry));
+        let severity = entry.severity.clone();
         self.entries.push_back(entry);
-        self.unread_count += 1;
+
+        if self.entries.iter().filter(|entry| entry.severity == severity).count() > self.limit_for(&severity) {
+            self.remove_oldest(&severity);
+        }
+    }
+
+    /// Drop the oldest entry of a severity, keeping the selection and expanded entries in place
+    fn remove_oldest(&mut self, severity: &ErrorSeverity) {
+        let Some(index) = self.entries.iter().position(|entry| &entry.severity == severity) else {
+            return;
+        };
+        self.entries.remove(index);
+
+        self.expanded_entries = self.expanded_entries
+            .iter()
+            .filter(|&&expanded| expanded != index)
+            .map(|&expanded| if expanded > index { expanded - 1 } else { expanded })
+            .collect();
+        if self.selected_index > index || self.selected_index >= self.entries.len() {
+            self.selected_index = self.selected_index.saturating_sub(1);
+        }
     }
 
     /// Add an error message
@@ -134,9 +241,28 @@ impl ErrorLog {
         &self.entries
     }
 
-    /// Get the number of unread error entries
-    pub fn unread_count(&self) -> usize {
-        self.unread_count
+    /// Get the number of entries that haven't been acknowledged
+    pub fn unacknowledged_count(&self) -> usize {
+        self.entries.iter().filter(|entry| !entry.acknowledged).count()
+    }
+
+    /// Check if any error (as opposed to a warning or info) hasn't been acknowledged
+    pub fn has_unacknowledged_errors(&self) -> bool {
+        self.entries.iter().any(|entry| !entry.acknowledged && entry.severity == ErrorSeverity::Error)
+    }
+
+    /// Acknowledge the selected entry, or take back its acknowledgement
+    pub fn toggle_selected_acknowledged(&mut self) {
+        if let Some(entry) = self.entries.get_mut(self.selected_index) {
+            entry.acknowledged = !entry.acknowledged;
+        }
+    }
+
+    /// Acknowledge every entry
+    pub fn acknowledge_all(&mut self) {
+        for entry in &mut self.entries {
+            entry.acknowledged = true;
+        }
     }
 
     /// Check if the error log panel is visible
@@ -148,8 +274,6 @@ impl ErrorLog {
     pub fn toggle_visibility(&mut self) {
         self.is_visible = !self.is_visible;
         if self.is_visible {
-            // Mark all entries as read when panel becomes visible
-            self.unread_count = 0;
             // Reset selection to the most recent entry
             if !self.entries.is_empty() {
                 self.selected_index = self.entries.len() - 1;
@@ -195,10 +319,21 @@ impl ErrorLog {
         }
     }
 
+    /// Get the selected entry
+    pub fn selected_entry(&self) -> Option<&ErrorEntry> {
+        self.entries.get(self.selected_index)
+    }
+
+    /// Path the selected entry is about, if it came from a file operation
+    pub fn selected_path(&self) -> Option<&Path> {
+        self.selected_entry()
+            .and_then(|entry| entry.source.as_ref())
+            .map(|source| source.path.as_path())
+    }
+
     /// Clear all error entries
     pub fn clear(&mut self) {
         self.entries.clear();
-        self.unread_count = 0;
         self.selected_index = 0;
     }
 
@@ -236,7 +371,13 @@ pub fn render_error_log(frame: &mut Frame, error_log: &ErrorLog, area: Rect) {
         return;
     }
 
-    let title = format!("Error Log ({} entries) - Press Enter to expand/collapse", error_log.entries().len());
+    let go_help = if error_log.selected_path().is_some() { ", g to go to its path" } else { "" };
+    let title = format!(
+        "Error Log ({} entries, {} new) - Press Enter to expand/collapse, a to acknowledge{}",
+        error_log.entries().len(),
+        error_log.unacknowledged_count(),
+        go_help
+    );
 
     // For expanded entries, we need to use a different approach
     let selected_index = error_log.selected_index();
@@ -258,12 +399,13 @@ pub fn render_error_log(frame: &mut Frame, error_log: &ErrorLog, area: Rect) {
                 let display_text = entry.format_for_display();
                 let text = truncate_text(&display_text, chunks[0].width.saturating_sub(4) as usize);
 
-                // Color code by severity
+                // Color code by severity, dimmed once acknowledged
                 let style = match entry.severity {
                     ErrorSeverity::Error => Style::default().fg(Color::Red),
                     ErrorSeverity::Warning => Style::default().fg(Color::Yellow),
                     ErrorSeverity::Info => Style::default().fg(Color::Blue),
                 };
+                let style = if entry.acknowledged { style.add_modifier(Modifier::DIM) } else { style };
 
                 // Add expansion indicator for selected item
                 let final_text = if index == selected_index {
@@ -292,7 +434,7 @@ pub fn render_error_log(frame: &mut Frame, error_log: &ErrorLog, area: Rect) {
 
         // Render the expanded entry in the bottom area
         if let Some(entry) = error_log.entries().get(selected_index) {
-            let display_text = entry.format_for_display();
+            let display_text = entry.details().join("\n");
             let style = match entry.severity {
                 ErrorSeverity::Error => Style::default().fg(Color::Red),
                 ErrorSeverity::Warning => Style::default().fg(Color::Yellow),
@@ -321,12 +463,13 @@ pub fn render_error_log(frame: &mut Frame, error_log: &ErrorLog, area: Rect) {
                 let display_text = entry.format_for_display();
                 let text = truncate_text(&display_text, area.width.saturating_sub(4) as usize);
 
-                // Color code by severity
+                // Color code by severity, dimmed once acknowledged
                 let style = match entry.severity {
                     ErrorSeverity::Error => Style::default().fg(Color::Red),
                     ErrorSeverity::Warning => Style::default().fg(Color::Yellow),
                     ErrorSeverity::Info => Style::default().fg(Color::Blue),
                 };
+                let style = if entry.acknowledged { style.add_modifier(Modifier::DIM) } else { style };
 
                 // Add expansion indicator for selected item
                 let final_text = if index == selected_index {
@@ -367,7 +510,7 @@ mod tests {
         let mut error_log = ErrorLog::new();
 
         // Test initial state
-        assert_eq!(error_log.unread_count(), 0);
+        assert_eq!(error_log.unacknowledged_count(), 0);
         assert_eq!(error_log.entries().len(), 0);
         assert!(!error_log.is_visible());
 
@@ -376,14 +519,14 @@ mod tests {
         error_log.warning("Test warning".to_string(), None);
         error_log.info("Test info".to_string(), Some("Info".to_string()));
 
-        assert_eq!(error_log.unread_count(), 3);
+        assert_eq!(error_log.unacknowledged_count(), 3);
         assert_eq!(error_log.entries().len(), 3);
         assert!(error_log.has_errors());
 
         // Test visibility toggle
         error_log.toggle_visibility();
         assert!(error_log.is_visible());
-        assert_eq!(error_log.unread_count(), 0); // Should be marked as read
+        assert_eq!(error_log.unacknowledged_count(), 3); // Opening the log doesn't acknowledge anything
 
         // Test navigation - should start at last entry (index 2)
         assert_eq!(error_log.selected_index(), 2);
@@ -404,10 +547,33 @@ mod tests {
         // Test clear
         error_log.clear();
         assert_eq!(error_log.entries().len(), 0);
-        assert_eq!(error_log.unread_count(), 0);
+        assert_eq!(error_log.unacknowledged_count(), 0);
         assert!(!error_log.is_entry_expanded(0)); // Should clear expanded entries too
     }
 
+    #[test]
+    fn test_acknowledged_entries() {
+        let mut error_log = ErrorLog::new();
+        error_log.error("Disk full".to_string(), None);
+        error_log.warning("Slow mount".to_string(), Some("Mounts".to_string()));
+        assert!(error_log.has_unacknowledged_errors());
+
+        error_log.select_first();
+        error_log.toggle_selected_acknowledged();
+        assert!(error_log.entries()[0].acknowledged);
+        assert!(!error_log.has_unacknowledged_errors());
+        assert!(error_log.has_errors());
+        assert_eq!(error_log.unacknowledged_count(), 1);
+
+        // A known error coming back stays quiet, a new one doesn't
+        error_log.error("Disk full".to_string(), None);
+        error_log.error("Disk full".to_string(), Some("Copy".to_string()));
+        assert_eq!(error_log.unacknowledged_count(), 2);
+
+        error_log.acknowledge_all();
+        assert_eq!(error_log.unacknowledged_count(), 0);
+    }
+
     #[test]
     fn test_error_entry_formatting() {
         let entry = ErrorEntry::error(
@@ -422,6 +588,58 @@ mod tests {
         assert!(formatted.contains("❌"));
     }
 
+    #[test]
+    fn test_error_entry_source_and_causes() {
+        let inner = io::Error::new(io::ErrorKind::PermissionDenied, "Permission denied");
+        let error = color_eyre::eyre::Report::new(inner).wrap_err("Failed to read /srv/data");
+        let entry = ErrorEntry::error(format!("Cannot open /srv/data: {}", error), None)
+            .with_source("open directory", "/srv/data", error.as_ref());
+
+        let source = entry.source.as_ref().unwrap();
+        assert_eq!(source.operation, "open directory");
+        assert_eq!(source.kind, Some(io::ErrorKind::PermissionDenied));
+        // The outer error is already in the message, so only the cause is kept
+        assert_eq!(entry.causes, ["Permission denied"]);
+
+        let details = entry.details().join("\n");
+        assert!(details.contains("Path: /srv/data"));
+        assert!(details.contains("Kind: permission denied"));
+        assert!(details.contains("Caused by: Permission denied"));
+    }
+
+    #[test]
+    fn test_retention_limits_per_severity() {
+        let mut error_log = ErrorLog::new();
+        error_log.set_limits(ErrorLogLimits { errors: 2, warnings: 1, info: 3 });
+
+        error_log.error("first error".to_string(), None);
+        for i in 0..10 {
+            error_log.info(format!("click {}", i), None);
+        }
+        error_log.warning("old warning".to_string(), None);
+        error_log.warning("new warning".to_string(), None);
+
+        // The info flood only pushed out older info entries
+        let messages: Vec<&str> = error_log.entries().iter().map(|entry| entry.message.as_str()).collect();
+        assert_eq!(messages, ["first error", "click 7", "click 8", "click 9", "new warning"]);
+
+        error_log.error("second error".to_string(), None);
+        error_log.error("third error".to_string(), None);
+        assert!(!error_log.entries().iter().any(|entry| entry.message == "first error"));
+        assert_eq!(error_log.entries().len(), 6);
+
+        // Expanded entries stay with their entry as older ones are dropped
+        error_log.toggle_visibility();
+        error_log.select_first();
+        error_log.select_next();
+        error_log.toggle_selected_wrap();
+        assert!(error_log.is_entry_expanded(1));
+        error_log.info("click 10".to_string(), None);
+        assert!(error_log.is_entry_expanded(0));
+        assert_eq!(error_log.selected_index(), 0);
+        assert_eq!(error_log.entries()[0].message, "click 8");
+    }
+
     #[test]
     fn test_error_severity() {
         assert_eq!(ErrorSeverity::Error.display_prefix(), "❌");
@@ -0,0 +1,366 @@
+//! Running external programs
+//!
+//! Commands that take over the terminal, such as opening a file in another
+//! application, are queued by the App and run by the main loop, which suspends
+//! the TUI around them. Shell commands typed after `!` run in the background
+//! with their output captured instead.
+//!
+//! The documentation of a program is read the same way: its man page when it
+//! sits in a directory programs are run from, or else what it prints for
+//! `--help`, which is given a few seconds before the program is killed.
+
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+use std::process::{self, ExitStatus, Output, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How often a running shell command is checked for having finished or been cancelled
+const SHELL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long `man` or a program's `--help` may run before it is killed
+const HELP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The platform's default opener
+#[cfg(target_os = "macos")]
+pub const SYSTEM_OPENER: &str = "open";
+#[cfg(not(target_os = "macos"))]
+pub const SYSTEM_OPENER: &str = "xdg-open";
+
+/// A program to run with the terminal handed over to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalCommand {
+    pub program: OsString,
+    pub args: Vec<OsString>,
+    /// Working directory of the program
+    pub dir: PathBuf,
+    /// The file the program works on
+    pub path: PathBuf,
+    /// What the command does, e.g. "open" or "edit", for error messages
+    pub operation: &'static str,
+    /// Variables added to the program's environment
+    pub env: Vec<(&'static str, OsString)>,
+}
+
+impl ExternalCommand {
+    /// Open `path` with a configured shell command, or the system opener without one
+    ///
+    /// The path is passed to the shell as `$1` after the command, so it needs no quoting.
+    pub fn open(path: &Path, command: Option<&str>) -> Self {
+        match command {
+            Some(command) => Self::shell(command, path, "open"),
+            None => Self {
+                program: OsString::from(SYSTEM_OPENER),
+                args: vec![path.as_os_str().to_owned()],
+                dir: parent_dir(path),
+                path: path.to_path_buf(),
+                operation: "open",
+                env: Vec::new(),
+            },
+        }
+    }
+
+    /// Edit `path` with `$VISUAL` or `$EDITOR`, falling back to `vi`
+    pub fn edit(path: &Path) -> Self {
+        let editor = ["VISUAL", "EDITOR"]
+            .into_iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|editor| !editor.trim().is_empty())
+            .unwrap_or_else(|| "vi".to_string());
+        Self::shell(&editor, path, "edit")
+    }
+
+    /// Run a shell command with `path` as its last argument
+    ///
+    /// The command may include its own arguments, as in `code --wait`.
+    fn shell(command: &str, path: &Path, operation: &'static str) -> Self {
+        Self {
+            program: OsString::from("sh"),
+            args: vec![
+                OsString::from("-c"),
+                OsString::from(format!("{} \"$@\"", command)),
+                OsString::from("browse"),
+                path.as_os_str().to_owned(),
+            ],
+            dir: parent_dir(path),
+            path: path.to_path_buf(),
+            operation,
+            env: Vec::new(),
+        }
+    }
+
+    /// Add variables to the program's environment
+    pub fn with_env(mut self, env: Vec<(&'static str, OsString)>) -> Self {
+        self.env = env;
+        self
+    }
+
+    /// Run the program and wait for it to exit
+    ///
+    /// Its output goes to `stdout` when given, rather than to ours.
+    pub fn run(&self, stdout: Option<File>) -> io::Result<ExitStatus> {
+        let mut command = process::Command::new(&self.program);
+        command.args(&self.args).envs(self.env.iter().cloned());
+        if self.dir.is_dir() {
+            command.current_dir(&self.dir);
+        }
+        if let Some(stdout) = stdout {
+            command.stdout(stdout);
+        }
+        command.status()
+    }
+
+    /// The command line, for error messages
+    pub fn display(&self) -> String {
+        std::iter::once(&self.program)
+            .chain(&self.args)
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Expand the placeholders in a shell command, quoting the paths they stand for
+///
+/// `%f` is the selected file, `%d` the current directory, `%s` the marked files
+/// separated by spaces and `%%` a literal `%`. Anything else is left as it is.
+pub fn expand_placeholders(command: &str, file: Option<&Path>, dir: &Path, marked: &[PathBuf]) -> OsString {
+    let mut expanded = Vec::new();
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('%', Some(placeholder @ ('f' | 'd' | 's' | '%'))) => {
+                chars.next();
+                match placeholder {
+                    'f' => expanded.extend(shell_quote(file.map(Path::as_os_str).unwrap_or_default())),
+                    'd' => expanded.extend(shell_quote(dir.as_os_str())),
+                    's' => {
+                        let quoted: Vec<Vec<u8>> = marked.iter().map(|path| shell_quote(path.as_os_str())).collect();
+                        expanded.extend(quoted.join(&b' '));
+                    }
+                    _ => expanded.push(b'%'),
+                }
+            }
+            _ => expanded.extend(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    OsString::from_vec(expanded)
+}
+
+/// Quote a string for the shell so it is passed as one word, whatever it contains
+fn shell_quote(value: &OsStr) -> Vec<u8> {
+    let mut quoted = vec![b'\''];
+    for &byte in value.as_bytes() {
+        if byte == b'\'' {
+            quoted.extend(b"'\\''");
+        } else {
+            quoted.push(byte);
+        }
+    }
+    quoted.push(b'\'');
+    quoted
+}
+
+/// Environment variables telling a program started from browse where it was started
+///
+/// `BROWSE_CURRENT_DIR` is the current directory, `BROWSE_SELECTED` the selected
+/// entry and `BROWSE_MARKED` the marked entries, one per line. They are separated
+/// by newlines rather than NUL bytes because an environment variable can't hold NUL.
+pub fn context_env(dir: &Path, selected: Option<&Path>, marked: &[PathBuf]) -> Vec<(&'static str, OsString)> {
+    let marked: Vec<&[u8]> = marked.iter().map(|path| path.as_os_str().as_bytes()).collect();
+    vec![
+        ("BROWSE_CURRENT_DIR", dir.as_os_str().to_owned()),
+        ("BROWSE_SELECTED", selected.map(|path| path.as_os_str().to_owned()).unwrap_or_default()),
+        ("BROWSE_MARKED", OsString::from_vec(marked.join(&b'\n'))),
+    ]
+}
+
+/// Run a shell command in `dir` without a terminal and collect its output
+///
+/// The command is killed if `is_cancelled` returns true while it runs.
+pub fn run_captured(command: &OsStr, dir: &Path, env: &[(&'static str, OsString)], is_cancelled: impl Fn() -> bool) -> io::Result<Output> {
+    let mut shell = process::Command::new("sh");
+    shell.arg("-c").arg(command).envs(env.iter().cloned()).current_dir(dir);
+    capture(shell, is_cancelled)
+}
+
+/// Run a program without a terminal and collect its output, killing it if
+/// `is_cancelled` returns true while it runs
+fn capture(mut command: process::Command, is_cancelled: impl Fn() -> bool) -> io::Result<Output> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Drain both pipes as the command runs so it never blocks on a full one
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if is_cancelled() {
+            _ = child.kill();
+            break child.wait()?;
+        }
+        thread::sleep(SHELL_POLL_INTERVAL);
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// Read a pipe to the end on another thread
+fn drain(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+/// Whether a file is executable by someone
+pub fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path).is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// Whether a program is in a directory programs are run from: one on `PATH`, or
+/// any `bin` or `sbin`
+pub fn in_program_dir(path: &Path) -> bool {
+    let Some(dir) = path.parent() else { return false };
+    if dir.file_name().is_some_and(|name| name == "bin" || name == "sbin") {
+        return true;
+    }
+    let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    env::var_os("PATH").is_some_and(|paths| {
+        env::split_paths(&paths).any(|entry| fs::canonicalize(&entry).is_ok_and(|entry| entry == dir))
+    })
+}
+
+/// Documentation of a program: its man page, or else what it prints for `--help`
+///
+/// Only programs in a directory programs are run from are looked up at all: a
+/// script elsewhere may share its name with an unrelated man page, and may do
+/// anything but print help when run. The man page is laid out `width` columns
+/// wide. With `run_program` false, the program itself is never run. Either
+/// command is killed when `is_cancelled` returns true.
+pub fn program_help(path: &Path, width: u16, run_program: bool, is_cancelled: impl Fn() -> bool) -> Result<String, String> {
+    let name = path.file_name().unwrap_or_default();
+    let no_man_page = || format!("No man page for {}", name.to_string_lossy());
+    if !in_program_dir(path) {
+        return Err(no_man_page());
+    }
+    let mut man = process::Command::new("man");
+    man.arg(name).env("MANPAGER", "cat").env("PAGER", "cat").env("MANWIDTH", width.to_string());
+    let started = Instant::now();
+    if let Ok(output) = capture(man, || started.elapsed() > HELP_TIMEOUT || is_cancelled())
+        && output.status.success()
+        && !output.stdout.is_empty()
+    {
+        return Ok(strip_overstrikes(&String::from_utf8_lossy(&output.stdout)));
+    }
+    if is_cancelled() {
+        return Err("cancelled".to_string());
+    }
+
+    if !run_program {
+        return Err(no_man_page());
+    }
+    let mut program = process::Command::new(path);
+    program.arg("--help").current_dir(parent_dir(path));
+    let started = Instant::now();
+    let output = capture(program, || started.elapsed() > HELP_TIMEOUT || is_cancelled())
+        .map_err(|e| format!("Cannot run {}: {}", path.display(), e))?;
+    if is_cancelled() {
+        return Err("cancelled".to_string());
+    }
+    if started.elapsed() > HELP_TIMEOUT {
+        return Err(format!("{} --help didn't finish within {} seconds", name.to_string_lossy(), HELP_TIMEOUT.as_secs()));
+    }
+    // Plenty of programs print their usage to stderr, and exit with an error after it
+    let text = if output.stdout.is_empty() { output.stderr } else { output.stdout };
+    if text.is_empty() {
+        return Err(format!("No man page for {}, and --help printed nothing", name.to_string_lossy()));
+    }
+    Ok(String::from_utf8_lossy(&text).into_owned())
+}
+
+/// Remove the backspace overstrikes `man` makes bold and underlined text with
+fn strip_overstrikes(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '\u{8}' {
+            stripped.pop();
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+fn parent_dir(path: &Path) -> PathBuf {
+    path.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_command() {
+        let path = Path::new("/tmp/my file.txt");
+
+        let default = ExternalCommand::open(path, None);
+        assert_eq!(default.program, SYSTEM_OPENER);
+        assert_eq!(default.args, vec![OsString::from("/tmp/my file.txt")]);
+        assert_eq!(default.dir, Path::new("/tmp"));
+
+        let configured = ExternalCommand::open(path, Some("less -R"));
+        assert_eq!(configured.program, "sh");
+        assert_eq!(configured.args[1], "less -R \"$@\"");
+        assert_eq!(configured.args[3], "/tmp/my file.txt");
+    }
+
+    #[test]
+    fn test_expand_placeholders() {
+        let marked = [PathBuf::from("/srv/a b.txt"), PathBuf::from("/srv/it's.txt")];
+        let expanded = expand_placeholders("cp %s %d && wc -c %f # 100%% %x", Some(Path::new("/srv/c.txt")), Path::new("/srv"), &marked);
+        assert_eq!(expanded, "cp '/srv/a b.txt' '/srv/it'\\''s.txt' '/srv' && wc -c '/srv/c.txt' # 100% %x");
+
+        // The quoting holds up when the shell reads it back
+        let output = run_captured(&expand_placeholders("printf '%%s|' %s", None, Path::new("/"), &marked), Path::new("/"), &[], || false).unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"/srv/a b.txt|/srv/it's.txt|");
+
+        let output = run_captured(OsStr::new("echo oops >&2; exit 3"), Path::new("/"), &[], || false).unwrap();
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(output.stderr, b"oops\n");
+    }
+
+    #[test]
+    fn test_context_env() {
+        let marked = [PathBuf::from("/srv/a b.txt"), PathBuf::from("/srv/c.txt")];
+        let env = context_env(Path::new("/srv"), Some(Path::new("/srv/c.txt")), &marked);
+        let script = OsStr::new("printf '%s|%s|%s' \"$BROWSE_CURRENT_DIR\" \"$BROWSE_SELECTED\" \"$BROWSE_MARKED\"");
+        let output = run_captured(script, Path::new("/"), &env, || false).unwrap();
+        assert_eq!(output.stdout, b"/srv|/srv/c.txt|/srv/a b.txt\n/srv/c.txt");
+    }
+
+    #[test]
+    fn test_strip_overstrikes() {
+        assert_eq!(strip_overstrikes("N\u{8}NA\u{8}AM\u{8}ME\u{8}E\n  _\u{8}f_\u{8}i_\u{8}l_\u{8}e"), "NAME\n  file");
+    }
+}
@@ -1,16 +1,74 @@
+//! File operations and previews
+//!
+//! The copy, move, trash, rename and delete functions here don't depend on the
+//! TUI and are what the browser, batch mode and background jobs all use. The
+//! `_with` variants take a [`ConflictPolicy`] for targets that already exist and
+//! a callback that is told how many bytes have been copied and can stop the
+//! operation.
+
 use crate::config::Settings;
-use crate::error::ErrorLog;
+use crate::error::{ErrorEntry, ErrorLog};
+use crate::graphics::{first_linked_image, LinkedImage, LINKING_MIME_TYPES};
+use crate::icons::{self, file_icon};
+use crate::media::{is_media_type, media_info, MediaField};
+use crate::outline::{Outline, OUTLINE_MAX_BYTES, OUTLINE_MIME_TYPES};
+use crate::pdf::{read_pdf, PdfSummary, PDF_MAX_BYTES, PDF_MIME_TYPE};
+use crate::tabular::{delimiter_for, Tabular};
+use crate::trace;
 use chrono::{DateTime, Local};
 use std::fs::{self, DirEntry};
-use std::io::{self, Read};
-use std::os::unix::fs::PermissionsExt;
+use std::io::{self, Read, Write};
+use std::ops::ControlFlow;
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::ffi::{OsStr, OsString};
 use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, SystemTime};
 
-/// Maximum size for file content preview (4KB)
-const MAX_PREVIEW_SIZE: u64 = 4096;
+/// What the content section of a file preview shows
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreviewContent {
+    /// Leading text of the file; `truncated` when the file continues past it
+    Text { text: String, truncated: bool },
+    /// Last lines of the file, while it is followed as it grows
+    Tail(String),
+    /// The whole of a JSON or YAML file, with foldable nodes
+    Outline(Outline),
+    /// Leading rows of a CSV or TSV file
+    Table(Tabular),
+    /// Metadata and first page text of a PDF
+    Pdf(PdfSummary),
+    /// A note explaining why there is no text preview
+    Message(String),
+    /// Previews are disabled for this file type
+    Disabled,
+    /// The file is over the large file threshold and large previews aren't allowed
+    TooLarge,
+    /// The file is under one of the `untrusted_roots`, so its contents aren't read
+    Untrusted,
+    /// The file can't be read by this user
+    PermissionDenied {
+        owner: String,
+        /// Permission bits, as in `chmod`
+        mode: u32,
+        /// The file belongs to this user, who can give themselves access
+        owned: bool,
+    },
+}
 
-/// Maximum number of directory entries to display (performance limit)
-const MAX_DIRECTORY_ENTRIES: usize = 1000;
+impl PreviewContent {
+    /// Get the previewed file text, if there is any
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            PreviewContent::Text { text, .. } | PreviewContent::Tail(text) => Some(text),
+            PreviewContent::Outline(outline) => Some(&outline.source),
+            PreviewContent::Table(table) => Some(&table.source),
+            PreviewContent::Pdf(summary) => Some(&summary.first_page),
+            _ => None,
+        }
+    }
+}
 
 /// File details for preview display
 #[derive(Debug, Clone)]
@@ -19,10 +77,15 @@ pub struct FileDetails {
     pub size: u64,
     pub created: Option<DateTime<Local>>,
     pub modified: Option<DateTime<Local>>,
+    pub accessed: Option<DateTime<Local>>,
     pub permissions: String,
     pub symlink_target: Option<PathBuf>,
-    pub content_preview: String,
+    pub content_preview: PreviewContent,
     pub mime_type: Option<String>,
+    /// Dimensions, duration, codecs and tags of an image, audio or video file
+    pub media: Vec<MediaField>,
+    /// First local image a markdown or HTML file links to, with `linked_images` on
+    pub linked_image: Option<LinkedImage>,
 }
 
 impl FileDetails {
@@ -32,6 +95,7 @@ impl FileDetails {
 
         let created = metadata.created().ok().map(DateTime::from);
         let modified = metadata.modified().ok().map(DateTime::from);
+        let accessed = metadata.accessed().ok().map(DateTime::from);
         let permissions = crate::utils::format_permissions(metadata.permissions().mode());
 
         let symlink_target = if metadata.file_type().is_symlink() {
@@ -40,17 +104,35 @@ impl FileDetails {
             None
         };
 
-        let mime_type = if metadata.is_file() {
-            get_mime_type(path)
-        } else {
-            None
+        let untrusted = config.is_untrusted(path);
+        let mime_type = if metadata.is_file() { mime_type_for(path, config) } else { None };
+
+        let media = match &mime_type {
+            Some(mime_type) if is_media_type(mime_type) && !untrusted => media_info(path),
+            _ => Vec::new(),
         };
 
-        let content_preview = if metadata.is_file() {
-            read_file_preview(path, &mime_type, config)
-                .unwrap_or_else(|_| "[Could not read file]".to_string())
+        let content_preview = if metadata.is_file() && untrusted {
+            PreviewContent::Untrusted
+        } else if metadata.is_file() {
+            read_file_preview(path, &mime_type, config).unwrap_or_else(|e| match e.kind() {
+                io::ErrorKind::PermissionDenied => PreviewContent::PermissionDenied {
+                    owner: crate::utils::user_name(metadata.uid()),
+                    mode: metadata.permissions().mode() & 0o7777,
+                    // SAFETY: geteuid can't fail and has no side effects
+                    owned: metadata.uid() == unsafe { libc::geteuid() },
+                },
+                _ => PreviewContent::Message("[Could not read file]".to_string()),
+            })
         } else {
-            "[Not a regular file]".to_string()
+            PreviewContent::Message("[Not a regular file]".to_string())
+        };
+
+        let linked_image = match (&mime_type, content_preview.text(), path.parent()) {
+            (Some(mime_type), Some(text), Some(dir)) if config.linked_images && LINKING_MIME_TYPES.contains(&mime_type.as_str()) => {
+                first_linked_image(text, dir, config)
+            }
+            _ => None,
         };
 
         Ok(Self {
@@ -58,34 +140,250 @@ impl FileDetails {
             size: metadata.len(),
             created,
             modified,
+            accessed,
             permissions,
             symlink_target,
             content_preview,
             mime_type,
+            media,
+            linked_image,
         })
     }
+
+    /// Read the next part of a text preview that doesn't show the whole file yet
+    ///
+    /// Returns false when there was nothing more to read.
+    pub fn load_more(&mut self, config: &Settings) -> bool {
+        let read = match &self.content_preview {
+            PreviewContent::Text { text, truncated: true } => text.len(),
+            PreviewContent::Table(table) if table.truncated => table.source.len(),
+            _ => return false,
+        };
+        let chunk = fs::File::open(&self.path).and_then(|mut file| {
+            use std::io::{Seek, SeekFrom};
+            file.seek(SeekFrom::Start(read as u64))?;
+            let mut buffer = Vec::new();
+            file.take(config.preview_bytes.max(1)).read_to_end(&mut buffer)?;
+            Ok(buffer)
+        });
+        // An empty chunk means the file shrank, or stopped being text, since the preview was read
+        let more = chunk.ok().and_then(decode_chunk).unwrap_or_default();
+        let size = fs::metadata(&self.path).map_or(self.size, |metadata| metadata.len());
+        let more_to_read = !more.is_empty() && ((read + more.len()) as u64) < size;
+        match &mut self.content_preview {
+            PreviewContent::Text { text, truncated } => {
+                text.push_str(&more);
+                *truncated = more_to_read;
+            }
+            PreviewContent::Table(table) => table.extend(&more, more_to_read),
+            _ => {}
+        }
+        !more.is_empty()
+    }
+
+    /// Show the end of the file instead of its start, reading it again when it changed
+    ///
+    /// Reads the last `preview_bytes` of the file from the start of a line, for
+    /// following a log as it grows. Returns false when the file has no text
+    /// preview, or the end shown is still current.
+    pub fn read_tail(&mut self, config: &Settings) -> bool {
+        if !matches!(self.content_preview, PreviewContent::Text { .. } | PreviewContent::Tail(_)) {
+            return false;
+        }
+        let Ok(metadata) = fs::metadata(&self.path) else {
+            return false;
+        };
+        let modified = metadata.modified().ok().map(DateTime::from);
+        if matches!(self.content_preview, PreviewContent::Tail(_)) && metadata.len() == self.size && modified == self.modified {
+            return false;
+        }
+        let tail = fs::File::open(&self.path).and_then(|mut file| {
+            use std::io::{Seek, SeekFrom};
+            let start = metadata.len().saturating_sub(config.preview_bytes.max(1));
+            file.seek(SeekFrom::Start(start))?;
+            let mut buffer = Vec::new();
+            file.read_to_end(&mut buffer)?;
+            // Start at a line, which also skips a character cut off at the start
+            if start > 0 {
+                let line_start = buffer.iter().position(|&byte| byte == b'\n').map_or(buffer.len(), |newline| newline + 1);
+                buffer.drain(..line_start);
+            }
+            Ok(buffer)
+        });
+        let Some(text) = tail.ok().and_then(decode_chunk) else {
+            return false;
+        };
+        self.size = metadata.len();
+        self.modified = modified;
+        self.content_preview = PreviewContent::Tail(text);
+        true
+    }
+}
+
+/// Decode a chunk of a text file, leaving out a character cut off at its end
+///
+/// Returns None when the bytes aren't UTF-8 text.
+fn decode_chunk(buffer: Vec<u8>) -> Option<String> {
+    match String::from_utf8(buffer) {
+        Ok(text) => Some(text),
+        Err(e) if e.utf8_error().error_len().is_none() => {
+            let valid = e.utf8_error().valid_up_to();
+            let mut bytes = e.into_bytes();
+            bytes.truncate(valid);
+            String::from_utf8(bytes).ok()
+        }
+        Err(_) => None,
+    }
+}
+
+/// Check whether a directory entry is hidden
+///
+/// Dot files are hidden everywhere. On macOS the `UF_HIDDEN` flag (set with
+/// `chflags hidden`) also hides an entry, and on Windows the hidden attribute does.
+pub fn is_hidden(entry: &DirEntry) -> bool {
+    entry.file_name().as_encoded_bytes().starts_with(b".") || has_hidden_attribute(entry)
+}
+
+#[cfg(target_os = "macos")]
+fn has_hidden_attribute(entry: &DirEntry) -> bool {
+    use std::os::macos::fs::MetadataExt;
+    const UF_HIDDEN: u32 = 0x8000;
+    entry.metadata().is_ok_and(|metadata| metadata.st_flags() & UF_HIDDEN != 0)
+}
+
+#[cfg(windows)]
+fn has_hidden_attribute(entry: &DirEntry) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    entry.metadata().is_ok_and(|metadata| metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn has_hidden_attribute(_entry: &DirEntry) -> bool {
+    false
+}
+
+/// Free and total space of a filesystem, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskSpace {
+    /// Space available to unprivileged users, which leaves out blocks reserved for root
+    pub free: u64,
+    pub total: u64,
+}
+
+/// Ask the filesystem holding `path` how much space it has left
+pub fn disk_space(path: &Path) -> io::Result<DiskSpace> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).map_err(io::Error::other)?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is NUL-terminated and `stat` is only read once statvfs has filled it in
+    let stat = unsafe {
+        if libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        stat.assume_init()
+    };
+    let block_size = stat.f_frsize as u64;
+    Ok(DiskSpace {
+        free: stat.f_bavail as u64 * block_size,
+        total: stat.f_blocks as u64 * block_size,
+    })
+}
+
+/// Entries of a directory as `list_directory` reads them, with the errors of single entries
+type Listing = io::Result<(Vec<DirEntry>, Vec<io::Error>)>;
+
+/// Run `work` listing a directory on a helper thread, giving up on it after `timeout`
+///
+/// Returns None when it took too long. The helper is left to finish, or to stay
+/// stuck, on its own, so a hung network mount blocks it rather than the caller.
+/// Until it answers, listing the same directory again waits on it rather than
+/// starting another helper, so retries don't pile up stuck threads.
+fn with_timeout(key: (PathBuf, bool), timeout: Duration, work: impl FnOnce() -> Listing + Send + 'static) -> Option<Listing> {
+    use std::collections::HashMap;
+    use std::sync::{Mutex, OnceLock};
+    // Reads that didn't answer in time, by directory and whether hidden entries are listed
+    type Reads = HashMap<(PathBuf, bool), mpsc::Receiver<Listing>>;
+    static OUTSTANDING: OnceLock<Mutex<Reads>> = OnceLock::new();
+
+    let outstanding = OUTSTANDING.get_or_init(|| Mutex::new(HashMap::new()));
+    let pending = outstanding.lock().ok().and_then(|mut reads| reads.remove(&key));
+    let receiver = pending.unwrap_or_else(|| {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(work());
+        });
+        receiver
+    });
+    match receiver.recv_timeout(timeout) {
+        Ok(listing) => Some(listing),
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            if let Ok(mut reads) = outstanding.lock() {
+                reads.insert(key, receiver);
+            }
+            None
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => None,
+    }
 }
 
 /// Safely read directory entries with error logging
-pub fn read_directory_with_error_log(path: &Path, config: &Settings, mut error_log: Option<&mut ErrorLog>) -> io::Result<Vec<DirEntry>> {
+///
+/// Large directories are cut down to the entry limit in the settings unless
+/// listing them in full was allowed. Returns the entries along with how many
+/// there were before that.
+///
+/// With a read timeout set, the directory is listed on a helper thread, and a
+/// directory that doesn't answer in time gives an error of kind `TimedOut`.
+pub fn read_directory_with_error_log(path: &Path, config: &Settings, error_log: Option<&mut ErrorLog>) -> io::Result<(Vec<DirEntry>, usize)> {
+    let _span = trace::span_with("directory load", || path.display().to_string());
+    let show_hidden = config.show_hidden_files;
+    let (mut entries, errors) = match config.read_timeout() {
+        Some(timeout) => {
+            let dir = path.to_path_buf();
+            with_timeout((dir.clone(), show_hidden), timeout, move || list_directory(&dir, show_hidden)).unwrap_or_else(|| {
+                Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("{} didn't answer within {:.1}s (slow filesystem?)", path.display(), timeout.as_secs_f64()),
+                ))
+            })?
+        }
+        None => list_directory(path, show_hidden)?,
+    };
+
+    if let Some(log) = error_log {
+        for e in errors {
+            let error_msg = format!("Failed to read an entry of {}: {}", path.display(), e);
+            log.add_entry(
+                ErrorEntry::warning(error_msg, Some("Directory Reading".to_string()))
+                    .with_source("read directory entry", path, &e),
+            );
+        }
+    }
+
+    // Limit entries for performance in very large directories
+    let total = entries.len();
+    if let Some(limit) = config.directory_entry_limit(path) {
+        entries.truncate(limit);
+    }
+
+    Ok((entries, total))
+}
+
+/// List and sort a directory's entries, along with the errors of those that couldn't be read
+fn list_directory(path: &Path, show_hidden: bool) -> io::Result<(Vec<DirEntry>, Vec<io::Error>)> {
+    let mut errors = Vec::new();
     let mut entries: Vec<_> = fs::read_dir(path)?
         .filter_map(|entry| match entry {
             Ok(entry) => {
                 // Filter hidden files if not showing them
-                if !config.show_hidden_files {
-                    if let Some(name) = entry.file_name().to_str() {
-                        if name.starts_with('.') {
-                            return None;
-                        }
-                    }
+                if !show_hidden && is_hidden(&entry) {
+                    return None;
                 }
                 Some(entry)
             }
             Err(e) => {
-                let error_msg = format!("Failed to read directory entry: {}", e);
-                if let Some(ref mut log) = error_log {
-                    log.warning(error_msg, Some("Directory Reading".to_string()));
-                }
+                errors.push(e);
                 None
             }
         })
@@ -102,25 +400,40 @@ pub fn read_directory_with_error_log(path: &Path, config: &Settings, mut error_l
             _ => a.file_name().cmp(&b.file_name()),
         }
     });
+    Ok((entries, errors))
+}
 
-    // Limit entries for performance in very large directories
-    if entries.len() > MAX_DIRECTORY_ENTRIES {
-        entries.truncate(MAX_DIRECTORY_ENTRIES);
-        let warning_msg = format!("Directory has more than {} entries, showing first {}",
-                                 MAX_DIRECTORY_ENTRIES, MAX_DIRECTORY_ENTRIES);
-        if let Some(ref mut log) = error_log {
-            log.warning(warning_msg, Some("File Operation".to_string()));
-        }
-    }
+/// How many leading bytes of a file are read to tell its type from its contents
+const SNIFF_BYTES: u64 = 1024;
 
-    Ok(entries)
+/// MIME type of a file, from its contents when the settings allow reading them
+///
+/// Untrusted files and, with `sniff_mime_types` off, all files go by their
+/// extension alone, which needs no read.
+pub fn mime_type_for(path: &Path, config: &Settings) -> Option<String> {
+    if config.sniff_mime_types && !config.is_untrusted(path) {
+        get_mime_type(path)
+    } else {
+        get_mime_type_from_extension(path)
+    }
 }
 
 /// Get MIME type with fallback to extension-based detection
+///
+/// Only the first `SNIFF_BYTES` of the file are read for its magic number, and
+/// only regular files are read at all: opening a FIFO or a device can block.
 pub fn get_mime_type(path: &Path) -> Option<String> {
-    // First try infer crate for magic number detection
-    if let Ok(Some(kind)) = infer::get_from_path(path) {
-        return Some(kind.mime_type().to_string());
+    if fs::metadata(path).is_ok_and(|metadata| metadata.is_file()) {
+        let mut buffer = Vec::new();
+        // Non-blocking in case the file was swapped for a FIFO since
+        let sniffed = fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)
+            .and_then(|file| file.take(SNIFF_BYTES).read_to_end(&mut buffer));
+        if sniffed.is_ok() && let Some(kind) = infer::get(&buffer) {
+            return Some(kind.mime_type().to_string());
+        }
     }
 
     // Fallback to extension-based detection
@@ -144,6 +457,7 @@ fn get_mime_type_from_extension(path: &Path) -> Option<String> {
             ("md", "text/markdown"), ("markdown", "text/markdown"),
             ("html", "text/html"), ("htm", "text/html"),
             ("xml", "application/xml"), ("css", "text/css"), ("csv", "text/csv"),
+            ("tsv", "text/tab-separated-values"),
         ]);
 
         // Programming languages
@@ -170,6 +484,9 @@ fn get_mime_type_from_extension(path: &Path) -> Option<String> {
             ("gif", "image/gif"), ("svg", "image/svg+xml"),
         ]);
 
+        // Documents
+        map.extend([("pdf", "application/pdf")]);
+
         // Archives
         map.extend([
             ("zip", "application/zip"), ("gz", "application/gzip"), ("tar", "application/x-tar"),
@@ -187,47 +504,89 @@ pub fn get_icon_with_error_log(entry: &DirEntry, config: &Settings, _error_log:
     if !config.show_icons {
         return String::new();
     }
+    entry_icon(entry, config, None)
+}
 
+/// Whether a file's type is told from its contents, which `MimeTypes` reads in the background
+pub fn is_sniffable(entry: &DirEntry, config: &Settings) -> bool {
+    config.sniff_mime_types && entry.file_type().is_ok_and(|file_type| file_type.is_file()) && !config.is_untrusted(&entry.path())
+}
+
+/// Icon for a file or directory, whether or not icons are shown
+///
+/// A file goes by the `sniffed` MIME type when its contents have been read, and
+/// by its extension otherwise. Nothing is read here, but it takes a few
+/// `stat`s, so callers that draw it repeatedly keep it with the entry.
+pub fn entry_icon(entry: &DirEntry, config: &Settings, sniffed: Option<&str>) -> String {
     let path = entry.path();
+    let is_symlink = entry.file_type().is_ok_and(|ft| ft.is_symlink());
+
+    if is_symlink && !path.exists() {
+        return category_icon(config, "broken-symlink");
+    }
 
     // Directory icons
     if path.is_dir() {
-        return "📁".to_string();
+        return category_icon(config, directory_category(&path, is_symlink));
     }
 
-    // Symlink icon
-    if entry.file_type().map_or(false, |ft| ft.is_symlink()) {
-        if let Some(rule) = config.get_rule("symlink") {
-            return rule.icon.clone();
-        }
-        return "🔗".to_string();
+    if is_symlink {
+        return category_icon(config, "symlink");
     }
 
     // Executable files
     if let Ok(metadata) = entry.metadata() {
         if metadata.permissions().mode() & 0o111 != 0 {
-            return "🚀".to_string();
+            return category_icon(config, "executable");
         }
     }
 
-    // MIME type-based icons
-    if let Some(mime_type) = get_mime_type(&path) {
-        if let Some(rule) = config.get_rule(&mime_type) {
-            return rule.icon.clone();
-        }
+    // An icon picked in the settings, then the theme's icon by extension or MIME type
+    let mime_type = sniffed.map(str::to_string).or_else(|| get_mime_type_from_extension(&path));
+    if let Some(icon) = mime_type.as_deref().and_then(|mime_type| config.custom_icon(mime_type)) {
+        return icon.to_string();
     }
+    let extension = path.extension().and_then(OsStr::to_str);
+    file_icon(config.icon_theme, extension, mime_type.as_deref()).to_string()
+}
+
+/// Icon for one of the built-in `ENTRY_CATEGORIES`, as overridden in the settings
+fn category_icon(config: &Settings, category: &str) -> String {
+    config
+        .custom_icon(category)
+        .unwrap_or_else(|| icons::category_icon(config.icon_theme, category))
+        .to_string()
+}
+
+/// Most specific category of a directory: mount point, git repository, empty, or plain
+fn directory_category(path: &Path, is_symlink: bool) -> &'static str {
+    use std::os::unix::fs::MetadataExt;
 
-    // Default file icon
-    "📄".to_string()
+    // A symlink's target may live on another device without being a mount point
+    let is_mount_point = !is_symlink
+        && match (fs::metadata(path), fs::metadata(path.join(".."))) {
+            (Ok(dir), Ok(parent)) => dir.dev() != parent.dev(),
+            _ => false,
+        };
+
+    if is_mount_point {
+        "mount-point"
+    } else if path.join(".git").exists() {
+        "git-repo"
+    } else if fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none()) {
+        "empty-directory"
+    } else {
+        "directory"
+    }
 }
 
 /// Read file content for preview with size limits and encoding safety
-fn read_file_preview(path: &Path, mime_type: &Option<String>, config: &Settings) -> io::Result<String> {
+fn read_file_preview(path: &Path, mime_type: &Option<String>, config: &Settings) -> io::Result<PreviewContent> {
     read_file_preview_with_error_log(path, mime_type, config, None)
 }
 
 /// Read file content for preview with error logging
-fn read_file_preview_with_error_log(path: &Path, mime_type: &Option<String>, config: &Settings, _error_log: Option<&mut ErrorLog>) -> io::Result<String> {
+fn read_file_preview_with_error_log(path: &Path, mime_type: &Option<String>, config: &Settings, _error_log: Option<&mut ErrorLog>) -> io::Result<PreviewContent> {
     // Check if preview is enabled for this file type
     let can_preview = mime_type
         .as_ref()
@@ -235,32 +594,659 @@ fn read_file_preview_with_error_log(path: &Path, mime_type: &Option<String>, con
         .map_or(false, |rule| rule.preview);
 
     if !can_preview {
-        return Ok(String::new());
+        return Ok(PreviewContent::Disabled);
+    }
+
+    let size = fs::metadata(path)?.len();
+    if !config.allows_preview_of(size) {
+        return Ok(PreviewContent::TooLarge);
+    }
+
+    if mime_type.as_deref() == Some(PDF_MIME_TYPE) {
+        if size > PDF_MAX_BYTES {
+            return Ok(PreviewContent::Message(format!("[PDF over {} - not read]", crate::utils::format_file_size(PDF_MAX_BYTES))));
+        }
+        return Ok(match read_pdf(&fs::read(path)?) {
+            Some(summary) => PreviewContent::Pdf(summary),
+            None => PreviewContent::Message("[Not a readable PDF]".to_string()),
+        });
+    }
+
+    // Structured files small enough to read whole are shown as an outline
+    if let Some(mime_type) = mime_type.as_deref().filter(|mime_type| OUTLINE_MIME_TYPES.contains(mime_type))
+        && size <= OUTLINE_MAX_BYTES
+        && let Some(outline) = fs::read_to_string(path).ok().and_then(|text| Outline::parse(&text, mime_type))
+    {
+        return Ok(PreviewContent::Outline(outline));
     }
 
     // Read file content safely with size limit (always read first chunk)
     let file = fs::File::open(path)?;
     let mut buffer = Vec::new();
-    let _bytes_read = file.take(MAX_PREVIEW_SIZE).read_to_end(&mut buffer)?;
-
-    // Check if we read a partial file
-    let metadata = fs::metadata(path)?;
-    let is_truncated = metadata.len() > MAX_PREVIEW_SIZE;
+    let _bytes_read = file.take(config.preview_bytes.max(1)).read_to_end(&mut buffer)?;
 
     // Convert to string, handling invalid UTF-8 gracefully
-    match String::from_utf8(buffer) {
-        Ok(mut content) => {
-            if is_truncated {
-                let total_size_kb = metadata.len() / 1024;
-                let preview_size_kb = MAX_PREVIEW_SIZE / 1024;
-                content.push_str(&format!(
-                    "\n\n[... File truncated - showing first {} KB of {} KB total ...]",
-                    preview_size_kb, total_size_kb
+    match decode_chunk(buffer) {
+        Some(text) => {
+            // Check if we read a partial file
+            let truncated = (text.len() as u64) < fs::metadata(path)?.len();
+            match mime_type.as_deref().and_then(delimiter_for) {
+                Some(delimiter) => Ok(PreviewContent::Table(Tabular::new(text, truncated, delimiter))),
+                None => Ok(PreviewContent::Text { text, truncated }),
+            }
+        }
+        None => Ok(PreviewContent::Message("[Binary file - preview not available]".to_string())),
+    }
+}
+
+/// What to do when a copy, move or rename would replace an existing entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// Fail with an `AlreadyExists` error
+    #[default]
+    Fail,
+    /// Leave the existing entry alone and skip the operation
+    Skip,
+    /// Replace the existing entry once the new one is complete, so a copy or move that
+    /// fails or is stopped leaves it as it was
+    Overwrite,
+    /// Use a free name next to the existing entry, such as `notes (2).txt`
+    KeepBoth,
+}
+
+/// How far a copy or move has got
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Bytes of file contents copied so far
+    pub bytes_done: u64,
+    /// Bytes of file contents in the whole tree
+    pub bytes_total: u64,
+}
+
+/// Called as a copy or move goes; breaking stops it with an `Interrupted` error
+pub type ProgressCallback<'a> = &'a mut dyn FnMut(Progress) -> ControlFlow<()>;
+
+/// Size of the chunks files are copied in, and so how often progress is reported
+const COPY_CHUNK_SIZE: usize = 256 * 1024;
+
+/// Copy a file, symlink or directory tree into a destination directory
+///
+/// Returns the path of the new copy. Existing files are never overwritten.
+pub fn copy_into(source: &Path, dest_dir: &Path) -> io::Result<PathBuf> {
+    copy_into_with(source, dest_dir, ConflictPolicy::Fail, &mut |_| ControlFlow::Continue(()))
+        .map(|target| target.expect("only skipping returns no target"))
+}
+
+/// Copy into a destination directory, resolving name conflicts with `conflict`
+///
+/// Returns the path of the new copy, or None if it was skipped. A copy that fails
+/// or is stopped part way leaves what was copied so far in place, unless it was
+/// replacing an entry: then the partial copy is removed and the entry kept.
+pub fn copy_into_with(source: &Path, dest_dir: &Path, conflict: ConflictPolicy, progress: ProgressCallback) -> io::Result<Option<PathBuf>> {
+    let Some(target) = resolve_target(source, dest_dir, "copy", conflict)? else {
+        return Ok(None);
+    };
+
+    let mut tracker = ProgressTracker::new(tree_size(source)?, progress);
+    replace_with(&target, |staging| copy_recursive(source, staging, &mut tracker))?;
+    Ok(Some(target))
+}
+
+/// Total size of the regular files in a tree, not following symlinks
+pub fn tree_size(path: &Path) -> io::Result<u64> {
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        fs::read_dir(path)?.try_fold(0, |total, entry| Ok(total + tree_size(&entry?.path())?))
+    } else if metadata.is_file() {
+        Ok(metadata.len())
+    } else {
+        Ok(0)
+    }
+}
+
+/// Counts copied bytes and passes them on to a progress callback
+struct ProgressTracker<'a> {
+    progress: Progress,
+    callback: ProgressCallback<'a>,
+}
+
+impl<'a> ProgressTracker<'a> {
+    fn new(bytes_total: u64, callback: ProgressCallback<'a>) -> Self {
+        Self { progress: Progress { bytes_done: 0, bytes_total }, callback }
+    }
+
+    fn advance(&mut self, bytes: u64) -> io::Result<()> {
+        self.progress.bytes_done += bytes;
+        match (self.callback)(self.progress) {
+            ControlFlow::Continue(()) => Ok(()),
+            ControlFlow::Break(()) => Err(io::Error::new(io::ErrorKind::Interrupted, "Stopped")),
+        }
+    }
+}
+
+/// Copy a path to a target, recursing into directories and preserving symlinks
+fn copy_recursive(source: &Path, target: &Path, tracker: &mut ProgressTracker) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(source)?;
+
+    // A tree from a filesystem that tells case apart can hold names that clash on one that doesn't
+    if metadata.file_type().is_symlink() {
+        std::os::unix::fs::symlink(fs::read_link(source)?, target).map_err(|e| clash_error(e, target))
+    } else if metadata.is_dir() {
+        fs::create_dir(target).map_err(|e| clash_error(e, target))?;
+        for entry in fs::read_dir(source)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &target.join(entry.file_name()), tracker)?;
+        }
+        fs::set_permissions(target, metadata.permissions())
+    } else {
+        copy_file(source, target, tracker)
+    }
+}
+
+/// Copy a file's contents and permissions in chunks, reporting each one
+fn copy_file(source: &Path, target: &Path, tracker: &mut ProgressTracker) -> io::Result<()> {
+    let mut reader = fs::File::open(source)?;
+    let mut writer = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(target)
+        .map_err(|e| clash_error(e, target))?;
+    let mut buffer = vec![0; COPY_CHUNK_SIZE];
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        writer.write_all(&buffer[..read])?;
+        tracker.advance(read as u64)?;
+    }
+    writer.set_permissions(reader.metadata()?.permissions())
+}
+
+/// Move a file, symlink or directory tree into a destination directory
+///
+/// Renames when possible and falls back to copy-then-delete across filesystems.
+/// Returns the new path. Existing files are never overwritten.
+pub fn move_into(source: &Path, dest_dir: &Path) -> io::Result<PathBuf> {
+    move_into_with(source, dest_dir, ConflictPolicy::Fail, &mut |_| ControlFlow::Continue(()))
+        .map(|target| target.expect("only skipping returns no target"))
+}
+
+/// Move into a destination directory, resolving name conflicts with `conflict`
+///
+/// Returns the new path, or None if the move was skipped. Progress is only
+/// reported in steps when the move has to copy across filesystems.
+pub fn move_into_with(source: &Path, dest_dir: &Path, conflict: ConflictPolicy, progress: ProgressCallback) -> io::Result<Option<PathBuf>> {
+    let Some(target) = resolve_target(source, dest_dir, "move", conflict)? else {
+        return Ok(None);
+    };
+
+    replace_with(&target, |staging| move_path_with(source, staging, progress))?;
+    Ok(Some(target))
+}
+
+/// Rename a path, falling back to copy-then-delete across filesystems
+pub fn move_path(source: &Path, target: &Path) -> io::Result<()> {
+    move_path_with(source, target, &mut |_| ControlFlow::Continue(()))
+}
+
+fn move_path_with(source: &Path, target: &Path, progress: ProgressCallback) -> io::Result<()> {
+    match fs::rename(source, target) {
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {
+            let mut tracker = ProgressTracker::new(tree_size(source)?, progress);
+            copy_recursive(source, target, &mut tracker)?;
+            delete_path(source)
+        }
+        Ok(()) => {
+            // A rename is done in one step with nothing copied, so a stop asked for now comes too late
+            let _ = ProgressTracker::new(0, progress).advance(0);
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// Where `source` goes in `dest_dir`, or None if the conflict policy skips it
+fn resolve_target(source: &Path, dest_dir: &Path, verb: &str, conflict: ConflictPolicy) -> io::Result<Option<PathBuf>> {
+    let name = source.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("Cannot {} {}", verb, source.display()))
+    })?;
+
+    if dest_dir.starts_with(source) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Cannot {} {} into itself", verb, source.display()),
+        ));
+    }
+
+    resolve_conflict(source, dest_dir.join(name), conflict)
+}
+
+/// Apply a conflict policy to a target that may already exist
+fn resolve_conflict(source: &Path, target: PathBuf, conflict: ConflictPolicy) -> io::Result<Option<PathBuf>> {
+    let Ok(existing) = fs::symlink_metadata(&target) else {
+        return Ok(Some(target));
+    };
+
+    match conflict {
+        ConflictPolicy::Fail => Err(already_exists(&target)),
+        ConflictPolicy::Skip => Ok(None),
+        ConflictPolicy::KeepBoth => Ok(Some(free_name(&target))),
+        ConflictPolicy::Overwrite => {
+            if is_same_entry(&fs::symlink_metadata(source)?, &existing) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Cannot replace {} with itself", target.display()),
                 ));
             }
-            Ok(content)
-        },
-        Err(_) => Ok("[Binary file - preview not available]".to_string()),
+            Ok(Some(target))
+        }
+    }
+}
+
+/// Create an entry at `target` with `create`, replacing an entry already there only
+/// once the new one is complete
+///
+/// A replacement is created under a hidden name next to the target and then
+/// renamed over it. If creating it fails, it is removed and the existing entry kept.
+fn replace_with(target: &Path, create: impl FnOnce(&Path) -> io::Result<()>) -> io::Result<()> {
+    let Ok(existing) = fs::symlink_metadata(target) else {
+        return create(target);
+    };
+    let staging = hidden_name(target, "partial");
+    if let Err(e) = create(&staging) {
+        if fs::symlink_metadata(&staging).is_ok() {
+            _ = delete_path(&staging);
+        }
+        return Err(e);
+    }
+    if !existing.is_dir() && !fs::symlink_metadata(&staging)?.is_dir() {
+        return fs::rename(&staging, target);
+    }
+
+    // A rename can't put a directory over a non-empty one or change an entry's kind,
+    // so the existing entry is moved aside first and put back if that fails
+    let old = hidden_name(target, "old");
+    if let Err(e) = fs::rename(target, &old) {
+        _ = delete_path(&staging);
+        return Err(e);
+    }
+    if let Err(e) = fs::rename(&staging, target) {
+        _ = fs::rename(&old, target);
+        _ = delete_path(&staging);
+        return Err(e);
+    }
+    delete_path(&old)
+}
+
+/// A free hidden name next to `target` for an entry on its way in or out
+fn hidden_name(target: &Path, purpose: &str) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(target.file_name().unwrap_or_default());
+    name.push(format!(".browse-{}", purpose));
+    let candidate = target.with_file_name(name);
+    if fs::symlink_metadata(&candidate).is_err() { candidate } else { free_name(&candidate) }
+}
+
+/// Whether two sets of metadata belong to the same entry
+fn is_same_entry(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+/// Name of the entry an existing target resolves to, when its directory holds it
+/// under another name
+///
+/// That happens on filesystems that ignore case, such as the defaults on macOS
+/// and Windows, where `Notes.txt` finds `notes.txt`.
+fn name_on_disk(target: &Path) -> Option<OsString> {
+    let metadata = fs::symlink_metadata(target).ok()?;
+    let name = target.file_name()?;
+    let mut found = None;
+    for entry in fs::read_dir(target.parent()?).ok()?.flatten() {
+        if entry.file_name() == name {
+            return None;
+        }
+        if entry.metadata().is_ok_and(|entry_metadata| is_same_entry(&entry_metadata, &metadata)) {
+            found = Some(entry.file_name());
+        }
+    }
+    found
+}
+
+/// Error for a target that already exists, naming the entry it clashes with when
+/// the names only differ in case
+fn already_exists(target: &Path) -> io::Error {
+    let message = match name_on_disk(target) {
+        Some(existing) => format!(
+            "{} clashes with {}, whose name differs only in case",
+            target.display(),
+            existing.to_string_lossy()
+        ),
+        None => format!("{} already exists", target.display()),
+    };
+    io::Error::new(io::ErrorKind::AlreadyExists, message)
+}
+
+/// Explain an `AlreadyExists` error from creating a target, leaving others as they are
+fn clash_error(error: io::Error, target: &Path) -> io::Error {
+    if error.kind() == io::ErrorKind::AlreadyExists { already_exists(target) } else { error }
+}
+
+/// A path next to `path` that doesn't exist yet: `notes (2).txt`, `notes (3).txt`, ...
+fn free_name(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default();
+    (2..)
+        .map(|counter| {
+            let mut name = stem.to_os_string();
+            name.push(format!(" ({})", counter));
+            if let Some(extension) = path.extension() {
+                name.push(".");
+                name.push(extension);
+            }
+            path.with_file_name(name)
+        })
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .expect("some name is free")
+}
+
+/// Move a file, symlink or directory tree to the user's trash
+///
+/// Uses `~/.Trash` on macOS and the freedesktop.org trash elsewhere, recording the
+/// original location so other file managers can restore it. Returns the path of the
+/// entry inside the trash.
+///
+/// An entry on another filesystem than the home trash, such as a USB drive, goes
+/// to that filesystem's own trash rather than being copied home.
+pub fn trash_path(path: &Path) -> io::Result<PathBuf> {
+    let home = dirs::home_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Cannot find home directory"))?;
+
+    if cfg!(target_os = "macos") {
+        let trash = home.join(".Trash");
+        fs::create_dir_all(&trash)?;
+        let name = unique_trash_name(path, |candidate| fs::symlink_metadata(trash.join(candidate)).is_err())?;
+        let target = trash.join(name);
+        move_path(path, &target)?;
+        Ok(target)
+    } else {
+        let data_dir = dirs::data_dir().unwrap_or_else(|| home.join(".local/share"));
+        let home_trash = data_dir.join("Trash");
+        let device = fs::symlink_metadata(path)?.dev();
+        if device_of(&home_trash)? == device {
+            move_to_xdg_trash(path, &home_trash)
+        } else {
+            let path = std::path::absolute(path)?;
+            let trash = topdir_trash(&mount_top(&path, device)).map_err(|e| {
+                io::Error::new(e.kind(), format!("No trash on the filesystem of {} ({}); delete it permanently instead", path.display(), e))
+            })?;
+            move_to_xdg_trash(&path, &trash)
+        }
+    }
+}
+
+/// Device of a path, or of its nearest ancestor that exists yet
+fn device_of(path: &Path) -> io::Result<u64> {
+    let existing = path.ancestors().find(|ancestor| fs::symlink_metadata(ancestor).is_ok()).unwrap_or(Path::new("/"));
+    Ok(fs::metadata(existing)?.dev())
+}
+
+/// Top directory of the filesystem an absolute path on `device` is on, where its trash goes
+fn mount_top(path: &Path, device: u64) -> PathBuf {
+    let mut top = path.parent().unwrap_or(path);
+    while let Some(parent) = top.parent()
+        && fs::metadata(parent).is_ok_and(|metadata| metadata.dev() == device)
+    {
+        top = parent;
+    }
+    top.to_path_buf()
+}
+
+/// The trash directory in a filesystem's top directory, made if need be
+///
+/// As the trash spec has it: `$topdir/.Trash/$uid` when an administrator has set up
+/// a shared `.Trash` with the sticky bit, and `$topdir/.Trash-$uid` otherwise.
+fn topdir_trash(topdir: &Path) -> io::Result<PathBuf> {
+    use std::os::unix::fs::DirBuilderExt;
+    // SAFETY: geteuid can't fail and has no side effects
+    let uid = unsafe { libc::geteuid() };
+
+    let shared = topdir.join(".Trash");
+    if let Ok(metadata) = fs::symlink_metadata(&shared)
+        && metadata.is_dir()
+        && metadata.permissions().mode() & 0o1000 != 0
+    {
+        let trash = shared.join(uid.to_string());
+        if fs::DirBuilder::new().mode(0o700).recursive(true).create(&trash).is_ok() {
+            return Ok(trash);
+        }
+    }
+
+    let trash = topdir.join(format!(".Trash-{}", uid));
+    fs::DirBuilder::new().mode(0o700).recursive(true).create(&trash)?;
+    Ok(trash)
+}
+
+/// Move a path into a freedesktop.org trash directory
+fn move_to_xdg_trash(path: &Path, trash: &Path) -> io::Result<PathBuf> {
+    let path = std::path::absolute(path)?;
+    let files = trash.join("files");
+    let info = trash.join("info");
+    fs::create_dir_all(&files)?;
+    fs::create_dir_all(&info)?;
+
+    let info_text = format!(
+        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+        percent_encode_path(&path),
+        Local::now().format("%Y-%m-%dT%H:%M:%S"),
+    );
+
+    // Creating the info file exclusively reserves the name against other trashers
+    loop {
+        let name = unique_trash_name(&path, |candidate| fs::symlink_metadata(files.join(candidate)).is_err())?;
+        let mut info_name = name.clone();
+        info_name.push(".trashinfo");
+        let info_path = info.join(info_name);
+        match fs::OpenOptions::new().write(true).create_new(true).open(&info_path) {
+            Ok(mut file) => {
+                file.write_all(info_text.as_bytes())?;
+
+                // Trash is always on the entry's filesystem, so this is a rename and never a copy
+                let target = files.join(&name);
+                if let Err(e) = fs::rename(&path, &target) {
+                    let _ = fs::remove_file(&info_path);
+                    return Err(e);
+                }
+                return Ok(target);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Pick a name for a path in the trash that `is_free` accepts: `name`, `name.2`, ...
+fn unique_trash_name(path: &Path, is_free: impl Fn(&OsStr) -> bool) -> io::Result<OsString> {
+    let name = path.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("Cannot trash {}", path.display()))
+    })?;
+
+    let mut candidate = name.to_os_string();
+    let mut counter = 2;
+    while !is_free(&candidate) {
+        candidate = name.to_os_string();
+        candidate.push(format!(".{}", counter));
+        counter += 1;
+    }
+    Ok(candidate)
+}
+
+/// Percent-encode a path for a `.trashinfo` file, leaving `/` and unreserved characters
+fn percent_encode_path(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut encoded = String::new();
+    for &byte in path.as_os_str().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Rename an entry within its directory
+///
+/// The new name must be a single path component and must not already exist.
+/// Returns the new path.
+pub fn rename_entry(path: &Path, new_name: &str) -> io::Result<PathBuf> {
+    rename_entry_with(path, new_name, ConflictPolicy::Fail)
+        .map(|target| target.expect("only skipping returns no target"))
+}
+
+/// Rename an entry within its directory, resolving name conflicts with `conflict`
+///
+/// Returns the new path, or None if the rename was skipped.
+pub fn rename_entry_with(path: &Path, new_name: &str, conflict: ConflictPolicy) -> io::Result<Option<PathBuf>> {
+    if new_name.is_empty() || new_name == "." || new_name == ".." || new_name.contains('/') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{}' is not a valid file name", new_name),
+        ));
+    }
+
+    let target = path.with_file_name(new_name);
+    if target == path {
+        return Ok(Some(target));
+    }
+    // Changing only the case of a name finds the entry itself where case is ignored
+    let renames_itself = match (fs::symlink_metadata(path), fs::symlink_metadata(&target)) {
+        (Ok(source), Ok(existing)) => is_same_entry(&source, &existing) && name_on_disk(&target).is_some(),
+        _ => false,
+    };
+    if renames_itself {
+        fs::rename(path, &target)?;
+        return Ok(Some(target));
+    }
+    let Some(target) = resolve_conflict(path, target, conflict)? else {
+        return Ok(None);
+    };
+
+    replace_with(&target, |staging| fs::rename(path, staging))?;
+    Ok(Some(target))
+}
+
+/// Set the modification time of an existing file or directory, like `touch`
+///
+/// The entry isn't opened, so this needs no read permission and doesn't block on a FIFO.
+pub fn touch_path(path: &Path, time: SystemTime) -> io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(path.as_os_str().as_bytes()).map_err(io::Error::other)?;
+    // A time before the epoch counts back whole seconds, then forward in nanoseconds
+    let (seconds, nanos) = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(since) => (since.as_secs() as i64, since.subsec_nanos()),
+        Err(e) if e.duration().subsec_nanos() == 0 => (-(e.duration().as_secs() as i64), 0),
+        Err(e) => (-(e.duration().as_secs() as i64) - 1, 1_000_000_000 - e.duration().subsec_nanos()),
+    };
+    let times = [
+        libc::timespec { tv_sec: 0, tv_nsec: libc::UTIME_OMIT },
+        libc::timespec { tv_sec: seconds as libc::time_t, tv_nsec: nanos as _ },
+    ];
+    // SAFETY: `path` is NUL-terminated and `times` holds the two timestamps utimensat reads
+    if unsafe { libc::utimensat(libc::AT_FDCWD, path.as_ptr(), times.as_ptr(), 0) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Permanently delete a file, symlink or directory tree
+pub fn delete_path(path: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(path)?;
+    if metadata.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// What a delete of a tree got through
+#[derive(Debug, Default)]
+pub struct DeleteReport {
+    /// Files, symlinks and directories removed
+    pub removed: u64,
+    /// Entries that couldn't be removed, and why
+    pub failures: Vec<(PathBuf, io::Error)>,
+}
+
+/// Number of entries in a tree, itself included, not following symlinks
+pub fn count_tree_entries(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => {
+            let entries = fs::read_dir(path).into_iter().flatten().flatten();
+            1 + entries.map(|entry| count_tree_entries(&entry.path())).sum::<u64>()
+        }
+        _ => 1,
+    }
+}
+
+/// Permanently delete a file, symlink or directory tree, going on past entries
+/// that can't be removed
+///
+/// A directory stays if anything in it does. `on_removed` is told how many
+/// entries have been removed after each one, and breaking stops the delete with
+/// an `Interrupted` error.
+pub fn delete_tree_with(path: &Path, on_removed: &mut dyn FnMut(u64) -> ControlFlow<()>) -> io::Result<DeleteReport> {
+    let mut report = DeleteReport::default();
+    delete_recursive(path, &mut report, on_removed)?;
+    Ok(report)
+}
+
+/// Delete a path into a report, returning whether it is gone
+fn delete_recursive(path: &Path, report: &mut DeleteReport, on_removed: &mut dyn FnMut(u64) -> ControlFlow<()>) -> io::Result<bool> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            report.failures.push((path.to_path_buf(), e));
+            return Ok(false);
+        }
+    };
+
+    let removed = if metadata.is_dir() {
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                report.failures.push((path.to_path_buf(), e));
+                return Ok(false);
+            }
+        };
+        let mut emptied = true;
+        for entry in entries {
+            match entry {
+                Ok(entry) => emptied &= delete_recursive(&entry.path(), report, on_removed)?,
+                Err(e) => {
+                    report.failures.push((path.to_path_buf(), e));
+                    emptied = false;
+                }
+            }
+        }
+        if !emptied {
+            return Ok(false);
+        }
+        fs::remove_dir(path)
+    } else {
+        fs::remove_file(path)
+    };
+
+    if let Err(e) = removed {
+        report.failures.push((path.to_path_buf(), e));
+        return Ok(false);
+    }
+    report.removed += 1;
+    match on_removed(report.removed) {
+        ControlFlow::Continue(()) => Ok(true),
+        ControlFlow::Break(()) => Err(io::Error::new(io::ErrorKind::Interrupted, "Stopped")),
     }
 }
 
@@ -288,3 +1274,409 @@ pub fn is_safe_path(path: &Path) -> bool {
 
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_to_xdg_trash() {
+        let dir = std::env::temp_dir().join(format!("browse-trash-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let trash = dir.join("Trash");
+        fs::create_dir_all(&dir).unwrap();
+
+        for _ in 0..2 {
+            fs::write(dir.join("my notes.txt"), "x").unwrap();
+            move_to_xdg_trash(&dir.join("my notes.txt"), &trash).unwrap();
+        }
+
+        assert!(!dir.join("my notes.txt").exists());
+        assert!(trash.join("files/my notes.txt").exists());
+        assert!(trash.join("files/my notes.txt.2").exists());
+
+        let info = fs::read_to_string(trash.join("info/my notes.txt.2.trashinfo")).unwrap();
+        assert!(info.starts_with("[Trash Info]\n"));
+        assert!(info.contains(&format!("Path={}/my%20notes.txt\n", dir.display())));
+        assert!(info.contains("DeletionDate="));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_trash_on_other_filesystems() {
+        let dir = std::env::temp_dir().join(format!("browse-topdir-trash-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("plain")).unwrap();
+        fs::create_dir_all(dir.join("shared/.Trash")).unwrap();
+        let uid = unsafe { libc::geteuid() };
+
+        let trash = topdir_trash(&dir.join("plain")).unwrap();
+        assert_eq!(trash, dir.join(format!("plain/.Trash-{}", uid)));
+        assert_eq!(fs::metadata(&trash).unwrap().permissions().mode() & 0o777, 0o700);
+        // A shared .Trash is only used when it has the sticky bit
+        assert_eq!(topdir_trash(&dir.join("shared")).unwrap(), dir.join(format!("shared/.Trash-{}", uid)));
+        fs::set_permissions(dir.join("shared/.Trash"), fs::Permissions::from_mode(0o1777)).unwrap();
+        assert_eq!(topdir_trash(&dir.join("shared")).unwrap(), dir.join(format!("shared/.Trash/{}", uid)));
+
+        // The top directory is where the filesystem begins
+        let device = fs::metadata(&dir).unwrap().dev();
+        let top = mount_top(&dir.join("plain"), device);
+        assert!(dir.starts_with(&top));
+        assert!(top.parent().is_none_or(|parent| fs::metadata(parent).unwrap().dev() != device));
+        assert_eq!(device_of(&dir.join("not/made/yet")).unwrap(), device);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_conflict_policies() {
+        let dir = std::env::temp_dir().join(format!("browse-conflict-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("out")).unwrap();
+        fs::write(dir.join("notes.txt"), "new").unwrap();
+        fs::write(dir.join("out/notes.txt"), "old").unwrap();
+        let mut quiet = |_| ControlFlow::Continue(());
+
+        let error = copy_into_with(&dir.join("notes.txt"), &dir.join("out"), ConflictPolicy::Fail, &mut quiet).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(copy_into_with(&dir.join("notes.txt"), &dir.join("out"), ConflictPolicy::Skip, &mut quiet).unwrap(), None);
+        assert_eq!(fs::read_to_string(dir.join("out/notes.txt")).unwrap(), "old");
+
+        let kept = copy_into_with(&dir.join("notes.txt"), &dir.join("out"), ConflictPolicy::KeepBoth, &mut quiet).unwrap();
+        assert_eq!(kept, Some(dir.join("out/notes (2).txt")));
+
+        move_into_with(&dir.join("notes.txt"), &dir.join("out"), ConflictPolicy::Overwrite, &mut quiet).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("out/notes.txt")).unwrap(), "new");
+        assert!(!dir.join("notes.txt").exists());
+
+        // Replacing an entry with itself would lose it
+        let error = copy_into_with(&dir.join("out/notes.txt"), &dir.join("out"), ConflictPolicy::Overwrite, &mut quiet).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert!(dir.join("out/notes.txt").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_names_that_are_not_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = std::env::temp_dir().join(format!("browse-not-utf8-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let trash = dir.join("Trash");
+        fs::create_dir_all(dir.join("out")).unwrap();
+        // Two names that read the same once their bad bytes become `�`
+        let latin1 = OsStr::from_bytes(b"caf\xe9.txt");
+        let other = OsStr::from_bytes(b"caf\xff.txt");
+        let mut quiet = |_| ControlFlow::Continue(());
+
+        fs::write(dir.join(latin1), "x").unwrap();
+        fs::write(dir.join("out").join(latin1), "x").unwrap();
+        let kept = copy_into_with(&dir.join(latin1), &dir.join("out"), ConflictPolicy::KeepBoth, &mut quiet).unwrap();
+        assert_eq!(kept, Some(dir.join("out").join(OsStr::from_bytes(b"caf\xe9 (2).txt"))));
+
+        fs::write(dir.join(other), "x").unwrap();
+        move_to_xdg_trash(&dir.join(latin1), &trash).unwrap();
+        move_to_xdg_trash(&dir.join(other), &trash).unwrap();
+        assert!(trash.join("files").join(latin1).exists() && trash.join("files").join(other).exists());
+        let info = fs::read_to_string(trash.join("info").join(OsStr::from_bytes(b"caf\xff.txt.trashinfo"))).unwrap();
+        assert!(info.contains("/caf%FF.txt\n"), "{}", info);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_names_that_differ_only_in_case() {
+        let dir = std::env::temp_dir().join(format!("browse-case-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("out")).unwrap();
+        fs::write(dir.join("Notes.txt"), "new").unwrap();
+        fs::write(dir.join("out/notes.txt"), "old").unwrap();
+        let mut quiet = |_| ControlFlow::Continue(());
+        let ignores_case = dir.join("NOTES.TXT").exists();
+
+        let copied = copy_into_with(&dir.join("Notes.txt"), &dir.join("out"), ConflictPolicy::Fail, &mut quiet);
+        if ignores_case {
+            let error = copied.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+            assert!(error.to_string().ends_with("clashes with notes.txt, whose name differs only in case"), "{}", error);
+            assert_eq!(fs::read_to_string(dir.join("out/notes.txt")).unwrap(), "old");
+
+            // Changing only the case of a name renames the entry instead of clashing with itself
+            let renamed = rename_entry(&dir.join("out/notes.txt"), "NOTES.txt").unwrap();
+            assert_eq!(renamed, dir.join("out/NOTES.txt"));
+            let names: Vec<OsString> = fs::read_dir(dir.join("out")).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+            assert_eq!(names, ["NOTES.txt"]);
+        } else {
+            // Where case tells names apart, they don't clash
+            assert_eq!(copied.unwrap(), Some(dir.join("out/Notes.txt")));
+            assert_eq!(fs::read_to_string(dir.join("out/notes.txt")).unwrap(), "old");
+            let error = rename_entry(&dir.join("out/notes.txt"), "Notes.txt").unwrap_err();
+            assert_eq!(error.to_string(), format!("{} already exists", dir.join("out/Notes.txt").display()));
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_copy_progress_and_stopping() {
+        let dir = std::env::temp_dir().join(format!("browse-progress-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("tree/sub")).unwrap();
+        fs::create_dir_all(dir.join("out")).unwrap();
+        fs::write(dir.join("tree/a.bin"), vec![0; COPY_CHUNK_SIZE + 10]).unwrap();
+        fs::write(dir.join("tree/sub/b.bin"), vec![0; 5]).unwrap();
+
+        let mut reports = Vec::new();
+        copy_into_with(&dir.join("tree"), &dir.join("out"), ConflictPolicy::Fail, &mut |progress| {
+            reports.push(progress);
+            ControlFlow::Continue(())
+        }).unwrap();
+        let total = COPY_CHUNK_SIZE as u64 + 15;
+        assert!(reports.iter().all(|progress| progress.bytes_total == total));
+        assert_eq!(reports.last().unwrap().bytes_done, total);
+        assert_eq!(fs::metadata(dir.join("out/tree/a.bin")).unwrap().len(), COPY_CHUNK_SIZE as u64 + 10);
+
+        let error = copy_into_with(&dir.join("tree"), &dir.join("out"), ConflictPolicy::KeepBoth, &mut |_| ControlFlow::Break(()))
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+
+        // Stopping a copy that replaces an entry keeps the entry and removes the partial copy
+        fs::remove_dir_all(dir.join("out")).unwrap();
+        fs::create_dir_all(dir.join("out/tree")).unwrap();
+        fs::write(dir.join("out/tree/old.txt"), "old").unwrap();
+        fs::write(dir.join("out/a.bin"), "old").unwrap();
+        for source in ["tree", "tree/a.bin"] {
+            let error = copy_into_with(&dir.join(source), &dir.join("out"), ConflictPolicy::Overwrite, &mut |_| ControlFlow::Break(()))
+                .unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+        }
+        assert_eq!(fs::read_to_string(dir.join("out/tree/old.txt")).unwrap(), "old");
+        assert_eq!(fs::read_to_string(dir.join("out/a.bin")).unwrap(), "old");
+        assert_eq!(fs::read_dir(dir.join("out")).unwrap().count(), 2);
+
+        // Finished, the copies take the entries' places, a directory over a directory too
+        for source in ["tree", "tree/a.bin"] {
+            copy_into_with(&dir.join(source), &dir.join("out"), ConflictPolicy::Overwrite, &mut |_| ControlFlow::Continue(())).unwrap();
+        }
+        assert!(!dir.join("out/tree/old.txt").exists());
+        assert_eq!(fs::metadata(dir.join("out/a.bin")).unwrap().len(), COPY_CHUNK_SIZE as u64 + 10);
+        assert_eq!(fs::read_dir(dir.join("out")).unwrap().count(), 2);
+
+        // A move within a filesystem is a rename, done before a stop could take effect
+        fs::create_dir_all(dir.join("moved")).unwrap();
+        let moved = move_into_with(&dir.join("tree"), &dir.join("moved"), ConflictPolicy::Fail, &mut |_| ControlFlow::Break(()));
+        assert_eq!(moved.unwrap(), Some(dir.join("moved/tree")));
+        assert!(dir.join("moved/tree/sub/b.bin").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_delete_tree_counts_goes_on_and_stops() {
+        let dir = std::env::temp_dir().join(format!("browse-delete-tree-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("tree/kept")).unwrap();
+        fs::create_dir_all(dir.join("tree/sub")).unwrap();
+        fs::write(dir.join("tree/a.txt"), "").unwrap();
+        fs::write(dir.join("tree/kept/b.txt"), "").unwrap();
+        fs::write(dir.join("tree/sub/c.txt"), "").unwrap();
+        assert_eq!(count_tree_entries(&dir.join("tree")), 6);
+
+        // Nothing can be removed from a read-only directory, unless running as root
+        fs::set_permissions(dir.join("tree/kept"), fs::Permissions::from_mode(0o555)).unwrap();
+        let privileged = fs::write(dir.join("tree/kept/probe"), "").is_ok();
+        let _ = fs::remove_file(dir.join("tree/kept/probe"));
+
+        let mut counts = Vec::new();
+        let report = delete_tree_with(&dir.join("tree"), &mut |removed| {
+            counts.push(removed);
+            ControlFlow::Continue(())
+        }).unwrap();
+        if privileged {
+            assert_eq!(report.removed, 6);
+            assert!(report.failures.is_empty());
+        } else {
+            // The directory holding the file that stays stays too, without failing itself
+            assert_eq!(report.removed, 3);
+            assert_eq!(report.failures.len(), 1);
+            assert_eq!(report.failures[0].0, dir.join("tree/kept/b.txt"));
+            assert!(dir.join("tree/kept/b.txt").exists() && !dir.join("tree/sub").exists());
+            fs::set_permissions(dir.join("tree/kept"), fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        assert_eq!(counts, (1..=report.removed).collect::<Vec<_>>());
+
+        fs::create_dir_all(dir.join("again/sub")).unwrap();
+        fs::write(dir.join("again/sub/a.txt"), "").unwrap();
+        let error = delete_tree_with(&dir.join("again"), &mut |_| ControlFlow::Break(())).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+        assert!(dir.join("again/sub").exists() && !dir.join("again/sub/a.txt").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_is_hidden() {
+        let dir = std::env::temp_dir().join(format!("browse-hidden-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(".profile"), "").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let mut hidden: Vec<_> = fs::read_dir(&dir).unwrap()
+            .flatten()
+            .map(|entry| (entry.file_name(), is_hidden(&entry)))
+            .collect();
+        hidden.sort();
+        assert_eq!(hidden, [(".profile".into(), true), ("notes.txt".into(), false)]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_untrusted_files_are_not_read() {
+        let dir = std::env::temp_dir().join(format!("browse-untrusted-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("downloads")).unwrap();
+        // A PNG header under a text name: sniffing would call it an image
+        fs::write(dir.join("downloads/notes.txt"), b"\x89PNG\r\n\x1a\n").unwrap();
+        fs::write(dir.join("notes.txt"), "hello").unwrap();
+        let config = Settings {
+            untrusted_roots: vec![dir.join("downloads").to_string_lossy().to_string()],
+            ..Settings::default()
+        };
+
+        let details = FileDetails::from_path(&dir.join("downloads/notes.txt"), &config).unwrap();
+        assert_eq!(details.content_preview, PreviewContent::Untrusted);
+        assert_eq!(details.mime_type.as_deref(), Some("text/plain"));
+        assert_eq!(details.size, 8);
+
+        let details = FileDetails::from_path(&dir.join("notes.txt"), &config).unwrap();
+        assert_eq!(details.content_preview.text(), Some("hello"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_unreadable_file_preview() {
+        // SAFETY: geteuid can't fail and has no side effects
+        if unsafe { libc::geteuid() } == 0 {
+            // Root reads any file, so there is nothing to refuse
+            return;
+        }
+        let dir = std::env::temp_dir().join(format!("browse-unreadable-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("secret.txt"), "hidden").unwrap();
+        fs::set_permissions(dir.join("secret.txt"), fs::Permissions::from_mode(0o200)).unwrap();
+
+        let details = FileDetails::from_path(&dir.join("secret.txt"), &Settings::default()).unwrap();
+        assert!(matches!(details.content_preview, PreviewContent::PermissionDenied { mode: 0o200, owned: true, .. }));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_mime_type_sniffing_can_be_turned_off() {
+        let dir = std::env::temp_dir().join(format!("browse-sniff-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("image.txt"), b"\x89PNG\r\n\x1a\n").unwrap();
+
+        let config = Settings::default();
+        assert_eq!(mime_type_for(&dir.join("image.txt"), &config).as_deref(), Some("image/png"));
+        let config = Settings { sniff_mime_types: false, ..Settings::default() };
+        assert_eq!(mime_type_for(&dir.join("image.txt"), &config).as_deref(), Some("text/plain"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_preview_loads_more_on_demand() {
+        let dir = std::env::temp_dir().join(format!("browse-preview-chunks-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        // "é" takes two bytes, so the first 10 bytes end halfway through one
+        fs::write(dir.join("log.txt"), format!("a{}", "é".repeat(10))).unwrap();
+        let config = Settings { preview_bytes: 10, ..Settings::default() };
+
+        let mut details = FileDetails::from_path(&dir.join("log.txt"), &config).unwrap();
+        assert_eq!(details.content_preview, PreviewContent::Text { text: format!("a{}", "é".repeat(4)), truncated: true });
+        assert!(details.load_more(&config));
+        assert_eq!(details.content_preview.text(), Some(format!("a{}", "é".repeat(9)).as_str()));
+        assert!(details.load_more(&config));
+        assert_eq!(details.content_preview, PreviewContent::Text { text: format!("a{}", "é".repeat(10)), truncated: false });
+        assert!(!details.load_more(&config));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_disk_space() {
+        let space = disk_space(&std::env::temp_dir()).unwrap();
+        assert!(space.total > 0);
+        assert!(space.free <= space.total);
+        assert_eq!(disk_space(Path::new("/no/such/place")).unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_touch_path_opens_nothing() {
+        let dir = std::env::temp_dir().join(format!("browse-touch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // Opening a FIFO nothing writes to would block
+        let fifo = std::ffi::CString::new(dir.join("pipe").into_os_string().into_encoded_bytes()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o644) }, 0);
+        fs::write(dir.join("secret.txt"), "").unwrap();
+        fs::set_permissions(dir.join("secret.txt"), fs::Permissions::from_mode(0o200)).unwrap();
+
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        for name in ["pipe", "secret.txt"] {
+            touch_path(&dir.join(name), time).unwrap();
+            assert_eq!(fs::metadata(dir.join(name)).unwrap().modified().unwrap(), time);
+        }
+        let before_epoch = SystemTime::UNIX_EPOCH - Duration::from_millis(1500);
+        touch_path(&dir.join("secret.txt"), before_epoch).unwrap();
+        assert_eq!(fs::metadata(dir.join("secret.txt")).unwrap().modified().unwrap(), before_epoch);
+        assert_eq!(touch_path(&dir.join("missing"), time).unwrap_err().kind(), io::ErrorKind::NotFound);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_with_timeout() {
+        let dir = PathBuf::from("/browse-with-timeout");
+        let quick = with_timeout((dir.clone(), false), Duration::from_secs(5), || Ok((Vec::new(), Vec::new())));
+        assert!(quick.is_some_and(|listing| listing.is_ok()));
+
+        // A hung read, like one on a dead network mount, is given up on
+        let hung = || {
+            thread::sleep(Duration::from_millis(200));
+            Err(io::Error::other("hung"))
+        };
+        assert!(with_timeout((dir.clone(), true), Duration::from_millis(20), hung).is_none());
+        // Until it answers, trying again waits on it rather than reading again
+        let read_again = || panic!("read again while the first read was outstanding");
+        assert!(with_timeout((dir.clone(), true), Duration::from_millis(20), read_again).is_none());
+        let answer = with_timeout((dir.clone(), true), Duration::from_secs(5), read_again);
+        assert_eq!(answer.unwrap().unwrap_err().to_string(), "hung");
+        // Once it has, the next try reads afresh
+        let read_afresh = with_timeout((dir, true), Duration::from_secs(5), || Ok((Vec::new(), Vec::new())));
+        assert!(read_afresh.is_some_and(|listing| listing.is_ok()));
+    }
+
+    #[test]
+    fn test_directory_category() {
+        let dir = std::env::temp_dir().join(format!("browse-category-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("empty")).unwrap();
+        fs::create_dir_all(dir.join("repo/.git")).unwrap();
+        fs::create_dir_all(dir.join("full")).unwrap();
+        fs::write(dir.join("full/file.txt"), "").unwrap();
+
+        assert_eq!(directory_category(&dir.join("empty"), false), "empty-directory");
+        assert_eq!(directory_category(&dir.join("repo"), false), "git-repo");
+        assert_eq!(directory_category(&dir.join("full"), false), "directory");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,12 @@
+use crate::ansi::{ansi_text, has_escapes};
 use crate::browser::content_width;
-use crate::utils::{format_file_size, truncate_text};
-use crate::file_operations::{FileDetails};
+use crate::config::Settings;
+use crate::utils::{escaped_name, format_file_size, truncate_text};
+use crate::file_operations::{FileDetails, PreviewContent};
+use crate::outline::{Outline, OutlineMove};
+use crate::pdf::PdfSummary;
+use crate::tabular::{one_line, Tabular};
+use crossterm::event::{KeyCode, KeyEvent};
 
 use ratatui::{
     prelude::*,
@@ -8,16 +14,196 @@ use ratatui::{
 };
 
 const SYMLINK_PREFIX_WIDTH: usize = 16; // "Symlink -> " + padding
+const LOAD_AHEAD_LINES: usize = 100; // the full screen preview reads this far past the scroll
+const LINKED_IMAGE_HEIGHT: u16 = 12; // most rows the box of a linked image takes, borders included
 
-/// Render file preview panel
-pub fn render_file_preview(frame: &mut Frame, details: &FileDetails, area: Rect) {
-    let chunks = Layout::vertical([Constraint::Max(8), Constraint::Min(0)]).split(area);
+/// MIME type given to a diff shown in place of a file's content, which colors its lines
+pub const DIFF_MIME_TYPE: &str = "text/x-diff";
 
-    let title = details
-        .path
-        .file_name()
-        .unwrap_or_default()
-        .to_string_lossy();
+/// A file's preview shown over the whole screen, for file types whose Enter action is `preview`
+#[derive(Debug)]
+pub struct FullPreview {
+    pub details: FileDetails,
+    /// Lines of the content scrolled past
+    pub scroll: u16,
+}
+
+impl FullPreview {
+    pub fn new(details: FileDetails) -> Self {
+        Self { details, scroll: 0 }
+    }
+
+    /// Show a diff of the file in place of its content
+    pub fn diff(mut details: FileDetails, diff: String) -> Self {
+        details.content_preview = PreviewContent::Text { text: diff, truncated: false };
+        details.mime_type = Some(DIFF_MIME_TYPE.to_string());
+        Self::new(details)
+    }
+
+    /// Show a program's documentation in place of its content
+    pub fn help(mut details: FileDetails, help: String) -> Self {
+        details.content_preview = PreviewContent::Text { text: help, truncated: false };
+        details.mime_type = Some("text/plain".to_string());
+        Self::new(details)
+    }
+
+    /// Handle full screen preview key input, returning true when it should close
+    ///
+    /// More of the file is read as the scroll nears the end of what was read. In
+    /// an outline the arrows and Space move the cursor and fold, and the view
+    /// follows the cursor through `page` lines. A table scrolls sideways with
+    /// Left and Right.
+    pub fn handle_key(&mut self, key: KeyEvent, page: usize, config: &Settings) -> bool {
+        if let PreviewContent::Outline(outline) = &mut self.details.content_preview {
+            let action = match key.code {
+                KeyCode::Up => Some(OutlineMove::Up),
+                KeyCode::Down => Some(OutlineMove::Down),
+                KeyCode::Left => Some(OutlineMove::Fold),
+                KeyCode::Right => Some(OutlineMove::Unfold),
+                KeyCode::Char(' ') => Some(OutlineMove::Toggle),
+                _ => None,
+            };
+            if let Some(action) = action {
+                outline.apply(action);
+                self.scroll = scroll_to_cursor(outline, self.scroll, page);
+                return false;
+            }
+        }
+        if let PreviewContent::Table(table) = &mut self.details.content_preview {
+            match key.code {
+                KeyCode::Left => table.scroll_columns(-1),
+                KeyCode::Right => table.scroll_columns(1),
+                _ => {}
+            }
+        }
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Enter => return true,
+            KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+            KeyCode::Home => self.scroll = 0,
+            _ => {}
+        }
+        let line_count = |details: &FileDetails| details.content_preview.text().map_or(0, |text| text.lines().count());
+        while line_count(&self.details) < self.scroll as usize + LOAD_AHEAD_LINES && self.details.load_more(config) {}
+        // Stop at the last line of the content
+        let lines = line_count(&self.details);
+        self.scroll = self.scroll.min(lines.saturating_sub(1) as u16);
+        false
+    }
+}
+
+/// Scroll that keeps an outline's cursor among the `page` lines in view, moving as little as it can
+pub fn scroll_to_cursor(outline: &Outline, scroll: u16, page: usize) -> u16 {
+    let row = outline.cursor_row();
+    let scroll = (scroll as usize).min(row).max((row + 1).saturating_sub(page));
+    scroll.min(u16::MAX as usize) as u16
+}
+
+/// Render a file's preview over the whole screen
+pub fn render_full_preview(frame: &mut Frame, preview: &FullPreview) {
+    let area = frame.area();
+    frame.render_widget(Clear, area);
+    render_file_preview(frame, &preview.details, area, preview.scroll);
+}
+
+/// Split a preview's area between the metadata and the content
+fn preview_layout(area: Rect, media_lines: usize) -> [Rect; 2] {
+    Layout::vertical([Constraint::Max(8 + media_lines as u16), Constraint::Min(0)]).areas(area)
+}
+
+/// Split a preview's content area between the text and the box of the file's linked image, if any
+fn content_layout(details: &FileDetails, area: Rect) -> (Rect, Option<Rect>) {
+    let content = preview_layout(area, details.media.len())[1];
+    if details.linked_image.is_none() {
+        return (content, None);
+    }
+    let height = LINKED_IMAGE_HEIGHT.min(content.height / 2);
+    let [text, image] = Layout::vertical([Constraint::Min(0), Constraint::Length(height)]).areas(content);
+    (text, Some(image))
+}
+
+/// Cells a preview's linked image is drawn over, inside its box
+pub fn linked_image_area(details: &FileDetails, area: Rect) -> Option<Rect> {
+    content_layout(details, area).1.map(|image| Block::default().borders(Borders::ALL).inner(image))
+}
+
+/// Number of content lines a preview of this area shows, for a file without media facts
+pub fn content_height(area: Rect) -> usize {
+    preview_layout(area, 0)[1].height.saturating_sub(2) as usize
+}
+
+/// Text of the content section of a preview
+pub fn content_text(details: &FileDetails) -> String {
+    match &details.content_preview {
+        PreviewContent::Text { text, truncated: false } => text.clone(),
+        PreviewContent::Text { text, truncated: true } => format!(
+            "{}\n\n[... File truncated - showing first {} of {} total ...]",
+            text,
+            format_file_size(text.len() as u64),
+            format_file_size(details.size)
+        ),
+        PreviewContent::Tail(text) if (text.len() as u64) < details.size => format!(
+            "[... Following - showing last {} of {} total ...]\n\n{}",
+            format_file_size(text.len() as u64),
+            format_file_size(details.size),
+            text
+        ),
+        PreviewContent::Tail(text) => text.clone(),
+        PreviewContent::Outline(outline) => outline.visible_text(),
+        PreviewContent::Table(table) => table.plain_text(),
+        PreviewContent::Pdf(summary) => pdf_text(summary),
+        PreviewContent::Message(message) => message.clone(),
+        PreviewContent::Disabled => String::new(),
+        PreviewContent::TooLarge => format!("[{} file - preview skipped]", format_file_size(details.size)),
+        PreviewContent::Untrusted => "[Untrusted location - contents not read]".to_string(),
+        PreviewContent::PermissionDenied { owner, mode, owned } => format!(
+            "[Permission denied]\n\nOwner: {}\nMode:  {:04o} ({})\n\n{}",
+            owner,
+            mode,
+            details.permissions,
+            if *owned {
+                "The file is yours: chmod u+r gives you read access".to_string()
+            } else {
+                format!("Ask {} for read access, or open it as root", owner)
+            }
+        ),
+    }
+}
+
+/// A PDF's metadata, then the text of its first page
+fn pdf_text(summary: &PdfSummary) -> String {
+    let mut text = String::new();
+    for (label, value) in [("Title: ", &summary.title), ("Author:", &summary.author)] {
+        if let Some(value) = value {
+            text.push_str(&format!("{} {}\n", label, one_line(value)));
+        }
+    }
+    if let Some(pages) = summary.pages {
+        text.push_str(&format!("Pages:  {}\n", pages));
+    }
+    let first_page = if summary.encrypted {
+        "[Encrypted - text not read]"
+    } else if summary.first_page.is_empty() {
+        "[No text read from the first page - it may be scanned, or use fonts with their own encodings]"
+    } else {
+        &summary.first_page
+    };
+    format!("{}\n{}", text, first_page).trim_start().to_string()
+}
+
+/// Render file preview panel with the content scrolled down by `scroll` lines
+///
+/// When the content doesn't fit, the content's title tells which lines are shown.
+/// Scrolling past the end shows the last lines.
+pub fn render_file_preview(frame: &mut Frame, details: &FileDetails, area: Rect, scroll: u16) {
+    let chunks = preview_layout(area, details.media.len());
+    let (text_area, image_area) = content_layout(details, area);
+
+    // A name that isn't UTF-8 shows its bytes, which the columns can only mark with `�`
+    let name = details.path.file_name().unwrap_or_default();
+    let title = escaped_name(name).unwrap_or_else(|| name.to_string_lossy().to_string());
 
     let truncated_title = truncate_text(&title, content_width(area));
 
@@ -27,12 +213,21 @@ pub fn render_file_preview(frame: &mut Frame, details: &FileDetails, area: Rect)
             Span::styled("Size: ", Style::default().add_modifier(Modifier::BOLD)),
             Span::raw(format_file_size(details.size)),
         ]),
-        Line::from(vec![
-            Span::styled("Permissions: ", Style::default().add_modifier(Modifier::BOLD)),
-            Span::raw(details.permissions.clone()),
-        ]),
     ];
 
+    // Media facts come right after the size, so a short preview still shows them
+    for (label, value) in &details.media {
+        lines.push(Line::from(vec![
+            Span::styled(format!("{}: ", label), Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(value.clone()),
+        ]));
+    }
+
+    lines.push(Line::from(vec![
+        Span::styled("Permissions: ", Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(details.permissions.clone()),
+    ]));
+
     if let Some(created) = details.created {
         lines.push(Line::from(vec![
             Span::styled("Created: ", Style::default().add_modifier(Modifier::BOLD)),
@@ -47,6 +242,13 @@ pub fn render_file_preview(frame: &mut Frame, details: &FileDetails, area: Rect)
         ]));
     }
 
+    if let Some(accessed) = details.accessed {
+        lines.push(Line::from(vec![
+            Span::styled("Accessed: ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(accessed.format("%Y-%m-%d %H:%M:%S").to_string()),
+        ]));
+    }
+
     if let Some(target) = &details.symlink_target {
         let target_str = target.to_string_lossy().to_string();
         let target_width = content_width(area).saturating_sub(SYMLINK_PREFIX_WIDTH);
@@ -70,9 +272,111 @@ pub fn render_file_preview(frame: &mut Frame, details: &FileDetails, area: Rect)
     );
 
     // Content preview section
-    let content_widget = Paragraph::new(details.content_preview.clone())
-        .block(Block::default().borders(Borders::ALL).title("Preview"));
-
+    let content_text = content_text(details);
+    let total = content_text.lines().count();
+    let height = text_area.height.saturating_sub(2) as usize;
+    let scroll = scroll.min(total.saturating_sub(height).min(u16::MAX as usize) as u16);
+    let title = if total > height {
+        let first = (scroll as usize + 1).min(total);
+        format!("Preview {}-{}/{}", first, (scroll as usize + height).min(total), total)
+    } else {
+        "Preview".to_string()
+    };
+    let content = if let PreviewContent::Outline(outline) = &details.content_preview {
+        outline_text(outline)
+    } else if details.mime_type.as_deref() == Some(DIFF_MIME_TYPE) {
+        Text::from(content_text.lines().map(diff_line).collect::<Vec<_>>())
+    } else if has_escapes(&content_text) {
+        ansi_text(&content_text)
+    } else {
+        Text::from(content_text)
+    };
     frame.render_widget(metadata_widget, chunks[0]);
-    frame.render_widget(content_widget, chunks[1]);
+    // The image itself is drawn over the box after the frame, when the terminal can
+    if let (Some(image), Some(image_area)) = (&details.linked_image, image_area) {
+        frame.render_widget(Block::default().borders(Borders::ALL).title(image.title()), image_area);
+    }
+    if let PreviewContent::Table(table) = &details.content_preview {
+        render_table(frame, table, text_area, scroll as usize);
+        return;
+    }
+    let content_widget = Paragraph::new(content)
+        .scroll((scroll, 0))
+        .block(Block::default().borders(Borders::ALL).title(title));
+
+    frame.render_widget(content_widget, text_area);
+}
+
+/// Draw a table preview's header and the records from `scroll` on, from its first shown column
+/// to as many as fit
+fn render_table(frame: &mut Frame, table: &Tabular, area: Rect, scroll: usize) {
+    let width = content_width(area);
+    // Shown columns with their widths and whether they are aligned right
+    let mut columns = Vec::new();
+    let mut used = 0;
+    for column in table.column_offset..table.column_count() {
+        let column_width = table.column_width(column);
+        if !columns.is_empty() && used + column_width > width {
+            break;
+        }
+        used += column_width + 1;
+        columns.push((column, column_width, table.is_numeric(column)));
+    }
+    let row = |row: &Vec<String>| {
+        Row::new(columns.iter().map(|&(column, width, numeric)| {
+            let text = truncate_text(&one_line(row.get(column).map_or("", String::as_str)), width);
+            if numeric { Line::from(text).alignment(Alignment::Right) } else { Line::from(text) }
+        }))
+    };
+
+    let records = table.rows.len().saturating_sub(1);
+    let height = content_height(area).saturating_sub(1);
+    let first = scroll.min(records.saturating_sub(height));
+    let more = if table.truncated { "+" } else { "" };
+    let mut title = format!("Table {}-{}/{}{} rows", (first + 1).min(records), (first + height).min(records), records, more);
+    if columns.len() < table.column_count() {
+        let last = columns.last().map_or(0, |(column, _, _)| column + 1);
+        title.push_str(&format!(", columns {}-{}/{}", table.column_offset + 1, last, table.column_count()));
+    }
+
+    let widths = columns.iter().map(|&(_, width, _)| Constraint::Length(width as u16));
+    let header = table.rows.first().map(|header| row(header).style(Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED)));
+    let mut widget = Table::new(table.rows.iter().skip(1 + first).take(height).map(row), widths)
+        .column_spacing(1)
+        .block(Block::default().borders(Borders::ALL).title(title));
+    if let Some(header) = header {
+        widget = widget.header(header);
+    }
+    frame.render_widget(widget, area);
+}
+
+/// Visible lines of an outline, with fold markers dimmed and the cursor's line highlighted
+fn outline_text(outline: &Outline) -> Text<'static> {
+    let cursor = outline.cursor_row();
+    let lines = outline.visible_lines().into_iter().enumerate().map(|(row, index)| {
+        let text = outline.display_line(index);
+        let (marker, line) = text.split_at(text.char_indices().nth(2).map_or(text.len(), |(at, _)| at));
+        let line = Line::from(vec![
+            Span::styled(marker.to_string(), Style::default().fg(Color::DarkGray)),
+            Span::raw(line.to_string()),
+        ]);
+        if row == cursor { line.style(Style::default().add_modifier(Modifier::REVERSED)) } else { line }
+    });
+    Text::from(lines.collect::<Vec<_>>())
+}
+
+/// A line of a unified diff, colored by what it is
+fn diff_line(line: &str) -> Line<'_> {
+    let color = if line.starts_with("+++") || line.starts_with("---") || line.starts_with("diff ") || line.starts_with("index ") {
+        Color::White
+    } else if line.starts_with('+') {
+        Color::Green
+    } else if line.starts_with('-') {
+        Color::Red
+    } else if line.starts_with("@@") {
+        Color::Cyan
+    } else {
+        return Line::raw(line);
+    };
+    Line::styled(line, Style::default().fg(color))
 }
@@ -0,0 +1,444 @@
+//! Subtree search
+//!
+//! A find walks the tree under a directory on a background job and streams every
+//! entry that matches its query to a results panel, where matches can be opened.
+//!
+//! Queries are whitespace-separated terms that must all match:
+//! - `age<2d` - modified less than two days ago
+//! - `age>1y` - modified more than a year ago
+//! - `size>100M` - larger than 100 MB
+//! - `size<1k` - smaller than 1 KB
+//! - `empty` - empty directories and zero-byte files
+//!
+//! Ages are a number followed by a unit: `s`, `m` (minutes), `h`, `d`, `w` or `y`.
+//! Sizes are a number with an optional `K`, `M`, `G` or `T` suffix (powers of 1024).
+
+use crate::file_operations::is_hidden;
+use crate::jobs::{JobContext, JobId};
+use crate::ui::centered_rect;
+use crate::utils::{format_date_compact, format_file_size};
+use chrono::{DateTime, Local};
+use crossterm::event::{KeyCode, KeyEvent};
+use std::collections::BTreeSet;
+use std::fs::{self, Metadata};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender};
+use std::time::{Duration, SystemTime};
+
+use ratatui::{
+    prelude::*,
+    widgets::*,
+};
+
+/// A single condition on an entry
+#[derive(Debug, Clone, PartialEq)]
+pub enum FindTerm {
+    /// Modified less than this long ago
+    NewerThan(Duration),
+    /// Modified more than this long ago
+    OlderThan(Duration),
+    /// More than this many bytes
+    LargerThan(u64),
+    /// Fewer than this many bytes
+    SmallerThan(u64),
+    /// Empty directory or zero-byte file
+    Empty,
+}
+
+/// Conditions an entry must all satisfy to be reported
+#[derive(Debug, Clone, PartialEq)]
+pub struct FindQuery {
+    pub terms: Vec<FindTerm>,
+}
+
+impl FindQuery {
+    /// Parse a query such as `age<2d`
+    pub fn parse(input: &str) -> Result<Self, String> {
+        let terms = input
+            .split_whitespace()
+            .map(parse_term)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if terms.is_empty() {
+            return Err("Empty query; try age<2d or size>100M".to_string());
+        }
+        Ok(Self { terms })
+    }
+
+    /// Check whether an entry matches every term
+    ///
+    /// Only files match, except that directories can match `empty`.
+    pub fn matches(&self, path: &Path, metadata: &Metadata, now: SystemTime) -> bool {
+        let is_dir = metadata.is_dir();
+        if !(metadata.is_file() || is_dir && self.terms.contains(&FindTerm::Empty)) {
+            return false;
+        }
+
+        let age = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .unwrap_or_default();
+
+        self.terms.iter().all(|term| match term {
+            FindTerm::NewerThan(limit) => age < *limit,
+            FindTerm::OlderThan(limit) => age > *limit,
+            FindTerm::LargerThan(limit) => metadata.len() > *limit,
+            FindTerm::SmallerThan(limit) => metadata.len() < *limit,
+            FindTerm::Empty if is_dir => fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none()),
+            FindTerm::Empty => metadata.len() == 0,
+        })
+    }
+}
+
+fn parse_term(term: &str) -> Result<FindTerm, String> {
+    if term == "empty" {
+        return Ok(FindTerm::Empty);
+    }
+    if let Some(age) = term.strip_prefix("age<") {
+        return parse_age(age).map(FindTerm::NewerThan);
+    }
+    if let Some(age) = term.strip_prefix("age>") {
+        return parse_age(age).map(FindTerm::OlderThan);
+    }
+    if let Some(size) = term.strip_prefix("size>") {
+        return parse_size(size).map(FindTerm::LargerThan);
+    }
+    if let Some(size) = term.strip_prefix("size<") {
+        return parse_size(size).map(FindTerm::SmallerThan);
+    }
+    Err(format!("Unknown search term '{}'", term))
+}
+
+/// Parse an age such as `90m`, `2d` or `1y`
+pub fn parse_age(input: &str) -> Result<Duration, String> {
+    let split = input.find(|c: char| !c.is_ascii_digit()).unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: u64 = number.parse().map_err(|_| format!("Invalid age '{}'", input))?;
+
+    let unit_secs: u64 = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        "y" => 365 * 24 * 60 * 60,
+        _ => return Err(format!("Invalid age '{}': use s, m, h, d, w or y", input)),
+    };
+    let secs = number.checked_mul(unit_secs).ok_or_else(|| format!("Age '{}' is too large", input))?;
+    Ok(Duration::from_secs(secs))
+}
+
+/// Parse a size such as `512`, `10K`, `1.5G` or `100MB`
+pub fn parse_size(input: &str) -> Result<u64, String> {
+    let split = input.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: f64 = number.parse().map_err(|_| format!("Invalid size '{}'", input))?;
+
+    let unit = unit.to_ascii_uppercase();
+    let multiplier: u64 = match unit.trim_end_matches('B') {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => return Err(format!("Invalid size '{}': use K, M, G or T", input)),
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// An entry that matched a query
+#[derive(Debug, Clone)]
+pub struct FindResult {
+    /// Position in discovery order
+    pub order: u64,
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// Walk the tree under `root`, sending each match to `results`
+///
+/// Symlinks are not followed. Hidden entries are skipped unless `show_hidden` is set.
+/// Returns the number of matches, or an error if the job was cancelled.
+pub fn find(root: &Path, query: &FindQuery, show_hidden: bool, ctx: &JobContext, results: &Sender<FindResult>) -> Result<u64, String> {
+    let now = SystemTime::now();
+    let mut pending = vec![root.to_path_buf()];
+    let mut scanned = 0;
+    let mut matches = 0;
+
+    while let Some(dir) = pending.pop() {
+        if ctx.is_cancelled() {
+            return Err("cancelled".to_string());
+        }
+
+        // Unreadable directories are skipped rather than failing the whole search
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            if !show_hidden && is_hidden(&entry) {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+
+            if query.matches(&entry.path(), &metadata, now) {
+                let result = FindResult {
+                    order: matches,
+                    is_dir: metadata.is_dir(),
+                    path: entry.path(),
+                    size: metadata.len(),
+                    modified: metadata.modified().ok(),
+                };
+                if results.send(result).is_err() {
+                    // Nobody is listening any more
+                    return Err("cancelled".to_string());
+                }
+                matches += 1;
+            }
+
+            if metadata.is_dir() {
+                pending.push(entry.path());
+            }
+        }
+
+        scanned += 1;
+        ctx.progress(scanned, None);
+    }
+
+    Ok(matches)
+}
+
+/// Result of a key press in the results panel
+#[derive(Debug, PartialEq)]
+pub enum ResultsOutcome {
+    Continue,
+    Close,
+    /// Close the panel and reveal this path in the browser
+    Open(PathBuf),
+    /// Delete these results
+    Delete(Vec<PathBuf>),
+}
+
+/// Panel listing the matches of a running or finished search
+#[derive(Debug)]
+pub struct ResultsPanel {
+    pub query: String,
+    pub root: PathBuf,
+    pub results: Vec<FindResult>,
+    pub state: ListState,
+    pub job: JobId,
+    receiver: Receiver<FindResult>,
+    pub finished: bool,
+    /// Largest results first instead of in the order they were found
+    pub sort_by_size: bool,
+    /// Results picked for a bulk operation
+    pub marked: BTreeSet<PathBuf>,
+}
+
+impl ResultsPanel {
+    pub fn new(query: String, root: PathBuf, job: JobId, receiver: Receiver<FindResult>) -> Self {
+        Self {
+            query,
+            root,
+            results: Vec::new(),
+            state: ListState::default(),
+            job,
+            receiver,
+            finished: false,
+            sort_by_size: false,
+            marked: BTreeSet::new(),
+        }
+    }
+
+    /// Collect matches the search has sent since the last poll
+    pub fn poll(&mut self) {
+        let count = self.results.len();
+        self.results.extend(self.receiver.try_iter());
+        if self.sort_by_size && self.results.len() > count {
+            self.sort();
+        }
+        if self.state.selected().is_none() && !self.results.is_empty() {
+            self.state.select(Some(0));
+        }
+    }
+
+    /// Switch between largest-first and discovery order
+    pub fn toggle_sort(&mut self) {
+        self.sort_by_size = !self.sort_by_size;
+        self.sort();
+    }
+
+    /// Reorder the results, keeping the same result selected
+    fn sort(&mut self) {
+        let selected_path = self.state.selected()
+            .and_then(|i| self.results.get(i))
+            .map(|result| result.path.clone());
+
+        // Stable sorts, so equal sizes keep discovery order; `order` restores it
+        if self.sort_by_size {
+            self.results.sort_by_key(|result| std::cmp::Reverse(result.size));
+        } else {
+            self.results.sort_by_key(|result| result.order);
+        }
+
+        if let Some(path) = selected_path {
+            self.state.select(self.results.iter().position(|result| result.path == path));
+        }
+    }
+
+    /// Paths a bulk operation applies to: the marked results, or else all of them
+    pub fn target_paths(&self) -> Vec<PathBuf> {
+        if self.marked.is_empty() {
+            self.results.iter().map(|result| result.path.clone()).collect()
+        } else {
+            self.marked.iter().cloned().collect()
+        }
+    }
+
+    /// Drop results that an operation has removed
+    pub fn remove_paths(&mut self, paths: &[PathBuf]) {
+        self.results.retain(|result| !paths.contains(&result.path));
+        self.marked.retain(|path| !paths.contains(path));
+        if self.results.is_empty() {
+            self.state.select(None);
+        } else if let Some(selected) = self.state.selected() {
+            self.state.select(Some(selected.min(self.results.len() - 1)));
+        }
+    }
+
+    /// Handle results panel key input
+    pub fn handle_key(&mut self, key: KeyEvent) -> ResultsOutcome {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return ResultsOutcome::Close,
+            KeyCode::Enter => {
+                return match self.state.selected().and_then(|i| self.results.get(i)) {
+                    Some(result) => ResultsOutcome::Open(result.path.clone()),
+                    None => ResultsOutcome::Continue,
+                };
+            }
+            KeyCode::Up => self.state.select_previous(),
+            KeyCode::Down => self.state.select_next(),
+            KeyCode::PageUp => self.state.scroll_up_by(10),
+            KeyCode::PageDown => self.state.scroll_down_by(10),
+            KeyCode::Home => self.state.select_first(),
+            KeyCode::End => self.state.select_last(),
+            KeyCode::Char('s') => self.toggle_sort(),
+            KeyCode::Char(' ') => {
+                if let Some(path) = self.state.selected().and_then(|i| self.results.get(i)).map(|result| result.path.clone()) {
+                    if !self.marked.remove(&path) {
+                        self.marked.insert(path);
+                    }
+                    self.state.select_next();
+                }
+            }
+            KeyCode::Delete if !self.results.is_empty() => return ResultsOutcome::Delete(self.target_paths()),
+            _ => {}
+        }
+
+        // ListState doesn't know the list length, so keep the selection on a result
+        if let Some(selected) = self.state.selected() {
+            self.state.select(Some(selected.min(self.results.len().saturating_sub(1))));
+        }
+        ResultsOutcome::Continue
+    }
+}
+
+/// Render the results panel popup
+pub fn render_results_panel(frame: &mut Frame, panel: &ResultsPanel) {
+    let area = centered_rect(80, 80, frame.area());
+    frame.render_widget(Clear, area);
+
+    let status = if panel.finished { "" } else { ", searching..." };
+    let order = if panel.sort_by_size { ", largest first" } else { "" };
+    let marked = if panel.marked.is_empty() { String::new() } else { format!(", {} marked", panel.marked.len()) };
+    let title = format!(
+        " {} in {} ({} found{}{}{}) ",
+        panel.query,
+        panel.root.display(),
+        panel.results.len(),
+        marked,
+        order,
+        status
+    );
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_bottom(Line::from(" Enter open, Space mark, Del delete, s sort by size, Esc close ").centered())
+        .border_style(Style::default().fg(Color::Cyan))
+        .padding(Padding::horizontal(1));
+
+    let items: Vec<ListItem> = panel
+        .results
+        .iter()
+        .map(|result| {
+            let relative = result.path.strip_prefix(&panel.root).unwrap_or(&result.path);
+            let modified = result
+                .modified
+                .map(|time| format_date_compact(&DateTime::<Local>::from(time)))
+                .unwrap_or_default();
+            let size = if result.is_dir { "dir".to_string() } else { format_file_size(result.size) };
+            let mut name = relative.to_string_lossy().to_string();
+            if result.is_dir {
+                name.push('/');
+            }
+            let name_style = if panel.marked.contains(&result.path) {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:>9}  {:>6}  ", size, modified), Style::default().fg(Color::DarkGray)),
+                Span::styled(name, name_style),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut state = panel.state.clone();
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query() {
+        assert_eq!(parse_age("90m"), Ok(Duration::from_secs(90 * 60)));
+        assert_eq!(parse_age("2d"), Ok(Duration::from_secs(2 * 24 * 60 * 60)));
+        assert!(parse_age("2").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("3 days").is_err());
+        assert_eq!(parse_age("99999999999999y"), Err("Age '99999999999999y' is too large".to_string()));
+
+        let query = FindQuery::parse("age>1w  age<1y").unwrap();
+        assert_eq!(query.terms, vec![
+            FindTerm::OlderThan(Duration::from_secs(7 * 24 * 60 * 60)),
+            FindTerm::NewerThan(Duration::from_secs(365 * 24 * 60 * 60)),
+        ]);
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("10K"), Ok(10 * 1024));
+        assert_eq!(parse_size("100mb"), Ok(100 * 1024 * 1024));
+        assert_eq!(parse_size("1.5G"), Ok(3 * 512 * 1024 * 1024));
+        assert!(parse_size("10X").is_err());
+        assert!(parse_size("M").is_err());
+        assert_eq!(
+            FindQuery::parse("size>100M").unwrap().terms,
+            vec![FindTerm::LargerThan(100 * 1024 * 1024)]
+        );
+
+        assert_eq!(FindQuery::parse("empty").unwrap().terms, vec![FindTerm::Empty]);
+
+        assert!(FindQuery::parse("").is_err());
+        assert!(FindQuery::parse("color=blue").is_err());
+    }
+}
@@ -0,0 +1,313 @@
+//! Fuzzy file finder
+//!
+//! Walks the tree under a directory on a background job while the user types, and
+//! lists the paths containing the typed characters in order, best matches first.
+//! Matches in the file name, at word boundaries and in runs rank higher.
+
+use crate::config::Settings;
+use crate::file_operations::is_hidden;
+use crate::input::{render_text_input, TextInput};
+use crate::jobs::{JobContext, JobId};
+use crate::ui::centered_rect;
+use crate::utils::search_key;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, Sender};
+
+use ratatui::{
+    prelude::*,
+    widgets::*,
+};
+
+/// Most matches kept and listed; typing more narrows the rest down
+const MAX_MATCHES: usize = 500;
+
+/// Score how well `query` matches `candidate`, or None if it doesn't match at all
+///
+/// The query's characters must appear in the candidate in order. They are matched
+/// from the end, so a match in the file name is preferred over one spread across
+/// directories. Both strings should already be normalized with `search_key`.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let query: Vec<char> = query.chars().collect();
+    let candidate: Vec<char> = candidate.chars().collect();
+    let mut remaining = query.len();
+    let mut score = 0;
+    let mut last_match = None;
+    // Start of the last path component; directories end in a separator
+    let trimmed = candidate.strip_suffix(&['/']).unwrap_or(&candidate);
+    let name_start = trimmed.iter().rposition(|&c| c == '/').map_or(0, |i| i + 1);
+
+    for (i, &c) in candidate.iter().enumerate().rev() {
+        if remaining == 0 {
+            break;
+        }
+        if c != query[remaining - 1] {
+            continue;
+        }
+        remaining -= 1;
+        score += if i >= name_start { 3 } else { 1 };
+
+        match i.checked_sub(1).map(|previous| candidate[previous]) {
+            None | Some('/') => score += 8,
+            Some('_' | '-' | '.' | ' ') => score += 4,
+            _ => {}
+        }
+        if last_match == Some(i + 1) {
+            score += 8;
+        }
+        last_match = Some(i);
+    }
+
+    if remaining > 0 {
+        return None;
+    }
+    // Shorter paths win between equally good matches
+    Some(score * 256 - candidate.len().min(255) as i64)
+}
+
+/// Walk the tree under `root`, sending every entry and whether it is a directory
+///
+/// Symlinks are not followed. Hidden entries are skipped unless `show_hidden` is set.
+/// Returns the number of entries found, or an error if the job was cancelled.
+pub fn walk(root: &Path, show_hidden: bool, ctx: &JobContext, paths: &Sender<(PathBuf, bool)>) -> Result<u64, String> {
+    let mut pending = vec![root.to_path_buf()];
+    let mut scanned = 0;
+    let mut found = 0;
+
+    while let Some(dir) = pending.pop() {
+        if ctx.is_cancelled() {
+            return Err("cancelled".to_string());
+        }
+
+        // Unreadable directories are skipped rather than failing the whole walk
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            if !show_hidden && is_hidden(&entry) {
+                continue;
+            }
+            let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+            if paths.send((entry.path(), is_dir)).is_err() {
+                // The finder was closed
+                return Err("cancelled".to_string());
+            }
+            found += 1;
+
+            if is_dir {
+                pending.push(entry.path());
+            }
+        }
+
+        scanned += 1;
+        ctx.progress(scanned, None);
+    }
+
+    Ok(found)
+}
+
+/// An entry found under the finder's root
+#[derive(Debug)]
+struct Candidate {
+    path: PathBuf,
+    /// Path relative to the root, as shown
+    display: String,
+    /// `display` normalized for matching
+    key: String,
+}
+
+/// Result of a key press in the fuzzy finder
+#[derive(Debug, PartialEq)]
+pub enum FuzzyOutcome {
+    Continue,
+    Close,
+    /// Close the finder and show this path in the browser
+    Open(PathBuf),
+}
+
+/// Popup that fuzzy-matches the paths under a directory as they are found
+#[derive(Debug)]
+pub struct FuzzyFinder {
+    pub root: PathBuf,
+    pub input: TextInput,
+    candidates: Vec<Candidate>,
+    /// Scores and candidate indices of the best matches, best first
+    matches: Vec<(i64, usize)>,
+    pub state: ListState,
+    pub job: JobId,
+    receiver: Receiver<(PathBuf, bool)>,
+    pub finished: bool,
+}
+
+impl FuzzyFinder {
+    pub fn new(root: PathBuf, job: JobId, receiver: Receiver<(PathBuf, bool)>) -> Self {
+        Self {
+            root,
+            input: TextInput::default(),
+            candidates: Vec::new(),
+            matches: Vec::new(),
+            state: ListState::default(),
+            job,
+            receiver,
+            finished: false,
+        }
+    }
+
+    /// Number of entries found so far
+    pub fn candidate_count(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Number of entries matching the query, up to the listing limit
+    pub fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Path of the selected match
+    pub fn selected_path(&self) -> Option<&Path> {
+        self.state
+            .selected()
+            .and_then(|i| self.matches.get(i))
+            .map(|&(_, index)| self.candidates[index].path.as_path())
+    }
+
+    /// Collect entries the walk has found since the last poll and match them
+    pub fn poll(&mut self, config: &Settings) {
+        let start = self.candidates.len();
+        for (path, is_dir) in self.receiver.try_iter() {
+            let relative = path.strip_prefix(&self.root).unwrap_or(&path);
+            let mut display = relative.to_string_lossy().to_string();
+            if is_dir {
+                display.push('/');
+            }
+            let key = search_key(&display, config);
+            self.candidates.push(Candidate { path, display, key });
+        }
+
+        if self.candidates.len() > start {
+            self.add_matches(start, config);
+        }
+    }
+
+    /// Match the candidates from `start` on against the query, keeping the best overall
+    fn add_matches(&mut self, start: usize, config: &Settings) {
+        let selected = self.state.selected().and_then(|i| self.matches.get(i)).map(|&(_, index)| index);
+
+        let query = search_key(self.input.value(), config);
+        for (index, candidate) in self.candidates.iter().enumerate().skip(start) {
+            if let Some(score) = fuzzy_score(&query, &candidate.key) {
+                self.matches.push((score, index));
+            }
+        }
+        // Ties keep the order the entries were found in
+        self.matches.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        self.matches.truncate(MAX_MATCHES);
+
+        let position = selected.and_then(|selected| self.matches.iter().position(|&(_, index)| index == selected));
+        self.state.select(if self.matches.is_empty() { None } else { Some(position.unwrap_or(0)) });
+    }
+
+    /// Handle fuzzy finder key input
+    pub fn handle_key(&mut self, key: KeyEvent, config: &Settings) -> FuzzyOutcome {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return FuzzyOutcome::Close,
+            KeyCode::Enter => {
+                return match self.selected_path() {
+                    Some(path) => FuzzyOutcome::Open(path.to_path_buf()),
+                    None => FuzzyOutcome::Continue,
+                };
+            }
+            KeyCode::Up => self.state.select_previous(),
+            KeyCode::Char('p') if ctrl => self.state.select_previous(),
+            KeyCode::Down => self.state.select_next(),
+            KeyCode::Char('n') if ctrl => self.state.select_next(),
+            KeyCode::PageUp => self.state.scroll_up_by(10),
+            KeyCode::PageDown => self.state.scroll_down_by(10),
+            _ => {
+                let query = self.input.value().to_string();
+                self.input.handle_key(key);
+                if self.input.value() != query {
+                    self.matches.clear();
+                    self.state.select(None);
+                    self.add_matches(0, config);
+                }
+            }
+        }
+
+        // ListState doesn't know the list length, so keep the selection on a match
+        if let Some(selected) = self.state.selected() {
+            self.state.select(Some(selected.min(self.matches.len().saturating_sub(1))));
+        }
+        FuzzyOutcome::Continue
+    }
+}
+
+/// Render the fuzzy finder popup
+pub fn render_fuzzy_finder(frame: &mut Frame, finder: &FuzzyFinder) {
+    let area = centered_rect(80, 80, frame.area());
+    frame.render_widget(Clear, area);
+
+    let status = if finder.finished { "" } else { ", indexing..." };
+    let title = format!(
+        " Find file in {} ({} of {}{}) ",
+        finder.root.display(),
+        finder.match_count(),
+        finder.candidate_count(),
+        status
+    );
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(area);
+
+    let input_block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(Style::default().fg(Color::Cyan));
+    render_text_input(frame, &finder.input, input_block, layout[0], true);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title_bottom(Line::from(" Enter open, Up/Down select, Esc close ").centered())
+        .border_style(Style::default().fg(Color::Cyan))
+        .padding(Padding::horizontal(1));
+
+    let items: Vec<ListItem> = finder
+        .matches
+        .iter()
+        .map(|&(_, index)| ListItem::new(finder.candidates[index].display.as_str()))
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut state = finder.state.clone();
+    frame.render_stateful_widget(list, layout[1], &mut state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("abc", "a/b/c").is_some());
+        assert!(fuzzy_score("cba", "a/b/c").is_none());
+        assert!(fuzzy_score("", "anything").is_some());
+
+        // A run in the file name beats letters scattered over the path
+        let name = fuzzy_score("main", "src/main.rs").unwrap();
+        let scattered = fuzzy_score("main", "m/a/i/n.rs").unwrap();
+        let spread = fuzzy_score("main", "make/a/tiny/note.txt").unwrap();
+        assert!(name > scattered);
+        assert!(scattered > spread);
+
+        // Equal matches prefer the shorter path
+        assert!(fuzzy_score("app", "src/app.rs").unwrap() > fuzzy_score("app", "src/old/app.rs").unwrap());
+    }
+}
@@ -0,0 +1,357 @@
+//! Git status of directory entries
+//!
+//! Columns inside a git worktree show a badge for each entry that is modified,
+//! staged, untracked, ignored or in conflict. The status comes from
+//! `git status --porcelain` and is cached per directory for a couple of seconds,
+//! so moving the selection over directories doesn't run git for each preview.
+//! Reloading a column asks git again.
+//!
+//! The status bar shows the branch of the active directory's worktree, how far
+//! it is ahead of and behind its upstream, and whether it has changes. That is
+//! checked on a background thread every few seconds.
+
+use ratatui::style::Color;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a directory's status is reused before git is asked again
+const STATUS_CACHE_TTL: Duration = Duration::from_secs(2);
+
+/// How often the status bar's branch and sync state is checked again
+const REPO_INFO_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Status of an entry, ordered so a directory shows the most pressing status inside it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GitStatus {
+    Ignored,
+    Untracked,
+    Staged,
+    Modified,
+    Conflicted,
+}
+
+impl GitStatus {
+    /// Parse the two status letters of a porcelain line
+    fn from_codes(index: u8, worktree: u8) -> Self {
+        match (index, worktree) {
+            (b'?', _) => GitStatus::Untracked,
+            (b'!', _) => GitStatus::Ignored,
+            (b'U', _) | (_, b'U') | (b'A', b'A') | (b'D', b'D') => GitStatus::Conflicted,
+            (_, b' ') => GitStatus::Staged,
+            _ => GitStatus::Modified,
+        }
+    }
+
+    /// Letter shown after the entry's name
+    pub fn badge(self) -> char {
+        match self {
+            GitStatus::Ignored => '!',
+            GitStatus::Untracked => '?',
+            GitStatus::Staged => '+',
+            GitStatus::Modified => 'M',
+            GitStatus::Conflicted => 'U',
+        }
+    }
+
+    pub fn color(self) -> Color {
+        match self {
+            GitStatus::Ignored => Color::DarkGray,
+            GitStatus::Untracked => Color::Magenta,
+            GitStatus::Staged => Color::Green,
+            GitStatus::Modified => Color::Yellow,
+            GitStatus::Conflicted => Color::Red,
+        }
+    }
+}
+
+/// Git status of the entries of one directory, by name
+///
+/// Entries that are clean aren't listed.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DirGitStatus {
+    entries: HashMap<OsString, GitStatus>,
+}
+
+impl DirGitStatus {
+    /// Status of an entry by name, None when it is clean
+    pub fn get(&self, name: &OsStr) -> Option<GitStatus> {
+        self.entries.get(name).copied()
+    }
+
+    /// Build from `git status --porcelain=v1 -z` output for a directory
+    ///
+    /// `prefix` is the directory relative to the root of the repository, which is
+    /// what porcelain paths are relative to. Changes deeper down are summed up in
+    /// the entry they are under, except for ignored files.
+    pub fn parse(output: &[u8], prefix: &Path) -> Self {
+        let mut entries: HashMap<OsString, GitStatus> = HashMap::new();
+        let mut records = output.split(|&byte| byte == 0);
+        while let Some(record) = records.next() {
+            if record.len() < 4 {
+                continue;
+            }
+            let status = GitStatus::from_codes(record[0], record[1]);
+            // Renames and copies are followed by the path they came from
+            if matches!(record[0], b'R' | b'C') {
+                records.next();
+            }
+
+            let path = Path::new(OsStr::from_bytes(&record[3..]));
+            let Ok(relative) = path.strip_prefix(prefix) else {
+                continue;
+            };
+            let mut components = relative.components();
+            let Some(Component::Normal(name)) = components.next() else {
+                continue;
+            };
+            let deeper = components.next().is_some();
+            if deeper && status == GitStatus::Ignored {
+                continue;
+            }
+
+            let entry = entries.entry(name.to_os_string()).or_insert(status);
+            *entry = (*entry).max(status);
+        }
+        Self { entries }
+    }
+}
+
+/// Root of the worktree a directory is in, if any
+pub fn repo_root(dir: &Path) -> Option<&Path> {
+    if dir.components().any(|component| component.as_os_str() == ".git") {
+        return None;
+    }
+    dir.ancestors().find(|ancestor| ancestor.join(".git").exists())
+}
+
+type StatusCache = Mutex<HashMap<PathBuf, (Instant, Option<Arc<DirGitStatus>>)>>;
+
+fn cache() -> &'static StatusCache {
+    static CACHE: OnceLock<StatusCache> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// Git status of a directory's entries, or None outside a worktree or without git
+///
+/// A status from the last couple of seconds is reused unless `refresh` is set.
+pub fn dir_status(dir: &Path, refresh: bool) -> Option<Arc<DirGitStatus>> {
+    let dir = dir.canonicalize().ok()?;
+    let root = repo_root(&dir)?;
+
+    if !refresh
+        && let Some((checked, status)) = cache().lock().ok()?.get(&dir)
+        && checked.elapsed() < STATUS_CACHE_TTL
+    {
+        return status.clone();
+    }
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(&dir)
+        .args(["status", "--porcelain=v1", "-z", "--ignored", "--", "."])
+        // Checking status shouldn't get in the way of git commands run meanwhile
+        .env("GIT_OPTIONAL_LOCKS", "0")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success());
+    let status = output.map(|output| Arc::new(DirGitStatus::parse(&output.stdout, dir.strip_prefix(root).unwrap_or(&dir))));
+
+    if let Ok(mut cache) = cache().lock() {
+        cache.insert(dir.clone(), (Instant::now(), status.clone()));
+    }
+    status
+}
+
+/// Unified diff of a file's changes against HEAD, staged or not
+///
+/// Empty when the file has no changes.
+pub fn diff_against_head(path: &Path) -> Result<String, String> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(format!("{} is not a file in a repository", path.display()));
+    };
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["diff", "--no-color", "--no-ext-diff", "HEAD", "--"])
+        .arg(name)
+        .env("GIT_OPTIONAL_LOCKS", "0")
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Cannot run git: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Branch and sync state of a worktree, for the status bar
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RepoInfo {
+    /// Branch name, or the abbreviated commit when HEAD is detached
+    pub branch: String,
+    /// Commits not on the upstream branch yet
+    pub ahead: u64,
+    /// Upstream commits not merged yet
+    pub behind: u64,
+    /// Tracked files have changes, staged or not
+    pub dirty: bool,
+}
+
+impl RepoInfo {
+    /// Build from `git status --porcelain=v2 --branch -z` output
+    pub fn parse(output: &[u8]) -> Self {
+        let mut info = RepoInfo::default();
+        let mut oid = "";
+        for record in output.split(|&byte| byte == 0).filter_map(|record| std::str::from_utf8(record).ok()) {
+            if let Some(header) = record.strip_prefix("# ") {
+                let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+                match key {
+                    "branch.oid" => oid = value,
+                    "branch.head" => info.branch = value.to_string(),
+                    "branch.ab" => {
+                        for count in value.split(' ') {
+                            if let Some(ahead) = count.strip_prefix('+') {
+                                info.ahead = ahead.parse().unwrap_or(0);
+                            } else if let Some(behind) = count.strip_prefix('-') {
+                                info.behind = behind.parse().unwrap_or(0);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            } else if !record.is_empty() {
+                info.dirty = true;
+            }
+        }
+        if info.branch == "(detached)" {
+            info.branch = format!("({})", oid.get(..7).unwrap_or(oid));
+        }
+        info
+    }
+
+    /// Short description such as `main ↑1 ↓2 *`
+    pub fn summary(&self) -> String {
+        let mut summary = self.branch.clone();
+        if self.ahead > 0 {
+            summary.push_str(&format!(" ↑{}", self.ahead));
+        }
+        if self.behind > 0 {
+            summary.push_str(&format!(" ↓{}", self.behind));
+        }
+        if self.dirty {
+            summary.push_str(" *");
+        }
+        summary
+    }
+}
+
+/// Ask git for the branch and sync state of the worktree `dir` is in
+pub fn repo_info(dir: &Path) -> Option<RepoInfo> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["status", "--porcelain=v2", "--branch", "-z", "--untracked-files=no"])
+        .env("GIT_OPTIONAL_LOCKS", "0")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    Some(RepoInfo::parse(&output.stdout))
+}
+
+/// Keeps the repo info of the active directory up to date in the background
+#[derive(Debug, Default)]
+pub struct RepoWatcher {
+    root: Option<PathBuf>,
+    info: Option<RepoInfo>,
+    checked: Option<Instant>,
+    pending: Option<Receiver<Option<RepoInfo>>>,
+}
+
+impl RepoWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Info for the worktree of the directory last passed to `update`, once known
+    pub fn info(&self) -> Option<&RepoInfo> {
+        self.info.as_ref()
+    }
+
+    /// Collect a finished check, and start another when `dir` is in a different
+    /// worktree or the last check is a few seconds old
+    pub fn update(&mut self, dir: &Path) {
+        let root = repo_root(dir).map(Path::to_path_buf);
+        if root != self.root {
+            *self = Self { root, ..Self::default() };
+        }
+        let Some(root) = &self.root else {
+            return;
+        };
+
+        if let Some(receiver) = &self.pending {
+            match receiver.try_recv() {
+                Ok(info) => {
+                    self.info = info;
+                    self.pending = None;
+                }
+                Err(TryRecvError::Disconnected) => self.pending = None,
+                Err(TryRecvError::Empty) => return,
+            }
+        }
+
+        if self.checked.is_none_or(|checked| checked.elapsed() >= REPO_INFO_INTERVAL) {
+            let (sender, receiver) = mpsc::channel();
+            let root = root.clone();
+            thread::spawn(move || {
+                let _ = sender.send(repo_info(&root));
+            });
+            self.pending = Some(receiver);
+            self.checked = Some(Instant::now());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_porcelain() {
+        let output = b" M src/main.rs\0M  src/lib.rs\0?? src/new/\0!! src/target/\0!! src/deep/cache.o\0\
+R  src/renamed.rs\0src/old.rs\0UU src/deep/conflict.rs\0 M readme.md\0";
+        let status = DirGitStatus::parse(output, Path::new("src"));
+
+        assert_eq!(status.get(OsStr::new("main.rs")), Some(GitStatus::Modified));
+        assert_eq!(status.get(OsStr::new("lib.rs")), Some(GitStatus::Staged));
+        assert_eq!(status.get(OsStr::new("new")), Some(GitStatus::Untracked));
+        assert_eq!(status.get(OsStr::new("target")), Some(GitStatus::Ignored));
+        assert_eq!(status.get(OsStr::new("renamed.rs")), Some(GitStatus::Staged));
+        assert_eq!(status.get(OsStr::new("old.rs")), None);
+        // A directory shows the most pressing status inside it, but not ignored files
+        assert_eq!(status.get(OsStr::new("deep")), Some(GitStatus::Conflicted));
+        // Outside the directory
+        assert_eq!(status.get(OsStr::new("readme.md")), None);
+    }
+
+    #[test]
+    fn test_parse_repo_info() {
+        let output = b"# branch.oid 3ae5867320\0# branch.head main\0# branch.upstream origin/main\0# branch.ab +2 -1\01 .M N... 100644 100644 100644 0000 0000 src/main.rs\0";
+        let info = RepoInfo::parse(output);
+        assert_eq!(info, RepoInfo { branch: "main".to_string(), ahead: 2, behind: 1, dirty: true });
+        assert_eq!(info.summary(), "main ↑2 ↓1 *");
+
+        let detached = RepoInfo::parse(b"# branch.oid 3ae5867320\0# branch.head (detached)\0");
+        assert_eq!(detached.summary(), "(3ae5867)");
+    }
+}
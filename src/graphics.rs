@@ -0,0 +1,282 @@
+//! Images a document links to, drawn in the terminal
+//!
+//! A markdown or HTML preview can show the first local image the document
+//! links to, with `![alt](path)` or `<img src="path">`, in a box under its text.
+//! The box is titled with the image's name and dimensions on any terminal. On a
+//! terminal with an image protocol, the picture itself is drawn in the box after
+//! the frame, and taken down when the box moves or goes away.
+//!
+//! The kitty graphics protocol (kitty, Ghostty) is sent PNG files as they are;
+//! other formats would need decoding, so only PNGs are drawn there. iTerm2's
+//! inline images (iTerm2, WezTerm) take any format the terminal can read.
+
+use crate::clipboard::base64_encode;
+use crate::config::Settings;
+use crate::media::{media_info, MediaField};
+use ratatui::layout::Rect;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// MIME types whose previews look for a linked image
+pub const LINKING_MIME_TYPES: [&str; 2] = ["text/markdown", "text/html"];
+
+/// Images over this size aren't sent to the terminal
+const MAX_IMAGE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Extensions of the images a terminal can draw
+const IMAGE_EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "gif", "webp", "bmp"];
+
+/// Base64 sent to kitty in one escape sequence, as its protocol asks
+const KITTY_CHUNK: usize = 4096;
+
+/// A local image a document links to
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkedImage {
+    pub path: PathBuf,
+    /// Dimensions and the like, for the box's title
+    pub media: Vec<MediaField>,
+}
+
+impl LinkedImage {
+    /// Name and dimensions of the image, as the box's title
+    pub fn title(&self) -> String {
+        let name = self.path.file_name().unwrap_or_default().to_string_lossy();
+        match self.media.iter().find(|(label, _)| *label == "Dimensions") {
+            Some((_, dimensions)) => format!("{} {}", name, dimensions),
+            None => name.to_string(),
+        }
+    }
+}
+
+/// The first local image linked from a document's text, resolved against the document's directory
+///
+/// Links to the web and `data:` URLs are passed over, as are links to files that
+/// don't exist. So are images under `untrusted_roots`, before they are opened.
+pub fn first_linked_image(text: &str, dir: &Path, config: &Settings) -> Option<LinkedImage> {
+    let mut links = markdown_image_links(text);
+    links.extend(html_image_links(text));
+    links.sort_by_key(|&(at, _)| at);
+    links.into_iter().find_map(|(_, target)| {
+        let path = resolve_link(&target, dir).filter(|path| !config.is_untrusted(path))?;
+        Some(LinkedImage { media: media_info(&path), path })
+    })
+}
+
+/// Targets of `![alt](target "title")` with where they are in the text
+fn markdown_image_links(text: &str) -> Vec<(usize, String)> {
+    let mut links = Vec::new();
+    for (at, _) in text.match_indices("![") {
+        let rest = &text[at + 2..];
+        let Some(close) = rest.find("](") else {
+            continue;
+        };
+        let target = rest[close + 2..].trim_start();
+        let target = match target.strip_prefix('<') {
+            Some(bracketed) => bracketed.split('>').next(),
+            None => target.split([')', ' ', '\t', '\n']).next(),
+        };
+        links.extend(target.filter(|target| !target.is_empty()).map(|target| (at, target.to_string())));
+    }
+    links
+}
+
+/// `src` values of `<img>` tags with where they are in the text
+fn html_image_links(text: &str) -> Vec<(usize, String)> {
+    let lower = text.to_ascii_lowercase();
+    let mut links = Vec::new();
+    for (at, _) in lower.match_indices("<img") {
+        let tag = &text[at..lower[at..].find('>').map_or(text.len(), |end| at + end)];
+        let Some(src) = tag.to_ascii_lowercase().find("src=") else {
+            continue;
+        };
+        let value = &tag[src + 4..];
+        let target = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next(),
+            _ => value.split_whitespace().next().map(|value| value.trim_end_matches('/')),
+        };
+        links.extend(target.filter(|target| !target.is_empty()).map(|target| (at, target.to_string())));
+    }
+    links
+}
+
+/// Path of a link target to an image file, if it is a local one that exists
+fn resolve_link(target: &str, dir: &Path) -> Option<PathBuf> {
+    if target.contains("://") || target.starts_with("//") || target.starts_with("data:") {
+        return None;
+    }
+    let target = target.split(['#', '?']).next()?;
+    let path = dir.join(percent_decode(target.strip_prefix("file:").unwrap_or(target)));
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    (IMAGE_EXTENSIONS.contains(&extension.as_str()) && path.is_file()).then_some(path)
+}
+
+/// Decode the `%20`-style escapes in a URL path
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escape = (bytes[index] == b'%')
+            .then(|| text.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// How a terminal is sent images
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    Kitty,
+    Iterm,
+}
+
+/// The image protocol of the terminal browse runs in, told from its environment
+///
+/// Inside tmux or screen the sequences would need wrapping for the multiplexer,
+/// so no images are drawn there.
+pub fn detect_graphics() -> Option<GraphicsProtocol> {
+    let var = |name: &str| std::env::var(name).unwrap_or_default();
+    if std::env::var_os("TMUX").is_some() || var("TERM").starts_with("screen") {
+        return None;
+    }
+    if std::env::var_os("KITTY_WINDOW_ID").is_some() || var("TERM") == "xterm-kitty" || var("TERM_PROGRAM") == "ghostty" {
+        return Some(GraphicsProtocol::Kitty);
+    }
+    match var("TERM_PROGRAM").as_str() {
+        "iTerm.app" | "WezTerm" => Some(GraphicsProtocol::Iterm),
+        _ => None,
+    }
+}
+
+/// An image to draw over a block of cells
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImagePlacement {
+    pub path: PathBuf,
+    pub area: Rect,
+}
+
+/// Draw an image over its cells, leaving the cursor where it was
+///
+/// Images the terminal can't be sent, such as a JPEG to kitty, are left out.
+pub fn draw_image(out: &mut impl Write, protocol: GraphicsProtocol, placement: &ImagePlacement) -> io::Result<()> {
+    if placement.area.is_empty() || fs::metadata(&placement.path)?.len() > MAX_IMAGE_BYTES {
+        return Ok(());
+    }
+    let data = fs::read(&placement.path)?;
+    let Some(sequence) = image_sequence(protocol, &data, placement.area) else {
+        return Ok(());
+    };
+    let Rect { x, y, .. } = placement.area;
+    // Save the cursor, move to the top left cell (1-based), draw, then restore
+    write!(out, "\x1b7\x1b[{};{}H{}\x1b8", y + 1, x + 1, sequence)?;
+    out.flush()
+}
+
+/// Take down the images drawn so far
+///
+/// iTerm2 draws images into the cells themselves, so they go when the screen is
+/// redrawn rather than here.
+pub fn clear_images(out: &mut impl Write, protocol: GraphicsProtocol) -> io::Result<()> {
+    if protocol == GraphicsProtocol::Kitty {
+        write!(out, "\x1b_Ga=d,d=A,q=2\x1b\\")?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Escape sequences drawing an image's data over an area, keeping its aspect ratio
+fn image_sequence(protocol: GraphicsProtocol, data: &[u8], area: Rect) -> Option<String> {
+    match protocol {
+        GraphicsProtocol::Kitty => {
+            if !data.starts_with(b"\x89PNG") {
+                return None;
+            }
+            let size = |at: usize| data.get(at..at + 4).map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()));
+            let (columns, rows) = fit_cells(size(16)?, size(20)?, area);
+            let encoded = base64_encode(data);
+            let chunks: Vec<&str> = encoded.as_bytes().chunks(KITTY_CHUNK).map(|chunk| std::str::from_utf8(chunk).unwrap()).collect();
+            let mut sequence = String::new();
+            for (index, chunk) in chunks.iter().enumerate() {
+                let more = u8::from(index + 1 < chunks.len());
+                // The first chunk says what to do with the image; the rest only carry data.
+                // q=2 keeps the terminal from answering on stdin
+                if index == 0 {
+                    sequence.push_str(&format!("\x1b_Ga=T,f=100,c={},r={},C=1,q=2,m={};{}\x1b\\", columns, rows, more, chunk));
+                } else {
+                    sequence.push_str(&format!("\x1b_Gm={};{}\x1b\\", more, chunk));
+                }
+            }
+            Some(sequence)
+        }
+        GraphicsProtocol::Iterm => Some(format!(
+            "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=1:{}\x07",
+            data.len(),
+            area.width,
+            area.height,
+            base64_encode(data)
+        )),
+    }
+}
+
+/// Columns and rows an image of this many pixels takes, as large as fits the area
+///
+/// Cells are taken to be twice as tall as they are wide.
+fn fit_cells(width: u32, height: u32, area: Rect) -> (u16, u16) {
+    let (width, height) = (width.max(1) as f64, height.max(1) as f64 / 2.0);
+    let scale = (area.width as f64 / width).min(area.height as f64 / height);
+    let cells = |length: f64, most: u16| ((length * scale).round() as u16).clamp(1, most.max(1));
+    (cells(width, area.width), cells(height, area.height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_local_image_link_wins() {
+        let dir = std::env::temp_dir().join(format!("browse-graphics-{}", std::process::id()));
+        fs::create_dir_all(dir.join("img")).unwrap();
+        fs::write(dir.join("img/my diagram.png"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\x02\x80\0\0\x01\xe0").unwrap();
+        fs::write(dir.join("logo.gif"), b"GIF89a").unwrap();
+
+        let text = "# Title\n\n![badge](https://example.com/b.png) ![gone](missing.png)\n\
+            <IMG alt=\"x\" SRC='img/my%20diagram.png?raw=1'>\n![logo](<logo.gif> \"Logo\")\n";
+        let config = Settings::default();
+        let image = first_linked_image(text, &dir, &config).unwrap();
+        assert_eq!(image.path, dir.join("img/my diagram.png"));
+        assert_eq!(image.title(), "my diagram.png 640 × 480");
+
+        assert_eq!(first_linked_image("![logo](<logo.gif> \"Logo\") <img src=img/x.png>", &dir, &config).unwrap().path, dir.join("logo.gif"));
+        assert_eq!(first_linked_image("![a](a.txt) <img src=\"data:image/png;base64,AAAA\">", &dir, &config), None);
+
+        // Images under untrusted roots are passed over for the next link
+        let config = Settings { untrusted_roots: vec![dir.join("img").to_string_lossy().into_owned()], ..Settings::default() };
+        assert_eq!(first_linked_image(text, &dir, &config).unwrap().path, dir.join("logo.gif"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_images_keep_their_aspect_ratio() {
+        let area = Rect::new(0, 0, 40, 10);
+        assert_eq!(fit_cells(640, 480, area), (27, 10));
+        assert_eq!(fit_cells(1000, 100, area), (40, 2));
+
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x10\0\0\0\x08";
+        let sequence = image_sequence(GraphicsProtocol::Kitty, png, area).unwrap();
+        assert!(sequence.starts_with("\x1b_Ga=T,f=100,c=40,r=10,C=1,q=2,m=0;iVBORw0KGgo"), "{:?}", sequence);
+        assert_eq!(image_sequence(GraphicsProtocol::Kitty, b"GIF89a", area), None);
+        assert!(image_sequence(GraphicsProtocol::Iterm, b"GIF89a", area).unwrap().contains("width=40;height=10;"));
+    }
+}
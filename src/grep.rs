@@ -0,0 +1,286 @@
+//! Content search
+//!
+//! A grep searches the text of every file under a directory on a background job
+//! and streams each matching line to a panel, where a match can be opened. The text
+//! is matched literally. ripgrep does the searching when it is installed; otherwise
+//! a slower built-in search walks the tree itself.
+
+use crate::file_operations::is_hidden;
+use crate::finder::ResultsOutcome;
+use crate::jobs::{JobContext, JobId};
+use crate::ui::centered_rect;
+use crossterm::event::{KeyCode, KeyEvent};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{Receiver, Sender};
+
+use ratatui::{
+    prelude::*,
+    widgets::*,
+};
+
+/// Files larger than this are skipped by the built-in search
+const MAX_GREP_FILE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Leading bytes checked for a NUL to tell binary files apart
+const BINARY_CHECK_SIZE: usize = 8192;
+
+/// A line containing the searched text
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrepMatch {
+    pub path: PathBuf,
+    /// 1-based line number
+    pub line_number: u64,
+    pub line: String,
+}
+
+/// How a grep matches text
+#[derive(Debug, Clone)]
+pub struct GrepOptions {
+    pub ignore_case: bool,
+    pub show_hidden: bool,
+}
+
+/// Search the files under `root` for `text`, sending each matching line to `matches`
+///
+/// Uses ripgrep if it can be started and falls back to the built-in search.
+/// Returns the number of matches, or an error if the job was cancelled.
+pub fn grep(root: &Path, text: &str, options: &GrepOptions, ctx: &JobContext, matches: &Sender<GrepMatch>) -> Result<u64, String> {
+    match grep_with_ripgrep(root, text, options, ctx, matches) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => grep_builtin(root, text, options, ctx, matches),
+        Err(e) if e.kind() == io::ErrorKind::Interrupted => Err("cancelled".to_string()),
+        Err(e) => Err(format!("ripgrep failed: {}", e)),
+        Ok(count) => Ok(count),
+    }
+}
+
+/// Run `rg` and stream its matches, killing it if the job is cancelled
+fn grep_with_ripgrep(root: &Path, text: &str, options: &GrepOptions, ctx: &JobContext, matches: &Sender<GrepMatch>) -> io::Result<u64> {
+    let mut command = Command::new("rg");
+    // --null separates the path from the rest, since paths may contain colons
+    command.args(["--line-number", "--with-filename", "--null", "--no-heading", "--color", "never", "--fixed-strings"]);
+    if options.ignore_case {
+        command.arg("--ignore-case");
+    }
+    if options.show_hidden {
+        command.arg("--hidden");
+    }
+    command.arg("--").arg(text).arg(root);
+
+    let mut child = command.stdout(Stdio::piped()).stderr(Stdio::null()).spawn()?;
+    let Some(stdout) = child.stdout.take() else {
+        return Err(io::Error::other("no output from ripgrep"));
+    };
+
+    let mut count = 0;
+    for line in BufReader::new(stdout).lines() {
+        let line = line?;
+        let Some((path, rest)) = line.split_once('\0') else {
+            continue;
+        };
+        let Some((line_number, line)) = rest.split_once(':') else {
+            continue;
+        };
+        let found = GrepMatch {
+            path: PathBuf::from(path),
+            line_number: line_number.parse().unwrap_or(0),
+            line: line.to_string(),
+        };
+
+        if ctx.is_cancelled() || matches.send(found).is_err() {
+            _ = child.kill();
+            _ = child.wait();
+            return Err(io::Error::from(io::ErrorKind::Interrupted));
+        }
+        count += 1;
+        ctx.progress(count, None);
+    }
+
+    // ripgrep exits with 1 when nothing matched, which isn't a failure here
+    let status = child.wait()?;
+    match status.code() {
+        Some(0 | 1) => Ok(count),
+        _ => Err(io::Error::other(format!("exited with {}", status))),
+    }
+}
+
+/// Walk the tree under `root` and search each text file line by line
+///
+/// Symlinks are not followed. Binary files, files over `MAX_GREP_FILE_SIZE`
+/// and unreadable files are skipped.
+pub fn grep_builtin(root: &Path, text: &str, options: &GrepOptions, ctx: &JobContext, matches: &Sender<GrepMatch>) -> Result<u64, String> {
+    let needle = if options.ignore_case { text.to_lowercase() } else { text.to_string() };
+    let mut pending = vec![root.to_path_buf()];
+    let mut scanned = 0;
+    let mut count = 0;
+
+    while let Some(dir) = pending.pop() {
+        // Unreadable directories are skipped rather than failing the whole search
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            if ctx.is_cancelled() {
+                return Err("cancelled".to_string());
+            }
+            if !options.show_hidden && is_hidden(&entry) {
+                continue;
+            }
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                pending.push(entry.path());
+                continue;
+            }
+            if !file_type.is_file() || entry.metadata().map_or(true, |metadata| metadata.len() > MAX_GREP_FILE_SIZE) {
+                continue;
+            }
+
+            let Some(contents) = read_text_file(&entry.path()) else {
+                continue;
+            };
+            for (index, line) in contents.lines().enumerate() {
+                let found = if options.ignore_case {
+                    line.to_lowercase().contains(&needle)
+                } else {
+                    line.contains(&needle)
+                };
+                if !found {
+                    continue;
+                }
+
+                let found = GrepMatch {
+                    path: entry.path(),
+                    line_number: index as u64 + 1,
+                    line: line.to_string(),
+                };
+                if matches.send(found).is_err() {
+                    // Nobody is listening any more
+                    return Err("cancelled".to_string());
+                }
+                count += 1;
+            }
+
+            scanned += 1;
+            ctx.progress(scanned, None);
+        }
+    }
+
+    Ok(count)
+}
+
+/// Read a file as text, or None if it is binary or unreadable
+fn read_text_file(path: &Path) -> Option<String> {
+    let mut bytes = Vec::new();
+    fs::File::open(path).ok()?.read_to_end(&mut bytes).ok()?;
+    if bytes.iter().take(BINARY_CHECK_SIZE).any(|&byte| byte == 0) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Panel listing the matching lines of a running or finished content search
+#[derive(Debug)]
+pub struct GrepPanel {
+    pub query: String,
+    pub root: PathBuf,
+    pub matches: Vec<GrepMatch>,
+    pub state: ListState,
+    pub job: JobId,
+    receiver: Receiver<GrepMatch>,
+    pub finished: bool,
+}
+
+impl GrepPanel {
+    pub fn new(query: String, root: PathBuf, job: JobId, receiver: Receiver<GrepMatch>) -> Self {
+        Self {
+            query,
+            root,
+            matches: Vec::new(),
+            state: ListState::default(),
+            job,
+            receiver,
+            finished: false,
+        }
+    }
+
+    /// Collect matches the search has sent since the last poll
+    pub fn poll(&mut self) {
+        self.matches.extend(self.receiver.try_iter());
+        if self.state.selected().is_none() && !self.matches.is_empty() {
+            self.state.select(Some(0));
+        }
+    }
+
+    /// Handle grep panel key input
+    pub fn handle_key(&mut self, key: KeyEvent) -> ResultsOutcome {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return ResultsOutcome::Close,
+            KeyCode::Enter => {
+                return match self.state.selected().and_then(|i| self.matches.get(i)) {
+                    Some(found) => ResultsOutcome::Open(found.path.clone()),
+                    None => ResultsOutcome::Continue,
+                };
+            }
+            KeyCode::Up => self.state.select_previous(),
+            KeyCode::Down => self.state.select_next(),
+            KeyCode::PageUp => self.state.scroll_up_by(10),
+            KeyCode::PageDown => self.state.scroll_down_by(10),
+            KeyCode::Home => self.state.select_first(),
+            KeyCode::End => self.state.select_last(),
+            _ => {}
+        }
+
+        // ListState doesn't know the list length, so keep the selection on a match
+        if let Some(selected) = self.state.selected() {
+            self.state.select(Some(selected.min(self.matches.len().saturating_sub(1))));
+        }
+        ResultsOutcome::Continue
+    }
+}
+
+/// Render the grep panel popup
+pub fn render_grep_panel(frame: &mut Frame, panel: &GrepPanel) {
+    let area = centered_rect(80, 80, frame.area());
+    frame.render_widget(Clear, area);
+
+    let status = if panel.finished { "" } else { ", searching..." };
+    let title = format!(
+        " \"{}\" in {} ({} found{}) ",
+        panel.query,
+        panel.root.display(),
+        panel.matches.len(),
+        status
+    );
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .title_bottom(Line::from(" Enter open, Esc close ").centered())
+        .border_style(Style::default().fg(Color::Cyan))
+        .padding(Padding::horizontal(1));
+
+    let items: Vec<ListItem> = panel
+        .matches
+        .iter()
+        .map(|found| {
+            let relative = found.path.strip_prefix(&panel.root).unwrap_or(&found.path);
+            ListItem::new(Line::from(vec![
+                Span::styled(relative.to_string_lossy().to_string(), Style::default().fg(Color::Magenta)),
+                Span::styled(format!(":{}: ", found.line_number), Style::default().fg(Color::DarkGray)),
+                Span::raw(found.line.trim().to_string()),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    let mut state = panel.state.clone();
+    frame.render_stateful_widget(list, area, &mut state);
+}
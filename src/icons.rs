@@ -0,0 +1,223 @@
+//! Icon themes
+//!
+//! Entries are drawn with an icon from one of a few sets: emoji, Nerd Font
+//! glyphs (which need a patched font), or plain ASCII markers for terminals and
+//! fonts that have neither. The tables below map the entry categories, MIME
+//! types and file extensions to an icon in each set. An extension is more
+//! specific than a MIME type, so it is looked up first.
+//!
+//! The emoji set matches the icons of the default file type rules. An icon
+//! changed in the File Types settings tab is shown in every set, while the
+//! rules left at their default icon give way to the chosen set.
+
+use serde::{Deserialize, Serialize};
+
+/// Which set of icons entries are drawn with
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum IconTheme {
+    #[default]
+    Emoji,
+    NerdFont,
+    Ascii,
+    /// No icons at all
+    None,
+}
+
+impl IconTheme {
+    /// The theme after this one, for cycling through them in the settings
+    pub fn next(self) -> Self {
+        match self {
+            IconTheme::Emoji => IconTheme::NerdFont,
+            IconTheme::NerdFont => IconTheme::Ascii,
+            IconTheme::Ascii => IconTheme::None,
+            IconTheme::None => IconTheme::Emoji,
+        }
+    }
+
+    /// Name of the theme, for the settings
+    pub fn label(self) -> &'static str {
+        match self {
+            IconTheme::Emoji => "emoji",
+            IconTheme::NerdFont => "Nerd Font glyphs",
+            IconTheme::Ascii => "ASCII markers",
+            IconTheme::None => "none",
+        }
+    }
+
+    /// Column of this theme in the icon tables, or None for no icons
+    fn column(self) -> Option<usize> {
+        match self {
+            IconTheme::Emoji => Some(0),
+            IconTheme::NerdFont => Some(1),
+            IconTheme::Ascii => Some(2),
+            IconTheme::None => None,
+        }
+    }
+}
+
+/// Icons of the entry categories: emoji, Nerd Font, ASCII
+const CATEGORY_ICONS: [(&str, [&str; 3]); 7] = [
+    ("directory", ["📁", "\u{f07b}", "/"]),
+    ("empty-directory", ["📂", "\u{f07c}", "/"]),
+    ("git-repo", ["🌱", "\u{e5fb}", "+"]),
+    ("mount-point", ["💽", "\u{f0a0}", "="]),
+    ("executable", ["🚀", "\u{f489}", "*"]),
+    ("symlink", ["🔗", "\u{f0c1}", "@"]),
+    ("broken-symlink", ["💔", "\u{f127}", "!"]),
+];
+
+/// Icon of files nothing else matches: emoji, Nerd Font, ASCII
+const FILE_ICONS: [&str; 3] = ["📄", "\u{f15b}", "-"];
+
+/// Icons of primary and full MIME types: emoji, Nerd Font, ASCII
+const MIME_ICONS: [(&str, [&str; 3]); 37] = [
+    ("text", ["📄", "\u{f15c}", "-"]),
+    ("image", ["🖼️", "\u{f1c5}", "%"]),
+    ("video", ["🎬", "\u{f1c8}", ">"]),
+    ("audio", ["🎵", "\u{f1c7}", "~"]),
+    ("application", ["📦", "\u{f1b2}", "="]),
+    ("font", ["🔤", "\u{f031}", "a"]),
+    ("model", ["🧊", "\u{f1b2}", "^"]),
+    ("message", ["✉️", "\u{f0e0}", "m"]),
+    ("text/markdown", ["📝", "\u{e609}", "#"]),
+    ("text/x-rust", ["🦀", "\u{e7a8}", "$"]),
+    ("text/x-python", ["🐍", "\u{e606}", "$"]),
+    ("text/javascript", ["📜", "\u{e74e}", "$"]),
+    ("text/html", ["🌐", "\u{e736}", "<"]),
+    ("text/css", ["🎨", "\u{e749}", "<"]),
+    ("text/csv", ["📊", "\u{f1c3}", ","]),
+    ("application/toml", ["🦀", "\u{e6b2}", ":"]),
+    ("application/json", ["📋", "\u{e60b}", ":"]),
+    ("application/yaml", ["📋", "\u{e6a8}", ":"]),
+    ("application/x-yaml", ["📋", "\u{e6a8}", ":"]),
+    ("application/xml", ["📋", "\u{e619}", "<"]),
+    ("application/pdf", ["📕", "\u{f1c1}", "p"]),
+    ("application/x-sh", ["🚀", "\u{f489}", "$"]),
+    ("application/x-executable", ["⚙️", "\u{f489}", "*"]),
+    ("application/wasm", ["⚙️", "\u{f1b2}", "*"]),
+    ("application/vnd.sqlite3", ["🗄️", "\u{f1c0}", "="]),
+    ("application/zip", ["🗜️", "\u{f410}", "z"]),
+    ("application/gzip", ["🗜️", "\u{f410}", "z"]),
+    ("application/x-tar", ["🗜️", "\u{f410}", "z"]),
+    ("application/x-7z-compressed", ["🗜️", "\u{f410}", "z"]),
+    ("application/x-bzip2", ["🗜️", "\u{f410}", "z"]),
+    ("image/svg+xml", ["🖼️", "\u{f1c5}", "%"]),
+    ("audio/mpeg", ["🎵", "\u{f1c7}", "~"]),
+    ("audio/flac", ["🎵", "\u{f1c7}", "~"]),
+    ("video/mp4", ["🎬", "\u{f1c8}", ">"]),
+    ("font/ttf", ["🔤", "\u{f031}", "a"]),
+    ("font/otf", ["🔤", "\u{f031}", "a"]),
+    ("font/woff2", ["🔤", "\u{f031}", "a"]),
+];
+
+/// Icons of file extensions, lower case: emoji, Nerd Font, ASCII
+const EXTENSION_ICONS: [(&str, [&str; 3]); 46] = [
+    ("rs", ["🦀", "\u{e7a8}", "$"]),
+    ("py", ["🐍", "\u{e606}", "$"]),
+    ("js", ["📜", "\u{e74e}", "$"]),
+    ("mjs", ["📜", "\u{e74e}", "$"]),
+    ("ts", ["📜", "\u{e628}", "$"]),
+    ("tsx", ["📜", "\u{e7ba}", "$"]),
+    ("jsx", ["📜", "\u{e7ba}", "$"]),
+    ("go", ["🐹", "\u{e627}", "$"]),
+    ("c", ["📄", "\u{e61e}", "$"]),
+    ("h", ["📄", "\u{e61e}", "$"]),
+    ("cpp", ["📄", "\u{e61d}", "$"]),
+    ("hpp", ["📄", "\u{e61d}", "$"]),
+    ("java", ["☕", "\u{e738}", "$"]),
+    ("rb", ["💎", "\u{e739}", "$"]),
+    ("lua", ["🌙", "\u{e620}", "$"]),
+    ("swift", ["🐦", "\u{e755}", "$"]),
+    ("sh", ["🚀", "\u{f489}", "$"]),
+    ("bash", ["🚀", "\u{f489}", "$"]),
+    ("zsh", ["🚀", "\u{f489}", "$"]),
+    ("md", ["📝", "\u{e609}", "#"]),
+    ("txt", ["📄", "\u{f15c}", "-"]),
+    ("log", ["📄", "\u{f15c}", "-"]),
+    ("html", ["🌐", "\u{e736}", "<"]),
+    ("css", ["🎨", "\u{e749}", "<"]),
+    ("xml", ["📋", "\u{e619}", "<"]),
+    ("json", ["📋", "\u{e60b}", ":"]),
+    ("yaml", ["📋", "\u{e6a8}", ":"]),
+    ("yml", ["📋", "\u{e6a8}", ":"]),
+    ("toml", ["🦀", "\u{e6b2}", ":"]),
+    ("lock", ["🔒", "\u{f023}", ":"]),
+    ("csv", ["📊", "\u{f1c3}", ","]),
+    ("tsv", ["📊", "\u{f1c3}", ","]),
+    ("pdf", ["📕", "\u{f1c1}", "p"]),
+    ("png", ["🖼️", "\u{f1c5}", "%"]),
+    ("jpg", ["🖼️", "\u{f1c5}", "%"]),
+    ("jpeg", ["🖼️", "\u{f1c5}", "%"]),
+    ("gif", ["🖼️", "\u{f1c5}", "%"]),
+    ("svg", ["🖼️", "\u{f1c5}", "%"]),
+    ("mp3", ["🎵", "\u{f1c7}", "~"]),
+    ("flac", ["🎵", "\u{f1c7}", "~"]),
+    ("mp4", ["🎬", "\u{f1c8}", ">"]),
+    ("mkv", ["🎬", "\u{f1c8}", ">"]),
+    ("zip", ["🗜️", "\u{f410}", "z"]),
+    ("gz", ["🗜️", "\u{f410}", "z"]),
+    ("tar", ["🗜️", "\u{f410}", "z"]),
+    ("db", ["🗄️", "\u{f1c0}", "="]),
+];
+
+/// Look up a key in one of the tables, for a theme that has icons
+fn lookup(table: &[(&str, [&'static str; 3])], key: &str, column: usize) -> Option<&'static str> {
+    table.iter().find(|(name, _)| *name == key).map(|(_, icons)| icons[column])
+}
+
+/// Icon of one of the entry categories, empty for `IconTheme::None`
+pub fn category_icon(theme: IconTheme, category: &str) -> &'static str {
+    let Some(column) = theme.column() else { return "" };
+    lookup(&CATEGORY_ICONS, category, column).unwrap_or(FILE_ICONS[column])
+}
+
+/// Icon of a file, by its extension, its full MIME type or its primary type
+///
+/// Empty for `IconTheme::None`.
+pub fn file_icon(theme: IconTheme, extension: Option<&str>, mime_type: Option<&str>) -> &'static str {
+    let Some(column) = theme.column() else { return "" };
+    let extension = extension.map(str::to_lowercase);
+    extension
+        .and_then(|extension| lookup(&EXTENSION_ICONS, &extension, column))
+        .or_else(|| {
+            let mime_type = mime_type?;
+            let primary_type = mime_type.split('/').next().unwrap_or(mime_type);
+            lookup(&MIME_ICONS, mime_type, column).or_else(|| lookup(&MIME_ICONS, primary_type, column))
+        })
+        .unwrap_or(FILE_ICONS[column])
+}
+
+/// The emoji a file type rule or category starts out with, if it has one
+///
+/// A rule whose icon is still this one follows the icon theme.
+pub fn default_icon(name: &str) -> Option<&'static str> {
+    lookup(&CATEGORY_ICONS, name, 0).or_else(|| lookup(&MIME_ICONS, name, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Settings, ENTRY_CATEGORIES};
+
+    #[test]
+    fn test_emoji_theme_matches_default_rules() {
+        let config = Settings::default();
+        for (name, rule) in config.mime_types.primary.iter().chain(&config.mime_types.subtypes) {
+            assert_eq!(default_icon(name), Some(rule.icon.as_str()), "{}", name);
+        }
+        for (category, icon) in ENTRY_CATEGORIES {
+            assert_eq!(category_icon(IconTheme::Emoji, category), icon);
+        }
+    }
+
+    #[test]
+    fn test_file_icon_lookup_order() {
+        assert_eq!(file_icon(IconTheme::Emoji, Some("RS"), Some("text/plain")), "🦀");
+        assert_eq!(file_icon(IconTheme::NerdFont, None, Some("text/csv")), "\u{f1c3}");
+        assert_eq!(file_icon(IconTheme::Ascii, Some("unknown"), Some("image/x-new")), "%");
+        assert_eq!(file_icon(IconTheme::Ascii, None, None), "-");
+        assert_eq!(file_icon(IconTheme::None, Some("rs"), None), "");
+        assert_eq!(category_icon(IconTheme::Ascii, "symlink"), "@");
+    }
+}
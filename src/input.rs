@@ -0,0 +1,197 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use ratatui::{
+    prelude::*,
+    widgets::*,
+};
+
+/// Single-line text field with a cursor, shared by all prompts
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextInput {
+    value: String,
+    /// Cursor position in characters, 0..=len
+    cursor: usize,
+}
+
+impl TextInput {
+    /// Create an input with the cursor at the end of `value`
+    pub fn new(value: impl Into<String>) -> Self {
+        let value = value.into();
+        let cursor = value.chars().count();
+        Self { value, cursor }
+    }
+
+    /// Get the current text
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Get the cursor position in characters
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Replace the text and move the cursor to the end
+    pub fn set_value(&mut self, value: impl Into<String>) {
+        *self = Self::new(value);
+    }
+
+    /// Byte offset of a character position
+    fn byte_index(&self, char_index: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(char_index)
+            .map(|(i, _)| i)
+            .unwrap_or(self.value.len())
+    }
+
+    fn len(&self) -> usize {
+        self.value.chars().count()
+    }
+
+    /// Insert a character at the cursor
+    pub fn insert(&mut self, c: char) {
+        let index = self.byte_index(self.cursor);
+        self.value.insert(index, c);
+        self.cursor += 1;
+    }
+
+    /// Delete the character before the cursor
+    pub fn backspace(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            let index = self.byte_index(self.cursor);
+            self.value.remove(index);
+        }
+    }
+
+    /// Delete the character under the cursor
+    pub fn delete(&mut self) {
+        if self.cursor < self.len() {
+            let index = self.byte_index(self.cursor);
+            self.value.remove(index);
+        }
+    }
+
+    /// Delete the word before the cursor along with any separators after it
+    ///
+    /// Words are runs of letters and digits, so in a path this removes one component.
+    pub fn delete_word(&mut self) {
+        let chars: Vec<char> = self.value.chars().collect();
+        let mut start = self.cursor;
+        while start > 0 && !chars[start - 1].is_alphanumeric() {
+            start -= 1;
+        }
+        while start > 0 && chars[start - 1].is_alphanumeric() {
+            start -= 1;
+        }
+
+        let (from, to) = (self.byte_index(start), self.byte_index(self.cursor));
+        self.value.replace_range(from..to, "");
+        self.cursor = start;
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.len());
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.len();
+    }
+
+    /// Apply an editing key, returning false if the key isn't an editing key
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let word = key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => self.delete_word(),
+            KeyCode::Char(c) if !word => self.insert(c),
+            KeyCode::Backspace if word => self.delete_word(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left => self.move_left(),
+            KeyCode::Right => self.move_right(),
+            KeyCode::Home => self.move_home(),
+            KeyCode::End => self.move_end(),
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Render a text input in `area` and place the terminal cursor in it
+///
+/// The text scrolls horizontally so the cursor always stays visible.
+pub fn render_text_input(frame: &mut Frame, input: &TextInput, block: Block, area: Rect, focused: bool) {
+    let inner = block.inner(area);
+    let width = inner.width as usize;
+    let scroll = if width == 0 { 0 } else { input.cursor().saturating_sub(width - 1) };
+    let visible: String = input.value().chars().skip(scroll).take(width).collect();
+
+    frame.render_widget(Paragraph::new(visible).block(block), area);
+
+    if focused && width > 0 {
+        let x = inner.x + (input.cursor() - scroll) as u16;
+        frame.set_cursor_position(Position::new(x, inner.y));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_input_editing() {
+        let mut input = TextInput::new("hllo");
+        assert_eq!(input.cursor(), 4);
+
+        input.move_home();
+        input.move_right();
+        input.insert('e');
+        assert_eq!(input.value(), "hello");
+        assert_eq!(input.cursor(), 2);
+
+        input.move_end();
+        input.backspace();
+        assert_eq!(input.value(), "hell");
+
+        input.move_home();
+        input.delete();
+        assert_eq!(input.value(), "ell");
+
+        // Multi-byte characters are edited as whole characters
+        let mut input = TextInput::new("café");
+        input.move_left();
+        input.insert('f');
+        assert_eq!(input.value(), "caffé");
+        input.move_end();
+        input.backspace();
+        assert_eq!(input.value(), "caff");
+    }
+
+    #[test]
+    fn test_text_input_delete_word() {
+        let mut input = TextInput::new("/home/user/docs");
+        input.delete_word();
+        assert_eq!(input.value(), "/home/user/");
+        input.delete_word();
+        assert_eq!(input.value(), "/home/");
+
+        // Only the part before the cursor goes
+        let mut input = TextInput::new("text/x-rust");
+        input.move_left();
+        input.move_left();
+        input.handle_key(KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL));
+        assert_eq!(input.value(), "text/x-st");
+        assert_eq!(input.cursor(), 7);
+        input.handle_key(KeyEvent::new(KeyCode::Backspace, KeyModifiers::ALT));
+        assert_eq!(input.value(), "text/st");
+    }
+}
@@ -0,0 +1,483 @@
+//! Background jobs
+//!
+//! Long-running work (copies, searches, checksums) runs on worker threads so the UI
+//! stays responsive. Workers report progress and completion over a channel, and the
+//! App polls the manager once per tick.
+
+use crate::trace;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub type JobId = u64;
+
+/// Category of background job, used for display and per-kind preferences
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Copy,
+    Move,
+    Delete,
+    Trash,
+    Search,
+    Shell,
+    DiskUsage,
+}
+
+impl JobKind {
+    pub const ALL: [JobKind; 7] = [
+        JobKind::Copy,
+        JobKind::Move,
+        JobKind::Delete,
+        JobKind::Trash,
+        JobKind::Search,
+        JobKind::Shell,
+        JobKind::DiskUsage,
+    ];
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            JobKind::Copy => "Copy",
+            JobKind::Move => "Move",
+            JobKind::Delete => "Delete",
+            JobKind::Trash => "Trash",
+            JobKind::Search => "Search",
+            JobKind::Shell => "Shell",
+            JobKind::DiskUsage => "Disk usage",
+        }
+    }
+}
+
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+const SPINNER_FRAME_MS: u128 = 100;
+
+/// Throughput is measured over this much of the latest progress, so it follows changes in speed
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(5);
+/// A job reports its throughput once it has run this long, as the first moments are noisy
+const THROUGHPUT_SETTLE: Duration = Duration::from_secs(1);
+
+/// Messages sent from workers to the manager
+enum JobEvent {
+    Progress { id: JobId, done: u64, total: Option<u64> },
+    Bytes { id: JobId, done: u64, total: u64 },
+    Finished { id: JobId, result: Result<String, String> },
+}
+
+/// Handle given to a worker for reporting progress and checking for cancellation
+pub struct JobContext {
+    id: JobId,
+    sender: Sender<JobEvent>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl JobContext {
+    /// Report how much work is done, and the total when it is known
+    pub fn progress(&self, done: u64, total: Option<u64>) {
+        let _ = self.sender.send(JobEvent::Progress { id: self.id, done, total });
+    }
+
+    /// Report how many bytes of data the job has written out of its total, for its throughput
+    pub fn bytes(&self, done: u64, total: u64) {
+        let _ = self.sender.send(JobEvent::Bytes { id: self.id, done, total });
+    }
+
+    /// Check whether the user asked for this job to stop
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// A job that is still running
+#[derive(Debug)]
+pub struct Job {
+    pub id: JobId,
+    pub kind: JobKind,
+    pub label: String,
+    pub started: Instant,
+    pub done: u64,
+    pub total: Option<u64>,
+    /// Bytes written so far and in all, for jobs that copy data
+    pub bytes: Option<(u64, u64)>,
+    /// Recent readings of the bytes written, oldest first, which the throughput is measured over
+    samples: VecDeque<(Instant, u64)>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Job {
+    fn new(id: JobId, kind: JobKind, label: String, cancelled: Arc<AtomicBool>) -> Self {
+        let started = Instant::now();
+        Self {
+            id,
+            kind,
+            label,
+            started,
+            done: 0,
+            total: None,
+            bytes: None,
+            samples: VecDeque::from([(started, 0)]),
+            cancelled,
+        }
+    }
+
+    /// Completed fraction between 0 and 1, when the total is known
+    ///
+    /// Bytes count rather than items when the job reports them, so one big file
+    /// among small ones doesn't throw it off.
+    pub fn fraction(&self) -> Option<f64> {
+        let (done, total) = match self.bytes {
+            Some((done, total)) => (done, Some(total)),
+            None => (self.done, self.total),
+        };
+        total.filter(|&total| total > 0).map(|total| (done as f64 / total as f64).min(1.0))
+    }
+
+    fn record_bytes(&mut self, done: u64, total: u64, now: Instant) {
+        self.bytes = Some((done, total));
+        self.samples.push_back((now, done));
+        // Keep the last reading from before the window, so the window stays full
+        while self.samples.get(1).is_some_and(|&(at, _)| now.duration_since(at) >= THROUGHPUT_WINDOW) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Bytes written per second lately, once the job has run long enough to tell
+    pub fn throughput(&self) -> Option<f64> {
+        self.throughput_at(Instant::now())
+    }
+
+    fn throughput_at(&self, now: Instant) -> Option<f64> {
+        let (done, _) = self.bytes?;
+        if now.duration_since(self.started) < THROUGHPUT_SETTLE {
+            return None;
+        }
+        // Measured up to now rather than the last reading, so a stalled job slows down
+        let &(since, done_then) = self.samples.front()?;
+        let seconds = now.duration_since(since).as_secs_f64();
+        (seconds > 0.0).then(|| done.saturating_sub(done_then) as f64 / seconds)
+    }
+
+    /// Time left at the current throughput
+    pub fn eta(&self) -> Option<Duration> {
+        self.eta_at(Instant::now())
+    }
+
+    fn eta_at(&self, now: Instant) -> Option<Duration> {
+        let (done, total) = self.bytes?;
+        let rate = self.throughput_at(now).filter(|&rate| rate > 0.0)?;
+        Some(Duration::from_secs_f64(total.saturating_sub(done) as f64 / rate))
+    }
+
+    /// Frame of the activity spinner for this job, advancing with elapsed time
+    pub fn spinner(&self) -> char {
+        let frame = self.started.elapsed().as_millis() / SPINNER_FRAME_MS;
+        SPINNER_FRAMES[frame as usize % SPINNER_FRAMES.len()]
+    }
+
+    /// Ask the worker to stop at its next checkpoint
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// How a job ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobOutcome {
+    /// The work is done; a short summary of it
+    Succeeded(String),
+    /// The work went wrong; the error message
+    Failed(String),
+    /// The job was asked to stop and did; the worker's message, which starts with
+    /// "cancelled" and may say how far it got, as in "cancelled after 3 of 10 items"
+    Cancelled(String),
+}
+
+/// Outcome of a job that has finished
+#[derive(Debug)]
+pub struct FinishedJob {
+    pub id: JobId,
+    pub kind: JobKind,
+    pub label: String,
+    pub elapsed: Duration,
+    pub outcome: JobOutcome,
+}
+
+/// Tracks running jobs and collects their events
+pub struct JobManager {
+    jobs: Vec<Job>,
+    sender: Sender<JobEvent>,
+    receiver: Receiver<JobEvent>,
+    next_id: JobId,
+}
+
+impl JobManager {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            jobs: Vec::new(),
+            sender,
+            receiver,
+            next_id: 1,
+        }
+    }
+
+    /// Run `work` on a worker thread
+    ///
+    /// The closure returns a short summary on success or an error message on failure.
+    /// A worker that stops because it was cancelled returns an error starting with
+    /// "cancelled", and the job ends as [`JobOutcome::Cancelled`].
+    pub fn spawn<F>(&mut self, kind: JobKind, label: impl Into<String>, work: F) -> JobId
+    where
+        F: FnOnce(&JobContext) -> Result<String, String> + Send + 'static,
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let label = label.into();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let context = JobContext {
+            id,
+            sender: self.sender.clone(),
+            cancelled: cancelled.clone(),
+        };
+
+        let span_label = label.clone();
+        thread::spawn(move || {
+            let result = {
+                let _span = trace::span_with("job", || span_label);
+                work(&context)
+            };
+            let _ = context.sender.send(JobEvent::Finished { id, result });
+        });
+
+        self.jobs.push(Job::new(id, kind, label, cancelled));
+
+        id
+    }
+
+    /// Apply pending progress updates and return jobs that finished since the last poll
+    pub fn poll(&mut self) -> Vec<FinishedJob> {
+        let mut finished = Vec::new();
+
+        while let Ok(event) = self.receiver.try_recv() {
+            match event {
+                JobEvent::Progress { id, done, total } => {
+                    if let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) {
+                        job.done = done;
+                        job.total = total;
+                    }
+                }
+                JobEvent::Bytes { id, done, total } => {
+                    if let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) {
+                        job.record_bytes(done, total, Instant::now());
+                    }
+                }
+                JobEvent::Finished { id, result } => {
+                    if let Some(index) = self.jobs.iter().position(|job| job.id == id) {
+                        let job = self.jobs.remove(index);
+                        // A worker that was asked to stop ends with an error saying how far it got
+                        let outcome = match result {
+                            Ok(summary) => JobOutcome::Succeeded(summary),
+                            Err(message) if job.cancelled.load(Ordering::Relaxed) => JobOutcome::Cancelled(message),
+                            Err(message) => JobOutcome::Failed(message),
+                        };
+                        finished.push(FinishedJob {
+                            id,
+                            kind: job.kind,
+                            label: job.label,
+                            elapsed: job.started.elapsed(),
+                            outcome,
+                        });
+                    }
+                }
+            }
+        }
+
+        finished
+    }
+
+    /// Get all running jobs
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    /// Check if any job is running
+    pub fn is_busy(&self) -> bool {
+        !self.jobs.is_empty()
+    }
+
+    /// The job with the most work left, which the status bar reports on
+    ///
+    /// Jobs without a known total count as just started; ties go to the oldest job.
+    pub fn busiest(&self) -> Option<&Job> {
+        self.jobs.iter().min_by(|a, b| {
+            let a_fraction = a.fraction().unwrap_or(0.0);
+            let b_fraction = b.fraction().unwrap_or(0.0);
+            a_fraction.total_cmp(&b_fraction).then(a.started.cmp(&b.started))
+        })
+    }
+
+    /// Ask a running job to stop
+    pub fn cancel(&self, id: JobId) {
+        if let Some(job) = self.jobs.iter().find(|job| job.id == id) {
+            job.cancel();
+        }
+    }
+
+    /// Ask every running job to stop
+    pub fn cancel_all(&self) {
+        for job in &self.jobs {
+            job.cancel();
+        }
+    }
+}
+
+impl Default for JobManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Show a desktop notification using the platform's notifier
+///
+/// Uses `osascript` on macOS and `notify-send` elsewhere. The notifier runs detached,
+/// so a missing or slow notification daemon never blocks the UI.
+pub fn send_desktop_notification(title: &str, body: &str) -> std::io::Result<()> {
+    use std::process::{Command, Stdio};
+
+    let mut command = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {:?} with title {:?}",
+            body, title
+        );
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(script);
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.arg("--app-name=browse").arg(title).arg(body);
+        command
+    };
+
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|mut child| {
+            // Waited on out of the way, so the finished process doesn't linger as a zombie
+            std::thread::spawn(move || child.wait());
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait_for_finished(manager: &mut JobManager) -> Vec<FinishedJob> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            let finished = manager.poll();
+            if !finished.is_empty() || Instant::now() > deadline {
+                return finished;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_job_reports_progress_and_result() {
+        let mut manager = JobManager::new();
+        let (release, wait) = mpsc::channel::<()>();
+
+        manager.spawn(JobKind::Search, "sum", move |ctx| {
+            ctx.progress(1, Some(4));
+            wait.recv().unwrap();
+            Ok("done".to_string())
+        });
+
+        assert!(manager.is_busy());
+        thread::sleep(Duration::from_millis(20));
+        assert!(manager.poll().is_empty());
+        assert_eq!(manager.jobs()[0].fraction(), Some(0.25));
+
+        release.send(()).unwrap();
+        let finished = wait_for_finished(&mut manager);
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].kind, JobKind::Search);
+        assert_eq!(finished[0].outcome, JobOutcome::Succeeded("done".to_string()));
+        assert!(!manager.is_busy());
+    }
+
+    #[test]
+    fn test_throughput_and_eta_follow_recent_progress() {
+        let mut job = Job::new(1, JobKind::Copy, "copy".to_string(), Arc::default());
+        let start = job.started;
+        let at = |seconds: u64| start + Duration::from_secs(seconds);
+        assert_eq!(job.throughput_at(at(2)), None);
+
+        // 10 MB a second for the first 6 seconds, then 1 MB a second
+        for second in 1..=6 {
+            job.record_bytes(second * 10_000_000, 100_000_000, at(second));
+        }
+        assert_eq!(job.throughput_at(at(6)), Some(10_000_000.0));
+        assert_eq!(job.eta_at(at(6)), Some(Duration::from_secs(4)));
+        assert_eq!(job.fraction(), Some(0.6));
+        for second in 7..=11 {
+            job.record_bytes(60_000_000 + (second - 6) * 1_000_000, 100_000_000, at(second));
+        }
+        assert_eq!(job.throughput_at(at(11)), Some(1_000_000.0));
+
+        // A stall shows as the throughput falling
+        assert_eq!(job.throughput_at(at(16)), Some(500_000.0));
+    }
+
+    #[test]
+    fn test_busiest_job_has_most_work_left() {
+        let mut manager = JobManager::new();
+        let (release, wait) = mpsc::channel::<()>();
+        let wait = Arc::new(std::sync::Mutex::new(wait));
+
+        for (label, done) in [("nearly", 9), ("halfway", 5)] {
+            let wait = wait.clone();
+            manager.spawn(JobKind::Copy, label, move |ctx| {
+                ctx.progress(done, Some(10));
+                let _ = wait.lock().unwrap().recv();
+                Ok(String::new())
+            });
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while manager.jobs().iter().any(|job| job.total.is_none()) && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+            manager.poll();
+        }
+        assert_eq!(manager.busiest().map(|job| job.label.as_str()), Some("halfway"));
+
+        drop(release);
+        manager.cancel_all();
+    }
+
+    #[test]
+    fn test_job_cancellation() {
+        let mut manager = JobManager::new();
+        manager.spawn(JobKind::Search, "search", |ctx| {
+            while !ctx.is_cancelled() {
+                thread::sleep(Duration::from_millis(1));
+            }
+            Err("cancelled".to_string())
+        });
+
+        manager.cancel_all();
+        let finished = wait_for_finished(&mut manager);
+        assert_eq!(finished[0].outcome, JobOutcome::Cancelled("cancelled".to_string()));
+
+        // Failing without being asked to stop is a failure
+        manager.spawn(JobKind::Search, "search", |_| Err("unreadable".to_string()));
+        let finished = wait_for_finished(&mut manager);
+        assert_eq!(finished[0].outcome, JobOutcome::Failed("unreadable".to_string()));
+    }
+}
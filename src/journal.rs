@@ -0,0 +1,290 @@
+//! Journals of multi-file operations
+//!
+//! Before copying, moving, deleting or trashing several entries in the
+//! background, the plan is written to a journal file, and each entry is recorded
+//! as started and as finished as the operation goes. A journal whose operation
+//! ends, successfully or not, is removed. One left behind means the browser was
+//! killed mid-operation, so on the next start it is reported: what finished, what
+//! may be half done and what never started. Finished moves can be moved back.
+//!
+//! Journals are JSON lines files in `.browse_journal`, next to the settings
+//! file. A running operation holds a lock on its journal, so a browser started
+//! meanwhile leaves it alone.
+
+use crate::config::{settings_path, Settings};
+use crate::file_operations::move_path;
+use crate::jobs::JobKind;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One line of a journal
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum Record {
+    /// What the operation is going to do, always the first line
+    Plan {
+        kind: JobKind,
+        paths: Vec<PathBuf>,
+        #[serde(default)]
+        destination: Option<PathBuf>,
+    },
+    /// Work on `paths[index]` is about to start
+    Started { index: usize },
+    /// `paths[index]` is done, and ended up at `target` if it still exists
+    Finished {
+        index: usize,
+        #[serde(default)]
+        target: Option<PathBuf>,
+    },
+}
+
+/// Directory journals are kept in
+pub fn journal_dir(config: &Settings) -> PathBuf {
+    let settings = config.source.clone().unwrap_or_else(settings_path);
+    settings.parent().unwrap_or(Path::new(".")).join(".browse_journal")
+}
+
+/// The journal of a running operation
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+}
+
+impl Journal {
+    /// Create a journal in `dir` and record the plan of the operation
+    pub fn create(dir: &Path, kind: JobKind, paths: &[PathBuf], destination: Option<&Path>) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+        let path = dir.join(format!("{}-{}.jsonl", std::process::id(), stamp));
+        let file = OpenOptions::new().write(true).create_new(true).open(&path)?;
+        file.lock()?;
+
+        let mut journal = Self { path, file };
+        journal.write(&Record::Plan {
+            kind,
+            paths: paths.to_vec(),
+            destination: destination.map(Path::to_path_buf),
+        })?;
+        Ok(journal)
+    }
+
+    /// Record that work on the entry at `index` is starting
+    pub fn started(&mut self, index: usize) -> io::Result<()> {
+        self.write(&Record::Started { index })
+    }
+
+    /// Record that the entry at `index` is done, and where it ended up
+    pub fn finished(&mut self, index: usize, target: Option<PathBuf>) -> io::Result<()> {
+        self.write(&Record::Finished { index, target })
+    }
+
+    /// Remove the journal once the operation is over
+    pub fn complete(self) -> io::Result<()> {
+        fs::remove_file(&self.path)
+    }
+
+    /// Append a record and make sure it reaches the disk before going on
+    fn write(&mut self, record: &Record) -> io::Result<()> {
+        let mut line = serde_json::to_vec(record).map_err(io::Error::other)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.file.sync_data()
+    }
+}
+
+/// State of one entry of an interrupted operation
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepState {
+    /// Done, and where the entry ended up if it still exists
+    Finished(Option<PathBuf>),
+    /// Started but not finished, so it may be half done
+    InProgress,
+    NotStarted,
+}
+
+/// An operation whose journal was left behind
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterruptedOperation {
+    pub journal: PathBuf,
+    pub kind: JobKind,
+    pub destination: Option<PathBuf>,
+    /// Every entry of the operation with what became of it
+    pub steps: Vec<(PathBuf, StepState)>,
+}
+
+impl InterruptedOperation {
+    /// Read a journal, or None if it is empty or unreadable
+    fn read(path: &Path, file: File) -> Option<Self> {
+        let mut records = BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            // The last line may have been cut short by the crash
+            .map_while(|line| serde_json::from_str::<Record>(&line).ok());
+
+        let Some(Record::Plan { kind, paths, destination }) = records.next() else {
+            return None;
+        };
+        let mut steps: Vec<(PathBuf, StepState)> = paths.into_iter().map(|path| (path, StepState::NotStarted)).collect();
+        for record in records {
+            match record {
+                Record::Started { index } if index < steps.len() => steps[index].1 = StepState::InProgress,
+                Record::Finished { index, target } if index < steps.len() => steps[index].1 = StepState::Finished(target),
+                _ => {}
+            }
+        }
+
+        Some(Self { journal: path.to_path_buf(), kind, destination, steps })
+    }
+
+    /// One-line summary, such as "Move of 3 items into /tmp: 1 finished, 1 in progress, 1 not started"
+    pub fn summary(&self) -> String {
+        let count = |wanted: fn(&StepState) -> bool| self.steps.iter().filter(|(_, state)| wanted(state)).count();
+        let into = self.destination.as_ref().map(|dest| format!(" into {}", dest.display())).unwrap_or_default();
+        format!(
+            "{} of {} item{}{}: {} finished, {} in progress, {} not started",
+            self.kind.display_name(),
+            self.steps.len(),
+            if self.steps.len() == 1 { "" } else { "s" },
+            into,
+            count(|state| matches!(state, StepState::Finished(_))),
+            count(|state| *state == StepState::InProgress),
+            count(|state| *state == StepState::NotStarted),
+        )
+    }
+
+    /// What became of each entry, one line each
+    pub fn details(&self) -> Vec<String> {
+        let verb = self.kind.display_name();
+        self.steps
+            .iter()
+            .map(|(path, state)| match state {
+                StepState::Finished(Some(target)) => format!("{} finished: {} -> {}", verb, path.display(), target.display()),
+                StepState::Finished(None) => format!("{} finished: {}", verb, path.display()),
+                StepState::InProgress => match &self.destination {
+                    Some(dest) => format!("{} was in progress, check both {} and {}", verb, path.display(), dest.display()),
+                    None => format!("{} was in progress, check {}", verb, path.display()),
+                },
+                StepState::NotStarted => format!("{} not started: {}", verb, path.display()),
+            })
+            .collect()
+    }
+
+    /// Check whether there are finished moves to put back
+    pub fn can_roll_back(&self) -> bool {
+        self.kind == JobKind::Move && self.steps.iter().any(|(_, state)| matches!(state, StepState::Finished(Some(_))))
+    }
+
+    /// Move the finished entries of a move back where they came from
+    ///
+    /// Entries whose old place has been taken meanwhile are left alone. Returns
+    /// the entries that couldn't be moved back, with the reason.
+    pub fn roll_back(&self) -> Vec<(PathBuf, io::Error)> {
+        let mut failures = Vec::new();
+        if self.kind != JobKind::Move {
+            return failures;
+        }
+        for (path, state) in self.steps.iter().rev() {
+            let StepState::Finished(Some(target)) = state else {
+                continue;
+            };
+            let result = if fs::symlink_metadata(path).is_ok() {
+                Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", path.display())))
+            } else {
+                move_path(target, path)
+            };
+            if let Err(e) = result {
+                failures.push((target.clone(), e));
+            }
+        }
+        failures
+    }
+
+    /// Remove the journal, once it has been dealt with
+    pub fn discard(&self) -> io::Result<()> {
+        fs::remove_file(&self.journal)
+    }
+}
+
+/// Operations whose journals in `dir` were left behind by a browser that didn't finish them
+///
+/// Journals still locked by a running browser are skipped, and so are ones that
+/// can't be read, which are removed.
+pub fn interrupted_operations(dir: &Path) -> Vec<InterruptedOperation> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "jsonl"))
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let file = File::open(&path).ok()?;
+            file.try_lock().ok()?;
+            let operation = InterruptedOperation::read(&path, file);
+            if operation.is_none() {
+                let _ = fs::remove_file(&path);
+            }
+            operation
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interrupted_move_and_roll_back() {
+        let dir = std::env::temp_dir().join(format!("browse-journal-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let journals = dir.join("journals");
+        let dest = dir.join("dest");
+        fs::create_dir_all(&dest).unwrap();
+        let paths: Vec<PathBuf> = ["a.txt", "b.txt", "c.txt"].iter().map(|name| dir.join(name)).collect();
+        for path in &paths {
+            fs::write(path, "").unwrap();
+        }
+
+        // A move that got through the first entry and was killed during the second
+        let mut journal = Journal::create(&journals, JobKind::Move, &paths, Some(&dest)).unwrap();
+        journal.started(0).unwrap();
+        fs::rename(&paths[0], dest.join("a.txt")).unwrap();
+        journal.finished(0, Some(dest.join("a.txt"))).unwrap();
+        journal.started(1).unwrap();
+
+        // While the journal is locked it belongs to a running operation
+        assert!(interrupted_operations(&journals).is_empty());
+        drop(journal);
+
+        let operations = interrupted_operations(&journals);
+        assert_eq!(operations.len(), 1);
+        let operation = &operations[0];
+        assert_eq!(operation.summary(), format!("Move of 3 items into {}: 1 finished, 1 in progress, 1 not started", dest.display()));
+        assert_eq!(operation.steps[1].1, StepState::InProgress);
+        assert!(operation.details()[2].starts_with("Move not started"));
+
+        assert!(operation.can_roll_back());
+        assert!(operation.roll_back().is_empty());
+        assert!(paths[0].exists());
+        assert!(!dest.join("a.txt").exists());
+
+        operation.discard().unwrap();
+        assert!(interrupted_operations(&journals).is_empty());
+
+        // A finished operation leaves no journal behind
+        let journal = Journal::create(&journals, JobKind::Delete, &paths, None).unwrap();
+        journal.complete().unwrap();
+        assert!(interrupted_operations(&journals).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,14 +1,48 @@
+pub mod actions_menu;
+pub mod ansi;
 pub mod app;
+pub mod batch;
+pub mod bookmarks;
 pub mod browser;
+pub mod cli;
+pub mod clipboard;
 pub mod commands;
 pub mod config;
+pub mod dialog;
+pub mod dir_sizes;
+pub mod entry_counts;
+pub mod disk_usage;
 pub mod error;
+pub mod external;
 pub mod file_operations;
 pub mod file_preview;
+pub mod finder;
+pub mod fuzzy;
+pub mod git;
+pub mod graphics;
+pub mod grep;
+pub mod icons;
+pub mod input;
+pub mod jobs;
+pub mod journal;
+pub mod media;
+pub mod mime_types;
+pub mod outline;
+pub mod pdf;
+pub mod project;
 pub mod ui;
 pub mod utils;
 pub mod settings;
+pub mod session;
+pub mod tabular;
 pub mod tabs;
+pub mod trace;
+pub mod tree;
 
 pub use app::App;
 pub use config::{Settings, FileTypeRule, MimeTypeConfig};
+pub use file_operations::{
+    copy_into, copy_into_with, delete_path, move_into, move_into_with, rename_entry, rename_entry_with, trash_path,
+    ConflictPolicy, Progress, ProgressCallback,
+};
+pub use ui::render_to_string;
@@ -1,61 +1,194 @@
+use clap::Parser;
 use color_eyre::Result;
-use crossterm::event::{self, Event, EnableMouseCapture, DisableMouseCapture};
+use crossterm::event::{self, Event, EnableFocusChange, EnableMouseCapture, DisableFocusChange, DisableMouseCapture};
+use crossterm::cursor::Show;
 use crossterm::execute;
-use ratatui::DefaultTerminal;
-use std::io::stdout;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::Terminal;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, stdin, stdout, Write};
+use std::path::Path;
+use std::process::ExitStatus;
 use std::time::Duration;
 
-mod app;
-mod browser;
-mod commands;
-mod config;
-mod error;
-mod file_operations;
-mod file_preview;
-mod settings;
-mod tabs;
-mod ui;
-mod utils;
-
-use app::App;
-use config::{save_settings, DEFAULT_POLL_INTERVAL_MS};
+use browse::App;
+use browse::batch::run_batch;
+use browse::cli::{write_completions, write_man_page, write_shell_init, Cli, CliCommand};
+use browse::external::ExternalCommand;
+use browse::graphics::{clear_images, detect_graphics, draw_image, GraphicsProtocol, ImagePlacement};
+use browse::config::{load_settings_from, save_settings, DEFAULT_POLL_INTERVAL_MS};
+use browse::session::save_session;
+use browse::trace;
 
 fn main() -> Result<()> {
     color_eyre::install()?;
+    let cli = Cli::parse();
 
-    // Enable mouse capture
-    execute!(stdout(), EnableMouseCapture)?;
+    match cli.command {
+        Some(CliCommand::Completions { shell }) => {
+            write_completions(shell, &mut stdout());
+            return Ok(());
+        }
+        Some(CliCommand::Man) => {
+            write_man_page(&mut stdout())?;
+            return Ok(());
+        }
+        Some(CliCommand::ShellInit { shell }) => {
+            write_shell_init(shell, &mut stdout())?;
+            return Ok(());
+        }
+        None => {}
+    }
+
+    if let Some(trace_path) = &cli.trace {
+        trace::init(trace_path, cli.trace_format)
+            .map_err(|e| color_eyre::eyre::eyre!("Failed to open trace file {:?}: {}", trace_path, e))?;
+    }
+
+    let mut config = load_settings_from(&cli.settings_path())
+        .map_err(|e| color_eyre::eyre::eyre!("Failed to load settings: {}", e))?;
+    cli.apply_overrides(&mut config);
+
+    if cli.batch {
+        let start_dir = std::env::current_dir()?;
+        let result = run_batch(stdin().lock(), stdout().lock(), start_dir, &config);
+        _ = trace::finish();
+        // Scripts want a one-line diagnostic and an exit status, not a report
+        if let Err(e) = result {
+            eprintln!("browse: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    // Bad paths are reported before the terminal is taken over
+    let mut app = App::new(&cli.paths, config)?;
+    app.run_startup_commands();
 
-    let mut terminal = ratatui::init();
-    let mut app = App::new()?;
+    let picking = app.config().pick.is_some();
+    if !picking {
+        app.enable_autosave();
+    }
+    let result = if picking || cli.print_cwd_on_exit {
+        run_on_tty(&mut app)
+    } else {
+        // Enable mouse capture and focus reporting
+        execute!(stdout(), EnableMouseCapture, EnableFocusChange)?;
+
+        let mut terminal = ratatui::init();
 
-    let result = run(&mut terminal, &mut app);
+        let result = run(&mut terminal, &mut app, &mut stdout(), None);
 
-    // Disable mouse capture and restore terminal
-    execute!(stdout(), DisableMouseCapture)?;
-    ratatui::restore();
+        // Disable mouse capture and focus reporting, then restore terminal
+        execute!(stdout(), DisableMouseCapture, DisableFocusChange)?;
+        ratatui::restore();
+        result
+    };
 
-    // Save settings before exiting
+    // Save settings and the open tabs before exiting; a picker's tabs aren't a session
+    if !picking && let Err(e) = save_session(&app.session(), app.config()) {
+        eprintln!("Warning: Failed to save session: {}", e);
+    }
     if let Err(e) = save_settings(&app.config()) {
         eprintln!("Warning: Failed to save settings: {}", e);
     }
 
+    if let Err(e) = trace::finish() {
+        eprintln!("Warning: Failed to write trace file: {}", e);
+    }
+
+    if result.is_ok() {
+        let cwd = &app.browser().active_column().path;
+        if let Some(cwd_file) = &cli.cwd_file
+            && let Err(e) = fs::write(cwd_file, cwd.as_os_str().as_encoded_bytes())
+        {
+            eprintln!("Warning: Failed to write {}: {}", cwd_file.display(), e);
+        }
+        if cli.print_cwd_on_exit {
+            print_path(cwd)?;
+        }
+    }
+
+    // A picker prints what was chosen, and fails if it was closed without choosing
+    if picking && result.is_ok() {
+        if app.chosen_paths().is_empty() {
+            std::process::exit(1);
+        }
+        for path in app.chosen_paths() {
+            print_path(path)?;
+        }
+    }
+
     result
 }
 
-fn run(terminal: &mut DefaultTerminal, app: &mut App) -> Result<()> {
+/// Print a path as its raw bytes on a line of its own, so a name that isn't UTF-8 reaches the caller intact
+fn print_path(path: &Path) -> io::Result<()> {
+    let mut out = stdout().lock();
+    out.write_all(path.as_os_str().as_encoded_bytes())?;
+    out.write_all(b"\n")
+}
+
+/// Run the browser drawn on the terminal device rather than stdout
+///
+/// When browse prints a result, stdout is usually captured by the caller, as in
+/// `$(browse --choose-file)`.
+fn run_on_tty(app: &mut App) -> Result<()> {
+    let mut tty = OpenOptions::new().write(true).open("/dev/tty")?;
+    enable_raw_mode()?;
+    execute!(tty, EnterAlternateScreen, EnableMouseCapture, EnableFocusChange)?;
+
+    let mut terminal = Terminal::new(CrosstermBackend::new(tty.try_clone()?))?;
+    let result = run(&mut terminal, app, &mut tty.try_clone()?, Some(&tty));
+
+    terminal.show_cursor()?;
+    execute!(tty, DisableMouseCapture, DisableFocusChange, LeaveAlternateScreen)?;
+    disable_raw_mode()?;
+    result
+}
+
+/// Run the browser until it quits
+///
+/// `out` is where the terminal is drawn, and `tty` the terminal device when that
+/// isn't stdout, so that external programs show up in the same place.
+fn run<B: Backend>(terminal: &mut Terminal<B>, app: &mut App, out: &mut impl Write, tty: Option<&File>) -> Result<()> {
     let poll_duration = Duration::from_millis(DEFAULT_POLL_INTERVAL_MS);
+    let graphics = detect_graphics();
+    let mut drawn_image: Option<ImagePlacement> = None;
 
     while !app.should_quit() {
         let mut layout_info = None;
-        terminal.draw(|f| {
-            layout_info = Some(app.render(f));
-        })?;
+        {
+            let _span = trace::span("frame render");
+            terminal.draw(|f| {
+                layout_info = Some(app.render(f));
+            })?;
+        }
 
         if let Some(info) = layout_info {
             app.set_layout_info(info);
         }
 
+        if let Some(protocol) = graphics {
+            let placement = app.image_placement();
+            if placement != drawn_image {
+                clear_images(out, protocol)?;
+                // iTerm2 images live in the cells, which only a full redraw paints over
+                if protocol == GraphicsProtocol::Iterm && drawn_image.is_some() {
+                    terminal.clear()?;
+                    terminal.draw(|f| {
+                        app.render(f);
+                    })?;
+                }
+                if let Some(placement) = &placement {
+                    // An image that can't be read just isn't drawn; its box still names it
+                    _ = draw_image(out, protocol, placement);
+                }
+                drawn_image = placement;
+            }
+        }
+
         if event::poll(poll_duration)? {
             match event::read()? {
                 Event::Key(key) => {
@@ -64,9 +197,40 @@ fn run(terminal: &mut DefaultTerminal, app: &mut App) -> Result<()> {
                 Event::Mouse(mouse) => {
                     app.handle_mouse(mouse)?;
                 }
+                Event::FocusGained => app.set_focused(true),
+                Event::FocusLost => app.set_focused(false),
                 _ => {}
             }
         }
+
+        if let Some(command) = app.take_external_command() {
+            let result = run_external(terminal, out, tty, &command)?;
+            app.finish_external_command(&command, result);
+            drawn_image = None;
+        }
+
+        app.tick();
     }
     Ok(())
 }
+
+/// Hand the terminal to an external program, then take it back and redraw
+fn run_external<B: Backend>(
+    terminal: &mut Terminal<B>,
+    out: &mut impl Write,
+    tty: Option<&File>,
+    command: &ExternalCommand,
+) -> Result<io::Result<ExitStatus>> {
+    execute!(out, DisableMouseCapture, DisableFocusChange, LeaveAlternateScreen, Show)?;
+    disable_raw_mode()?;
+
+    let result = match tty.map(File::try_clone).transpose() {
+        Ok(tty) => command.run(tty),
+        Err(e) => Err(e),
+    };
+
+    enable_raw_mode()?;
+    execute!(out, EnterAlternateScreen, EnableMouseCapture, EnableFocusChange)?;
+    terminal.clear()?;
+    Ok(result)
+}
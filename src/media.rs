@@ -0,0 +1,799 @@
+//! Facts about images, audio and video for the preview's metadata
+//!
+//! Common formats are told by their first bytes and their headers read directly:
+//! PNG, JPEG with its EXIF data, GIF, WebP and BMP images; MP3 with its ID3 tags,
+//! FLAC and WAV audio; and MP4, MOV and M4A files. Only the parts holding these
+//! facts are read, so a large video previews as quickly as a small one.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// A labelled fact about a media file, such as its dimensions
+pub type MediaField = (&'static str, String);
+
+/// Most bytes read of an MP4 file's `moov` box or of an ID3 tag
+const MAX_HEADER_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Most segments of a JPEG walked looking for its size
+const MAX_JPEG_SEGMENTS: usize = 64;
+
+/// Whether a MIME type is one whose files may have media facts
+pub fn is_media_type(mime_type: &str) -> bool {
+    ["image/", "audio/", "video/"].iter().any(|kind| mime_type.starts_with(kind))
+}
+
+/// Facts about a media file, none when its format isn't known or it can't be read
+pub fn media_info(path: &Path) -> Vec<MediaField> {
+    File::open(path).and_then(|mut file| read_media(&mut file)).unwrap_or_default()
+}
+
+fn read_media<R: Read + Seek>(source: &mut R) -> io::Result<Vec<MediaField>> {
+    let len = source.seek(SeekFrom::End(0))?;
+    let head = read_at(source, 0, 64)?;
+    let mut fields = Vec::new();
+    if head.starts_with(b"\x89PNG") {
+        fields.extend(dimensions(be32(&head, 16), be32(&head, 20)));
+    } else if head.starts_with(b"GIF8") {
+        fields.extend(dimensions(le16(&head, 6).map(u32::from), le16(&head, 8).map(u32::from)));
+    } else if head.starts_with(b"BM") {
+        fields.extend(dimensions(le32(&head, 18), le32(&head, 22).map(|height| (height as i32).unsigned_abs())));
+    } else if head.starts_with(b"RIFF") && head.get(8..12) == Some(b"WEBP") {
+        fields.extend(webp_dimensions(&head));
+    } else if head.starts_with(&[0xff, 0xd8, 0xff]) {
+        read_jpeg(source, &mut fields)?;
+    } else if head.starts_with(b"RIFF") && head.get(8..12) == Some(b"WAVE") {
+        read_wav(source, len, &mut fields)?;
+    } else if head.starts_with(b"fLaC") {
+        read_flac(source, len, &mut fields)?;
+    } else if head.get(4..8) == Some(b"ftyp") {
+        read_mp4(source, len, &mut fields)?;
+    } else if head.starts_with(b"ID3") || is_mpeg_frame(be32(&head, 0).unwrap_or(0)) {
+        read_mp3(source, len, &mut fields)?;
+    }
+    Ok(fields)
+}
+
+/// Bytes from an offset, fewer when the file ends first
+fn read_at<R: Read + Seek>(source: &mut R, offset: u64, len: u64) -> io::Result<Vec<u8>> {
+    source.seek(SeekFrom::Start(offset))?;
+    let mut buffer = Vec::new();
+    source.take(len).read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn bytes<const N: usize>(data: &[u8], at: usize) -> Option<[u8; N]> {
+    data.get(at..at + N)?.try_into().ok()
+}
+
+fn be16(data: &[u8], at: usize) -> Option<u16> {
+    bytes(data, at).map(u16::from_be_bytes)
+}
+
+fn be32(data: &[u8], at: usize) -> Option<u32> {
+    bytes(data, at).map(u32::from_be_bytes)
+}
+
+fn be64(data: &[u8], at: usize) -> Option<u64> {
+    bytes(data, at).map(u64::from_be_bytes)
+}
+
+fn le16(data: &[u8], at: usize) -> Option<u16> {
+    bytes(data, at).map(u16::from_le_bytes)
+}
+
+fn le32(data: &[u8], at: usize) -> Option<u32> {
+    bytes(data, at).map(u32::from_le_bytes)
+}
+
+fn le24(data: &[u8], at: usize) -> Option<u32> {
+    data.get(at..at + 3).map(|b| u32::from(b[0]) | u32::from(b[1]) << 8 | u32::from(b[2]) << 16)
+}
+
+fn dimensions(width: Option<u32>, height: Option<u32>) -> Option<MediaField> {
+    Some(("Dimensions", format!("{} × {}", width?, height?)))
+}
+
+/// A duration as `1:02:03` or `2:03`, or in seconds when it is short, as in `4.5 s`
+fn format_duration(seconds: f64) -> String {
+    if seconds < 10.0 {
+        return format!("{:.1} s", seconds);
+    }
+    let seconds = seconds.round() as u64;
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, minutes, seconds) => format!("{}:{:02}", minutes, seconds),
+        (hours, minutes, seconds) => format!("{}:{:02}:{:02}", hours, minutes, seconds),
+    }
+}
+
+/// Sample rate, channels and, when known, bits per sample, as in `44.1 kHz stereo 16-bit`
+fn format_audio(codec: &str, sample_rate: u32, channels: u32, bits: Option<u32>) -> String {
+    let rate = f64::from(sample_rate) / 1000.0;
+    let channels = match channels {
+        1 => "mono".to_string(),
+        2 => "stereo".to_string(),
+        channels => format!("{} channels", channels),
+    };
+    let bits = bits.map(|bits| format!(" {}-bit", bits)).unwrap_or_default();
+    format!("{} {} kHz {}{}", codec, rate, channels, bits).trim_start().to_string()
+}
+
+fn bitrate(bits_per_second: f64) -> MediaField {
+    ("Bitrate", format!("{} kb/s", (bits_per_second / 1000.0).round()))
+}
+
+fn webp_dimensions(head: &[u8]) -> Option<MediaField> {
+    match head.get(12..16)? {
+        b"VP8X" => dimensions(le24(head, 24).map(|width| width + 1), le24(head, 27).map(|height| height + 1)),
+        b"VP8L" => {
+            let bits = le32(head, 21)?;
+            dimensions(Some((bits & 0x3fff) + 1), Some((bits >> 14 & 0x3fff) + 1))
+        }
+        b"VP8 " => dimensions(le16(head, 26).map(|width| u32::from(width & 0x3fff)), le16(head, 28).map(|height| u32::from(height & 0x3fff))),
+        _ => None,
+    }
+}
+
+/// A JPEG's size from its frame header, and what its EXIF data says about the shot
+fn read_jpeg<R: Read + Seek>(source: &mut R, fields: &mut Vec<MediaField>) -> io::Result<()> {
+    let mut offset = 2;
+    let mut exif = Vec::new();
+    for _ in 0..MAX_JPEG_SEGMENTS {
+        let header = read_at(source, offset, 4)?;
+        let (Some(&0xff), Some(&marker), Some(length)) = (header.first(), header.get(1), be16(&header, 2)) else {
+            break;
+        };
+        match marker {
+            // Start of frame, except for the markers sharing its range
+            0xc0..=0xcf if ![0xc4, 0xc8, 0xcc].contains(&marker) => {
+                let frame = read_at(source, offset + 4, 5)?;
+                fields.extend(dimensions(be16(&frame, 3).map(u32::from), be16(&frame, 1).map(u32::from)));
+                break;
+            }
+            0xe1 if exif.is_empty() => {
+                let segment = read_at(source, offset + 4, u64::from(length).saturating_sub(2))?;
+                if let Some(tiff) = segment.strip_prefix(b"Exif\0\0") {
+                    exif = exif_fields(tiff);
+                }
+            }
+            0xda | 0xd9 => break,
+            _ => {}
+        }
+        offset += 2 + u64::from(length);
+    }
+    fields.extend(exif);
+    Ok(())
+}
+
+/// EXIF tags read from TIFF data
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl Tiff<'_> {
+    fn u16(&self, at: usize) -> Option<u16> {
+        if self.little_endian { le16(self.data, at) } else { be16(self.data, at) }
+    }
+
+    fn u32(&self, at: usize) -> Option<u32> {
+        if self.little_endian { le32(self.data, at) } else { be32(self.data, at) }
+    }
+
+    /// Offset of the value of each entry of the directory at `at`, by tag, with its type and count
+    fn entries(&self, at: usize) -> Vec<(u16, u16, u32, usize)> {
+        let count = self.u16(at).unwrap_or(0) as usize;
+        (0..count)
+            .filter_map(|index| {
+                let entry = at + 2 + index * 12;
+                let (tag, kind, count) = (self.u16(entry)?, self.u16(entry + 2)?, self.u32(entry + 4)?);
+                let size = match kind {
+                    3 => 2,
+                    4 | 9 => 4,
+                    5 | 10 => 8,
+                    _ => 1,
+                } * count as usize;
+                let value = if size <= 4 { entry + 8 } else { self.u32(entry + 8)? as usize };
+                Some((tag, kind, count, value))
+            })
+            .collect()
+    }
+
+    fn text(&self, at: usize, count: u32) -> Option<String> {
+        let bytes = self.data.get(at..at + count as usize)?;
+        let text = String::from_utf8_lossy(bytes).trim_end_matches('\0').trim().to_string();
+        (!text.is_empty()).then_some(text)
+    }
+
+    fn rational(&self, at: usize) -> Option<f64> {
+        let (numerator, denominator) = (self.u32(at)?, self.u32(at + 4)?);
+        (denominator != 0).then(|| f64::from(numerator) / f64::from(denominator))
+    }
+}
+
+const TAG_MAKE: u16 = 0x010f;
+const TAG_MODEL: u16 = 0x0110;
+const TAG_DATE_TIME: u16 = 0x0132;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_EXPOSURE_TIME: u16 = 0x829a;
+const TAG_F_NUMBER: u16 = 0x829d;
+const TAG_ISO: u16 = 0x8827;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+const TAG_FOCAL_LENGTH: u16 = 0x920a;
+
+/// The camera, when the photo was taken and its exposure
+fn exif_fields(data: &[u8]) -> Vec<MediaField> {
+    let tiff = Tiff { data, little_endian: data.starts_with(b"II") };
+    let Some(first) = tiff.u32(4) else {
+        return Vec::new();
+    };
+    let mut entries = tiff.entries(first as usize);
+    if let Some(&(_, _, _, at)) = entries.iter().find(|entry| entry.0 == TAG_EXIF_IFD)
+        && let Some(exif) = tiff.u32(at)
+    {
+        entries.extend(tiff.entries(exif as usize));
+    }
+    let find = |tag: u16| entries.iter().find(|entry| entry.0 == tag);
+    let text = |tag: u16| find(tag).and_then(|&(_, _, count, at)| tiff.text(at, count));
+    let rational = |tag: u16| find(tag).and_then(|&(_, _, _, at)| tiff.rational(at));
+
+    let mut fields = Vec::new();
+    let camera = match (text(TAG_MAKE), text(TAG_MODEL)) {
+        // Models often repeat the make, as in "Canon EOS R5"
+        (Some(make), Some(model)) if model.starts_with(&make) => Some(model),
+        (Some(make), Some(model)) => Some(format!("{} {}", make, model)),
+        (make, model) => make.or(model),
+    };
+    fields.extend(camera.map(|camera| ("Camera", camera)));
+    if let Some(taken) = text(TAG_DATE_TIME_ORIGINAL).or_else(|| text(TAG_DATE_TIME)) {
+        // EXIF writes dates as 2024:05:01 12:00:00
+        fields.push(("Taken", taken.replacen(':', "-", 2)));
+    }
+    let mut exposure = Vec::new();
+    if let Some(seconds) = rational(TAG_EXPOSURE_TIME).filter(|&seconds| seconds > 0.0) {
+        exposure.push(if seconds < 1.0 { format!("1/{} s", (1.0 / seconds).round()) } else { format!("{} s", seconds) });
+    }
+    exposure.extend(rational(TAG_F_NUMBER).map(|f_number| format!("f/{}", f_number)));
+    exposure.extend(find(TAG_ISO).and_then(|&(_, _, _, at)| tiff.u16(at)).map(|iso| format!("ISO {}", iso)));
+    exposure.extend(rational(TAG_FOCAL_LENGTH).map(|length| format!("{} mm", length)));
+    if !exposure.is_empty() {
+        fields.push(("Exposure", exposure.join(", ")));
+    }
+    fields
+}
+
+/// A WAV file's format from its `fmt ` chunk, and its length from its `data` chunk
+fn read_wav<R: Read + Seek>(source: &mut R, len: u64, fields: &mut Vec<MediaField>) -> io::Result<()> {
+    let mut offset = 12;
+    let mut format = None;
+    while offset + 8 <= len {
+        let header = read_at(source, offset, 8)?;
+        let size = u64::from(le32(&header, 4).unwrap_or(0));
+        match header.get(..4) {
+            Some(b"fmt ") => format = Some(read_at(source, offset + 8, 16)?),
+            Some(b"data") => {
+                let Some(format) = &format else {
+                    break;
+                };
+                let (Some(tag), Some(channels), Some(rate), Some(byte_rate), Some(bits)) =
+                    (le16(format, 0), le16(format, 2), le32(format, 4), le32(format, 8), le16(format, 14))
+                else {
+                    break;
+                };
+                let codec = match tag {
+                    1 | 0xfffe => "PCM".to_string(),
+                    3 => "Float".to_string(),
+                    6 => "A-law".to_string(),
+                    7 => "μ-law".to_string(),
+                    tag => format!("Format 0x{:04x}", tag),
+                };
+                if byte_rate > 0 {
+                    // The data may be cut short, or its size left unset while recording
+                    let size = size.min(len - offset - 8);
+                    fields.push(("Duration", format_duration(size as f64 / f64::from(byte_rate))));
+                    fields.push(bitrate(f64::from(byte_rate) * 8.0));
+                }
+                fields.push(("Audio", format_audio(&codec, rate, u32::from(channels), Some(u32::from(bits)))));
+                break;
+            }
+            _ => {}
+        }
+        offset += 8 + size + size % 2;
+    }
+    Ok(())
+}
+
+/// A FLAC file's stream info and Vorbis comments
+fn read_flac<R: Read + Seek>(source: &mut R, len: u64, fields: &mut Vec<MediaField>) -> io::Result<()> {
+    let mut offset = 4;
+    let mut tags = Vec::new();
+    loop {
+        let header = read_at(source, offset, 4)?;
+        let (Some(&kind), Some(size)) = (header.first(), be32(&header, 0).map(|word| u64::from(word & 0xff_ffff))) else {
+            break;
+        };
+        match kind & 0x7f {
+            0 => {
+                let info = read_at(source, offset + 4, size)?;
+                if let Some(bits) = be64(&info, 10) {
+                    let rate = (bits >> 44) as u32;
+                    let channels = (bits >> 41 & 7) as u32 + 1;
+                    let depth = (bits >> 36 & 31) as u32 + 1;
+                    let samples = bits & 0xf_ffff_ffff;
+                    if rate > 0 && samples > 0 {
+                        let seconds = samples as f64 / f64::from(rate);
+                        fields.push(("Duration", format_duration(seconds)));
+                        fields.push(bitrate(len as f64 * 8.0 / seconds));
+                    }
+                    fields.push(("Audio", format_audio("FLAC", rate, channels, Some(depth))));
+                }
+            }
+            4 => tags = vorbis_comments(&read_at(source, offset + 4, size.min(MAX_HEADER_BYTES))?),
+            _ => {}
+        }
+        offset += 4 + size;
+        if kind & 0x80 != 0 || offset >= len {
+            break;
+        }
+    }
+    fields.extend(tags);
+    Ok(())
+}
+
+/// Title, artist, album and year from Vorbis comments, which are `KEY=value` pairs
+fn vorbis_comments(data: &[u8]) -> Vec<MediaField> {
+    let mut comments = Vec::new();
+    let mut at = 4 + le32(data, 0).unwrap_or(0) as usize;
+    let count = le32(data, at).unwrap_or(0);
+    at += 4;
+    for _ in 0..count {
+        let Some(size) = le32(data, at).map(|size| size as usize) else {
+            break;
+        };
+        let Some(comment) = data.get(at + 4..at + 4 + size) else {
+            break;
+        };
+        if let Some((key, value)) = String::from_utf8_lossy(comment).split_once('=') {
+            comments.push((key.to_ascii_uppercase(), value.to_string()));
+        }
+        at += 4 + size;
+    }
+    let tag = |key: &str| comments.iter().find(|(own, _)| own == key).map(|(_, value)| value.clone());
+    tag_fields([tag("TITLE"), tag("ARTIST"), tag("ALBUM"), tag("DATE")])
+}
+
+/// Fields of a track's title, artist, album and year, leaving out the missing ones
+fn tag_fields(tags: [Option<String>; 4]) -> Vec<MediaField> {
+    ["Title", "Artist", "Album", "Year"]
+        .into_iter()
+        .zip(tags)
+        .filter_map(|(label, value)| Some((label, value?.trim().to_string())).filter(|(_, value)| !value.is_empty()))
+        .collect()
+}
+
+/// Whether a word starts an MPEG audio frame: the sync bits, then a known version, layer,
+/// bitrate and sample rate
+fn is_mpeg_frame(word: u32) -> bool {
+    word >> 21 == 0x7ff && word >> 19 & 3 != 1 && word >> 17 & 3 != 0 && word >> 12 & 15 != 15 && word >> 12 & 15 != 0 && word >> 10 & 3 != 3
+}
+
+/// An MP3's ID3 tags, and its format and length from its first frame
+fn read_mp3<R: Read + Seek>(source: &mut R, len: u64, fields: &mut Vec<MediaField>) -> io::Result<()> {
+    let head = read_at(source, 0, 10)?;
+    let mut tags = Vec::new();
+    let mut audio_start = 0;
+    if head.starts_with(b"ID3") {
+        // Too short to hold the tag's header, so there is nothing to read either
+        let Some(size) = bytes::<4>(&head, 6) else {
+            return Ok(());
+        };
+        let size = size.iter().fold(0u64, |size, &byte| size << 7 | u64::from(byte & 0x7f));
+        let footer = if head[5] & 0x10 != 0 { 10 } else { 0 };
+        audio_start = 10 + size + footer;
+        tags = id3_fields(&read_at(source, 10, size.min(MAX_HEADER_BYTES))?, head[3]);
+    }
+    if tags.is_empty() && len >= 128 {
+        let tail = read_at(source, len - 128, 128)?;
+        if tail.starts_with(b"TAG") {
+            let text = |range: std::ops::Range<usize>| Some(tail[range].iter().map(|&byte| byte as char).collect::<String>().trim_end_matches(['\0', ' ']).to_string());
+            tags = tag_fields([text(3..33), text(33..63), text(63..93), text(93..97)]);
+        }
+    }
+
+    // The first frame follows the tag, perhaps after some padding
+    let search = read_at(source, audio_start, 64 * 1024)?;
+    if let Some(at) = (0..search.len().saturating_sub(4)).find(|&at| be32(&search, at).is_some_and(is_mpeg_frame)) {
+        let frame = &search[at..];
+        fields.extend(mpeg_fields(frame, len.saturating_sub(audio_start + at as u64)));
+    }
+    fields.extend(tags);
+    Ok(())
+}
+
+/// Kilobits per second by bitrate index, for MPEG-1 layers I to III and MPEG-2 layer I and layers II and III
+const BITRATES: [[u32; 15]; 5] = [
+    [0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448],
+    [0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384],
+    [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320],
+    [0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256],
+    [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+];
+
+/// Format, bitrate and duration from the first MPEG audio frame, using its Xing or VBRI
+/// header for a variable bitrate file
+fn mpeg_fields(frame: &[u8], audio_bytes: u64) -> Vec<MediaField> {
+    let Some(word) = be32(frame, 0) else {
+        return Vec::new();
+    };
+    let version = word >> 19 & 3; // 3 is MPEG-1, 2 MPEG-2 and 0 MPEG-2.5
+    let layer = 4 - (word >> 17 & 3);
+    let mpeg1 = version == 3;
+    let table = match (mpeg1, layer) {
+        (true, layer) => layer as usize - 1,
+        (false, 1) => 3,
+        (false, _) => 4,
+    };
+    let kbps = BITRATES[table][(word >> 12 & 15) as usize];
+    let rate = [44100, 48000, 32000][(word >> 10 & 3) as usize] >> match version {
+        3 => 0,
+        2 => 1,
+        _ => 2,
+    };
+    let mono = word >> 6 & 3 == 3;
+    let samples_per_frame = match layer {
+        1 => 384,
+        2 => 1152,
+        _ if mpeg1 => 1152,
+        _ => 576,
+    };
+
+    // A Xing (or Info) header after the side information, or a VBRI header, counts the frames
+    let side_info = match (mpeg1, mono) {
+        (true, false) => 32,
+        (true, true) | (false, false) => 17,
+        (false, true) => 9,
+    };
+    let xing = frame
+        .get(4 + side_info..8 + side_info)
+        .filter(|id| *id == b"Xing" || *id == b"Info")
+        .and_then(|_| be32(frame, 8 + side_info).filter(|flags| flags & 1 != 0))
+        .and_then(|_| be32(frame, 12 + side_info));
+    let frames = xing.or_else(|| frame.get(36..40).filter(|id| *id == b"VBRI").and_then(|_| be32(frame, 50)));
+
+    let codec = if layer == 3 { "MP3".to_string() } else { format!("MPEG layer {}", layer) };
+    let mut fields = Vec::new();
+    let seconds = match frames {
+        Some(frames) if rate > 0 => Some(f64::from(frames) * f64::from(samples_per_frame) / f64::from(rate)),
+        _ if kbps > 0 => Some(audio_bytes as f64 * 8.0 / (f64::from(kbps) * 1000.0)),
+        _ => None,
+    };
+    if let Some(seconds) = seconds.filter(|&seconds| seconds > 0.0) {
+        fields.push(("Duration", format_duration(seconds)));
+        fields.push(if frames.is_some() { bitrate(audio_bytes as f64 * 8.0 / seconds) } else { bitrate(f64::from(kbps) * 1000.0) });
+    }
+    fields.push(("Audio", format_audio(&codec, rate, if mono { 1 } else { 2 }, None)));
+    fields
+}
+
+/// Title, artist, album and year from the text frames of an ID3v2 tag
+fn id3_fields(tag: &[u8], major: u8) -> Vec<MediaField> {
+    // ID3v2.2 has three letter frame ids and three byte sizes
+    let (id_len, header_len) = if major == 2 { (3, 6) } else { (4, 10) };
+    let mut frames = Vec::new();
+    let mut at = 0;
+    while at + header_len <= tag.len() && tag[at] != 0 {
+        let id = String::from_utf8_lossy(&tag[at..at + id_len]).to_string();
+        let size = match major {
+            2 => tag[at + 3..at + 6].iter().fold(0, |size, &byte| size << 8 | byte as usize),
+            // ID3v2.4 sizes are sync-safe, keeping the top bit of each byte clear
+            4 => tag[at + 4..at + 8].iter().fold(0, |size, &byte| size << 7 | (byte & 0x7f) as usize),
+            _ => be32(tag, at + 4).unwrap_or(0) as usize,
+        };
+        let Some(body) = tag.get(at + header_len..at + header_len + size) else {
+            break;
+        };
+        if id.starts_with('T') {
+            frames.push((id, id3_text(body)));
+        }
+        at += header_len + size;
+    }
+    let frame = |ids: &[&str]| frames.iter().find(|(id, _)| ids.contains(&id.as_str())).map(|(_, text)| text.clone());
+    let year = frame(&["TDRC", "TYER", "TYE"]).map(|date| date.chars().take(4).collect());
+    tag_fields([frame(&["TIT2", "TT2"]), frame(&["TPE1", "TP1"]), frame(&["TALB", "TAL"]), year])
+}
+
+/// The text of an ID3 text frame, in the encoding named by its first byte
+fn id3_text(body: &[u8]) -> String {
+    let Some((&encoding, text)) = body.split_first() else {
+        return String::new();
+    };
+    let utf16 = |text: &[u8], big_endian: bool| {
+        let units: Vec<u16> = text.chunks_exact(2).map(|pair| if big_endian { u16::from_be_bytes([pair[0], pair[1]]) } else { u16::from_le_bytes([pair[0], pair[1]]) }).collect();
+        String::from_utf16_lossy(&units)
+    };
+    let text = match encoding {
+        1 if text.starts_with(&[0xfe, 0xff]) => utf16(&text[2..], true),
+        1 => utf16(text.get(2..).unwrap_or_default(), false),
+        2 => utf16(text, true),
+        3 => String::from_utf8_lossy(text).into_owned(),
+        _ => text.iter().map(|&byte| byte as char).collect(),
+    };
+    // Several values are separated by nulls; the first is enough
+    text.split('\0').next().unwrap_or_default().to_string()
+}
+
+/// The boxes of an MP4 file inside a parent's data, by type, with their data
+fn mp4_boxes(data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+    let mut boxes = Vec::new();
+    let mut at = 0;
+    while let (Some(size), Some(kind)) = (be32(data, at), bytes::<4>(data, at + 4)) {
+        let (header, size) = match size {
+            0 => (8, data.len() - at),
+            1 => (16, be64(data, at + 8).unwrap_or(0) as usize),
+            size => (8, size as usize),
+        };
+        let Some(end) = at.checked_add(size.max(header)) else {
+            break;
+        };
+        let Some(body) = data.get(at + header..end) else {
+            break;
+        };
+        boxes.push((kind, body));
+        at = end;
+    }
+    boxes
+}
+
+fn mp4_child<'a>(data: &'a [u8], path: &[&[u8; 4]]) -> Option<&'a [u8]> {
+    let (first, rest) = path.split_first()?;
+    let body = mp4_boxes(data).into_iter().find(|(kind, _)| kind == *first)?.1;
+    if rest.is_empty() { Some(body) } else { mp4_child(body, rest) }
+}
+
+/// Name of the codec of an MP4 sample entry
+fn codec_name(format: &[u8]) -> String {
+    match format {
+        b"avc1" | b"avc3" => "H.264".to_string(),
+        b"hvc1" | b"hev1" => "H.265".to_string(),
+        b"av01" => "AV1".to_string(),
+        b"vp08" => "VP8".to_string(),
+        b"vp09" => "VP9".to_string(),
+        b"mp4v" => "MPEG-4".to_string(),
+        b"apch" | b"apcn" | b"apcs" | b"apco" | b"ap4h" => "ProRes".to_string(),
+        b"mp4a" => "AAC".to_string(),
+        b"ac-3" => "AC-3".to_string(),
+        b"ec-3" => "E-AC-3".to_string(),
+        b"alac" => "ALAC".to_string(),
+        b"Opus" => "Opus".to_string(),
+        b"fLaC" => "FLAC".to_string(),
+        format => String::from_utf8_lossy(format).trim().to_string(),
+    }
+}
+
+/// An MP4 or QuickTime file's duration and tracks from its `moov` box, and its iTunes tags
+fn read_mp4<R: Read + Seek>(source: &mut R, len: u64, fields: &mut Vec<MediaField>) -> io::Result<()> {
+    // The top-level boxes, skipping the media data, which may come before `moov`
+    let mut offset = 0;
+    let mut moov = None;
+    while offset + 8 <= len {
+        let header = read_at(source, offset, 16)?;
+        let size = match be32(&header, 0).unwrap_or(0) {
+            0 => len - offset,
+            1 => be64(&header, 8).unwrap_or(0),
+            size => u64::from(size),
+        };
+        if size < 8 {
+            break;
+        }
+        if header.get(4..8) == Some(b"moov") && size <= MAX_HEADER_BYTES {
+            moov = Some(read_at(source, offset + 8, size - 8)?);
+            break;
+        }
+        // A box running past the end of the file ends the walk, so a bad size can't wrap it around
+        if size > len - offset {
+            break;
+        }
+        offset += size;
+    }
+    let Some(moov) = moov else {
+        return Ok(());
+    };
+
+    let mut seconds = None;
+    if let Some(header) = mp4_child(&moov, &[b"mvhd"]) {
+        let (timescale, duration) = if header.first() == Some(&1) {
+            (be32(header, 20), be64(header, 24))
+        } else {
+            (be32(header, 12), be32(header, 16).map(u64::from))
+        };
+        if let (Some(timescale), Some(duration)) = (timescale.filter(|&timescale| timescale > 0), duration) {
+            seconds = Some(duration as f64 / f64::from(timescale));
+        }
+    }
+    let mut video = None;
+    let mut audio = None;
+    let mut size = None;
+    for (_, track) in mp4_boxes(&moov).into_iter().filter(|(kind, _)| kind == b"trak") {
+        let handler = mp4_child(track, &[b"mdia", b"hdlr"]).and_then(|hdlr| hdlr.get(8..12));
+        let Some(entry) = mp4_child(track, &[b"mdia", b"minf", b"stbl", b"stsd"]).and_then(|stsd| stsd.get(8..)) else {
+            continue;
+        };
+        let format = entry.get(4..8).unwrap_or_default();
+        match handler {
+            Some(b"vide") if video.is_none() => {
+                video = Some(codec_name(format));
+                size = dimensions(be16(entry, 32).map(u32::from), be16(entry, 34).map(u32::from));
+            }
+            Some(b"soun") if audio.is_none() => {
+                let (channels, rate) = (be16(entry, 24), be32(entry, 32).map(|rate| rate >> 16));
+                audio = Some(match (channels, rate) {
+                    (Some(channels), Some(rate)) if rate > 0 => format_audio(&codec_name(format), rate, u32::from(channels), None),
+                    _ => codec_name(format),
+                });
+            }
+            _ => {}
+        }
+    }
+    fields.extend(size);
+    if let Some(seconds) = seconds.filter(|&seconds| seconds > 0.0) {
+        fields.push(("Duration", format_duration(seconds)));
+        fields.push(bitrate(len as f64 * 8.0 / seconds));
+    }
+    fields.extend(video.map(|video| ("Video", video)));
+    fields.extend(audio.map(|audio| ("Audio", audio)));
+    fields.extend(itunes_tags(&moov));
+    Ok(())
+}
+
+/// Title, artist, album and year from the iTunes metadata list of a `moov` box
+fn itunes_tags(moov: &[u8]) -> Vec<MediaField> {
+    let Some(meta) = mp4_child(moov, &[b"udta", b"meta"]) else {
+        return Vec::new();
+    };
+    // The meta box has a version and flags first in MP4 files but not in QuickTime ones
+    let meta = if meta.get(4..8) == Some(b"hdlr") { meta } else { meta.get(4..).unwrap_or_default() };
+    let Some(list) = mp4_child(meta, &[b"ilst"]) else {
+        return Vec::new();
+    };
+    let tag = |kind: &[u8; 4]| {
+        let data = mp4_child(list, &[kind, b"data"])?;
+        Some(String::from_utf8_lossy(data.get(8..)?).into_owned())
+    };
+    let year = tag(b"\xa9day").map(|date| date.chars().take(4).collect());
+    tag_fields([tag(b"\xa9nam"), tag(b"\xa9ART"), tag(b"\xa9alb"), year])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn read(data: Vec<u8>) -> Vec<MediaField> {
+        read_media(&mut Cursor::new(data)).unwrap()
+    }
+
+    fn field<'a>(fields: &'a [MediaField], label: &str) -> Option<&'a str> {
+        fields.iter().find(|(own, _)| *own == label).map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn test_image_dimensions_and_exif() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        png.extend(640u32.to_be_bytes());
+        png.extend(480u32.to_be_bytes());
+        assert_eq!(read(png), [("Dimensions", "640 × 480".to_string())]);
+
+        // Little-endian TIFF: IFD0 with make, model and a pointer to the EXIF directory
+        let mut tiff = b"II*\0\x08\0\0\0".to_vec();
+        let entry = |tag: u16, kind: u16, count: u32, value: u32| [tag.to_le_bytes().to_vec(), kind.to_le_bytes().to_vec(), count.to_le_bytes().to_vec(), value.to_le_bytes().to_vec()].concat();
+        tiff.extend(3u16.to_le_bytes());
+        tiff.extend(entry(TAG_MAKE, 2, 6, 50));
+        tiff.extend(entry(TAG_MODEL, 2, 10, 56));
+        tiff.extend(entry(TAG_EXIF_IFD, 4, 1, 66));
+        tiff.extend(0u32.to_le_bytes());
+        tiff.extend(b"Canon\0Canon R5\0\0");
+        tiff.extend(4u16.to_le_bytes());
+        tiff.extend(entry(TAG_EXPOSURE_TIME, 5, 1, 120));
+        tiff.extend(entry(TAG_F_NUMBER, 5, 1, 128));
+        tiff.extend(entry(TAG_ISO, 3, 1, 400));
+        tiff.extend(entry(TAG_DATE_TIME_ORIGINAL, 2, 20, 136));
+        tiff.extend(0u32.to_le_bytes());
+        for (numerator, denominator) in [(1u32, 250u32), (28, 10)] {
+            tiff.extend(numerator.to_le_bytes());
+            tiff.extend(denominator.to_le_bytes());
+        }
+        tiff.extend(b"2024:05:01 12:30:00\0");
+
+        let mut jpeg = vec![0xff, 0xd8, 0xff, 0xe1];
+        jpeg.extend((tiff.len() as u16 + 8).to_be_bytes());
+        jpeg.extend(b"Exif\0\0");
+        jpeg.extend(&tiff);
+        jpeg.extend([0xff, 0xc0, 0, 11, 8]);
+        jpeg.extend(3000u16.to_be_bytes());
+        jpeg.extend(4000u16.to_be_bytes());
+        jpeg.extend([3, 0, 0, 0, 0]);
+        let fields = read(jpeg);
+        assert_eq!(field(&fields, "Dimensions"), Some("4000 × 3000"));
+        assert_eq!(field(&fields, "Camera"), Some("Canon R5"));
+        assert_eq!(field(&fields, "Taken"), Some("2024-05-01 12:30:00"));
+        assert_eq!(field(&fields, "Exposure"), Some("1/250 s, f/2.8, ISO 400"));
+    }
+
+    #[test]
+    fn test_audio_format_length_and_tags() {
+        // 2 seconds of 16-bit stereo at 8 kHz
+        let mut wav = b"RIFF\0\0\0\0WAVEfmt \x10\0\0\0".to_vec();
+        for part in [&1u16.to_le_bytes()[..], &2u16.to_le_bytes(), &8000u32.to_le_bytes(), &32000u32.to_le_bytes(), &4u16.to_le_bytes(), &16u16.to_le_bytes()] {
+            wav.extend(part);
+        }
+        wav.extend(b"data");
+        wav.extend(64000u32.to_le_bytes());
+        wav.extend(vec![0; 64000]);
+        let fields = read(wav);
+        assert_eq!(field(&fields, "Duration"), Some("2.0 s"));
+        assert_eq!(field(&fields, "Audio"), Some("PCM 8 kHz stereo 16-bit"));
+        assert_eq!(field(&fields, "Bitrate"), Some("256 kb/s"));
+
+        // An ID3v2.3 tag, then 128 kb/s MPEG-1 layer III frames at 44.1 kHz
+        let mut frames = Vec::new();
+        for (id, text) in [("TIT2", "Song"), ("TPE1", "Band"), ("TYER", "1999")] {
+            frames.extend(id.as_bytes());
+            frames.extend((text.len() as u32 + 1).to_be_bytes());
+            frames.extend([0, 0, 0]);
+            frames.extend(text.as_bytes());
+        }
+        let mut mp3 = b"ID3\x03\0\0".to_vec();
+        mp3.extend([0, 0, 0, frames.len() as u8]);
+        mp3.extend(frames);
+        mp3.extend([0xff, 0xfb, 0x90, 0x00]);
+        mp3.extend(vec![0; 160_000 - 4]);
+        let fields = read(mp3);
+        assert_eq!(field(&fields, "Duration"), Some("0:10"));
+        assert_eq!(field(&fields, "Bitrate"), Some("128 kb/s"));
+        assert_eq!(field(&fields, "Audio"), Some("MP3 44.1 kHz stereo"));
+        assert_eq!((field(&fields, "Title"), field(&fields, "Artist"), field(&fields, "Year")), (Some("Song"), Some("Band"), Some("1999")));
+    }
+
+    #[test]
+    fn test_mp4_tracks_and_duration() {
+        let mp4_box = |kind: &[u8], body: &[u8]| [&(body.len() as u32 + 8).to_be_bytes()[..], kind, body].concat();
+        let track = |handler: &[u8], entry: Vec<u8>| {
+            let hdlr = mp4_box(b"hdlr", &[&[0; 8][..], handler, &[0; 12]].concat());
+            let stsd = mp4_box(b"stsd", &[&[0, 0, 0, 0, 0, 0, 0, 1][..], &entry].concat());
+            let stbl = mp4_box(b"stbl", &stsd);
+            mp4_box(b"trak", &mp4_box(b"mdia", &[hdlr, mp4_box(b"minf", &stbl)].concat()))
+        };
+        let mut video_entry = [&0u32.to_be_bytes()[..], b"avc1", &[0; 24]].concat();
+        video_entry.extend(1920u16.to_be_bytes());
+        video_entry.extend(1080u16.to_be_bytes());
+        let mut audio_entry = [&0u32.to_be_bytes()[..], b"mp4a", &[0; 16]].concat();
+        audio_entry.extend(2u16.to_be_bytes());
+        audio_entry.extend([0; 6]);
+        audio_entry.extend((48000u32 << 16).to_be_bytes());
+        // 90 seconds at a timescale of 1000
+        let mvhd = [&[0; 12][..], &1000u32.to_be_bytes(), &90_000u32.to_be_bytes()].concat();
+        let moov = mp4_box(b"moov", &[mp4_box(b"mvhd", &mvhd), track(b"vide", video_entry), track(b"soun", audio_entry)].concat());
+
+        // The media data comes first, as it does in files written without moving `moov` forward
+        let mp4 = [mp4_box(b"ftyp", b"isom"), mp4_box(b"mdat", &[0; 1000]), moov].concat();
+        let fields = read(mp4);
+        assert_eq!(field(&fields, "Dimensions"), Some("1920 × 1080"));
+        assert_eq!(field(&fields, "Duration"), Some("1:30"));
+        assert_eq!(field(&fields, "Video"), Some("H.264"));
+        assert_eq!(field(&fields, "Audio"), Some("AAC 48 kHz stereo"));
+        assert_eq!(read(b"not media at all".to_vec()), []);
+    }
+
+    #[test]
+    fn test_truncated_and_oversized_headers() {
+        // Only the start of a tag, and an ID3 tag claiming more than the file holds
+        assert_eq!(read(b"ID3".to_vec()), []);
+        assert_eq!(read(b"ID3\x03\0\0\x7f\x7f\x7f\x7f".to_vec()), []);
+
+        // A top-level box and a nested one with 64-bit sizes that would overflow the walk
+        let huge = [&1u32.to_be_bytes()[..], b"free", &u64::MAX.to_be_bytes()].concat();
+        assert_eq!(read([&[0, 0, 0, 12][..], b"ftypisom", &huge].concat()), []);
+        let moov = [&24u32.to_be_bytes()[..], b"moov", &1u32.to_be_bytes(), b"mvhd", &u64::MAX.to_be_bytes()].concat();
+        assert_eq!(read([&[0, 0, 0, 12][..], b"ftypisom", &moov].concat()), []);
+    }
+}
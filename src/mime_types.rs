@@ -0,0 +1,104 @@
+//! MIME types of files, sniffed in the background
+//!
+//! With `sniff_mime_types` turned on, the files in view have their first bytes
+//! read on a worker thread to tell their type, which picks their icon. Until a
+//! file's type arrives, its icon goes by its name, so a slow filesystem never
+//! holds up drawing. The worker sniffs the files it is handed in one batch and
+//! the next batch is sent once it is done. Types are remembered until files
+//! change in their directory.
+
+use crate::file_operations::get_mime_type;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+/// Hands files to a worker thread and collects their sniffed MIME types
+#[derive(Debug, Default)]
+pub struct MimeTypes {
+    types: HashMap<PathBuf, Option<String>>,
+    /// Where the types of the batch being sniffed arrive
+    results: Option<Receiver<(PathBuf, Option<String>)>>,
+}
+
+impl MimeTypes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// MIME type of a file, `Some(None)` when it has none, or None if it hasn't been sniffed yet
+    pub fn get(&self, path: &Path) -> Option<Option<&str>> {
+        self.types.get(path).map(Option::as_deref)
+    }
+
+    /// Collect finished types, then, once the last batch is done, sniff the
+    /// files in `files` that haven't been sniffed
+    pub fn update(&mut self, files: Vec<PathBuf>) {
+        if let Some(results) = &self.results {
+            loop {
+                match results.try_recv() {
+                    Ok((file, mime_type)) => {
+                        self.types.insert(file, mime_type);
+                    }
+                    Err(TryRecvError::Empty) => return,
+                    Err(TryRecvError::Disconnected) => break,
+                }
+            }
+            self.results = None;
+        }
+
+        let wanted: Vec<PathBuf> = files.into_iter().filter(|file| !self.types.contains_key(file)).collect();
+        if wanted.is_empty() {
+            return;
+        }
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for file in wanted {
+                let mime_type = get_mime_type(&file);
+                if sender.send((file, mime_type)).is_err() {
+                    return;
+                }
+            }
+        });
+        self.results = Some(receiver);
+    }
+
+    /// Forget the types of files at or under any of `paths`, after files changed there
+    pub fn invalidate(&mut self, paths: &[PathBuf]) {
+        self.types.retain(|file, _| !paths.iter().any(|path| file.starts_with(path)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_types_arrive_and_fifos_are_not_opened() {
+        let dir = std::env::temp_dir().join(format!("browse-mime-types-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // A PNG header under a text name, and a FIFO nothing ever writes to
+        fs::write(dir.join("image.txt"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        let fifo = std::ffi::CString::new(dir.join("pipe.txt").into_os_string().into_encoded_bytes()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o644) }, 0);
+
+        let mut types = MimeTypes::new();
+        let files = vec![dir.join("image.txt"), dir.join("pipe.txt")];
+        types.update(files.clone());
+        assert_eq!(types.get(&dir.join("image.txt")), None);
+
+        let started = Instant::now();
+        while types.get(&dir.join("pipe.txt")).is_none() && started.elapsed() < Duration::from_secs(5) {
+            thread::sleep(Duration::from_millis(10));
+            types.update(files.clone());
+        }
+        assert_eq!(types.get(&dir.join("image.txt")), Some(Some("image/png")));
+        assert_eq!(types.get(&dir.join("pipe.txt")), Some(Some("text/plain")));
+
+        types.invalidate(&[dir.clone()]);
+        assert_eq!(types.get(&dir.join("image.txt")), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,265 @@
+//! Structured previews of JSON and YAML
+//!
+//! JSON is laid out one value per line, keeping its keys in the order written;
+//! YAML is shown as written. Either way the lines nest by indentation, and a line
+//! followed by more indented ones opens a node that can be folded to that one
+//! line. A cursor picks the node to fold or unfold: Shift+arrows move it in the
+//! preview pane, plain arrows in the full screen preview.
+//!
+//! Nodes below the top level start folded, so a large file opens as a short list
+//! of its top-level keys and what is directly under them.
+
+use std::collections::BTreeSet;
+
+/// MIME types previewed as an outline
+pub const OUTLINE_MIME_TYPES: [&str; 3] = ["application/json", "application/yaml", "application/x-yaml"];
+
+/// Files up to this size are read whole for an outline; larger ones are previewed as text
+pub const OUTLINE_MAX_BYTES: u64 = 1024 * 1024;
+
+/// What a key does to an outline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutlineMove {
+    Up,
+    Down,
+    /// Fold the node under the cursor, or go to its parent when it is folded already
+    Fold,
+    /// Unfold the node under the cursor, or go into it when it is open already
+    Unfold,
+    Toggle,
+}
+
+/// Lines of a structured file, with which nodes are folded
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outline {
+    /// The file's text as read, which copying the preview copies
+    pub source: String,
+    lines: Vec<String>,
+    /// Index of the last line of the node each line opens; the line's own index when it opens none
+    ends: Vec<usize>,
+    folded: BTreeSet<usize>,
+    /// Line the cursor is on, always a visible one
+    cursor: usize,
+}
+
+impl Outline {
+    /// Outline of a file's text, or None when it isn't valid JSON
+    pub fn parse(text: &str, mime_type: &str) -> Option<Self> {
+        let laid_out = if mime_type == "application/json" {
+            serde_json::from_str::<serde::de::IgnoredAny>(text).ok()?;
+            lay_out_json(text)
+        } else {
+            text.to_string()
+        };
+        let lines: Vec<String> = laid_out.lines().map(|line| line.trim_end().to_string()).collect();
+        let ends = node_ends(&lines);
+        let mut outline = Self { source: text.to_string(), lines, ends, folded: BTreeSet::new(), cursor: 0 };
+        // Fold every node inside another
+        for index in 0..outline.lines.len() {
+            let inside = (0..index).any(|parent| outline.opens_node(parent) && outline.ends[parent] >= index);
+            if outline.opens_node(index) && inside {
+                outline.folded.insert(index);
+            }
+        }
+        Some(outline)
+    }
+
+    fn opens_node(&self, index: usize) -> bool {
+        self.ends[index] > index
+    }
+
+    /// Indexes of the lines not hidden in a folded node, in order
+    pub fn visible_lines(&self) -> Vec<usize> {
+        let mut visible = Vec::new();
+        let mut index = 0;
+        while index < self.lines.len() {
+            visible.push(index);
+            index = if self.folded.contains(&index) { self.ends[index] + 1 } else { index + 1 };
+        }
+        visible
+    }
+
+    /// Row of the cursor among the visible lines
+    pub fn cursor_row(&self) -> usize {
+        self.visible_lines().iter().position(|&index| index == self.cursor).unwrap_or(0)
+    }
+
+    /// A visible line as shown: a fold marker, then the line, ending in `…` when folded
+    pub fn display_line(&self, index: usize) -> String {
+        let line = &self.lines[index];
+        if self.folded.contains(&index) {
+            let closer = self.lines[self.ends[index]].trim();
+            let closer = if closer.starts_with(['}', ']']) { closer } else { "" };
+            format!("▸ {} … {}", line, closer).trim_end().to_string()
+        } else if self.opens_node(index) {
+            format!("▾ {}", line)
+        } else {
+            format!("  {}", line)
+        }
+    }
+
+    /// The visible lines as shown, one per line
+    pub fn visible_text(&self) -> String {
+        self.visible_lines().into_iter().map(|index| self.display_line(index)).collect::<Vec<_>>().join("\n")
+    }
+
+    pub fn apply(&mut self, action: OutlineMove) {
+        let visible = self.visible_lines();
+        let row = self.cursor_row();
+        let is_folded = self.folded.contains(&self.cursor);
+        match action {
+            OutlineMove::Up => self.cursor = visible[row.saturating_sub(1)],
+            OutlineMove::Down => self.cursor = visible[(row + 1).min(visible.len() - 1)],
+            OutlineMove::Fold if self.opens_node(self.cursor) && !is_folded => {
+                self.folded.insert(self.cursor);
+            }
+            OutlineMove::Fold => {
+                if let Some(parent) = (0..self.cursor).rev().find(|&parent| self.ends[parent] >= self.cursor && self.opens_node(parent)) {
+                    self.cursor = parent;
+                }
+            }
+            OutlineMove::Unfold if is_folded => {
+                self.folded.remove(&self.cursor);
+            }
+            OutlineMove::Unfold if self.opens_node(self.cursor) => self.cursor += 1,
+            OutlineMove::Unfold => {}
+            OutlineMove::Toggle if is_folded => {
+                self.folded.remove(&self.cursor);
+            }
+            OutlineMove::Toggle if self.opens_node(self.cursor) => {
+                self.folded.insert(self.cursor);
+            }
+            OutlineMove::Toggle => {}
+        }
+    }
+}
+
+/// Spaces a line is indented by, or None for a blank line
+fn indent(line: &str) -> Option<usize> {
+    let trimmed = line.trim_start();
+    (!trimmed.is_empty()).then(|| line.len() - trimmed.len())
+}
+
+/// Last line of the node each line opens: the more indented lines after it, and
+/// the bracket closing it in JSON
+fn node_ends(lines: &[String]) -> Vec<usize> {
+    let mut ends: Vec<usize> = (0..lines.len()).collect();
+    for (index, line) in lines.iter().enumerate() {
+        let Some(own) = indent(line) else {
+            continue;
+        };
+        let mut end = index;
+        for (next, next_line) in lines.iter().enumerate().skip(index + 1) {
+            match indent(next_line) {
+                None => {}
+                Some(deeper) if deeper > own => end = next,
+                Some(same) => {
+                    if end > index && same == own && next_line.trim_start().starts_with(['}', ']']) {
+                        end = next;
+                    }
+                    break;
+                }
+            }
+        }
+        ends[index] = end;
+    }
+    ends
+}
+
+/// Put each value and key of a JSON text on its own line, indented by nesting,
+/// leaving the keys in their order
+///
+/// The text must be valid JSON.
+fn lay_out_json(text: &str) -> String {
+    const INDENT: &str = "  ";
+    let mut out = String::new();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = text.chars().peekable();
+    let newline = |out: &mut String, depth: usize| {
+        out.push('\n');
+        out.push_str(&INDENT.repeat(depth));
+    };
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            '{' | '[' => {
+                out.push(c);
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                if chars.peek().is_some_and(|&next| next == '}' || next == ']') {
+                    out.extend(chars.next());
+                } else {
+                    depth += 1;
+                    newline(&mut out, depth);
+                }
+            }
+            '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                newline(&mut out, depth);
+                out.push(c);
+            }
+            ',' => {
+                out.push(c);
+                newline(&mut out, depth);
+            }
+            ':' => out.push_str(": "),
+            c if c.is_whitespace() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_and_unfold_json() {
+        let mut outline = Outline::parse(r#"{"name": "browse", "deps": {"b": [1, 2], "a": {}}, "s": "{,}"}"#, "application/json").unwrap();
+        assert_eq!(
+            outline.lines,
+            ["{", "  \"name\": \"browse\",", "  \"deps\": {", "    \"b\": [", "      1,", "      2", "    ],", "    \"a\": {}", "  },", "  \"s\": \"{,}\"", "}"]
+        );
+        // Nodes inside the top one start folded
+        assert_eq!(outline.visible_text(), "▾ {\n    \"name\": \"browse\",\n▸   \"deps\": { … },\n    \"s\": \"{,}\"\n  }");
+
+        outline.apply(OutlineMove::Down);
+        outline.apply(OutlineMove::Down);
+        outline.apply(OutlineMove::Unfold);
+        assert_eq!(outline.visible_lines(), [0, 1, 2, 3, 7, 8, 9, 10]);
+        outline.apply(OutlineMove::Unfold);
+        outline.apply(OutlineMove::Toggle);
+        assert_eq!(outline.visible_lines().len(), 11);
+
+        // Folding a leaf goes to its parent, and folding that hides it again
+        outline.apply(OutlineMove::Down);
+        outline.apply(OutlineMove::Fold);
+        assert_eq!(outline.cursor, 3);
+        outline.apply(OutlineMove::Fold);
+        outline.apply(OutlineMove::Fold);
+        outline.apply(OutlineMove::Fold);
+        assert_eq!((outline.cursor, outline.visible_lines()), (2, vec![0, 1, 2, 9, 10]));
+    }
+
+    #[test]
+    fn test_yaml_nests_by_indentation() {
+        let outline = Outline::parse("server:\n  tls:\n    cert: a.pem\n  port: 80\n\nname: x\n", "application/yaml").unwrap();
+        assert_eq!(outline.visible_text(), "▾ server:\n▸   tls: …\n    port: 80\n  \n  name: x");
+        assert!(Outline::parse("{\"a\": ", "application/json").is_none());
+    }
+}
@@ -0,0 +1,663 @@
+//! Previews of PDF documents
+//!
+//! A PDF is read just far enough to show its title, author and page count and
+//! the text of its first page. There is no layout engine: the text is taken from
+//! the page's text operators in the order they are drawn, with a line break
+//! wherever the text moves down a line. Text in fonts with their own glyph
+//! encodings, which many PDF writers use, can't be read this way and is left out.
+//!
+//! Only Flate compressed streams are unpacked, which covers what PDF writers
+//! produce for pages and object streams.
+
+use std::collections::HashMap;
+
+pub const PDF_MIME_TYPE: &str = "application/pdf";
+
+/// PDFs up to this size are read for a preview
+pub const PDF_MAX_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Most bytes a compressed stream is unpacked to
+const MAX_STREAM_BYTES: usize = 16 * 1024 * 1024;
+
+/// Deepest the page tree is followed to the first page
+const MAX_PAGE_TREE_DEPTH: usize = 32;
+
+/// What a PDF preview shows
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PdfSummary {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub pages: Option<usize>,
+    /// Text of the first page, empty when none could be read
+    pub first_page: String,
+    /// The document's strings and streams are encrypted, so only its page count is read
+    pub encrypted: bool,
+}
+
+/// Summary of a PDF file's bytes, or None when they aren't a PDF
+pub fn read_pdf(bytes: &[u8]) -> Option<PdfSummary> {
+    if !bytes.starts_with(b"%PDF-") {
+        return None;
+    }
+    let document = Document::parse(bytes);
+    let trailer = document.trailer();
+    let encrypted = trailer.contains_key("Encrypt");
+    let catalog = trailer.get("Root").and_then(|root| document.dict(root));
+    let page_tree = catalog.and_then(|catalog| document.dict(catalog.get("Pages")?));
+
+    let mut summary = PdfSummary {
+        pages: page_tree.and_then(|pages| document.resolve(pages.get("Count")?)?.as_number()).map(|count| count as usize),
+        encrypted,
+        ..PdfSummary::default()
+    };
+    if encrypted {
+        return Some(summary);
+    }
+    if let Some(info) = trailer.get("Info").and_then(|info| document.dict(info)) {
+        let text = |key: &str| info.get(key).and_then(|value| document.resolve(value)?.as_string()).map(decode_text).filter(|text| !text.trim().is_empty());
+        summary.title = text("Title");
+        summary.author = text("Author");
+    }
+    if let Some(page) = page_tree.and_then(|pages| document.first_page(pages, 0)) {
+        summary.first_page = page_text(&document.page_content(page));
+    }
+    Some(summary)
+}
+
+/// A value in a PDF file or content stream
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(f64),
+    Name(String),
+    String(Vec<u8>),
+    Array(Vec<Value>),
+    Dict(HashMap<String, Value>),
+    /// Reference to an object by its number
+    Ref(u32),
+    /// An operator in a content stream, or `true`, `false` or `null`
+    Keyword(Vec<u8>),
+}
+
+impl Value {
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(number) => Some(*number),
+            _ => None,
+        }
+    }
+
+    fn as_string(&self) -> Option<&[u8]> {
+        match self {
+            Value::String(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    fn as_dict(&self) -> Option<&HashMap<String, Value>> {
+        match self {
+            Value::Dict(dict) => Some(dict),
+            _ => None,
+        }
+    }
+
+    fn is_name(&self, name: &str) -> bool {
+        matches!(self, Value::Name(own) if own == name)
+    }
+}
+
+/// An object of the file, with the raw bytes of its stream if it has one
+#[derive(Debug)]
+struct Object<'a> {
+    value: Value,
+    stream: Option<&'a [u8]>,
+}
+
+/// The objects of a PDF file, found by scanning it rather than through its cross-reference table
+///
+/// Scanning copes with files whose offsets are off, and an object written again by
+/// a later update replaces the earlier one as it comes later in the file.
+struct Document<'a> {
+    bytes: &'a [u8],
+    objects: HashMap<u32, Object<'a>>,
+}
+
+impl<'a> Document<'a> {
+    fn parse(bytes: &'a [u8]) -> Self {
+        let mut document = Self { bytes, objects: HashMap::new() };
+        let mut from = 0;
+        while let Some(at) = find(bytes, b"obj", from) {
+            from = at + 3;
+            let Some(number) = object_number(bytes, at) else {
+                continue;
+            };
+            let mut parser = Parser { bytes, pos: from };
+            let Some(value) = parser.value() else {
+                continue;
+            };
+            parser.skip_space();
+            let stream = if bytes[parser.pos..].starts_with(b"stream") {
+                let start = parser.pos + 6;
+                let start = start + usize::from(bytes.get(start) == Some(&b'\r'));
+                let start = start + usize::from(bytes.get(start) == Some(&b'\n'));
+                // A length too big to add up is as wrong as one not followed by `endstream`
+                let length_end = value
+                    .as_dict()
+                    .and_then(|dict| dict.get("Length")?.as_number())
+                    .and_then(|length| start.checked_add(length as usize));
+                let end = match length_end {
+                    Some(length_end) if find(bytes, b"endstream", length_end).is_some_and(|end| end - length_end <= 2) => length_end,
+                    _ => find(bytes, b"endstream", start).unwrap_or(bytes.len()),
+                };
+                from = end;
+                Some(&bytes[start.min(end)..end])
+            } else {
+                from = parser.pos;
+                None
+            };
+            document.objects.insert(number, Object { value, stream });
+        }
+        document.unpack_object_streams();
+        document
+    }
+
+    /// Add the objects kept compressed together in object streams
+    fn unpack_object_streams(&mut self) {
+        let mut unpacked = Vec::new();
+        for object in self.objects.values() {
+            let Some(dict) = object.value.as_dict().filter(|dict| dict.get("Type").is_some_and(|kind| kind.is_name("ObjStm"))) else {
+                continue;
+            };
+            let (Some(data), Some(count), Some(first)) = (
+                decode_stream(object),
+                dict.get("N").and_then(Value::as_number),
+                dict.get("First").and_then(Value::as_number),
+            ) else {
+                continue;
+            };
+            // A header of object numbers and offsets, then the objects
+            let mut header = Parser { bytes: &data, pos: 0 };
+            for _ in 0..count as usize {
+                let (Some(Value::Number(number)), Some(Value::Number(offset))) = (header.value(), header.value()) else {
+                    break;
+                };
+                let Some(pos) = (first as usize).checked_add(offset as usize) else {
+                    continue;
+                };
+                let mut parser = Parser { bytes: &data, pos };
+                if let Some(value) = parser.value() {
+                    unpacked.push((number as u32, value));
+                }
+            }
+        }
+        for (number, value) in unpacked {
+            self.objects.entry(number).or_insert(Object { value, stream: None });
+        }
+    }
+
+    /// The trailer dictionary, or the dictionary of the cross-reference stream holding it
+    fn trailer(&self) -> HashMap<String, Value> {
+        let mut trailer = HashMap::new();
+        let mut from = 0;
+        while let Some(at) = find(self.bytes, b"trailer", from) {
+            from = at + 7;
+            if let Some(Value::Dict(dict)) = (Parser { bytes: self.bytes, pos: from }).value() {
+                trailer.extend(dict);
+            }
+        }
+        if !trailer.contains_key("Root") {
+            for object in self.objects.values() {
+                if let Some(dict) = object.value.as_dict().filter(|dict| dict.get("Type").is_some_and(|kind| kind.is_name("XRef")) && dict.contains_key("Root")) {
+                    trailer.extend(dict.clone());
+                }
+            }
+        }
+        trailer
+    }
+
+    /// Follow a reference to the value it refers to
+    fn resolve<'v>(&'v self, value: &'v Value) -> Option<&'v Value> {
+        match value {
+            Value::Ref(number) => self.objects.get(number).map(|object| &object.value),
+            value => Some(value),
+        }
+    }
+
+    fn dict<'v>(&'v self, value: &'v Value) -> Option<&'v HashMap<String, Value>> {
+        self.resolve(value)?.as_dict()
+    }
+
+    /// First leaf of the page tree under a node
+    fn first_page<'v>(&'v self, node: &'v HashMap<String, Value>, depth: usize) -> Option<&'v HashMap<String, Value>> {
+        if node.get("Type").is_some_and(|kind| kind.is_name("Page")) || !node.contains_key("Kids") {
+            return Some(node);
+        }
+        let Some(Value::Array(kids)) = self.resolve(node.get("Kids")?) else {
+            return None;
+        };
+        if depth >= MAX_PAGE_TREE_DEPTH {
+            return None;
+        }
+        self.first_page(self.dict(kids.first()?)?, depth + 1)
+    }
+
+    /// The unpacked content streams of a page, one after another
+    fn page_content(&self, page: &HashMap<String, Value>) -> Vec<u8> {
+        let refs = match page.get("Contents").and_then(|contents| self.resolve(contents)) {
+            Some(Value::Array(items)) => items.iter().collect(),
+            Some(_) => page.get("Contents").into_iter().collect(),
+            None => Vec::new(),
+        };
+        let mut content = Vec::new();
+        for value in refs {
+            if let Value::Ref(number) = value
+                && let Some(data) = self.objects.get(number).and_then(decode_stream)
+            {
+                content.extend(data);
+                content.push(b'\n');
+            }
+        }
+        content
+    }
+}
+
+/// A stream's data with its filter undone, if it has none or is Flate compressed
+fn decode_stream(object: &Object) -> Option<Vec<u8>> {
+    let stream = object.stream?;
+    let filters = match object.value.as_dict()?.get("Filter") {
+        None => Vec::new(),
+        Some(Value::Array(filters)) => filters.iter().collect(),
+        Some(filter) => vec![filter],
+    };
+    match filters.as_slice() {
+        [] => Some(stream.to_vec()),
+        [filter] if filter.is_name("FlateDecode") => miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(stream, MAX_STREAM_BYTES)
+            .or_else(|_| miniz_oxide::inflate::decompress_to_vec_with_limit(stream, MAX_STREAM_BYTES))
+            .ok(),
+        _ => None,
+    }
+}
+
+/// Number of the object whose `obj` keyword is at `at`, as in `12 0 obj`
+fn object_number(bytes: &[u8], at: usize) -> Option<u32> {
+    if bytes.get(at + 3).is_some_and(|&byte| is_regular(byte)) {
+        return None;
+    }
+    let before = &bytes[..at];
+    let before = before.trim_ascii_end();
+    if before.len() == at {
+        return None;
+    }
+    let generation_start = before.iter().rposition(|byte| !byte.is_ascii_digit()).map_or(0, |pos| pos + 1);
+    let rest = before[..generation_start].trim_ascii_end();
+    if generation_start == before.len() || rest.len() == generation_start {
+        return None;
+    }
+    let number_start = rest.iter().rposition(|byte| !byte.is_ascii_digit()).map_or(0, |pos| pos + 1);
+    if number_start == rest.len() || number_start > 0 && is_regular(rest[number_start - 1]) {
+        return None;
+    }
+    std::str::from_utf8(&rest[number_start..]).ok()?.parse().ok()
+}
+
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack.get(from..)?.windows(needle.len()).position(|window| window == needle).map(|pos| from + pos)
+}
+
+fn is_space(byte: u8) -> bool {
+    matches!(byte, 0 | b'\t' | b'\n' | 0x0c | b'\r' | b' ')
+}
+
+fn is_delimiter(byte: u8) -> bool {
+    matches!(byte, b'(' | b')' | b'<' | b'>' | b'[' | b']' | b'{' | b'}' | b'/' | b'%')
+}
+
+fn is_regular(byte: u8) -> bool {
+    !is_space(byte) && !is_delimiter(byte)
+}
+
+/// Reads values one after another from PDF syntax
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    /// Skip whitespace and comments
+    fn skip_space(&mut self) {
+        while let Some(byte) = self.peek() {
+            if byte == b'%' {
+                while self.peek().is_some_and(|byte| byte != b'\n' && byte != b'\r') {
+                    self.pos += 1;
+                }
+            } else if is_space(byte) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn regular_run(&mut self) -> &[u8] {
+        let start = self.pos;
+        while self.peek().is_some_and(is_regular) {
+            self.pos += 1;
+        }
+        &self.bytes[start..self.pos]
+    }
+
+    /// The next value, or None at the end or at syntax that can't be read
+    fn value(&mut self) -> Option<Value> {
+        self.skip_space();
+        match self.peek()? {
+            b'<' if self.bytes.get(self.pos + 1) == Some(&b'<') => {
+                self.pos += 2;
+                let mut dict = HashMap::new();
+                loop {
+                    self.skip_space();
+                    if self.bytes[self.pos..].starts_with(b">>") {
+                        self.pos += 2;
+                        return Some(Value::Dict(dict));
+                    }
+                    let Value::Name(key) = self.value()? else {
+                        return None;
+                    };
+                    dict.insert(key, self.value()?);
+                }
+            }
+            b'<' => {
+                self.pos += 1;
+                let mut digits = Vec::new();
+                while let Some(byte) = self.peek() {
+                    self.pos += 1;
+                    match byte {
+                        b'>' => break,
+                        byte if byte.is_ascii_hexdigit() => digits.push(byte),
+                        _ => {}
+                    }
+                }
+                let bytes = digits
+                    .chunks(2)
+                    .map(|pair| {
+                        let digit = |byte: Option<&u8>| byte.and_then(|&byte| (byte as char).to_digit(16)).unwrap_or(0) as u8;
+                        digit(pair.first()) << 4 | digit(pair.get(1))
+                    })
+                    .collect();
+                Some(Value::String(bytes))
+            }
+            b'(' => Some(Value::String(self.literal_string())),
+            b'[' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    self.skip_space();
+                    if self.peek() == Some(b']') {
+                        self.pos += 1;
+                        return Some(Value::Array(items));
+                    }
+                    items.push(self.value()?);
+                }
+            }
+            b'/' => {
+                self.pos += 1;
+                let name = decode_name(self.regular_run());
+                Some(Value::Name(name))
+            }
+            b'+' | b'-' | b'.' | b'0'..=b'9' => {
+                let run = self.regular_run();
+                let is_integer = run.iter().all(u8::is_ascii_digit);
+                let number: f64 = std::str::from_utf8(run).ok()?.parse().unwrap_or(0.0);
+                if is_integer && let Some(reference) = self.reference(number) {
+                    return Some(reference);
+                }
+                Some(Value::Number(number))
+            }
+            byte if is_delimiter(byte) => {
+                // A stray `)`, `>`, `]` or brace
+                self.pos += 1;
+                Some(Value::Keyword(vec![byte]))
+            }
+            _ => Some(Value::Keyword(self.regular_run().to_vec())),
+        }
+    }
+
+    /// A reference `number generation R` after its number, leaving the position as it was when there is none
+    fn reference(&mut self, number: f64) -> Option<Value> {
+        let start = self.pos;
+        self.skip_space();
+        let generation = self.regular_run();
+        let is_generation = !generation.is_empty() && generation.iter().all(u8::is_ascii_digit);
+        self.skip_space();
+        if is_generation && self.regular_run() == b"R" {
+            return Some(Value::Ref(number as u32));
+        }
+        self.pos = start;
+        None
+    }
+
+    /// A string in parentheses, which may nest and has backslash escapes
+    fn literal_string(&mut self) -> Vec<u8> {
+        self.pos += 1;
+        let mut bytes = Vec::new();
+        let mut depth = 0;
+        while let Some(byte) = self.peek() {
+            self.pos += 1;
+            match byte {
+                b'(' => {
+                    depth += 1;
+                    bytes.push(byte);
+                }
+                b')' if depth == 0 => break,
+                b')' => {
+                    depth -= 1;
+                    bytes.push(byte);
+                }
+                b'\\' => {
+                    let Some(escaped) = self.peek() else {
+                        break;
+                    };
+                    self.pos += 1;
+                    match escaped {
+                        b'n' => bytes.push(b'\n'),
+                        b'r' => bytes.push(b'\r'),
+                        b't' => bytes.push(b'\t'),
+                        b'b' => bytes.push(0x08),
+                        b'f' => bytes.push(0x0c),
+                        b'0'..=b'7' => {
+                            let mut code = u32::from(escaped - b'0');
+                            for _ in 0..2 {
+                                match self.peek() {
+                                    Some(digit @ b'0'..=b'7') => {
+                                        code = code * 8 + u32::from(digit - b'0');
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            bytes.push(code as u8);
+                        }
+                        // A line break after a backslash continues the string
+                        b'\r' => {
+                            if self.peek() == Some(b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        b'\n' => {}
+                        other => bytes.push(other),
+                    }
+                }
+                byte => bytes.push(byte),
+            }
+        }
+        bytes
+    }
+
+    /// Move past an inline image's data to the `EI` ending it
+    fn skip_inline_image(&mut self) {
+        let mut from = self.pos;
+        while let Some(at) = find(self.bytes, b"EI", from) {
+            from = at + 2;
+            if at > 0 && is_space(self.bytes[at - 1]) && self.bytes.get(at + 2).is_none_or(|&byte| !is_regular(byte)) {
+                break;
+            }
+        }
+        self.pos = from.min(self.bytes.len());
+    }
+}
+
+/// A name with its `#xx` escapes undone
+fn decode_name(run: &[u8]) -> String {
+    let mut bytes = Vec::new();
+    let mut index = 0;
+    while index < run.len() {
+        match run.get(index + 1..index + 3).and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()) {
+            Some(byte) if run[index] == b'#' => {
+                bytes.push(byte);
+                index += 3;
+            }
+            _ => {
+                bytes.push(run[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// A text string: UTF-16 or UTF-8 after a byte order mark, and otherwise single bytes
+///
+/// Single bytes are taken as Latin-1, which PDF's own encoding matches for letters.
+fn decode_text(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(&[0xfe, 0xff]) {
+        let units: Vec<u16> = utf16.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+        return String::from_utf16_lossy(&units);
+    }
+    if let Some(utf8) = bytes.strip_prefix(&[0xef, 0xbb, 0xbf]) {
+        return String::from_utf8_lossy(utf8).into_owned();
+    }
+    bytes.iter().map(|&byte| byte as char).filter(|c| !c.is_control()).collect()
+}
+
+/// Text drawn by a page's content stream, a line per line of text
+fn page_text(content: &[u8]) -> String {
+    let mut text = String::new();
+    let newline = |text: &mut String| {
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+    };
+    let space = |text: &mut String| {
+        if !text.is_empty() && !text.ends_with([' ', '\n']) {
+            text.push(' ');
+        }
+    };
+    // Strings in fonts with glyph encodings are mostly control bytes, which read as nothing
+    let push = |text: &mut String, value: &Value| {
+        if let Value::String(bytes) = value
+            && bytes.iter().filter(|&&byte| byte < 0x20).count() * 2 <= bytes.len()
+        {
+            text.push_str(&decode_text(bytes));
+        }
+    };
+
+    let mut parser = Parser { bytes: content, pos: 0 };
+    let mut operands = Vec::new();
+    let mut line_y = None;
+    while let Some(value) = parser.value() {
+        let Value::Keyword(operator) = value else {
+            operands.push(value);
+            continue;
+        };
+        let numbers: Vec<f64> = operands.iter().filter_map(Value::as_number).collect();
+        match operator.as_slice() {
+            b"Tj" => operands.last().into_iter().for_each(|string| push(&mut text, string)),
+            b"'" | b"\"" => {
+                newline(&mut text);
+                operands.last().into_iter().for_each(|string| push(&mut text, string));
+            }
+            b"TJ" => {
+                if let Some(Value::Array(items)) = operands.last() {
+                    for item in items {
+                        match item {
+                            // A wide gap, in thousandths of the font size, between words
+                            Value::Number(gap) if *gap < -200.0 => space(&mut text),
+                            item => push(&mut text, item),
+                        }
+                    }
+                }
+            }
+            b"Td" | b"TD" => match numbers[..] {
+                [.., _, y] if y != 0.0 => newline(&mut text),
+                [.., x, _] if x > 0.0 => space(&mut text),
+                _ => {}
+            },
+            b"Tm" => {
+                if let [.., y] = numbers[..] {
+                    if line_y.is_some_and(|line_y| line_y != y) {
+                        newline(&mut text);
+                    } else {
+                        space(&mut text);
+                    }
+                    line_y = Some(y);
+                }
+            }
+            b"T*" | b"ET" => newline(&mut text),
+            b"ID" => parser.skip_inline_image(),
+            _ => {}
+        }
+        operands.clear();
+    }
+    text.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A PDF of two pages whose first page's content is compressed
+    fn sample_pdf() -> Vec<u8> {
+        let content = b"BT /F1 12 Tf 72 720 Td (Hello,) Tj [(wor) -20 (ld) -400 (again)] TJ 0 -14 Td (Second \\(line\\)) Tj ET";
+        let compressed = miniz_oxide::deflate::compress_to_vec_zlib(content, 6);
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        pdf.extend(b"1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n");
+        pdf.extend(b"2 0 obj\n<< /Type /Pages /Kids [3 0 R 5 0 R] /Count 2 >>\nendobj\n");
+        pdf.extend(b"3 0 obj\n<< /Type /Page /Parent 2 0 R /Contents 4 0 R >>\nendobj\n");
+        pdf.extend(format!("4 0 obj\n<< /Length {} /Filter /FlateDecode >>\nstream\n", compressed.len()).as_bytes());
+        pdf.extend(&compressed);
+        pdf.extend(b"\nendstream\nendobj\n");
+        pdf.extend(b"5 0 obj\n<< /Type /Page /Parent 2 0 R >>\nendobj\n");
+        pdf.extend(b"6 0 obj\n<< /Title <FEFF00C90074006500CC> /Author (Ann \\374ber) >>\nendobj\n");
+        pdf.extend(b"trailer\n<< /Root 1 0 R /Info 6 0 R >>\n%%EOF\n");
+        pdf
+    }
+
+    #[test]
+    fn test_read_metadata_and_first_page() {
+        let summary = read_pdf(&sample_pdf()).unwrap();
+        assert_eq!(summary.title.as_deref(), Some("ÉteÌ"));
+        assert_eq!(summary.author.as_deref(), Some("Ann über"));
+        assert_eq!(summary.pages, Some(2));
+        assert_eq!(summary.first_page, "Hello,world again\nSecond (line)");
+        assert!(!summary.encrypted);
+
+        // An encrypted document gives away its page count only
+        let encrypted = String::from_utf8_lossy(&sample_pdf()).replace("/Info 6 0 R", "/Info 6 0 R /Encrypt 7 0 R");
+        let summary = read_pdf(encrypted.as_bytes()).unwrap();
+        assert_eq!((summary.pages, summary.title, summary.encrypted), (Some(2), None, true));
+        assert_eq!(read_pdf(b"not a pdf"), None);
+    }
+
+    #[test]
+    fn test_stream_length_too_big_to_add_up() {
+        // The stream is found by its `endstream` instead, and the page still reads
+        let pdf = String::from_utf8_lossy(&sample_pdf()).into_owned();
+        let start = pdf.find("/Length ").unwrap() + 8;
+        let end = start + pdf[start..].find(' ').unwrap();
+        let pdf = [&sample_pdf()[..start], b"99999999999999999999999", &sample_pdf()[end..]].concat();
+        let summary = read_pdf(&pdf).unwrap();
+        assert_eq!(summary.first_page, "Hello,world again\nSecond (line)");
+    }
+}
@@ -0,0 +1,114 @@
+//! Project roots
+//!
+//! A project is the nearest directory above the active one holding a
+//! `Cargo.toml`, a `package.json` or a `.git`. When that manifest belongs to a
+//! Cargo or npm workspace further up, the workspace is the project instead, so a
+//! crate of a workspace jumps to the workspace's root. The search doesn't leave
+//! the git worktree it starts in.
+//!
+//! The name shown in the status bar is the package name from the manifest, or
+//! the directory's name when there is none.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MANIFESTS: [&str; 2] = ["Cargo.toml", "package.json"];
+
+/// Root and name of the project a directory is in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    pub root: PathBuf,
+    pub name: String,
+}
+
+/// Project a directory is in, if any
+pub fn find_project(dir: &Path) -> Option<Project> {
+    let mut root: Option<&Path> = None;
+    for ancestor in dir.ancestors() {
+        let is_repo = ancestor.join(".git").exists();
+        let manifest = MANIFESTS.iter().map(|name| ancestor.join(name)).find(|path| path.is_file());
+        match &manifest {
+            // A workspace above the project takes it over
+            Some(manifest) if root.is_none() || is_workspace(manifest) => root = Some(ancestor),
+            None if root.is_none() && is_repo => root = Some(ancestor),
+            _ => {}
+        }
+        if is_repo {
+            break;
+        }
+    }
+    root.map(|root| Project { root: root.to_path_buf(), name: project_name(root) })
+}
+
+/// Whether a manifest declares a workspace of several packages
+fn is_workspace(manifest: &Path) -> bool {
+    let Ok(text) = fs::read_to_string(manifest) else {
+        return false;
+    };
+    if manifest.ends_with("package.json") {
+        serde_json::from_str::<serde_json::Value>(&text).is_ok_and(|json| json.get("workspaces").is_some())
+    } else {
+        text.lines().any(|line| line.trim() == "[workspace]")
+    }
+}
+
+/// Package name from the project's manifest, falling back to the directory's name
+fn project_name(root: &Path) -> String {
+    let from_cargo = fs::read_to_string(root.join("Cargo.toml")).ok().and_then(|text| cargo_package_name(&text));
+    let from_npm = || {
+        let text = fs::read_to_string(root.join("package.json")).ok()?;
+        let json: serde_json::Value = serde_json::from_str(&text).ok()?;
+        json.get("name")?.as_str().map(str::to_string)
+    };
+    from_cargo
+        .or_else(from_npm)
+        .or_else(|| root.file_name().map(|name| name.to_string_lossy().to_string()))
+        .unwrap_or_else(|| root.to_string_lossy().to_string())
+}
+
+/// `name` of the `[package]` table of a Cargo.toml, read without a TOML parser
+fn cargo_package_name(text: &str) -> Option<String> {
+    let mut in_package = false;
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+        } else if in_package && let Some((key, value)) = line.split_once('=') && key.trim() == "name" {
+            return Some(value.trim().trim_matches('"').to_string());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_takes_over_its_members() {
+        let dir = std::env::temp_dir().join(format!("browse-project-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("repo/.git")).unwrap();
+        fs::create_dir_all(dir.join("repo/crates/core/src")).unwrap();
+        fs::create_dir_all(dir.join("repo/web/src")).unwrap();
+        fs::write(dir.join("Cargo.toml"), "[workspace]\n").unwrap();
+        fs::write(dir.join("repo/Cargo.toml"), "[workspace]\nmembers = [\"crates/*\"]\n").unwrap();
+        fs::write(dir.join("repo/crates/core/Cargo.toml"), "[package]\nname = \"core\"\n\n[dependencies]\nname = \"1\"\n").unwrap();
+        fs::write(dir.join("repo/web/package.json"), "{\"name\": \"web\"}").unwrap();
+
+        // The workspace wins over the crate, and the search stops at the worktree
+        let project = find_project(&dir.join("repo/crates/core/src")).unwrap();
+        assert_eq!(project.root, dir.join("repo"));
+        assert_eq!(project.name, "repo");
+
+        let project = find_project(&dir.join("repo/web/src")).unwrap();
+        assert_eq!(project, Project { root: dir.join("repo"), name: "repo".to_string() });
+
+        fs::remove_file(dir.join("repo/Cargo.toml")).unwrap();
+        let project = find_project(&dir.join("repo/crates/core/src")).unwrap();
+        assert_eq!(project, Project { root: dir.join("repo/crates/core"), name: "core".to_string() });
+        let project = find_project(&dir.join("repo/web")).unwrap();
+        assert_eq!(project, Project { root: dir.join("repo/web"), name: "web".to_string() });
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
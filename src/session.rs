@@ -0,0 +1,193 @@
+//! Session persistence
+//!
+//! The open tabs and the entry selected in each are saved on quit, next to the
+//! settings file. When started without paths, the browser offers to reopen them.
+//!
+//! While the browser runs, the session is also saved whenever it has changed and
+//! then stayed put for a moment, so a crash or a dropped SSH connection loses
+//! little. Browsing that never settles is still saved every half minute.
+
+use crate::config::{settings_path, Settings};
+use crate::tabs::TabManager;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How long the session has to stay unchanged after a change before it is saved
+const AUTOSAVE_SETTLE: Duration = Duration::from_secs(2);
+
+/// Longest a changed session goes unsaved while it keeps changing
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A tab as it was when the session was saved
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionTab {
+    /// Directory shown in the tab
+    pub path: PathBuf,
+    /// Name of the selected entry in that directory
+    #[serde(default)]
+    pub selected: Option<String>,
+    /// Accent color picked for the tab, by name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accent: Option<String>,
+}
+
+/// The tabs open when the browser last quit
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Session {
+    pub tabs: Vec<SessionTab>,
+    /// Index of the active tab
+    #[serde(default)]
+    pub active: usize,
+}
+
+impl Session {
+    /// Capture the tabs of a tab manager
+    pub fn capture(tab_manager: &TabManager) -> Self {
+        let tabs = tab_manager
+            .tabs()
+            .iter()
+            .map(|tab| {
+                let column = tab.browser.active_column();
+                SessionTab {
+                    path: column.path.clone(),
+                    selected: column
+                        .selected_entry()
+                        .map(|entry| entry.file_name().to_string_lossy().to_string()),
+                    accent: tab.accent.map(|color| color.to_string()),
+                }
+            })
+            .collect();
+
+        Self {
+            tabs,
+            active: tab_manager.active_index(),
+        }
+    }
+
+    /// Check whether reopening the session would show more than a fresh start in `dir`
+    pub fn differs_from(&self, dir: &Path) -> bool {
+        match self.tabs.as_slice() {
+            [] => false,
+            [tab] => tab.path != dir,
+            _ => true,
+        }
+    }
+}
+
+/// Decides when to save the session while the browser runs
+#[derive(Debug, Default)]
+pub struct Autosave {
+    /// Session last saved
+    saved: Option<Session>,
+    /// Session last seen, and when it was first seen like that
+    seen: Option<(Session, Instant)>,
+    /// When the session first differed from the one saved
+    changed_since: Option<Instant>,
+}
+
+impl Autosave {
+    /// Look at the session as it is now, returning it when it should be saved
+    pub fn check(&mut self, session: Session, now: Instant) -> Option<Session> {
+        if self.saved.as_ref() == Some(&session) {
+            self.seen = None;
+            self.changed_since = None;
+            return None;
+        }
+        let changed_since = *self.changed_since.get_or_insert(now);
+        let settled_at = match &self.seen {
+            Some((seen, at)) if *seen == session => *at,
+            _ => {
+                self.seen = Some((session.clone(), now));
+                now
+            }
+        };
+        let due = now.duration_since(settled_at) >= AUTOSAVE_SETTLE || now.duration_since(changed_since) >= AUTOSAVE_INTERVAL;
+        due.then_some(session)
+    }
+
+    /// Note that a session was saved
+    pub fn saved(&mut self, session: Session) {
+        self.saved = Some(session);
+        self.seen = None;
+        self.changed_since = None;
+    }
+}
+
+/// Get the session file path, next to the settings file
+pub fn session_path(config: &Settings) -> PathBuf {
+    let settings = config.source.clone().unwrap_or_else(settings_path);
+    settings.parent().unwrap_or(Path::new(".")).join(".browse_session")
+}
+
+/// Load the saved session, or None if there isn't one
+pub fn load_session(config: &Settings) -> Result<Option<Session>, Box<dyn std::error::Error>> {
+    let path = session_path(config);
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let file = fs::File::open(&path)
+        .map_err(|e| format!("Failed to open session file {:?}: {}", path, e))?;
+
+    let session = serde_json::from_reader(file)
+        .map_err(|e| format!("Failed to parse session file: {}", e))?;
+
+    Ok(Some(session))
+}
+
+/// Save the session to file
+pub fn save_session(session: &Session, config: &Settings) -> Result<(), Box<dyn std::error::Error>> {
+    let path = session_path(config);
+
+    let file = fs::File::create(&path)
+        .map_err(|e| format!("Failed to create session file {:?}: {}", path, e))?;
+
+    serde_json::to_writer_pretty(file, session)
+        .map_err(|e| format!("Failed to write session: {}", e))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(dir: &str) -> Session {
+        Session { tabs: vec![SessionTab { path: PathBuf::from(dir), selected: None, accent: None }], active: 0 }
+    }
+
+    #[test]
+    fn test_autosave_after_settling_or_interval() {
+        let start = Instant::now();
+        let at = |seconds: u64| start + Duration::from_secs(seconds);
+        let mut autosave = Autosave::default();
+
+        // A change is saved once it has stayed put for a moment
+        assert_eq!(autosave.check(session("/a"), at(0)), None);
+        assert_eq!(autosave.check(session("/a"), at(2)), Some(session("/a")));
+        autosave.saved(session("/a"));
+        assert_eq!(autosave.check(session("/a"), at(10)), None);
+
+        // Browsing that keeps changing is still saved every interval
+        for second in 11..41 {
+            assert_eq!(autosave.check(session(&format!("/b{}", second)), at(second)), None);
+        }
+        assert_eq!(autosave.check(session("/c"), at(41)), Some(session("/c")));
+    }
+
+    #[test]
+    fn test_session_is_kept_next_to_the_settings() {
+        let dir = std::env::temp_dir().join(format!("browse-session-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config = Settings { source: Some(dir.join("settings.json")), ..Settings::default() };
+        assert_eq!(load_session(&config).unwrap(), None);
+
+        save_session(&session("/a"), &config).unwrap();
+        assert!(dir.join(".browse_session").exists());
+        assert_eq!(load_session(&config).unwrap(), Some(session("/a")));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,5 +1,8 @@
 use crate::app::App;
-use crate::config::Settings;
+use crate::commands::{CommandAction, CommandRegistry, KeyBinding};
+use crate::config::{complete_mime_type, validate_mime_type, FileTypeRule, Settings};
+use crate::input::{render_text_input, TextInput};
+use crate::ui::centered_rect;
 use color_eyre::Result;
 use crossterm::event::{KeyCode, KeyEvent};
 
@@ -11,11 +14,42 @@ use ratatui::{
 /// State for adding/editing file type rules
 #[derive(Debug)]
 pub struct AddFileTypeState {
-    pub mime_type: String,
-    pub icon: String,
+    pub mime_type: TextInput,
+    pub icon: TextInput,
     pub preview: bool,
     pub focused_field: usize,
     pub is_editing: Option<String>,
+    /// Why the rule can't be saved, or a warning waiting for confirmation
+    pub message: Option<String>,
+    /// Another rule has the typed name and Enter was pressed once; Enter again replaces it
+    pub confirm_overwrite: bool,
+}
+
+impl AddFileTypeState {
+    fn new(mime_type: TextInput, icon: TextInput, preview: bool, is_editing: Option<String>) -> Self {
+        Self {
+            mime_type,
+            icon,
+            preview,
+            focused_field: 0,
+            is_editing,
+            message: None,
+            confirm_overwrite: false,
+        }
+    }
+
+    /// Known MIME type the typed one could be completed to
+    pub fn suggestion(&self) -> Option<&'static str> {
+        complete_mime_type(self.mime_type.value())
+    }
+}
+
+/// Column the File Types table is sorted by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileTypeSort {
+    MimeType,
+    /// Rules that preview first, then by MIME type
+    Preview,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -53,7 +87,18 @@ pub struct SettingsState {
     pub file_type_selection: usize,
     pub file_type_column_selection: usize,
     pub file_type_table_state: TableState,
+    pub file_type_sort: FileTypeSort,
+    /// Only rules whose MIME type contains this text are listed
+    pub file_type_filter: TextInput,
+    /// Keys go to the filter until Enter or Esc
+    pub editing_file_type_filter: bool,
     pub add_file_type_state: Option<AddFileTypeState>,
+    pub keybinding_selection: usize,
+    /// Command waiting for its new key in the key binding editor
+    pub capturing_binding: Option<CommandAction>,
+    /// Captured key that is already taken, waiting for the user to confirm the reassignment
+    pub pending_binding: Option<(CommandAction, KeyBinding, CommandAction)>,
+    pub keybinding_message: Option<String>,
 }
 
 impl SettingsState {
@@ -65,7 +110,14 @@ impl SettingsState {
             file_type_selection: 0,
             file_type_column_selection: 0,
             file_type_table_state: TableState::default(),
+            file_type_sort: FileTypeSort::MimeType,
+            file_type_filter: TextInput::default(),
+            editing_file_type_filter: false,
             add_file_type_state: None,
+            keybinding_selection: 0,
+            capturing_binding: None,
+            pending_binding: None,
+            keybinding_message: None,
         }
     }
 }
@@ -78,6 +130,15 @@ pub enum SettingsFocus {
 }
 
 
+/// What the app needs to refresh after a key press in the settings panel
+#[derive(Debug, Default, PartialEq)]
+pub struct SettingsChanges {
+    /// Directory listings depend on a changed setting
+    pub reload_browser: bool,
+    /// Key bindings changed, so the command registry must be rebuilt and the settings saved
+    pub keybindings: bool,
+}
+
 /// Settings manager handling all settings UI state and interactions
 #[derive(Debug)]
 pub struct SettingsManager {
@@ -111,14 +172,13 @@ impl SettingsManager {
     }
 
     /// Handle settings panel key input
-    /// Returns true if browser needs to be reloaded due to settings changes
-    pub fn handle_key(&mut self, key: KeyEvent, config: &mut Settings) -> Result<bool> {
+    /// Returns what needs to be refreshed due to settings changes
+    pub fn handle_key(&mut self, key: KeyEvent, config: &mut Settings, registry: &CommandRegistry) -> Result<SettingsChanges> {
+        let mut changes = SettingsChanges::default();
         if self.state.is_none() {
-            return Ok(false);
+            return Ok(changes);
         }
 
-        let mut needs_browser_reload = false;
-
         // Extract the current state to avoid borrowing conflicts
         let (focus, active_tab, display_selection) = {
             let settings_state = self.state.as_ref().unwrap();
@@ -160,16 +220,44 @@ impl SettingsManager {
                     }
                     KeyCode::Down => {
                         if let Some(settings_state) = &mut self.state {
-                            settings_state.display_selection = (settings_state.display_selection + 1).min(1);
+                            settings_state.display_selection = (settings_state.display_selection + 1).min(17);
                         }
                     }
                     KeyCode::Char(' ') | KeyCode::Enter => {
                         match display_selection {
                             0 => {
                                 config.show_hidden_files = !config.show_hidden_files;
-                                needs_browser_reload = true;
+                                changes.reload_browser = true;
+                            }
+                            1 => {
+                                config.cycle_icon_theme();
+                                changes.reload_browser = true;
+                            }
+                            2 => config.notifications.enabled = !config.notifications.enabled,
+                            3 => config.delete_to_trash = !config.delete_to_trash,
+                            4 => config.confirm = config.confirm.next(),
+                            5 => config.search_ignore_case = !config.search_ignore_case,
+                            6 => config.search_ignore_diacritics = !config.search_ignore_diacritics,
+                            7 => config.restore_session = !config.restore_session,
+                            8 => {
+                                config.git_status = !config.git_status;
+                                changes.reload_browser = true;
+                            }
+                            9 => config.dim_git_ignored = !config.dim_git_ignored,
+                            10 => config.dir_sizes = !config.dir_sizes,
+                            11 => config.detail_view = !config.detail_view,
+                            12 => config.project_in_status = !config.project_in_status,
+                            13 => config.allocated_sizes = !config.allocated_sizes,
+                            14 => {
+                                config.sniff_mime_types = !config.sniff_mime_types;
+                                changes.reload_browser = true;
                             }
-                            1 => config.show_icons = !config.show_icons,
+                            15 => {
+                                config.linked_images = !config.linked_images;
+                                changes.reload_browser = true;
+                            }
+                            16 => config.entry_counts = !config.entry_counts,
+                            17 => config.tab_accents = !config.tab_accents,
                             _ => {}
                         }
                     }
@@ -178,21 +266,87 @@ impl SettingsManager {
                 SettingsTab::FileTypes => {
                     self.handle_file_types_key(key, config)?;
                 }
-                SettingsTab::Keybindings => match key.code {
-                    KeyCode::Esc | KeyCode::Char('?') => self.state = None,
-                    KeyCode::Left => {
-                        if let Some(settings_state) = &mut self.state {
-                            settings_state.focus = SettingsFocus::TabList;
-                        }
-                    }
-                    _ => {}
-                },
+                SettingsTab::Keybindings => {
+                    changes.keybindings = self.handle_keybindings_key(key, config, registry);
+                }
             },
             SettingsFocus::AddFileTypePopup => {
                 self.handle_add_file_type_key(key, config)?;
             }
         }
-        Ok(needs_browser_reload)
+        Ok(changes)
+    }
+
+    /// Handle keys in the key binding editor
+    ///
+    /// Enter captures the next key for the selected command and Backspace restores its
+    /// default. A key already bound elsewhere is only reassigned after confirmation,
+    /// leaving the other command unbound. Returns true if the bindings changed.
+    fn handle_keybindings_key(&mut self, key: KeyEvent, config: &mut Settings, registry: &CommandRegistry) -> bool {
+        let Some(settings_state) = &mut self.state else {
+            return false;
+        };
+        let commands = registry.remappable_commands();
+
+        if let Some((action, binding, other)) = settings_state.pending_binding.take() {
+            settings_state.keybinding_message = None;
+            if matches!(key.code, KeyCode::Enter | KeyCode::Char('y')) {
+                config.keybindings.insert(other.name(), String::new());
+                config.keybindings.insert(action.name(), binding.spec());
+                return true;
+            }
+            return false;
+        }
+
+        if let Some(action) = settings_state.capturing_binding.take() {
+            settings_state.keybinding_message = None;
+            if key.code == KeyCode::Esc {
+                return false;
+            }
+
+            let binding = KeyBinding::from_key_event(&key);
+            if binding.display_text() == "Unknown" {
+                settings_state.keybinding_message = Some("That key can't be bound".to_string());
+                return false;
+            }
+            if let Some(other) = registry.conflicting_command(&binding, &action) {
+                settings_state.keybinding_message = Some(format!(
+                    "{} is used by \"{}\"; Enter to reassign",
+                    binding.display_text(),
+                    other.description
+                ));
+                settings_state.pending_binding = Some((action, binding, other.action.clone()));
+                return false;
+            }
+
+            config.keybindings.insert(action.name(), binding.spec());
+            return true;
+        }
+
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('?') => self.state = None,
+            KeyCode::Left => settings_state.focus = SettingsFocus::TabList,
+            KeyCode::Up => {
+                settings_state.keybinding_selection = settings_state.keybinding_selection.saturating_sub(1);
+            }
+            KeyCode::Down => {
+                settings_state.keybinding_selection = (settings_state.keybinding_selection + 1)
+                    .min(commands.len().saturating_sub(1));
+            }
+            KeyCode::Enter => {
+                if let Some((action, _, _)) = commands.get(settings_state.keybinding_selection) {
+                    settings_state.capturing_binding = Some(action.clone());
+                    settings_state.keybinding_message = Some("Press the new key, or Esc to cancel".to_string());
+                }
+            }
+            KeyCode::Backspace => {
+                if let Some((action, _, _)) = commands.get(settings_state.keybinding_selection) {
+                    return config.keybindings.remove(&action.name()).is_some();
+                }
+            }
+            _ => {}
+        }
+        false
     }
 
     /// Handle file types tab key input
@@ -202,6 +356,23 @@ impl SettingsManager {
             return self.handle_add_file_type_key(key, config);
         }
 
+        if let Some(settings_state) = &mut self.state
+            && settings_state.editing_file_type_filter
+        {
+            match key.code {
+                KeyCode::Esc => {
+                    settings_state.file_type_filter = TextInput::default();
+                    settings_state.editing_file_type_filter = false;
+                }
+                KeyCode::Enter => settings_state.editing_file_type_filter = false,
+                _ => {
+                    settings_state.file_type_filter.handle_key(key);
+                }
+            }
+            settings_state.file_type_selection = 0;
+            return Ok(());
+        }
+
         match key.code {
             KeyCode::Esc | KeyCode::Char('?') => self.close(),
             KeyCode::Left => {
@@ -216,19 +387,32 @@ impl SettingsManager {
             }
             KeyCode::Down => {
                 if let Some(settings_state) = &mut self.state {
-                    let total_items = config.mime_types.primary.len() + config.mime_types.subtypes.len();
+                    let total_items = file_type_rows(config, settings_state).len();
                     settings_state.file_type_selection = (settings_state.file_type_selection + 1).min(total_items.saturating_sub(1));
                 }
             }
+            KeyCode::Char('s') | KeyCode::Char('S') => {
+                if let Some(settings_state) = &mut self.state {
+                    settings_state.file_type_sort = match settings_state.file_type_sort {
+                        FileTypeSort::MimeType => FileTypeSort::Preview,
+                        FileTypeSort::Preview => FileTypeSort::MimeType,
+                    };
+                    settings_state.file_type_selection = 0;
+                }
+            }
+            KeyCode::Char('/') => {
+                if let Some(settings_state) = &mut self.state {
+                    settings_state.editing_file_type_filter = true;
+                }
+            }
             KeyCode::Char('a') | KeyCode::Char('A') => {
                 if let Some(settings_state) = &mut self.state {
-                    settings_state.add_file_type_state = Some(AddFileTypeState {
-                        mime_type: String::new(),
-                        icon: String::new(),
-                        preview: false,
-                        focused_field: 0,
-                        is_editing: None,
-                    });
+                    settings_state.add_file_type_state = Some(AddFileTypeState::new(
+                        TextInput::default(),
+                        TextInput::default(),
+                        false,
+                        None,
+                    ));
                     settings_state.focus = SettingsFocus::AddFileTypePopup;
                 }
             }
@@ -257,7 +441,15 @@ impl SettingsManager {
             KeyCode::Tab => {
                 if let Some(settings_state) = &mut self.state {
                     if let Some(add_state) = &mut settings_state.add_file_type_state {
-                        add_state.focused_field = (add_state.focused_field + 1) % 3;
+                        // Tab completes the MIME type when there is a suggestion, then moves on
+                        match add_state.suggestion().filter(|_| focused_field == 0) {
+                            Some(suggestion) => {
+                                add_state.mime_type.set_value(suggestion);
+                                add_state.message = None;
+                                add_state.confirm_overwrite = false;
+                            }
+                            None => add_state.focused_field = (add_state.focused_field + 1) % 3,
+                        }
                     }
                 }
             }
@@ -278,111 +470,107 @@ impl SettingsManager {
                     }
                 }
             }
-            KeyCode::Char(c) => {
-                if let Some(settings_state) = &mut self.state {
-                    if let Some(add_state) = &mut settings_state.add_file_type_state {
-                        match focused_field {
-                            0 => add_state.mime_type.push(c),
-                            1 => add_state.icon.push(c),
-                            _ => {}
-                        }
-                    }
-                }
-            }
-            KeyCode::Backspace => {
+            _ => {
                 if let Some(settings_state) = &mut self.state {
                     if let Some(add_state) = &mut settings_state.add_file_type_state {
                         match focused_field {
-                            0 => { add_state.mime_type.pop(); }
-                            1 => { add_state.icon.pop(); }
+                            0 => {
+                                let edited = add_state.mime_type.handle_key(key);
+                                if edited {
+                                    add_state.message = None;
+                                    add_state.confirm_overwrite = false;
+                                }
+                            }
+                            1 => { add_state.icon.handle_key(key); }
                             _ => {}
                         }
                     }
                 }
             }
-            _ => {}
         }
         Ok(())
     }
 
     /// Delete the currently selected file type rule
     fn delete_selected_file_type(&mut self, config: &mut Settings) {
-        let file_type_selection = self.state.as_ref().unwrap().file_type_selection;
-
-        let mut sorted_exts: Vec<_> = config
-            .mime_types
-            .primary
-            .keys()
-            .chain(config.mime_types.subtypes.keys())
-            .collect();
-        sorted_exts.sort();
+        let Some(settings_state) = &mut self.state else {
+            return;
+        };
 
-        if let Some(ext) = sorted_exts.get(file_type_selection) {
-            let ext = ext.to_string();
-            config.mime_types.primary.remove(&ext);
-            config.mime_types.subtypes.remove(&ext);
+        let selected = file_type_rows(config, settings_state)
+            .get(settings_state.file_type_selection)
+            .map(|(ext, _)| ext.to_string());
+        if let Some(ext) = selected {
+            config.mime_types.remove(&ext);
 
             // Adjust selection if needed
-            if let Some(settings_state) = &mut self.state {
-                let total_items = config.mime_types.primary.len() + config.mime_types.subtypes.len();
-                if settings_state.file_type_selection >= total_items && total_items > 0 {
-                    settings_state.file_type_selection = total_items - 1;
-                }
+            let total_items = file_type_rows(config, settings_state).len();
+            if settings_state.file_type_selection >= total_items && total_items > 0 {
+                settings_state.file_type_selection = total_items - 1;
             }
         }
     }
 
     /// Edit the currently selected file type rule
     fn edit_selected_file_type(&mut self, config: &Settings) {
-        let file_type_selection = self.state.as_ref().unwrap().file_type_selection;
+        let Some(settings_state) = &mut self.state else {
+            return;
+        };
 
-        let mut sorted_exts: Vec<_> = config
-            .mime_types
-            .primary
-            .keys()
-            .chain(config.mime_types.subtypes.keys())
-            .collect();
-        sorted_exts.sort();
-
-        if let Some(ext) = sorted_exts.get(file_type_selection) {
-            let rule = if let Some(rule) = config.mime_types.primary.get(*ext) {
-                rule
-            } else {
-                config.mime_types.subtypes.get(*ext).unwrap()
-            }.clone();
-
-            if let Some(settings_state) = &mut self.state {
-                settings_state.add_file_type_state = Some(AddFileTypeState {
-                    mime_type: ext.to_string(),
-                    icon: rule.icon,
-                    preview: rule.preview,
-                    focused_field: 0,
-                    is_editing: Some(ext.to_string()),
-                });
-                settings_state.focus = SettingsFocus::AddFileTypePopup;
-            }
+        let selected = file_type_rows(config, settings_state)
+            .get(settings_state.file_type_selection)
+            .map(|(ext, rule)| (ext.to_string(), (*rule).clone()));
+        if let Some((ext, rule)) = selected {
+            settings_state.add_file_type_state = Some(AddFileTypeState::new(
+                TextInput::new(ext.clone()),
+                TextInput::new(rule.icon),
+                rule.preview,
+                Some(ext),
+            ));
+            settings_state.focus = SettingsFocus::AddFileTypePopup;
         }
     }
 
     /// Save file type rule from the add/edit popup
+    ///
+    /// An invalid MIME type keeps the popup open with the reason, and replacing a
+    /// different rule of the same name needs a second Enter.
     fn save_file_type_rule(&mut self, config: &mut Settings) -> Result<()> {
         if let Some(settings_state) = &mut self.state {
-            if let Some(add_state) = &settings_state.add_file_type_state {
-                if !add_state.mime_type.is_empty() {
-                    let rule = crate::config::FileTypeRule {
-                        icon: add_state.icon.clone(),
-                        preview: add_state.preview,
-                    };
+            if let Some(add_state) = &mut settings_state.add_file_type_state {
+                // MIME types are case-insensitive and detected ones are lowercase
+                let mime_type = add_state.mime_type.value().trim().to_ascii_lowercase();
+                if let Err(message) = validate_mime_type(&mime_type) {
+                    add_state.message = Some(message);
+                    return Ok(());
+                }
 
-                    // If editing, remove the old entry first
-                    if let Some(old_mime_type) = &add_state.is_editing {
-                        config.mime_types.primary.remove(old_mime_type);
-                        config.mime_types.subtypes.remove(old_mime_type);
-                    }
+                let replaces_other = config.mime_types.contains(&mime_type)
+                    && add_state.is_editing.as_deref() != Some(mime_type.as_str());
+                if replaces_other && !add_state.confirm_overwrite {
+                    add_state.message = Some(format!("{} already has a rule; Enter again to replace it", mime_type));
+                    add_state.confirm_overwrite = true;
+                    return Ok(());
+                }
 
-                    // Add the new/updated rule
-                    config.mime_types.primary.insert(add_state.mime_type.clone(), rule);
+                // The open command and Enter action aren't edited here, so an edited rule keeps them
+                let old_rule = add_state.is_editing.as_deref().and_then(|old| config.get_rule(old));
+                let open_with = old_rule.and_then(|rule| rule.open_with.clone());
+                let on_enter = old_rule.and_then(|rule| rule.on_enter);
+                let rule = FileTypeRule {
+                    icon: add_state.icon.value().to_string(),
+                    preview: add_state.preview,
+                    open_with,
+                    on_enter,
+                };
+
+                // If editing, remove the old entry first
+                if let Some(old_mime_type) = &add_state.is_editing {
+                    config.mime_types.remove(old_mime_type);
                 }
+
+                // Add the new/updated rule
+                config.mime_types.insert(mime_type, rule);
             }
 
             // Close the popup
@@ -469,9 +657,67 @@ fn render_display_settings(
             "[{}] Show hidden files",
             if config.show_hidden_files { "✓" } else { " " }
         )),
+        ListItem::new(format!("Icons: {} (Space cycles)", config.icon_theme.label())),
+        ListItem::new(format!(
+            "[{}] Desktop notification when a long job finishes in the background",
+            if config.notifications.enabled { "✓" } else { " " }
+        )),
+        ListItem::new(format!(
+            "[{}] Delete moves to trash (Shift+Delete always deletes permanently)",
+            if config.delete_to_trash { "✓" } else { " " }
+        )),
+        ListItem::new(format!("Ask before: {} (Space cycles)", config.confirm.label())),
+        ListItem::new(format!(
+            "[{}] Quick search ignores case",
+            if config.search_ignore_case { "✓" } else { " " }
+        )),
+        ListItem::new(format!(
+            "[{}] Quick search ignores accents (e finds é)",
+            if config.search_ignore_diacritics { "✓" } else { " " }
+        )),
+        ListItem::new(format!(
+            "[{}] Offer to restore the previous session at startup",
+            if config.restore_session { "✓" } else { " " }
+        )),
         ListItem::new(format!(
-            "[{}] Show icons",
-            if config.show_icons { "✓" } else { " " }
+            "[{}] Show git status in git repositories",
+            if config.git_status { "✓" } else { " " }
+        )),
+        ListItem::new(format!(
+            "[{}] Dim files git ignores",
+            if config.dim_git_ignored { "✓" } else { " " }
+        )),
+        ListItem::new(format!(
+            "[{}] Calculate directory sizes in the background",
+            if config.dir_sizes { "✓" } else { " " }
+        )),
+        ListItem::new(format!(
+            "[{}] Detail view: size, date, permissions and owner next to each entry",
+            if config.detail_view { "✓" } else { " " }
+        )),
+        ListItem::new(format!(
+            "[{}] Show the project name (Cargo.toml, package.json or .git) in the status bar",
+            if config.project_in_status { "✓" } else { " " }
+        )),
+        ListItem::new(format!(
+            "[{}] Show allocated size on disk next to apparent size (detail and disk usage views)",
+            if config.allocated_sizes { "✓" } else { " " }
+        )),
+        ListItem::new(format!(
+            "[{}] Tell file types from their contents, not just the extension (off is faster on slow filesystems)",
+            if config.sniff_mime_types { "✓" } else { " " }
+        )),
+        ListItem::new(format!(
+            "[{}] Show the first image a markdown or HTML file links to under its preview",
+            if config.linked_images { "✓" } else { " " }
+        )),
+        ListItem::new(format!(
+            "[{}] Show how many entries each directory holds next to its name",
+            if config.entry_counts { "✓" } else { " " }
+        )),
+        ListItem::new(format!(
+            "[{}] Give each tab its own accent color (C picks one for the current tab)",
+            if config.tab_accents { "✓" } else { " " }
         )),
     ];
 
@@ -500,30 +746,10 @@ fn render_file_types_settings(
     border_style: Style,
 ) {
     let file_types_chunks = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).split(area);
+    let focused = settings_state.focus == SettingsFocus::TabContent;
 
-    let mut sorted_exts: Vec<_> = config
-        .mime_types
-        .primary
-        .keys()
-        .chain(config.mime_types.subtypes.keys())
-        .collect();
-    sorted_exts.sort();
-
-    let rows = sorted_exts.iter().enumerate().map(|(row_index, ext)| {
-        let rule = if let Some(rule) = config.mime_types.primary.get(*ext) {
-            rule
-        } else {
-            config.mime_types.subtypes.get(*ext).unwrap()
-        };
-
-        let is_selected_row = row_index == settings_state.file_type_selection
-            && settings_state.focus == SettingsFocus::TabContent;
-
-        let row_style = if is_selected_row {
-            Style::default().bg(Color::DarkGray)
-        } else {
-            Style::default()
-        };
+    let rows = file_type_rows(config, settings_state).into_iter().enumerate().map(|(row_index, (ext, rule))| {
+        let is_selected_row = row_index == settings_state.file_type_selection && focused;
 
         let cells_data = [
             ext.to_string(),
@@ -540,7 +766,7 @@ fn render_file_types_settings(
                 let style = if is_selected_cell {
                     Style::default().add_modifier(Modifier::REVERSED)
                 } else {
-                    row_style
+                    Style::default()
                 };
                 Cell::from(data).style(style)
             })
@@ -551,11 +777,14 @@ fn render_file_types_settings(
 
     let table = Table::new(
         rows,
-        [Constraint::Length(20), Constraint::Length(4), Constraint::Length(8)],
+        [Constraint::Length(20), Constraint::Length(4), Constraint::Length(10)],
     )
     .header(
-        Row::new(vec!["MIME Type", "Icon", "Preview"])
-            .style(Style::new().add_modifier(Modifier::BOLD)),
+        Row::new(match settings_state.file_type_sort {
+            FileTypeSort::MimeType => ["MIME Type ▲", "Icon", "Preview"],
+            FileTypeSort::Preview => ["MIME Type", "Icon", "Preview ▲"],
+        })
+        .style(Style::new().add_modifier(Modifier::BOLD)),
     )
     .block(
         Block::default()
@@ -564,21 +793,59 @@ fn render_file_types_settings(
             .padding(Padding::uniform(1))
             .border_style(border_style),
     )
-    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    .row_highlight_style(Style::default().bg(Color::DarkGray));
 
+    // Selecting the row scrolls the table so it stays in view
     let mut table_state = settings_state.file_type_table_state.clone();
+    table_state.select(focused.then_some(settings_state.file_type_selection));
     frame.render_stateful_widget(table, file_types_chunks[0], &mut table_state);
 
-    let footer = Paragraph::new("[A]dd, [D]elete, [E]dit").alignment(Alignment::Center);
-    frame.render_widget(footer, file_types_chunks[1]);
+    let filter = settings_state.file_type_filter.value();
+    let footer = if settings_state.editing_file_type_filter {
+        format!("Filter: {}_  [Enter] Done, [Esc] Clear", filter)
+    } else if !filter.is_empty() {
+        format!("Filter: {}  [A]dd, [D]elete, [E]dit, [S]ort, [/] Filter", filter)
+    } else {
+        "[A]dd, [D]elete, [E]dit, [S]ort, [/] Filter".to_string()
+    };
+    frame.render_widget(Paragraph::new(footer).alignment(Alignment::Center), file_types_chunks[1]);
+}
+
+/// File type rules as listed in the File Types table, filtered and sorted
+fn file_type_rows<'a>(config: &'a Settings, settings_state: &SettingsState) -> Vec<(&'a str, &'a FileTypeRule)> {
+    let filter = settings_state.file_type_filter.value().to_lowercase();
+    let mut rows: Vec<(&str, &FileTypeRule)> = config
+        .mime_types
+        .primary
+        .iter()
+        .chain(&config.mime_types.subtypes)
+        .map(|(ext, rule)| (ext.as_str(), rule))
+        .filter(|(ext, _)| ext.to_lowercase().contains(&filter))
+        .collect();
+
+    match settings_state.file_type_sort {
+        FileTypeSort::MimeType => rows.sort_by_key(|(ext, _)| *ext),
+        FileTypeSort::Preview => rows.sort_by_key(|(ext, rule)| (!rule.preview, *ext)),
+    }
+    rows
 }
 
 /// Render keybindings settings tab
 fn render_keybindings_settings(frame: &mut Frame, area: Rect, border_style: Style, app: &App) {
-    let commands = app.command_registry().get_display_commands();
+    let Some(settings_state) = app.settings() else {
+        return;
+    };
+    let chunks = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).split(area);
+    let focused = settings_state.focus == SettingsFocus::TabContent;
 
-    let rows = commands.iter().map(|(key, desc)| {
-        Row::new(vec![Cell::from(key.clone()), Cell::from(*desc)])
+    let commands = app.command_registry().remappable_commands();
+    let rows = commands.iter().map(|(action, description, binding)| {
+        let key = if settings_state.capturing_binding.as_ref() == Some(action) {
+            "...".to_string()
+        } else {
+            binding.map(KeyBinding::display_text).unwrap_or_else(|| "-".to_string())
+        };
+        Row::new(vec![Cell::from(key), Cell::from(*description)])
     });
 
     let table = Table::new(rows, [Constraint::Percentage(30), Constraint::Percentage(70)])
@@ -592,31 +859,23 @@ fn render_keybindings_settings(frame: &mut Frame, area: Rect, border_style: Styl
         .header(
             Row::new(vec!["Key", "Description"])
                 .style(Style::new().add_modifier(Modifier::BOLD)),
-        );
-
-    frame.render_widget(table, area);
-}
+        )
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
 
-/// Create a centered rectangle
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    let popup_layout = Layout::vertical([
-        Constraint::Percentage((100 - percent_y) / 2),
-        Constraint::Percentage(percent_y),
-        Constraint::Percentage((100 - percent_y) / 2),
-    ])
-    .split(r);
+    let mut table_state = TableState::default();
+    if focused {
+        table_state.select(Some(settings_state.keybinding_selection));
+    }
+    frame.render_stateful_widget(table, chunks[0], &mut table_state);
 
-    Layout::horizontal([
-        Constraint::Percentage((100 - percent_x) / 2),
-        Constraint::Percentage(percent_x),
-        Constraint::Percentage((100 - percent_x) / 2),
-    ])
-    .split(popup_layout[1])[1]
+    let footer = settings_state.keybinding_message.as_deref()
+        .unwrap_or("[Enter] Change key, [Backspace] Restore default");
+    frame.render_widget(Paragraph::new(footer).alignment(Alignment::Center), chunks[1]);
 }
 
 /// Render add/edit file type popup
 fn render_add_file_type_popup(frame: &mut Frame, add_state: &AddFileTypeState) {
-    let popup_area = centered_rect(50, 30, frame.area());
+    let popup_area = centered_rect(50, 40, frame.area());
     frame.render_widget(Clear, popup_area);
 
     let chunks = Layout::vertical([
@@ -640,13 +899,10 @@ fn render_add_file_type_popup(frame: &mut Frame, add_state: &AddFileTypeState) {
         Style::default()
     };
 
-    let mime_type_widget = Paragraph::new(add_state.mime_type.as_str())
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("MIME Type")
-                .border_style(mime_type_style),
-        );
+    let mime_type_block = Block::default()
+        .borders(Borders::ALL)
+        .title("MIME Type")
+        .border_style(mime_type_style);
 
     // Icon field
     let icon_style = if add_state.focused_field == 1 {
@@ -655,13 +911,10 @@ fn render_add_file_type_popup(frame: &mut Frame, add_state: &AddFileTypeState) {
         Style::default()
     };
 
-    let icon_widget = Paragraph::new(add_state.icon.as_str())
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Icon")
-                .border_style(icon_style),
-        );
+    let icon_block = Block::default()
+        .borders(Borders::ALL)
+        .title("Icon")
+        .border_style(icon_style);
 
     // Preview checkbox
     let preview_style = if add_state.focused_field == 2 {
@@ -686,7 +939,17 @@ fn render_add_file_type_popup(frame: &mut Frame, add_state: &AddFileTypeState) {
         .padding(Padding::uniform(1));
 
     frame.render_widget(popup_block, popup_area);
-    frame.render_widget(mime_type_widget, chunks[0]);
-    frame.render_widget(icon_widget, chunks[1]);
+    render_text_input(frame, &add_state.mime_type, mime_type_block, chunks[0], add_state.focused_field == 0);
+    render_text_input(frame, &add_state.icon, icon_block, chunks[1], add_state.focused_field == 1);
     frame.render_widget(preview_widget, chunks[2]);
+
+    // Problems with the rule take precedence over the completion hint
+    let hint = match (&add_state.message, add_state.suggestion()) {
+        (Some(message), _) => Line::styled(message.as_str(), Style::default().fg(Color::Yellow)),
+        (None, Some(suggestion)) if add_state.focused_field == 0 => {
+            Line::styled(format!("Tab: {}", suggestion), Style::default().fg(Color::DarkGray))
+        }
+        _ => Line::default(),
+    };
+    frame.render_widget(Paragraph::new(hint).wrap(Wrap { trim: true }), chunks[3]);
 }
@@ -1,8 +1,14 @@
 use crate::browser::Browser;
 use crate::config::Settings;
 use crate::error::ErrorLog;
+use crate::utils::home_relative;
 use color_eyre::Result;
-use std::path::PathBuf;
+use ratatui::style::Color;
+use std::path::{Component, Path, PathBuf};
+
+/// Accent colors tabs take by position with `tab_accents` on, and that a tab's own
+/// accent is cycled through
+pub const TAB_ACCENTS: [Color; 6] = [Color::Blue, Color::Magenta, Color::Green, Color::Yellow, Color::Red, Color::Cyan];
 
 /// Represents a single tab containing a browser instance
 #[derive(Debug)]
@@ -11,22 +17,20 @@ pub struct Tab {
     pub browser: Browser,
     /// Display name for the tab (usually the directory name)
     pub name: String,
+    /// Accent color picked for this tab, over the one it would get by position
+    pub accent: Option<Color>,
 }
 
 impl Tab {
     /// Create a new tab with a browser instance
     pub fn new(path: PathBuf, config: &Settings, error_log: Option<&mut ErrorLog>) -> Result<Self> {
         let browser = Browser::new_with_error_log(path.clone(), config, error_log)?;
-
-        let name = path
-            .file_name()
-            .and_then(|name| name.to_str())
-            .unwrap_or("~")
-            .to_string();
+        let name = format_tab_name(&config.tab_name_template, &path);
 
         Ok(Self {
             browser,
             name,
+            accent: None,
         })
     }
 
@@ -36,18 +40,66 @@ impl Tab {
     }
 
     /// Update the tab name based on current directory
-    pub fn update_name(&mut self) {
-        if let Some(current_col) = self.browser.columns().back() {
-            let new_name = current_col.path
-                .file_name()
-                .and_then(|name| name.to_str())
-                .unwrap_or("~")
-                .to_string();
-            self.name = new_name;
+    pub fn update_name(&mut self, config: &Settings) {
+        self.name = format_tab_name(&config.tab_name_template, &self.browser.active_column().path);
+    }
+}
+
+/// Build a tab name for a directory from a template
+///
+/// `{name}` is the directory's name, `{parent}` its parent's name, `{path}` the
+/// full path with the home directory shown as `~`, and `{path:-N}` the last N
+/// components of the path. Anything else is kept as written.
+pub fn format_tab_name(template: &str, path: &Path) -> String {
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        name.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        let placeholder = &rest[start + 1..start + end];
+        match expand_placeholder(placeholder, path) {
+            Some(value) => name.push_str(&value),
+            None => name.push_str(&rest[start..=start + end]),
+        }
+        rest = &rest[start + end + 1..];
+    }
+    name.push_str(rest);
+
+    if name.is_empty() { component_name(path) } else { name }
+}
+
+/// Value of a single tab name placeholder, or None if it isn't one
+fn expand_placeholder(placeholder: &str, path: &Path) -> Option<String> {
+    match placeholder {
+        "name" => Some(component_name(path)),
+        "parent" => Some(path.parent().map(component_name).unwrap_or_default()),
+        "path" => Some(home_relative(path)),
+        _ => {
+            let count: usize = placeholder.strip_prefix("path:-")?.parse().ok()?;
+            let components: Vec<_> = path
+                .components()
+                .filter_map(|component| match component {
+                    Component::Normal(name) => Some(name.to_string_lossy()),
+                    _ => None,
+                })
+                .collect();
+            if count >= components.len() {
+                return Some(path.display().to_string());
+            }
+            Some(components[components.len() - count..].join("/"))
         }
     }
 }
 
+/// Last component of a path, or the whole path for the root
+fn component_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
 /// Manages a collection of tabs
 pub struct TabManager {
     /// Collection of all tabs
@@ -96,13 +148,25 @@ impl TabManager {
             std::env::current_dir()?
         };
 
-        let new_tab = Tab::new(current_path, config, error_log)?;
+        self.open_tab(current_path, config, error_log)
+    }
+
+    /// Open a new tab at a path and make it active
+    pub fn open_tab(&mut self, path: PathBuf, config: &Settings, error_log: Option<&mut ErrorLog>) -> Result<()> {
+        let new_tab = Tab::new(path, config, error_log)?;
         self.tabs.push(new_tab);
         self.active_index = self.tabs.len() - 1;
 
         Ok(())
     }
 
+    /// Make the tab at an index active; out of range indices are ignored
+    pub fn select_tab(&mut self, index: usize) {
+        if index < self.tabs.len() {
+            self.active_index = index;
+        }
+    }
+
     /// Close the current tab
     pub fn close_current_tab(&mut self) -> bool {
         if self.tabs.len() <= 1 {
@@ -138,6 +202,31 @@ impl TabManager {
         }
     }
 
+    /// Accent color of the tab at an index: its own, or with `tab_accents` on, the
+    /// one for its position
+    pub fn accent(&self, index: usize, config: &Settings) -> Option<Color> {
+        let tab = self.tabs.get(index)?;
+        tab.accent.or_else(|| automatic_accent(index, config))
+    }
+
+    /// Accent color of the active tab
+    pub fn active_accent(&self, config: &Settings) -> Option<Color> {
+        self.accent(self.active_index, config)
+    }
+
+    /// Give the active tab the next accent color that looks different, going back
+    /// to the automatic one (or none) after the last
+    pub fn cycle_accent(&mut self, config: &Settings) {
+        let automatic = automatic_accent(self.active_index, config);
+        let tab = self.active_tab_mut();
+        // Picking the automatic color would look the same as having none picked
+        let choices: Vec<Option<Color>> = std::iter::once(None)
+            .chain(TAB_ACCENTS.into_iter().filter(|&color| Some(color) != automatic).map(Some))
+            .collect();
+        let current = choices.iter().position(|&choice| choice == tab.accent).unwrap_or(0);
+        tab.accent = choices[(current + 1) % choices.len()];
+    }
+
     /// Get the number of tabs
     pub fn tab_count(&self) -> usize {
         self.tabs.len()
@@ -150,8 +239,66 @@ impl TabManager {
         }
     }
 
+    /// Reload the columns showing any of `dirs` in every tab
+    pub fn refresh_paths(&mut self, dirs: &[PathBuf], config: &Settings) {
+        for tab in &mut self.tabs {
+            tab.browser.refresh_paths(dirs, config);
+            tab.update_name(config);
+        }
+    }
+
     /// Update the name of the active tab based on current directory
-    pub fn update_active_tab_name(&mut self) {
-        self.active_tab_mut().update_name();
+    pub fn update_active_tab_name(&mut self, config: &Settings) {
+        self.active_tab_mut().update_name(config);
+    }
+}
+
+/// Accent a tab gets by its position, with `tab_accents` on
+fn automatic_accent(index: usize, config: &Settings) -> Option<Color> {
+    config.tab_accents.then(|| TAB_ACCENTS[index % TAB_ACCENTS.len()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_tab_name() {
+        let path = Path::new("/work/project/crates/core/src");
+        assert_eq!(format_tab_name("{name}", path), "src");
+        assert_eq!(format_tab_name("{parent}/{name}", path), "core/src");
+        assert_eq!(format_tab_name("{path:-3}", path), "crates/core/src");
+        assert_eq!(format_tab_name("{path:-9}", path), "/work/project/crates/core/src");
+        assert_eq!(format_tab_name("[{name}] {other}", path), "[src] {other}");
+        assert_eq!(format_tab_name("{name", path), "{name");
+        assert_eq!(format_tab_name("{name}", Path::new("/")), "/");
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(format_tab_name("{path}", &home.join("notes")), "~/notes");
+        }
+    }
+
+    #[test]
+    fn test_cycle_accent_skips_colors_that_look_the_same() {
+        let mut config = Settings::default();
+        let mut tabs = TabManager::new(std::env::temp_dir(), &config, None).unwrap();
+        tabs.open_tab(std::env::temp_dir(), &config, None).unwrap();
+        assert_eq!(tabs.active_accent(&config), None);
+        tabs.cycle_accent(&config);
+        assert_eq!(tabs.active_accent(&config), Some(Color::Blue));
+
+        // The second tab is magenta by position, so picking magenta is skipped
+        config.tab_accents = true;
+        tabs.active_tab_mut().accent = None;
+        assert_eq!(tabs.active_accent(&config), Some(Color::Magenta));
+        assert_eq!(tabs.accent(0, &config), Some(Color::Blue));
+        tabs.cycle_accent(&config);
+        assert_eq!(tabs.active_accent(&config), Some(Color::Blue));
+        tabs.cycle_accent(&config);
+        assert_eq!(tabs.active_accent(&config), Some(Color::Green));
+        for _ in 0..4 {
+            tabs.cycle_accent(&config);
+        }
+        assert_eq!(tabs.active_tab().accent, None);
+        assert_eq!(tabs.active_accent(&config), Some(Color::Magenta));
     }
 }
@@ -0,0 +1,137 @@
+//! Tabular previews of CSV and TSV
+//!
+//! The rows read so far are split into cells, honouring quoted fields, and shown
+//! as a table under the first row as its header. Columns whose cells are all
+//! numbers are aligned right. A table wider than the preview scrolls sideways a
+//! column at a time.
+
+/// Widest a column is drawn, in characters; longer cells are cut short
+pub const MAX_COLUMN_WIDTH: usize = 30;
+
+/// Field separator for a MIME type previewed as a table
+pub fn delimiter_for(mime_type: &str) -> Option<char> {
+    match mime_type {
+        "text/csv" => Some(','),
+        "text/tab-separated-values" => Some('\t'),
+        _ => None,
+    }
+}
+
+/// Rows of a delimited file read so far
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tabular {
+    /// The text read, which copying the preview copies
+    pub source: String,
+    /// The file continues past `source`
+    pub truncated: bool,
+    delimiter: char,
+    /// The header first, then the records
+    pub rows: Vec<Vec<String>>,
+    /// First column shown, as the table is scrolled sideways
+    pub column_offset: usize,
+}
+
+impl Tabular {
+    pub fn new(source: String, truncated: bool, delimiter: char) -> Self {
+        let rows = parse_rows(&source, delimiter, truncated);
+        Self { source, truncated, delimiter, rows, column_offset: 0 }
+    }
+
+    /// Add text read further on, which may complete the row cut off at the end
+    pub fn extend(&mut self, more: &str, truncated: bool) {
+        self.source.push_str(more);
+        self.truncated = truncated;
+        self.rows = parse_rows(&self.source, self.delimiter, truncated);
+    }
+
+    pub fn column_count(&self) -> usize {
+        self.rows.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    /// Whether every record has a number, or nothing, in a column
+    pub fn is_numeric(&self, column: usize) -> bool {
+        let mut cells = self.rows.iter().skip(1).filter_map(|row| row.get(column)).map(|cell| cell.trim()).filter(|cell| !cell.is_empty()).peekable();
+        cells.peek().is_some() && cells.all(|cell| cell.parse::<f64>().is_ok())
+    }
+
+    /// Width a column is drawn at: its widest cell, up to `MAX_COLUMN_WIDTH`
+    pub fn column_width(&self, column: usize) -> usize {
+        self.rows
+            .iter()
+            .filter_map(|row| row.get(column))
+            .map(|cell| cell.chars().count())
+            .max()
+            .unwrap_or(0)
+            .clamp(1, MAX_COLUMN_WIDTH)
+    }
+
+    /// Scroll sideways by a number of columns, keeping at least the last one in view
+    pub fn scroll_columns(&mut self, columns: isize) {
+        self.column_offset = self.column_offset.saturating_add_signed(columns).min(self.column_count().saturating_sub(1));
+    }
+
+    /// The rows as plain text, one per line with tabs between cells
+    pub fn plain_text(&self) -> String {
+        self.rows.iter().map(|row| row.iter().map(|cell| one_line(cell)).collect::<Vec<_>>().join("\t")).collect::<Vec<_>>().join("\n")
+    }
+}
+
+/// A cell's text on one line, with line breaks in quoted fields shown as spaces
+pub fn one_line(cell: &str) -> String {
+    cell.replace(['\r', '\n'], " ")
+}
+
+/// Split delimited text into rows of cells
+///
+/// A field in double quotes may hold the delimiter, line breaks and `""` for a
+/// quote. When `truncated`, the last row may be cut off and is left out.
+fn parse_rows(text: &str, delimiter: char, truncated: bool) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if cell.is_empty() => quoted = true,
+            c if quoted => cell.push(c),
+            c if c == delimiter => row.push(std::mem::take(&mut cell)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => cell.push(c),
+        }
+    }
+    if !truncated && (!cell.is_empty() || !row.is_empty()) {
+        row.push(cell);
+        rows.push(row);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quoted_cells_and_cut_rows() {
+        let table = Tabular::new("name,size\n\"a, \"\"b\"\"\",12\r\n\"two\nlines\",3.5\nc,".to_string(), true, ',');
+        assert_eq!(table.rows, [vec!["name", "size"], vec!["a, \"b\"", "12"], vec!["two\nlines", "3.5"]]);
+        assert!(table.is_numeric(1) && !table.is_numeric(0));
+
+        let mut table = table;
+        table.extend("40\n", false);
+        assert_eq!(table.rows.last().unwrap(), &["c", "40"]);
+
+        let table = Tabular::new("a\tb\n1\t\n".to_string(), false, '\t');
+        assert_eq!(table.rows, [vec!["a", "b"], vec!["1", ""]]);
+        assert!(!table.is_numeric(1));
+    }
+}
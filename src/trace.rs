@@ -0,0 +1,153 @@
+//! Optional performance tracing
+//!
+//! When enabled with `--trace <FILE>`, timed spans around the render path,
+//! directory loads, preview builds and background work are appended to a file.
+//! The default format is Chrome's trace event JSON, which can be opened in
+//! chrome://tracing or https://ui.perfetto.dev.
+
+use clap::ValueEnum;
+use serde_json::json;
+use std::cell::Cell;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+/// Output format for trace files
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum TraceFormat {
+    /// Chrome trace event JSON
+    Chrome,
+    /// One line per span: start (µs), duration (µs), name, detail
+    Text,
+}
+
+struct Tracer {
+    format: TraceFormat,
+    start: Instant,
+    state: Mutex<TracerState>,
+}
+
+struct TracerState {
+    writer: BufWriter<File>,
+    event_count: usize,
+}
+
+static TRACER: OnceLock<Tracer> = OnceLock::new();
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Small stable per-thread id for trace events
+fn thread_id() -> u64 {
+    THREAD_ID.with(|id| {
+        if id.get() == 0 {
+            id.set(NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed));
+        }
+        id.get()
+    })
+}
+
+/// Start writing trace spans to the given file
+pub fn init(path: &Path, format: TraceFormat) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    if format == TraceFormat::Chrome {
+        writer.write_all(b"[\n")?;
+    }
+
+    let tracer = Tracer {
+        format,
+        start: Instant::now(),
+        state: Mutex::new(TracerState { writer, event_count: 0 }),
+    };
+
+    TRACER
+        .set(tracer)
+        .map_err(|_| io::Error::new(io::ErrorKind::AlreadyExists, "Tracing already initialized"))
+}
+
+/// Check if tracing is enabled
+pub fn is_enabled() -> bool {
+    TRACER.get().is_some()
+}
+
+/// Flush and close the trace file
+pub fn finish() -> io::Result<()> {
+    if let Some(tracer) = TRACER.get() {
+        let mut state = tracer.state.lock().unwrap_or_else(|e| e.into_inner());
+        if tracer.format == TraceFormat::Chrome {
+            state.writer.write_all(b"\n]\n")?;
+        }
+        state.writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Start a span that is recorded when the returned guard is dropped
+pub fn span(name: &'static str) -> Span {
+    Span {
+        name,
+        detail: None,
+        start: is_enabled().then(Instant::now),
+    }
+}
+
+/// Start a span with a detail string (only computed when tracing is enabled)
+pub fn span_with(name: &'static str, detail: impl FnOnce() -> String) -> Span {
+    let enabled = is_enabled();
+    Span {
+        name,
+        detail: enabled.then(detail),
+        start: enabled.then(Instant::now),
+    }
+}
+
+/// A timed region of work; recorded on drop
+pub struct Span {
+    name: &'static str,
+    detail: Option<String>,
+    start: Option<Instant>,
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let (Some(start), Some(tracer)) = (self.start, TRACER.get()) else {
+            return;
+        };
+
+        let ts = start.duration_since(tracer.start).as_micros() as u64;
+        let dur = start.elapsed().as_micros() as u64;
+        let detail = self.detail.take().unwrap_or_default();
+
+        let line = match tracer.format {
+            TraceFormat::Chrome => json!({
+                "name": self.name,
+                "cat": "browse",
+                "ph": "X",
+                "ts": ts,
+                "dur": dur,
+                "pid": std::process::id(),
+                "tid": thread_id(),
+                "args": { "detail": detail },
+            })
+            .to_string(),
+            TraceFormat::Text => format!("{:>12} {:>10} {} {}", ts, dur, self.name, detail),
+        };
+
+        let mut state = tracer.state.lock().unwrap_or_else(|e| e.into_inner());
+        let (separator, terminator): (&[u8], &[u8]) = match tracer.format {
+            TraceFormat::Chrome if state.event_count > 0 => (b",\n", b""),
+            TraceFormat::Chrome => (b"", b""),
+            TraceFormat::Text => (b"", b"\n"),
+        };
+        // Tracing must never take the application down, so write errors are ignored
+        let _ = state.writer.write_all(separator);
+        let _ = state.writer.write_all(line.as_bytes());
+        let _ = state.writer.write_all(terminator);
+        state.event_count += 1;
+    }
+}
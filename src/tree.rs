@@ -0,0 +1,229 @@
+//! Tree view
+//!
+//! An alternative to the Miller columns for a tab: the directory of the first
+//! column shown as a tree whose directories expand and collapse in place. The tree
+//! only decides what is listed and which row is selected. The browser keeps its
+//! columns open down to the selected row, so the preview, the status bar and the
+//! commands work on the selection just as they do with columns.
+//!
+//! Symlinks to directories are listed but not expanded, so a link loop can't make
+//! the tree endless.
+
+use crate::config::Settings;
+use crate::file_operations::{entry_icon, read_directory_with_error_log};
+use crate::utils::scroll_offset;
+use std::cell::Cell;
+use std::collections::BTreeSet;
+use std::fs::DirEntry;
+use std::path::{Path, PathBuf};
+
+use ratatui::widgets::ListState;
+
+/// One listed entry of the tree
+#[derive(Debug)]
+pub struct TreeRow {
+    pub entry: DirEntry,
+    /// How many directories down from the root the entry is; the root's entries are at 0
+    pub depth: usize,
+    pub is_dir: bool,
+    /// Worked out as the row is listed, since a file's icon may take a read
+    pub icon: String,
+}
+
+/// Which directories of a tree are expanded, and the rows that makes
+#[derive(Debug)]
+pub struct TreeView {
+    pub root: PathBuf,
+    expanded: BTreeSet<PathBuf>,
+    rows: Vec<TreeRow>,
+    pub selected: ListState,
+    /// The rows no longer match the expanded directories or the disk, so they need listing again
+    stale: bool,
+    /// Rows the tree showed when last drawn, which selection changes scroll by
+    view_height: Cell<usize>,
+}
+
+impl TreeView {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            expanded: BTreeSet::new(),
+            rows: Vec::new(),
+            selected: ListState::default(),
+            stale: true,
+            view_height: Cell::new(0),
+        }
+    }
+
+    pub fn rows(&self) -> &[TreeRow] {
+        &self.rows
+    }
+
+    pub fn selected_row(&self) -> Option<&TreeRow> {
+        self.selected.selected().and_then(|index| self.rows.get(index))
+    }
+
+    pub fn selected_path(&self) -> Option<PathBuf> {
+        self.selected_row().map(|row| row.entry.path())
+    }
+
+    pub fn is_expanded(&self, path: &Path) -> bool {
+        self.expanded.contains(path)
+    }
+
+    /// Show the tree from another directory, keeping what is expanded under it
+    pub fn set_root(&mut self, root: PathBuf) {
+        if self.root != root {
+            self.root = root;
+            self.stale = true;
+        }
+    }
+
+    pub fn expand(&mut self, dir: PathBuf) {
+        if self.expanded.insert(dir) {
+            self.stale = true;
+        }
+    }
+
+    pub fn collapse(&mut self, dir: &Path) {
+        if self.expanded.remove(dir) {
+            self.stale = true;
+        }
+    }
+
+    /// Note that listed directories changed on disk
+    pub fn invalidate(&mut self) {
+        self.stale = true;
+    }
+
+    /// List the rows again if needed, keeping the same path selected
+    pub fn refresh(&mut self, config: &Settings) {
+        if !self.stale {
+            return;
+        }
+        let selected = self.selected_path();
+        self.rows.clear();
+        list_rows(&self.root, 0, config, &self.expanded, &mut self.rows);
+        self.stale = false;
+
+        let index = selected.and_then(|path| self.position(&path)).or(self.selected.selected());
+        self.select(index.unwrap_or(0));
+    }
+
+    /// Select a row, kept within the rows, scrolling as a column does to keep it in view
+    pub fn select(&mut self, index: usize) {
+        let index = (!self.rows.is_empty()).then(|| index.min(self.rows.len() - 1));
+        let view_height = self.view_height.get();
+        let offset = self.visible_offset(view_height);
+        self.selected.select(index);
+        *self.selected.offset_mut() = scroll_offset(offset, index, self.rows.len(), view_height);
+    }
+
+    /// Select the row of a path, returning false if it isn't listed
+    pub fn select_path(&mut self, path: &Path) -> bool {
+        match self.position(path) {
+            Some(index) => {
+                self.select(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Select the row above, wrapping around to the last like a column does
+    pub fn select_previous(&mut self) {
+        match self.selected.selected() {
+            Some(index) if index > 0 => self.select(index - 1),
+            _ => self.select(self.rows.len().saturating_sub(1)),
+        }
+    }
+
+    /// Select the row below, wrapping around to the first like a column does
+    pub fn select_next(&mut self) {
+        match self.selected.selected() {
+            Some(index) if index + 1 < self.rows.len() => self.select(index + 1),
+            _ => self.select(0),
+        }
+    }
+
+    /// Row of the directory holding the selected entry, unless that is the root
+    pub fn parent_row(&self) -> Option<usize> {
+        let index = self.selected.selected()?;
+        let depth = self.rows.get(index)?.depth;
+        self.rows[..index].iter().rposition(|row| row.depth < depth)
+    }
+
+    /// First visible row for a view of the given height, as a column works it out
+    pub fn visible_offset(&self, view_height: usize) -> usize {
+        scroll_offset(self.selected.offset(), self.selected.selected(), self.rows.len(), view_height)
+    }
+
+    /// Remember the rows the tree showed as it is drawn
+    pub fn set_view_height(&self, view_height: usize) {
+        self.view_height.set(view_height);
+    }
+
+    fn position(&self, path: &Path) -> Option<usize> {
+        self.rows.iter().position(|row| row.entry.path() == path)
+    }
+}
+
+/// List the entries of `dir`, each followed by the rows of its own entries when it is expanded
+fn list_rows(dir: &Path, depth: usize, config: &Settings, expanded: &BTreeSet<PathBuf>, rows: &mut Vec<TreeRow>) {
+    let Ok((entries, _)) = read_directory_with_error_log(dir, config, None) else {
+        return;
+    };
+    for entry in entries {
+        let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+        let path = entry.path();
+        let icon = entry_icon(&entry, config, None);
+        rows.push(TreeRow { entry, depth, is_dir, icon });
+        if is_dir && expanded.contains(&path) {
+            list_rows(&path, depth + 1, config, expanded, rows);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_expand_collapse_and_parents() {
+        let dir = std::env::temp_dir().join(format!("browse-tree-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("a/b")).unwrap();
+        fs::write(dir.join("a/b/deep.txt"), "").unwrap();
+        fs::write(dir.join("a/one.txt"), "").unwrap();
+        fs::write(dir.join("top.txt"), "").unwrap();
+        let config = Settings::default();
+        let names = |tree: &TreeView| -> Vec<(String, usize)> {
+            tree.rows().iter().map(|row| (row.entry.file_name().to_string_lossy().to_string(), row.depth)).collect()
+        };
+
+        let mut tree = TreeView::new(dir.clone());
+        tree.refresh(&config);
+        assert_eq!(names(&tree), [("a".to_string(), 0), ("top.txt".to_string(), 0)]);
+
+        tree.expand(dir.join("a"));
+        tree.expand(dir.join("a/b"));
+        tree.refresh(&config);
+        assert_eq!(names(&tree).len(), 5);
+        assert!(tree.select_path(&dir.join("a/b/deep.txt")));
+        assert_eq!(tree.rows()[tree.parent_row().unwrap()].entry.path(), dir.join("a/b"));
+
+        // Collapsing a directory hides everything under it, and the selection stays put if it can
+        tree.select_path(&dir.join("top.txt"));
+        tree.collapse(&dir.join("a"));
+        tree.refresh(&config);
+        assert_eq!(names(&tree), [("a".to_string(), 0), ("top.txt".to_string(), 0)]);
+        assert_eq!(tree.selected_path(), Some(dir.join("top.txt")));
+        assert_eq!(tree.parent_row(), None);
+
+        tree.select_next();
+        assert_eq!(tree.selected_path(), Some(dir.join("a")));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,13 +1,28 @@
+use crate::actions_menu::render_actions_menu;
+use crate::bookmarks::render_bookmark_manager;
+use crate::dialog::render_dialog;
+use crate::disk_usage::render_disk_usage_panel;
+use crate::file_preview::render_full_preview;
+use crate::finder::render_results_panel;
+use crate::fuzzy::render_fuzzy_finder;
+use crate::grep::render_grep_panel;
 use crate::app::{App, LayoutInfo};
-use crate::browser::{render_browser};
+use crate::browser::{browser_layout, render_browser, Browser};
 use crate::error::render_error_log;
-use crate::utils::{truncate_text};
+use crate::jobs::JobManager;
+use crate::utils::{format_file_size, text_width, truncate_text, truncate_text_with};
+use color_eyre::Result;
+use std::time::Duration;
 
 use ratatui::{
+    backend::TestBackend,
     prelude::*,
     widgets::*,
 };
 
+/// Longest job label shown in the status bar activity segment
+const ACTIVITY_LABEL_WIDTH: usize = 24;
+
 /// Main UI rendering function
 pub fn render_ui(frame: &mut Frame, app: &mut App) -> LayoutInfo {
     let layout_info = calculate_layout_info(frame.area(), app);
@@ -15,6 +30,48 @@ pub fn render_ui(frame: &mut Frame, app: &mut App) -> LayoutInfo {
     layout_info
 }
 
+/// Render the app off-screen at the given terminal size and return the screen text
+///
+/// Each terminal row becomes one line with trailing whitespace trimmed. Styling is
+/// dropped, so the result is what a user would read on screen. The layout info from
+/// this render is stored on the app, so mouse events can be fed in afterwards.
+pub fn render_to_string(app: &mut App, width: u16, height: u16) -> Result<String> {
+    let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+    let mut layout_info = None;
+    terminal.draw(|f| {
+        layout_info = Some(app.render(f));
+    })?;
+    if let Some(info) = layout_info {
+        app.set_layout_info(info);
+    }
+
+    Ok(buffer_to_string(terminal.backend().buffer()))
+}
+
+/// Convert a rendered buffer to plain text, one line per row
+fn buffer_to_string(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut lines = Vec::with_capacity(area.height as usize);
+
+    for y in area.top()..area.bottom() {
+        let mut line = String::new();
+        let mut skip = 0;
+        for x in area.left()..area.right() {
+            // Wide characters occupy several cells; the trailing cells are placeholders
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            let symbol = buffer[(x, y)].symbol();
+            skip = Span::raw(symbol).width().saturating_sub(1);
+            line.push_str(symbol);
+        }
+        lines.push(line.trim_end().to_string());
+    }
+
+    lines.join("\n")
+}
+
 /// Calculate layout information for mouse interactions
 fn calculate_layout_info(area: Rect, app: &App) -> LayoutInfo {
     let mut layout_info = LayoutInfo::default();
@@ -34,22 +91,27 @@ fn calculate_layout_info(area: Rect, app: &App) -> LayoutInfo {
         ]).split(area)
     };
 
+    layout_info.screen_area = area;
     layout_info.tab_area = main_layout[0];
     layout_info.browser_area = main_layout[1];
 
     // Calculate browser column areas
     let browser = app.browser();
-    let num_cols = browser.columns().len() + if browser.preview().is_some() { 1 } else { 0 };
-    let constraints = (0..num_cols)
-        .map(|_| Constraint::Ratio(1, num_cols as u32))
-        .collect::<Vec<_>>();
-    layout_info.column_areas = Layout::horizontal(constraints).split(main_layout[1]).to_vec();
+    let (areas, preview_area) = browser_layout(main_layout[1], browser, app.config());
+    if browser.tree().is_some() {
+        layout_info.tree_area = Some(areas[0]);
+    } else {
+        layout_info.column_areas = areas;
+        layout_info.column_areas.extend(preview_area);
+    }
+    layout_info.preview_area = preview_area;
 
     if app.error_log().is_visible() {
         layout_info.status_area = main_layout[3];
     } else {
         layout_info.status_area = main_layout[2];
     }
+    layout_info.status_path_area = status_path_area(app, layout_info.status_area);
 
     layout_info
 }
@@ -87,6 +149,37 @@ fn render_ui_with_layout(frame: &mut Frame, app: &mut App, _layout_info: &Layout
         // Render status bar in the bottom area
         render_status_bar(frame, app, main_layout[2]);
     }
+
+    // Popups go on top of everything else
+    if let Some(menu) = app.actions_menu() {
+        render_actions_menu(frame, menu);
+    }
+    if let Some(manager) = app.bookmark_manager() {
+        render_bookmark_manager(frame, manager);
+    }
+    if let Some(panel) = app.results_panel() {
+        render_results_panel(frame, panel);
+    }
+    if let Some(panel) = app.grep_panel() {
+        render_grep_panel(frame, panel);
+    }
+    if let Some(panel) = app.disk_usage_panel() {
+        render_disk_usage_panel(frame, panel, app.config().allocated_sizes);
+    }
+    if let Some(preview) = app.full_preview() {
+        render_full_preview(frame, preview);
+    }
+    if let Some(finder) = app.fuzzy_finder() {
+        render_fuzzy_finder(frame, finder);
+    }
+    if let Some(dialog) = app.dialog() {
+        render_dialog(frame, dialog);
+    }
+}
+
+/// Marker shown before the name of a tab that is locked to its directory
+fn lock_marker(browser: &Browser) -> &'static str {
+    if browser.root_lock().is_some() { "🔒 " } else { "" }
 }
 
 /// Render tab bar showing all open tabs
@@ -97,9 +190,10 @@ fn render_tab_bar(frame: &mut Frame, app: &App, area: Rect) {
 
     if tabs.len() <= 1 {
         // If only one tab, show a simple title bar
-        let title = format!(" {} ", tabs[0].display_name());
+        let title = format!(" {}{} ", lock_marker(&tabs[0].browser), tabs[0].display_name());
+        let accent = tab_manager.accent(0, app.config()).unwrap_or(Color::Blue);
         let title_paragraph = Paragraph::new(title)
-            .style(Style::default().bg(Color::Blue).fg(Color::White))
+            .style(Style::default().bg(accent).fg(Color::White))
             .alignment(Alignment::Left);
         frame.render_widget(title_paragraph, area);
         return;
@@ -126,19 +220,19 @@ fn render_tab_bar(frame: &mut Frame, app: &App, area: Rect) {
         let mut title = tab.display_name().to_string();
 
         // Truncate title if too long
-        if title.len() > tab_width - 2 {
-            title = format!("{}…", &title[..tab_width - 3]);
-        }
+        title = truncate_text_with(&title, tab_width - 2, "…");
 
         // Add padding
-        title = format!(" {} ", title);
+        title = format!(" {}{} ", lock_marker(&tab.browser), title);
 
         tab_titles.push(title);
 
+        // The active tab is filled with its accent, the others only have their name in it
+        let accent = tab_manager.accent(i, app.config());
         if is_active {
-            tab_styles.push(Style::default().bg(Color::Blue).fg(Color::White));
+            tab_styles.push(Style::default().bg(accent.unwrap_or(Color::Blue)).fg(Color::White));
         } else {
-            tab_styles.push(Style::default().bg(Color::DarkGray).fg(Color::White));
+            tab_styles.push(Style::default().bg(Color::DarkGray).fg(accent.unwrap_or(Color::White)));
         }
     }
 
@@ -149,7 +243,8 @@ fn render_tab_bar(frame: &mut Frame, app: &App, area: Rect) {
             break;
         }
 
-        let tab_width = std::cmp::min(title.len(), (area.width - x) as usize);
+        let tab_title = truncate_text_with(title, (area.width - x) as usize, "");
+        let tab_width = text_width(&tab_title);
         let tab_area = Rect {
             x: area.x + x,
             y: area.y,
@@ -157,12 +252,6 @@ fn render_tab_bar(frame: &mut Frame, app: &App, area: Rect) {
             height: 1,
         };
 
-        let tab_title = if title.len() > tab_width {
-            &title[..tab_width]
-        } else {
-            title
-        };
-
         let tab_paragraph = Paragraph::new(tab_title)
             .style(*style)
             .alignment(Alignment::Left);
@@ -185,12 +274,48 @@ fn render_tab_bar(frame: &mut Frame, app: &App, area: Rect) {
     }
 }
 
-/// Render status bar with helpful information
-fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
-    let current_path = app.browser().columns()
+/// Status bar text ahead of the current path: job activity, toast and search string
+fn status_prefix(app: &App) -> String {
+    let activity = activity_segment(app.job_manager());
+
+    let toast = app.toast()
+        .map(|toast| format!("{} | ", toast.message))
+        .unwrap_or_default();
+
+    let search = if !app.browser().search_string().is_empty() {
+        format!("Search: '{}' | ", app.browser().search_string())
+    } else {
+        String::new()
+    };
+
+    let column = app.browser().active_column();
+    let visual = column.visual_range()
+        .map(|range| format!("VISUAL: {} entries, Enter to mark | ", range.count()))
+        .unwrap_or_default();
+    let following = if app.browser().is_following() { "FOLLOWING | " } else { "" };
+
+    format!("{}{}{}{}{}", activity, toast, visual, following, search)
+}
+
+/// Current path as shown in the status bar
+fn status_path(app: &App) -> String {
+    app.browser().columns()
         .back()
         .map(|col| col.path.to_string_lossy().to_string())
-        .unwrap_or_else(|| "Unknown".to_string());
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// Area of the status bar covered by the current path
+fn status_path_area(app: &App, status_area: Rect) -> Rect {
+    let offset = Span::raw(status_prefix(app)).width() as u16;
+    let width = Span::raw(status_path(app)).width() as u16;
+    let x = status_area.x.saturating_add(offset).min(status_area.right());
+    Rect::new(x, status_area.y, width.min(status_area.right() - x), status_area.height)
+}
+
+/// Render status bar with helpful information
+fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
+    let current_path = status_path(app);
 
     let file_count = app.browser().columns()
         .back()
@@ -204,9 +329,9 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
         .unwrap_or_default();
 
     // Create error count display
-    let error_count = app.error_log().unread_count();
+    let error_count = app.error_log().unacknowledged_count();
     let error_indicator = if error_count > 0 {
-        if app.error_log().has_errors() {
+        if app.error_log().has_unacknowledged_errors() {
             format!(" | ❌ {} errors", error_count)
         } else {
             format!(" | ⚠️ {} warnings", error_count)
@@ -216,7 +341,7 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
     };
 
     let error_help = if app.error_log().is_visible() {
-        " | Enter to expand, Esc to hide"
+        " | Enter to expand, a to acknowledge, A for all, Esc to hide"
     } else {
         " | Ctrl+E for errors"
     };
@@ -227,16 +352,98 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
         String::new()
     };
 
-    let status_text = if !app.browser().search_string().is_empty() {
-        format!("Search: '{}' | {} | {} items{}{} | Esc to clear | ? for settings{}{}",
-                app.browser().search_string(), current_path, file_count, selected_info, tab_info, error_help, error_indicator)
+    let search_help = if !app.browser().search_string().is_empty() {
+        " | Esc to clear"
     } else {
-        format!("{} | {} items{}{} | ? for settings{}{}",
-                current_path, file_count, selected_info, tab_info, error_help, error_indicator)
+        ""
+    };
+
+    let disk_space = app.disk_space()
+        .map(|space| format!(" | {} free of {}", format_file_size(space.free), format_file_size(space.total)))
+        .unwrap_or_default();
+
+    let project = app.project()
+        .filter(|_| app.config().project_in_status)
+        .map(|project| format!(" | ◆ {}", project.name))
+        .unwrap_or_default();
+
+    let repo_info = app.repo_info()
+        .map(|info| format!(" | ⎇ {}", info.summary()))
+        .unwrap_or_default();
+
+    let read_only = if app.config().read_only { " | read-only" } else { "" };
+    let recording = if app.is_recording_macro() { " | recording macro" } else { "" };
+    let pick_help = match app.config().pick {
+        Some(mode) => format!(" | Enter to choose a {}", mode.noun()),
+        None => String::new(),
     };
 
+    let status_text = format!("{}{} | {} items{}{}{}{}{}{}{}{}{} | ? for settings{}{}",
+            status_prefix(app), current_path, file_count, selected_info, disk_space, project, repo_info, tab_info, read_only, recording, pick_help, search_help, error_help, error_indicator);
+
     let status_paragraph = Paragraph::new(truncate_text(&status_text, area.width as usize))
         .style(Style::default().bg(Color::DarkGray).fg(Color::White));
 
     frame.render_widget(status_paragraph, area);
 }
+
+/// Status bar segment for the busiest background job, with a count of any others
+fn activity_segment(jobs: &JobManager) -> String {
+    let Some(job) = jobs.busiest() else {
+        return String::new();
+    };
+
+    let percent = job.fraction()
+        .map(|fraction| format!(" {:.0}%", fraction * 100.0))
+        .unwrap_or_default();
+    let speed = job.throughput()
+        .map(|rate| format!(" {}/s", format_file_size(rate as u64)))
+        .unwrap_or_default();
+    let left = job.eta()
+        .map(|eta| format!(" {} left", format_time_left(eta)))
+        .unwrap_or_default();
+    let others = match jobs.jobs().len() - 1 {
+        0 => String::new(),
+        n => format!(" (+{})", n),
+    };
+
+    format!("{} {}{}{}{}{} | ", job.spinner(), truncate_text(&job.label, ACTIVITY_LABEL_WIDTH), percent, speed, left, others)
+}
+
+/// Time left as `m:ss`, or `h:mm:ss` from an hour up
+fn format_time_left(eta: Duration) -> String {
+    let seconds = eta.as_secs();
+    match seconds / 3600 {
+        0 => format!("{}:{:02}", seconds / 60, seconds % 60),
+        hours => format!("{}:{:02}:{:02}", hours, seconds / 60 % 60, seconds % 60),
+    }
+}
+
+/// Create a centered rectangle
+pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::vertical([
+        Constraint::Percentage((100 - percent_y) / 2),
+        Constraint::Percentage(percent_y),
+        Constraint::Percentage((100 - percent_y) / 2),
+    ])
+    .split(r);
+
+    Layout::horizontal([
+        Constraint::Percentage((100 - percent_x) / 2),
+        Constraint::Percentage(percent_x),
+        Constraint::Percentage((100 - percent_x) / 2),
+    ])
+    .split(popup_layout[1])[1]
+}
+
+/// Create a rectangle of a fixed size centered in `r`, shrunk to fit if needed
+pub fn centered_fixed_rect(width: u16, height: u16, r: Rect) -> Rect {
+    let width = width.min(r.width);
+    let height = height.min(r.height);
+    Rect {
+        x: r.x + (r.width - width) / 2,
+        y: r.y + (r.height - height) / 2,
+        width,
+        height,
+    }
+}
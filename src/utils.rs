@@ -1,9 +1,16 @@
 /// Utility functions for the file browser
 
-use chrono::{DateTime, Local};
+use crate::config::Settings;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+use std::ffi::OsStr;
 use std::fs;
+use std::os::unix::ffi::OsStrExt;
 use std::os::unix::fs::PermissionsExt;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 /// Format file size in human-readable format
 pub fn format_file_size(size: u64) -> String {
@@ -23,21 +30,221 @@ pub fn format_file_size(size: u64) -> String {
     }
 }
 
-/// Truncate text to fit within a given width
+/// Number of terminal cells text takes; CJK characters and most emoji take two
+pub fn text_width(text: &str) -> usize {
+    text.width()
+}
+
+/// Truncate text to fit within a given number of cells, ending it with `...`
 pub fn truncate_text(text: &str, max_width: usize) -> String {
-    if text.len() <= max_width {
+    truncate_text_with(text, max_width, "...")
+}
+
+/// Truncate text to fit within a given number of cells, ending it with `ellipsis`
+///
+/// The cut falls between grapheme clusters, so accented letters and emoji
+/// sequences are never split, and a wide character that would straddle the last
+/// cell is left out. When not even the ellipsis fits, as much of it as fits is used.
+pub fn truncate_text_with(text: &str, max_width: usize, ellipsis: &str) -> String {
+    if text_width(text) <= max_width {
+        return text.to_string();
+    }
+    let ellipsis_width = text_width(ellipsis);
+    if max_width <= ellipsis_width {
+        return fitting_prefix(ellipsis, max_width).to_string();
+    }
+    format!("{}{}", fitting_prefix(text, max_width - ellipsis_width), ellipsis)
+}
+
+/// The longest run of whole grapheme clusters from the start of text that fits in `width` cells
+fn fitting_prefix(text: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (at, grapheme) in text.grapheme_indices(true) {
+        used += grapheme.width();
+        if used > width {
+            return &text[..at];
+        }
+    }
+    text
+}
+
+/// Pad text with spaces on the right to fill `width` cells
+pub fn pad_text(text: &str, width: usize) -> String {
+    format!("{}{}", text, " ".repeat(width.saturating_sub(text_width(text))))
+}
+
+/// A file name with the bytes that aren't UTF-8 written as `\xNN` escapes, or None
+/// if it is all UTF-8
+///
+/// Names are otherwise shown with `�` in place of those bytes, which tells two such
+/// names apart no better than it tells what the bytes are.
+pub fn escaped_name(name: &OsStr) -> Option<String> {
+    if name.to_str().is_some() {
+        return None;
+    }
+    let mut escaped = String::new();
+    for chunk in name.as_bytes().utf8_chunks() {
+        escaped.push_str(chunk.valid());
+        for byte in chunk.invalid() {
+            escaped.push_str(&format!("\\x{:02X}", byte));
+        }
+    }
+    Some(escaped)
+}
+
+/// Match a name against a shell-style glob supporting `*` and `?`
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` in the pattern and the name index it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Resolve a path typed by the user
+///
+/// A leading `~` expands to the home directory and relative paths are taken
+/// relative to `base`.
+pub fn resolve_user_path(input: &str, base: &Path) -> PathBuf {
+    let input = input.trim();
+    let expanded = match input.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => match dirs::home_dir() {
+            Some(home) => home.join(rest.trim_start_matches('/')),
+            None => PathBuf::from(input),
+        },
+        _ => PathBuf::from(input),
+    };
+    base.join(expanded)
+}
+
+/// Write a path with the home directory as `~`, the way `resolve_user_path` reads it back
+pub fn home_relative(path: &Path) -> String {
+    match dirs::home_dir().and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf)) {
+        Some(relative) if relative.as_os_str().is_empty() => "~".to_string(),
+        Some(relative) => format!("~/{}", relative.display()),
+        None => path.display().to_string(),
+    }
+}
+
+/// Format used when showing and prompting for timestamps
+pub const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Parse a local timestamp typed by the user
+///
+/// Accepts `YYYY-MM-DD HH:MM:SS`, `YYYY-MM-DD HH:MM` or `YYYY-MM-DD` (midnight).
+/// An empty string or `now` means the current time.
+pub fn parse_timestamp(input: &str) -> Option<DateTime<Local>> {
+    let input = input.trim();
+    if input.is_empty() || input.eq_ignore_ascii_case("now") {
+        return Some(Local::now());
+    }
+
+    let naive = NaiveDateTime::parse_from_str(input, TIMESTAMP_FORMAT)
+        .or_else(|_| NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M"))
+        .or_else(|_| NaiveDate::parse_from_str(input, "%Y-%m-%d").map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default()))
+        .ok()?;
+    Local.from_local_datetime(&naive).earliest()
+}
+
+/// Normalize a name for matching according to the search settings
+///
+/// With diacritics ignored, accented letters are decomposed and their combining
+/// marks dropped, so "Café" and "Cafe" compare equal.
+pub fn search_key(text: &str, config: &Settings) -> String {
+    let text = if config.search_ignore_diacritics {
+        text.nfd().filter(|c| !is_combining_mark(*c)).collect()
+    } else {
         text.to_string()
-    } else if max_width <= 3 {
-        "...".to_string()
+    };
+
+    if config.search_ignore_case {
+        text.to_lowercase()
     } else {
-        format!("{}...", &text[..max_width - 3])
+        text
+    }
+}
+
+/// Rows kept between the selection and the top or bottom of a list, where there are more
+pub const SCROLL_MARGIN: usize = 2;
+
+/// First row to show of a list of `len` rows, starting from `offset`
+///
+/// The offset moves only as far as needed to show `selected` with `SCROLL_MARGIN`
+/// rows around it, a margin that shrinks in views too short for it. The list
+/// isn't scrolled past its last row. A height of 0, for a list not drawn yet,
+/// only keeps the selection from being above the offset.
+pub fn scroll_offset(offset: usize, selected: Option<usize>, len: usize, view_height: usize) -> usize {
+    if view_height == 0 {
+        return selected.map_or(offset, |selected| offset.min(selected)).min(len.saturating_sub(1));
     }
+    let margin = SCROLL_MARGIN.min(view_height.saturating_sub(1) / 2);
+    let offset = match selected {
+        Some(selected) => offset.min(selected.saturating_sub(margin)).max((selected + margin + 1).saturating_sub(view_height)),
+        None => offset,
+    };
+    offset.min(len.saturating_sub(view_height))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_scroll_offset_keeps_a_margin() {
+        // Moving down the list scrolls once the selection is within the margin of the bottom
+        assert_eq!(scroll_offset(0, Some(7), 100, 10), 0);
+        assert_eq!(scroll_offset(0, Some(8), 100, 10), 1);
+        // Jumps land with the margin around the selection, or at either end
+        assert_eq!(scroll_offset(0, Some(50), 100, 10), 43);
+        assert_eq!(scroll_offset(43, Some(20), 100, 10), 18);
+        assert_eq!(scroll_offset(0, Some(99), 100, 10), 90);
+        assert_eq!(scroll_offset(90, Some(0), 100, 10), 0);
+        // Nothing moves while the selection is away from the edges, and the end isn't passed
+        assert_eq!(scroll_offset(40, Some(45), 100, 10), 40);
+        assert_eq!(scroll_offset(95, Some(96), 100, 10), 90);
+        // Short views shrink the margin, and lists not drawn yet only follow the selection up
+        assert_eq!(scroll_offset(0, Some(3), 100, 3), 2);
+        assert_eq!(scroll_offset(0, Some(1), 100, 1), 1);
+        assert_eq!(scroll_offset(30, Some(12), 100, 0), 12);
+    }
+
+    #[test]
+    fn test_search_key() {
+        let mut config = Settings::default();
+        assert_eq!(search_key("Café", &config), "café");
+
+        config.search_ignore_diacritics = true;
+        assert_eq!(search_key("Café Tacvba", &config), "cafe tacvba");
+        assert_eq!(search_key("Ærøskøbing Ñu", &config), "ærøskøbing nu");
+
+        config.search_ignore_case = false;
+        assert_eq!(search_key("Émile", &config), "Emile");
+    }
+
     #[test]
     fn test_format_file_size() {
         assert_eq!(format_file_size(0), "0 B");
@@ -55,6 +262,85 @@ mod tests {
         assert_eq!(truncate_text("hello world", 8), "hello...");
         assert_eq!(truncate_text("hi", 2), "hi");
         assert_eq!(truncate_text("hello", 3), "...");
+        assert_eq!(truncate_text("hello", 2), "..");
+    }
+
+    #[test]
+    fn test_truncate_text_by_cells() {
+        // Two-byte letters are one cell each, and decomposed accents stay on their letter
+        assert_eq!(truncate_text("résumé final.pdf", 9), "résumé...");
+        assert_eq!(truncate_text("re\u{301}sume\u{301} final.pdf", 9), "re\u{301}sume\u{301}...");
+        // Wide characters take two cells and aren't cut in half
+        assert_eq!(text_width("日本語.txt"), 10);
+        assert_eq!(truncate_text("日本語.txt", 8), "日本...");
+        assert_eq!(truncate_text("日本語.txt", 7), "日本...");
+        // Emoji sequences stay whole
+        assert_eq!(truncate_text("👩‍👩‍👧 family photos", 8), "👩‍👩‍👧 fa...");
+        assert_eq!(truncate_text_with("notes.md", 6, "…"), "notes…");
+        assert_eq!(pad_text("日本", 6), "日本  ");
+    }
+
+    #[test]
+    fn test_escaped_name() {
+        use std::os::unix::ffi::OsStrExt;
+
+        assert_eq!(escaped_name(OsStr::new("café.txt")), None);
+        assert_eq!(escaped_name(OsStr::from_bytes(b"caf\xe9 \xff\xfe.txt")).as_deref(), Some("caf\\xE9 \\xFF\\xFE.txt"));
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.txt", "notes.txt"));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("file?.rs", "file1.rs"));
+        assert!(glob_match("a*b*c", "aXXbYYc"));
+        assert!(glob_match("*.tar.gz", "backup.tar.gz"));
+        assert!(!glob_match("*.txt", "notes.md"));
+        assert!(!glob_match("file?.rs", "file10.rs"));
+        assert!(!glob_match("abc", "abcd"));
+        assert!(glob_match("", ""));
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        let parsed = parse_timestamp("2024-02-29 13:45:10").unwrap();
+        assert_eq!(parsed.format(TIMESTAMP_FORMAT).to_string(), "2024-02-29 13:45:10");
+        assert_eq!(parse_timestamp("2024-02-29 13:45").unwrap().format(TIMESTAMP_FORMAT).to_string(), "2024-02-29 13:45:00");
+        assert_eq!(parse_timestamp("2024-02-29").unwrap().format(TIMESTAMP_FORMAT).to_string(), "2024-02-29 00:00:00");
+        assert!(parse_timestamp("now").is_some());
+        assert!(parse_timestamp("").is_some());
+        assert!(parse_timestamp("2024-02-30").is_none());
+        assert!(parse_timestamp("yesterday").is_none());
+    }
+
+    #[test]
+    fn test_user_name() {
+        assert_eq!(user_name(0), "root");
+        // Asked twice, the second answer comes from memory
+        assert_eq!(user_name(0), "root");
+        assert_eq!(user_name(3_999_999_999), "3999999999");
+    }
+
+    #[test]
+    fn test_resolve_user_path() {
+        let base = Path::new("/work/project");
+        assert_eq!(resolve_user_path("out", base), PathBuf::from("/work/project/out"));
+        assert_eq!(resolve_user_path(" /tmp ", base), PathBuf::from("/tmp"));
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(resolve_user_path("~", base), home);
+            assert_eq!(resolve_user_path("~/docs", base), home.join("docs"));
+        }
+        assert_eq!(resolve_user_path("~user", base), PathBuf::from("/work/project/~user"));
+    }
+
+    #[test]
+    fn test_home_relative() {
+        assert_eq!(home_relative(Path::new("/work/project")), "/work/project");
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(home_relative(&home), "~");
+            assert_eq!(home_relative(&home.join("docs/notes")), "~/docs/notes");
+            assert_eq!(resolve_user_path(&home_relative(&home.join("docs")), Path::new("/")), home.join("docs"));
+        }
     }
 
     #[test]
@@ -152,3 +438,38 @@ pub fn get_path_info(path: &Path) -> Option<(String, String)> {
 
     Some((permissions, date))
 }
+
+/// Name of the user with this id, or the id itself when there is no such user
+///
+/// Names are looked up once and remembered, as a listing asks for the same few over and over.
+pub fn user_name(uid: u32) -> String {
+    use std::collections::HashMap;
+    use std::sync::{Mutex, OnceLock};
+    static NAMES: OnceLock<Mutex<HashMap<u32, String>>> = OnceLock::new();
+
+    let names = NAMES.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(name) = names.lock().ok().and_then(|names| names.get(&uid).cloned()) {
+        return name;
+    }
+    let name = look_up_user_name(uid).unwrap_or_else(|| uid.to_string());
+    if let Ok(mut names) = names.lock() {
+        names.insert(uid, name.clone());
+    }
+    name
+}
+
+fn look_up_user_name(uid: u32) -> Option<String> {
+    let mut buffer = vec![0 as libc::c_char; 1024];
+    let mut passwd = std::mem::MaybeUninit::<libc::passwd>::uninit();
+    let mut found = std::ptr::null_mut();
+    // SAFETY: the buffers outlive the call, and `passwd` is only read when getpwuid_r
+    // reports it filled it in; its name points into `buffer`, which is still alive
+    unsafe {
+        let status = libc::getpwuid_r(uid, passwd.as_mut_ptr(), buffer.as_mut_ptr(), buffer.len(), &mut found);
+        if status != 0 || found.is_null() {
+            return None;
+        }
+        let name = std::ffi::CStr::from_ptr(passwd.assume_init().pw_name);
+        Some(name.to_string_lossy().into_owned())
+    }
+}
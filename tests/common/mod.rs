@@ -0,0 +1,197 @@
+//! Helpers shared by the end-to-end tests
+//!
+//! `Fixture` builds a throwaway directory tree and removes it again when dropped.
+//! `Driver` wraps an `App` running on that tree: it feeds key and mouse events the
+//! way the main loop would, renders off-screen so mouse positions resolve, and
+//! offers shortcuts for inspecting the browser state.
+
+#![allow(dead_code)]
+
+use browse::browser::Browser;
+use browse::{render_to_string, App, Settings};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Size of the off-screen terminal the driver renders to
+pub const SCREEN_WIDTH: u16 = 120;
+pub const SCREEN_HEIGHT: u16 = 30;
+
+/// How long `Driver::wait_for` keeps ticking before giving up
+const WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A temporary directory tree, removed when dropped
+pub struct Fixture {
+    root: PathBuf,
+}
+
+impl Fixture {
+    /// Create an empty directory unique to this test process
+    pub fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("browse-e2e-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        // The browser shows canonical paths, so compare against those
+        Self { root: root.canonicalize().unwrap() }
+    }
+
+    /// Add a directory, along with any missing parents
+    pub fn dir(self, path: &str) -> Self {
+        fs::create_dir_all(self.root.join(path)).unwrap();
+        self
+    }
+
+    /// Add a file with the given contents, along with any missing parent directories
+    pub fn file(self, path: &str, contents: &str) -> Self {
+        let path = self.root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Absolute path of an entry in the tree
+    pub fn path(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Drives an App the way the main loop does, without a terminal
+pub struct Driver {
+    pub app: App,
+}
+
+impl Driver {
+    /// Start in the root of a fixture with default settings
+    pub fn new(fixture: &Fixture) -> Self {
+        Self::with_config(fixture, Settings::default())
+    }
+
+    /// Start in the root of a fixture
+    ///
+    /// Settings are saved next to the fixture rather than to the user's settings file.
+    pub fn with_config(fixture: &Fixture, config: Settings) -> Self {
+        let config = Settings {
+            source: Some(fixture.path(".settings.json")),
+            ..config
+        };
+        let mut driver = Self { app: App::with_config(fixture.root().to_path_buf(), config).unwrap() };
+        driver.screen();
+        driver
+    }
+
+    /// Start with a tab for each of `paths` in a fixture, as when several are given on the command line
+    pub fn with_paths(fixture: &Fixture, paths: &[&str], config: Settings) -> Self {
+        let config = Settings {
+            source: Some(fixture.path(".settings.json")),
+            ..config
+        };
+        let paths: Vec<PathBuf> = paths.iter().map(|path| fixture.path(path)).collect();
+        let mut driver = Self { app: App::with_paths(&paths, config).unwrap() };
+        driver.screen();
+        driver
+    }
+
+    /// Send a key press
+    pub fn key(&mut self, key: KeyEvent) -> &mut Self {
+        self.app.handle_key(key).unwrap();
+        self.app.tick();
+        self
+    }
+
+    /// Send a key press without modifiers
+    pub fn press(&mut self, code: KeyCode) -> &mut Self {
+        self.key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    /// Send a key press with Ctrl held
+    pub fn ctrl(&mut self, c: char) -> &mut Self {
+        self.key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL))
+    }
+
+    /// Type each character of `text` as a key press
+    pub fn type_text(&mut self, text: &str) -> &mut Self {
+        for c in text.chars() {
+            self.press(KeyCode::Char(c));
+        }
+        self
+    }
+
+    /// Send a mouse event at a screen position
+    ///
+    /// Positions are resolved against the last render, as they would be on screen.
+    pub fn mouse(&mut self, kind: MouseEventKind, column: u16, row: u16) -> &mut Self {
+        self.app.handle_mouse(MouseEvent { kind, column, row, modifiers: KeyModifiers::NONE }).unwrap();
+        self.app.tick();
+        self
+    }
+
+    /// Left click at a screen position
+    pub fn click(&mut self, column: u16, row: u16) -> &mut Self {
+        self.mouse(MouseEventKind::Down(MouseButton::Left), column, row)
+    }
+
+    /// Left click on the first row showing `text`
+    pub fn click_text(&mut self, text: &str) -> &mut Self {
+        let (column, row) = self.find(text).unwrap_or_else(|| panic!("'{}' isn't on screen:\n{}", text, self.screen()));
+        self.click(column, row)
+    }
+
+    /// Render the screen and return its text, one line per row
+    pub fn screen(&mut self) -> String {
+        render_to_string(&mut self.app, SCREEN_WIDTH, SCREEN_HEIGHT).unwrap()
+    }
+
+    /// The bottom row of the screen
+    pub fn status_bar(&mut self) -> String {
+        self.screen().lines().last().unwrap_or_default().to_string()
+    }
+
+    /// Screen position of the first occurrence of `text`, rendering first
+    pub fn find(&mut self, text: &str) -> Option<(u16, u16)> {
+        self.screen().lines().enumerate().find_map(|(row, line)| {
+            let index = line.find(text)?;
+            Some((line[..index].chars().count() as u16, row as u16))
+        })
+    }
+
+    /// Tick until `done` holds, panicking when it doesn't within a few seconds
+    pub fn wait_for(&mut self, what: &str, done: impl Fn(&App) -> bool) -> &mut Self {
+        let deadline = Instant::now() + WAIT_TIMEOUT;
+        while !done(&self.app) {
+            assert!(Instant::now() < deadline, "timed out waiting for {}", what);
+            self.app.tick();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        self
+    }
+
+    pub fn browser(&self) -> &Browser {
+        self.app.browser()
+    }
+
+    /// Directory of the active column
+    pub fn current_dir(&self) -> PathBuf {
+        self.browser().active_column().path.clone()
+    }
+
+    /// File name of the selected entry
+    pub fn selected_name(&self) -> Option<String> {
+        self.browser().active_column().selected_entry().map(|entry| entry.file_name().to_string_lossy().to_string())
+    }
+
+    /// File names listed in the active column, in order
+    pub fn entry_names(&self) -> Vec<String> {
+        self.browser().active_column().entries.iter().map(|entry| entry.file_name().to_string_lossy().to_string()).collect()
+    }
+}
@@ -0,0 +1,1306 @@
+mod common;
+
+use browse::app::Preview;
+use browse::commands::CommandAction;
+use browse::dir_sizes::DirSize;
+use browse::entry_counts::EntryCount;
+use browse::jobs::JobKind;
+use browse::journal::{interrupted_operations, journal_dir, Journal};
+use browse::config::{ConfirmLevel, EnterAction};
+use browse::Settings;
+use browse::session::{Session, SessionTab};
+use browse::config::PickMode;
+use browse::render_to_string;
+use common::{Driver, Fixture};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEventKind};
+use std::fs;
+use std::path::PathBuf;
+
+/// docs/ with nested notes, src/ with two sources, and two files at the top
+fn project(name: &str) -> Fixture {
+    Fixture::new(name)
+        .file("docs/notes/todo.md", "- write tests\n")
+        .file("docs/readme.md", "# Docs\n")
+        .file("src/main.rs", "fn main() {\n    println!(\"hello\");\n}\n")
+        .file("src/lib.rs", "pub fn answer() -> u32 {\n    42\n}\n")
+        .file("alpha.txt", "hello from alpha\n")
+        .file("beta.txt", "")
+        .file(".hidden", "")
+}
+
+#[test]
+fn test_navigate_into_and_out_of_directories() {
+    let fixture = project("navigate");
+    let mut driver = Driver::new(&fixture);
+    assert_eq!(driver.entry_names(), ["docs", "src", "alpha.txt", "beta.txt"]);
+    assert_eq!(driver.selected_name().as_deref(), Some("docs"));
+
+    driver.press(KeyCode::Right);
+    assert_eq!(driver.current_dir(), fixture.path("docs"));
+    assert_eq!(driver.entry_names(), ["notes", "readme.md"]);
+    // The status bar keeps track of the space left on the volume
+    let status_bar = driver.status_bar();
+    assert!(status_bar.contains(" free of "), "status bar was: {}", status_bar);
+
+    driver.press(KeyCode::Right);
+    assert_eq!(driver.current_dir(), fixture.path("docs/notes"));
+    assert_eq!(driver.selected_name().as_deref(), Some("todo.md"));
+
+    // Going back up keeps the directory we came from selected
+    driver.press(KeyCode::Left).press(KeyCode::Left);
+    assert_eq!(driver.current_dir(), fixture.root());
+    assert_eq!(driver.selected_name().as_deref(), Some("docs"));
+
+    driver.press(KeyCode::End);
+    assert_eq!(driver.selected_name().as_deref(), Some("beta.txt"));
+    driver.press(KeyCode::Up);
+    assert_eq!(driver.selected_name().as_deref(), Some("alpha.txt"));
+    driver.press(KeyCode::Home);
+    assert_eq!(driver.selected_name().as_deref(), Some("docs"));
+
+    // `-` goes back to the previous directory
+    driver.press(KeyCode::Down).press(KeyCode::Right);
+    assert_eq!(driver.current_dir(), fixture.path("src"));
+    driver.press(KeyCode::Left).type_text("-");
+    assert_eq!(driver.current_dir(), fixture.path("src"));
+}
+
+#[test]
+fn test_jumps_keep_the_selection_in_view() {
+    let mut fixture = Fixture::new("jump-scroll");
+    for i in 0..60 {
+        fixture = fixture.file(&format!("file{:02}.txt", i), "");
+    }
+    let mut driver = Driver::with_config(&fixture, Settings { show_icons: false, ..Settings::default() });
+
+    // The stored offset is the drawn one, and the selection sits inside the scroll margin
+    let check = |driver: &mut Driver, selected: usize| {
+        let screen = driver.screen();
+        let column = driver.browser().active_column();
+        let view_height = column.view_height();
+        let offset = column.selected.offset();
+        assert_eq!(column.selected.selected(), Some(selected));
+        assert_eq!(column.visible_offset(view_height), offset);
+        assert!(offset == 0 || selected >= offset + 2, "offset {} selected {}", offset, selected);
+        assert!(offset + view_height >= 60 || selected + 2 < offset + view_height, "offset {} selected {}", offset, selected);
+        assert!(screen.contains(&format!("file{:02}.txt", selected)), "screen was:\n{}", screen);
+        offset
+    };
+
+    driver.press(KeyCode::End);
+    let bottom = check(&mut driver, 59);
+    // Moving up from the end doesn't scroll until the margin is reached
+    driver.press(KeyCode::Up);
+    assert_eq!(check(&mut driver, 58), bottom);
+
+    driver.press(KeyCode::Home);
+    assert_eq!(check(&mut driver, 0), 0);
+    for step in 1..=4 {
+        driver.press(KeyCode::PageDown);
+        check(&mut driver, step * 10);
+    }
+    driver.press(KeyCode::PageUp);
+    check(&mut driver, 30);
+}
+
+#[test]
+fn test_mouse_selects_and_goes_to_paths() {
+    let fixture = project("mouse");
+    let mut driver = Driver::new(&fixture);
+
+    driver.click_text("beta.txt");
+    assert_eq!(driver.selected_name().as_deref(), Some("beta.txt"));
+    assert!(driver.status_bar().contains("(4/4)"), "status bar was: {}", driver.status_bar());
+
+    // A right click selects the row and offers actions for it
+    let (column, row) = driver.find("alpha.txt").unwrap();
+    driver.mouse(MouseEventKind::Down(MouseButton::Right), column, row);
+    assert_eq!(driver.selected_name().as_deref(), Some("alpha.txt"));
+    assert!(driver.app.actions_menu().is_some());
+    driver.press(KeyCode::Esc);
+
+    // Clicking the path in the status bar prompts for a path to go to
+    let status_row = common::SCREEN_HEIGHT - 1;
+    let root = fixture.root().to_string_lossy().to_string();
+    let column = driver.status_bar().find(&root).unwrap() as u16;
+    driver.click(column, status_row);
+    assert!(driver.app.dialog().is_some());
+    driver.type_text("/docs/notes/todo.md").press(KeyCode::Enter);
+    assert!(driver.app.dialog().is_none());
+    assert_eq!(driver.current_dir(), fixture.path("docs/notes"));
+    assert_eq!(driver.selected_name().as_deref(), Some("todo.md"));
+}
+
+#[test]
+fn test_quick_search_and_filter() {
+    let fixture = project("search");
+    let mut driver = Driver::new(&fixture);
+
+    driver.type_text("al");
+    assert_eq!(driver.selected_name().as_deref(), Some("alpha.txt"));
+    assert_eq!(driver.browser().search_string(), "al");
+    driver.press(KeyCode::Esc);
+    assert_eq!(driver.browser().search_string(), "");
+
+    // Filtering narrows the listing as you type, Enter keeps it and Esc drops it
+    driver.type_text("/");
+    driver.type_text(".txt");
+    assert_eq!(driver.entry_names(), ["alpha.txt", "beta.txt"]);
+    assert_eq!(driver.selected_name().as_deref(), Some("alpha.txt"));
+    assert!(driver.screen().contains("Filter: .txt"), "screen was:\n{}", driver.screen());
+    driver.press(KeyCode::Enter);
+    assert_eq!(driver.entry_names(), ["alpha.txt", "beta.txt"]);
+    let screen = driver.screen();
+    assert!(screen.contains("(2 of 4 items)"), "screen was:\n{}", screen);
+    driver.type_text("/").press(KeyCode::Esc);
+    assert_eq!(driver.entry_names(), ["docs", "src", "alpha.txt", "beta.txt"]);
+}
+
+#[test]
+fn test_quick_search_takes_letters_bound_to_commands() {
+    let fixture = Fixture::new("search-letters").file("aquamover.txt", "").file("aquarium.txt", "").file("zebra.txt", "");
+    let mut driver = Driver::new(&fixture);
+
+    // Once a search is under way, q, m, o, v, e and r carry it on instead of
+    // quitting, bookmarking, opening, marking a range, editing or renaming
+    driver.type_text("aquamover");
+    assert_eq!(driver.browser().search_string(), "aquamover");
+    assert_eq!(driver.selected_name().as_deref(), Some("aquamover.txt"));
+    driver.press(KeyCode::Esc).type_text("aquarium");
+    assert_eq!(driver.selected_name().as_deref(), Some("aquarium.txt"));
+    assert!(!driver.app.should_quit());
+    assert!(driver.browser().active_column().rename_input.is_none());
+    assert!(!driver.status_bar().contains("VISUAL"));
+
+    // Without a search, the letters run their commands again
+    driver.press(KeyCode::Esc).type_text("r");
+    assert!(driver.browser().active_column().rename_input.is_some());
+}
+
+#[test]
+fn test_fuzzy_find_and_content_search() {
+    let fixture = project("finders");
+    let mut driver = Driver::new(&fixture);
+
+    driver.ctrl('p').type_text("todo");
+    driver.wait_for("the fuzzy finder", |app| app.fuzzy_finder().unwrap().finished);
+    driver.press(KeyCode::Enter);
+    assert_eq!(driver.current_dir(), fixture.path("docs/notes"));
+    assert_eq!(driver.selected_name().as_deref(), Some("todo.md"));
+    // Opening builds a column for each directory on the way
+    let paths: Vec<PathBuf> = driver.browser().columns().iter().map(|column| column.path.clone()).collect();
+    assert_eq!(paths, [fixture.root().to_path_buf(), fixture.path("docs"), fixture.path("docs/notes")]);
+
+    driver.press(KeyCode::Left).press(KeyCode::Left);
+    driver.ctrl('g').type_text("answer").press(KeyCode::Enter);
+    driver.wait_for("the content search", |app| app.grep_panel().is_some_and(|panel| panel.finished));
+    assert!(driver.screen().contains("pub fn answer()"), "screen was:\n{}", driver.screen());
+    driver.press(KeyCode::Enter);
+    assert_eq!(driver.current_dir(), fixture.path("src"));
+    assert_eq!(driver.selected_name().as_deref(), Some("lib.rs"));
+}
+
+#[test]
+fn test_settings_toggle_hidden_files() {
+    let fixture = project("hidden");
+    let mut driver = Driver::with_config(&fixture, Settings { show_hidden_files: false, ..Settings::default() });
+    assert!(!driver.entry_names().contains(&".hidden".to_string()));
+
+    driver.type_text("?");
+    assert!(driver.app.settings().is_some());
+    assert!(driver.screen().contains("Show hidden files"), "screen was:\n{}", driver.screen());
+
+    // The first Display setting shows hidden files, and the listing follows straight away
+    driver.press(KeyCode::Right).type_text(" ");
+    assert!(driver.app.config().show_hidden_files);
+    driver.press(KeyCode::Esc);
+    assert!(driver.app.settings().is_none());
+    assert!(driver.entry_names().contains(&".hidden".to_string()));
+}
+
+#[test]
+fn test_settings_rebind_key() {
+    let fixture = project("rebind");
+    let mut driver = Driver::new(&fixture);
+    let index = driver.app.command_registry().remappable_commands().iter()
+        .position(|(action, _, _)| *action == CommandAction::NavigateDown)
+        .unwrap();
+
+    // Key Bindings is the third settings tab
+    driver.type_text("?").press(KeyCode::Down).press(KeyCode::Down).press(KeyCode::Right);
+    for _ in 0..index {
+        driver.press(KeyCode::Down);
+    }
+    driver.press(KeyCode::Enter).key(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::CONTROL));
+    driver.press(KeyCode::Esc);
+
+    driver.ctrl('n');
+    assert_eq!(driver.selected_name().as_deref(), Some("src"));
+    // The change is saved to the settings file the app was started with
+    let saved = std::fs::read_to_string(fixture.path(".settings.json")).unwrap();
+    assert!(saved.contains("ctrl+n"), "settings were: {}", saved);
+}
+
+#[test]
+fn test_interrupted_move_is_reported_and_rolled_back() {
+    let fixture = project("journal");
+    let mut driver = Driver::new(&fixture);
+    let journals = journal_dir(driver.app.config());
+
+    // A finished move leaves no journal behind
+    driver.press(KeyCode::End).press(KeyCode::F(6)).type_text("/docs").press(KeyCode::Enter);
+    driver.wait_for("the move", |app| app.job_manager().jobs().is_empty());
+    assert!(fixture.path("docs/beta.txt").exists());
+    assert!(interrupted_operations(&journals).is_empty());
+
+    // A run killed after moving the first of two entries
+    let paths = [fixture.path("alpha.txt"), fixture.path("docs/readme.md")];
+    let mut journal = Journal::create(&journals, JobKind::Move, &paths, Some(&fixture.path("src"))).unwrap();
+    journal.started(0).unwrap();
+    std::fs::rename(&paths[0], fixture.path("src/alpha.txt")).unwrap();
+    journal.finished(0, Some(fixture.path("src/alpha.txt"))).unwrap();
+    journal.started(1).unwrap();
+    drop(journal);
+
+    driver.app.check_interrupted_operations();
+    assert!(driver.screen().contains("Move 1 finished entry back?"), "screen was:\n{}", driver.screen());
+    let log: Vec<&str> = driver.app.error_log().entries().iter().map(|entry| entry.message.as_str()).collect();
+    assert!(log.iter().any(|message| message.starts_with("Interrupted: Move of 2 items")), "log was: {:?}", log);
+    assert!(log.iter().any(|message| message.starts_with("Move was in progress")), "log was: {:?}", log);
+
+    driver.type_text("y");
+    assert!(fixture.path("alpha.txt").exists());
+    assert!(!fixture.path("src/alpha.txt").exists());
+    assert!(interrupted_operations(&journals).is_empty());
+}
+
+#[test]
+fn test_load_all_entries_after_declining() {
+    let fixture = Fixture::new("large-declined").dir("docs").file("alpha.txt", "").file("beta.txt", "");
+    let config = Settings { show_icons: false, large_directory_entries: 2, ..Settings::default() };
+    let mut driver = Driver::with_config(&fixture, config);
+    driver.app.tick();
+    assert!(driver.screen().contains("has 3 entries. Load them all?"));
+    driver.type_text("n");
+    assert_eq!(driver.entry_names(), ["docs", "alpha.txt"]);
+
+    // Shift+A lists the rest after all
+    driver.type_text("A");
+    assert_eq!(driver.entry_names(), ["docs", "alpha.txt", "beta.txt"]);
+    let screen = driver.screen();
+    assert!(screen.contains("(3 items)"), "screen was:\n{}", screen);
+}
+
+#[test]
+fn test_directory_sizes_fill_in() {
+    let fixture = project("sizes");
+    let mut driver = Driver::with_config(&fixture, Settings { dir_sizes: true, ..Settings::default() });
+
+    driver.wait_for("directory sizes", |app| {
+        let sizes = app.dir_sizes().unwrap();
+        ["docs", "src"].iter().all(|name| matches!(sizes.get(&fixture.path(name)), Some(DirSize::Known { .. })))
+    });
+    // docs holds todo.md (14 bytes) and readme.md (7 bytes) a level apart
+    let screen = driver.screen();
+    let docs_row = screen.lines().find(|line| line.contains("📁 docs")).unwrap();
+    let first_column = docs_row.split("││").next().unwrap();
+    assert!(first_column.trim_end_matches([' ', '│']).ends_with(" 21 B"), "screen was:\n{}", screen);
+
+    // Browsing into docs sizes its own subdirectory
+    driver.press(KeyCode::Right);
+    driver.wait_for("the size of notes", |app| {
+        matches!(app.dir_sizes().unwrap().get(&fixture.path("docs/notes")), Some(DirSize::Known { bytes: 14, complete: true }))
+    });
+}
+
+#[test]
+fn test_entry_counts_fill_in() {
+    let fixture = project("counts").dir("empty");
+    let mut driver = Driver::with_config(&fixture, Settings { entry_counts: true, ..Settings::default() });
+
+    driver.wait_for("entry counts", |app| {
+        let counts = app.entry_counts().unwrap();
+        ["docs", "empty", "src"].iter().all(|name| matches!(counts.get(&fixture.path(name)), Some(EntryCount::Known(_))))
+    });
+    // docs holds notes/ and readme.md; empty holds nothing
+    let screen = driver.screen();
+    let count_of = |name: &str| {
+        let row = screen.lines().find(|line| line.contains(&format!(" {}", name))).unwrap();
+        row.split("││").next().unwrap().trim_end_matches([' ', '│']).rsplit(' ').next().unwrap().to_string()
+    };
+    assert_eq!(count_of("docs"), "2", "screen was:\n{}", screen);
+    assert_eq!(count_of("empty"), "0", "screen was:\n{}", screen);
+}
+
+#[test]
+fn test_disk_usage_view() {
+    let fixture = project("usage");
+    let mut driver = Driver::new(&fixture);
+
+    driver.type_text("D");
+    driver.wait_for("the disk usage", |app| app.disk_usage_panel().is_some_and(|panel| panel.finished));
+    let panel = driver.app.disk_usage_panel().unwrap();
+    // src (71 bytes) outweighs docs (21 bytes), and hidden entries count too
+    let names: Vec<String> = panel.entries.iter().map(|entry| entry.path.file_name().unwrap().to_string_lossy().to_string()).collect();
+    assert_eq!(names[..3], ["src", "docs", "alpha.txt"]);
+    assert!(names.contains(&".hidden".to_string()));
+    let screen = driver.screen();
+    let src_row = screen.lines().find(|line| line.contains("src/")).unwrap();
+    assert!(src_row.contains("71 B") && src_row.contains('%') && src_row.contains('█'), "screen was:\n{}", screen);
+
+    // Browsing works like a column, and going back up selects where we came from
+    driver.press(KeyCode::Down).press(KeyCode::Right);
+    driver.wait_for("the usage of docs", |app| app.disk_usage_panel().is_some_and(|panel| panel.finished));
+    assert_eq!(driver.app.disk_usage_panel().unwrap().root, fixture.path("docs"));
+    // docs holds 21 of the 109 bytes of the directory the view started in
+    let title = format!("19.3% of {}", fixture.root().display());
+    assert!(driver.screen().contains(&title), "screen was:\n{}", driver.screen());
+    // The root was worked out already, so it comes straight back
+    driver.press(KeyCode::Left);
+    assert!(driver.app.disk_usage_panel().unwrap().finished);
+    let selected = driver.app.disk_usage_panel().unwrap().selected().unwrap().path.clone();
+    assert_eq!(selected, fixture.path("docs"));
+
+    // g closes the view and reveals the entry in the browser
+    driver.press(KeyCode::Down).type_text("g");
+    assert!(driver.app.disk_usage_panel().is_none());
+    assert_eq!(driver.selected_name().as_deref(), Some("alpha.txt"));
+}
+
+#[test]
+fn test_detail_view() {
+    let fixture = project("details");
+    let mut driver = Driver::new(&fixture);
+    let alpha_row = |driver: &mut Driver| {
+        let screen = driver.screen();
+        let row = screen.lines().find(|line| line.contains("alpha.txt")).unwrap().to_string();
+        row.split("││").next().unwrap().to_string()
+    };
+    assert!(!alpha_row(&mut driver).contains("17 B"));
+
+    driver.type_text("I");
+    assert!(driver.app.config().detail_view);
+    let row = alpha_row(&mut driver);
+    let owner = browse::utils::user_name(std::os::unix::fs::MetadataExt::uid(&std::fs::metadata(fixture.path("alpha.txt")).unwrap()));
+    assert!(row.contains("17 B") && row.contains("rw-") && row.contains(&owner), "row was: {}", row);
+    // Directories have no size of their own
+    let screen = driver.screen();
+    let docs_row = screen.lines().find(|line| line.contains("📁 docs")).unwrap();
+    assert!(docs_row.split("││").next().unwrap().contains(" - "), "screen was:\n{}", screen);
+
+    driver.type_text("I");
+    assert!(!alpha_row(&mut driver).contains("17 B"));
+}
+
+#[test]
+fn test_enter_action_per_file_type() {
+    let fixture = project("enter");
+    let mut config = Settings::default();
+    config.mime_types.primary.get_mut("text").unwrap().on_enter = Some(EnterAction::Preview);
+    let mut driver = Driver::with_config(&fixture, config);
+
+    // Text files are set to be previewed over the whole screen
+    driver.type_text("al").press(KeyCode::Esc).press(KeyCode::Enter);
+    assert!(driver.app.full_preview().is_some());
+    assert!(driver.app.take_external_command().is_none());
+    let screen = driver.screen();
+    assert!(screen.lines().next().unwrap().contains("alpha.txt"), "screen was:\n{}", screen);
+    assert!(screen.contains("hello from alpha"), "screen was:\n{}", screen);
+    driver.press(KeyCode::Esc);
+    assert!(driver.app.full_preview().is_none());
+
+    // Directories are still gone into
+    driver.press(KeyCode::Home).press(KeyCode::Enter);
+    assert_eq!(driver.current_dir(), fixture.path("docs"));
+}
+
+#[test]
+fn test_tree_view() {
+    let fixture = project("tree");
+    let mut driver = Driver::new(&fixture);
+    driver.type_text("W");
+    assert!(driver.browser().tree().is_some());
+    assert!(driver.screen().contains("▸ 📁 docs"));
+
+    // Right expands a directory in place, then goes into it
+    driver.press(KeyCode::Right);
+    let screen = driver.screen();
+    assert!(screen.contains("▾ 📁 docs") && screen.contains("  ▸ 📁 notes"), "screen was:\n{}", screen);
+    assert_eq!(driver.current_dir(), fixture.root());
+    driver.press(KeyCode::Down).press(KeyCode::Right).press(KeyCode::Right);
+    assert_eq!(driver.current_dir(), fixture.path("docs/notes"));
+    assert_eq!(driver.selected_name().as_deref(), Some("todo.md"));
+    assert!(driver.screen().contains("- write tests"));
+
+    // Left goes back up to the parent, then collapses it
+    driver.press(KeyCode::Left).press(KeyCode::Left);
+    assert_eq!(driver.selected_name().as_deref(), Some("notes"));
+    assert!(driver.screen().contains("  ▸ 📁 notes"));
+
+    driver.click_text("beta.txt");
+    assert_eq!(driver.current_dir(), fixture.root());
+    assert_eq!(driver.selected_name().as_deref(), Some("beta.txt"));
+
+    // The columns come back on the tree's selection
+    driver.type_text("W");
+    assert!(driver.browser().tree().is_none());
+    assert_eq!(driver.selected_name().as_deref(), Some("beta.txt"));
+}
+
+#[test]
+fn test_jump_to_project_root() {
+    let fixture = project("project").file("Cargo.toml", "[package]\nname = \"demo\"\n");
+    let mut driver = Driver::new(&fixture);
+    driver.type_text("s").press(KeyCode::Esc).press(KeyCode::Right).press(KeyCode::Right);
+    assert_eq!(driver.current_dir(), fixture.path("src"));
+    let status = driver.status_bar();
+    assert!(status.contains("◆ demo"), "status bar was: {}", status);
+
+    driver.type_text("P");
+    assert_eq!(driver.current_dir(), fixture.root());
+}
+
+#[test]
+fn test_resize_and_hide_preview() {
+    let fixture = project("preview-width");
+    let mut driver = Driver::new(&fixture);
+    driver.press(KeyCode::Down).press(KeyCode::Down);
+    assert!(driver.screen().contains("hello from alpha"));
+
+    // The preview starts with a column's share, half the screen here; drag its border to a quarter
+    driver.mouse(MouseEventKind::Down(MouseButton::Left), common::SCREEN_WIDTH / 2, 5);
+    driver.mouse(MouseEventKind::Drag(MouseButton::Left), common::SCREEN_WIDTH * 3 / 4, 5);
+    driver.mouse(MouseEventKind::Up(MouseButton::Left), common::SCREEN_WIDTH * 3 / 4, 5);
+    assert_eq!(driver.app.config().preview_percent, Some(25));
+    assert_eq!(driver.selected_name().as_deref(), Some("alpha.txt"));
+
+    driver.type_text("[[");
+    assert_eq!(driver.app.config().preview_percent, Some(15));
+    driver.type_text("]");
+    assert_eq!(driver.app.config().preview_percent, Some(20));
+
+    driver.type_text("|");
+    assert!(!driver.screen().contains("hello from alpha"));
+    driver.type_text("|");
+    assert!(driver.screen().contains("hello from alpha"));
+}
+
+#[test]
+fn test_mouse_drag_marks_a_range() {
+    let fixture = project("drag");
+    let mut driver = Driver::new(&fixture);
+    let marked = |driver: &Driver| driver.browser().active_column().marked.iter().map(|name| name.to_string_lossy().to_string()).collect::<Vec<_>>();
+
+    // Dragging from src down to alpha.txt marks both and everything between
+    let (column, src_row) = driver.find("src").unwrap();
+    let (_, alpha_row) = driver.find("alpha.txt").unwrap();
+    driver.mouse(MouseEventKind::Down(MouseButton::Left), column, src_row);
+    driver.mouse(MouseEventKind::Drag(MouseButton::Left), column, alpha_row);
+    assert!(marked(&driver).is_empty());
+    driver.mouse(MouseEventKind::Up(MouseButton::Left), column, alpha_row);
+    assert_eq!(marked(&driver), ["alpha.txt", "src"]);
+    assert_eq!(driver.selected_name().as_deref(), Some("alpha.txt"));
+
+    // Shift+click extends from the selection
+    driver.press(KeyCode::Char('U'));
+    let (_, docs_row) = driver.find("docs").unwrap();
+    driver.click(column, docs_row);
+    let (_, beta_row) = driver.find("beta.txt").unwrap();
+    driver.app.handle_mouse(crossterm::event::MouseEvent {
+        kind: MouseEventKind::Down(MouseButton::Left),
+        column,
+        row: beta_row,
+        modifiers: KeyModifiers::SHIFT,
+    }).unwrap();
+    assert_eq!(marked(&driver), ["alpha.txt", "beta.txt", "docs", "src"]);
+    assert_eq!(driver.selected_name().as_deref(), Some("beta.txt"));
+}
+
+#[test]
+fn test_scroll_file_preview() {
+    let text: String = (1..=100).map(|n| format!("line {}\n", n)).collect();
+    let fixture = project("scroll-preview").file("long.txt", &text);
+    let mut driver = Driver::new(&fixture);
+    driver.press(KeyCode::End);
+    assert_eq!(driver.selected_name().as_deref(), Some("long.txt"));
+    let screen = driver.screen();
+    assert!(screen.contains("Preview 1-18/100") && screen.contains("line 1 "), "screen was:\n{}", screen);
+
+    driver.key(KeyEvent::new(KeyCode::PageDown, KeyModifiers::SHIFT));
+    let screen = driver.screen();
+    assert!(screen.contains("Preview 19-36/100") && !screen.contains("line 18 "), "screen was:\n{}", screen);
+
+    // The wheel over the preview scrolls it too, and the selection stays put
+    let (column, row) = driver.find("line 20").unwrap();
+    driver.mouse(MouseEventKind::ScrollDown, column, row);
+    assert!(driver.screen().contains("Preview 22-39/100"));
+    driver.key(KeyEvent::new(KeyCode::PageUp, KeyModifiers::SHIFT)).key(KeyEvent::new(KeyCode::PageUp, KeyModifiers::SHIFT));
+    assert!(driver.screen().contains("Preview 1-18/100"));
+    assert_eq!(driver.selected_name().as_deref(), Some("long.txt"));
+}
+
+#[test]
+fn test_visual_mode() {
+    let fixture = project("visual");
+    let mut driver = Driver::new(&fixture);
+    let marked = |driver: &Driver| driver.browser().active_column().marked.iter().map(|name| name.to_string_lossy().to_string()).collect::<Vec<_>>();
+
+    // Moving the cursor extends the range, and Enter marks it instead of opening docs
+    driver.type_text("v").press(KeyCode::Down).press(KeyCode::Down);
+    assert!(driver.status_bar().contains("VISUAL: 3 entries"), "status bar was: {}", driver.status_bar());
+    assert!(marked(&driver).is_empty());
+    driver.press(KeyCode::Enter);
+    assert_eq!(driver.current_dir(), fixture.root());
+    assert_eq!(marked(&driver), ["alpha.txt", "docs", "src"]);
+    assert!(!driver.status_bar().contains("VISUAL"));
+
+    // Esc marks the range too
+    driver.type_text("v").press(KeyCode::Down).press(KeyCode::Esc);
+    assert_eq!(marked(&driver), ["alpha.txt", "beta.txt", "docs", "src"]);
+    assert_eq!(driver.browser().active_column().visual_anchor, None);
+}
+
+#[test]
+fn test_git_diff_of_modified_file() {
+    let fixture = project("git-diff");
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git").arg("-C").arg(fixture.root()).args(args).output().unwrap().status;
+        assert!(status.success(), "git {:?} failed", args);
+    };
+    git(&["init", "-q"]);
+    git(&["add", "."]);
+    git(&["-c", "user.name=test", "-c", "user.email=test@example.com", "commit", "-qm", "initial"]);
+    std::fs::write(fixture.path("alpha.txt"), "hello from alpha, changed\n").unwrap();
+
+    let mut driver = Driver::new(&fixture);
+    driver.press(KeyCode::Down).press(KeyCode::Down);
+    assert_eq!(driver.selected_name().as_deref(), Some("alpha.txt"));
+    driver.ctrl('d');
+    let screen = driver.screen();
+    assert!(screen.contains("-hello from alpha") && screen.contains("+hello from alpha, changed"), "screen was:\n{}", screen);
+
+    // A clean file has nothing to diff
+    driver.press(KeyCode::Esc).press(KeyCode::Down);
+    assert_eq!(driver.selected_name().as_deref(), Some("beta.txt"));
+    driver.ctrl('d');
+    assert!(!driver.screen().contains("+hello"));
+}
+
+#[test]
+fn test_follow_growing_file() {
+    let text: String = (1..=100).map(|n| format!("line {}\n", n)).collect();
+    let fixture = project("follow").file("log.txt", &text);
+    let mut driver = Driver::with_config(&fixture, Settings { preview_bytes: 256, ..Settings::default() });
+    driver.press(KeyCode::End);
+    assert_eq!(driver.selected_name().as_deref(), Some("log.txt"));
+    assert!(driver.screen().contains("line 1 "));
+
+    // Following shows the end of the file and keeps up as it grows
+    driver.type_text("F");
+    assert!(driver.status_bar().contains("FOLLOWING"), "status bar was: {}", driver.status_bar());
+    let screen = driver.screen();
+    assert!(screen.contains("line 100") && !screen.contains("line 1 "), "screen was:\n{}", screen);
+    let mut log = std::fs::OpenOptions::new().append(true).open(fixture.path("log.txt")).unwrap();
+    std::io::Write::write_all(&mut log, b"line 101\n").unwrap();
+    driver.wait_for("the new line", |app| {
+        matches!(app.browser().preview(), Some(Preview::File(details, _)) if details.content_preview.text().is_some_and(|text| text.ends_with("line 101\n")))
+    });
+    assert!(driver.screen().contains("line 101"));
+
+    driver.type_text("F");
+    assert!(!driver.status_bar().contains("FOLLOWING"));
+    assert!(driver.screen().contains("line 1 "));
+}
+
+#[test]
+fn test_fold_json_preview() {
+    let fixture = project("outline").file("config.json", r#"{"name": "browse", "server": {"port": 8080, "tls": {"cert": "a.pem"}}}"#);
+    let mut driver = Driver::new(&fixture);
+    driver.press(KeyCode::End);
+    assert_eq!(driver.selected_name().as_deref(), Some("config.json"));
+    let screen = driver.screen();
+    assert!(screen.contains("▸   \"server\": { … }") && !screen.contains("8080"), "screen was:\n{}", screen);
+
+    // Shift+arrows walk the outline without moving the selection
+    let shift = |code| KeyEvent::new(code, KeyModifiers::SHIFT);
+    driver.key(shift(KeyCode::Down)).key(shift(KeyCode::Down)).key(shift(KeyCode::Right));
+    let screen = driver.screen();
+    assert!(screen.contains("\"port\": 8080,") && screen.contains("▸     \"tls\": { … }"), "screen was:\n{}", screen);
+    driver.key(shift(KeyCode::Left));
+    assert!(!driver.screen().contains("8080"));
+    assert_eq!(driver.selected_name().as_deref(), Some("config.json"));
+}
+
+#[test]
+fn test_csv_table_preview() {
+    let header = (1..=12).map(|n| format!("column{}", n)).collect::<Vec<_>>().join(",");
+    let record = |name: &str, size: u32| format!("{},{}{}", name, size, ",filler".repeat(10));
+    let csv = format!("{}\n{}\n{}\n", header, record("\"a, b\"", 7), record("c", 1200));
+    let fixture = project("csv").file("data.csv", &csv);
+    let mut driver = Driver::new(&fixture);
+    driver.press(KeyCode::End);
+    assert_eq!(driver.selected_name().as_deref(), Some("data.csv"));
+    let screen = driver.screen();
+    assert!(screen.contains("Table 1-2/2 rows, columns 1-") && screen.contains("a, b"), "screen was:\n{}", screen);
+    // Numbers line up on the right
+    let (seven, _) = driver.find("      7").unwrap();
+    let (twelve_hundred, _) = driver.find("1200").unwrap();
+    assert_eq!(seven + 6, twelve_hundred + 3);
+
+    driver.key(KeyEvent::new(KeyCode::Right, KeyModifiers::SHIFT));
+    let screen = driver.screen();
+    assert!(screen.contains("columns 2-") && !screen.contains("a, b"), "screen was:\n{}", screen);
+}
+
+#[test]
+fn test_pdf_preview() {
+    let pdf = "%PDF-1.4\n\
+        1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n\
+        2 0 obj << /Type /Pages /Kids [3 0 R] /Count 1 >> endobj\n\
+        3 0 obj << /Type /Page /Parent 2 0 R /Contents 4 0 R >> endobj\n\
+        4 0 obj << /Length 44 >> stream\nBT 72 720 Td (Sales rose) Tj 0 -14 Td (again) Tj ET\nendstream endobj\n\
+        5 0 obj << /Title (Quarterly report) >> endobj\n\
+        trailer << /Root 1 0 R /Info 5 0 R >>\n%%EOF\n";
+    let fixture = project("pdf").file("report.pdf", pdf);
+    let mut driver = Driver::new(&fixture);
+    driver.press(KeyCode::End);
+    assert_eq!(driver.selected_name().as_deref(), Some("report.pdf"));
+    let screen = driver.screen();
+    for expected in ["Title:  Quarterly report", "Pages:  1", "Sales rose", "again"] {
+        assert!(screen.contains(expected), "no {:?} on screen:\n{}", expected, screen);
+    }
+}
+
+#[test]
+fn test_image_dimensions_in_preview() {
+    let fixture = project("media");
+    let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+    png.extend(1024u32.to_be_bytes());
+    png.extend(768u32.to_be_bytes());
+    std::fs::write(fixture.path("photo.png"), png).unwrap();
+    let mut driver = Driver::new(&fixture);
+    driver.press(KeyCode::End);
+    assert_eq!(driver.selected_name().as_deref(), Some("photo.png"));
+    let screen = driver.screen();
+    assert!(screen.contains("Dimensions: 1024 × 768") && screen.contains("Permissions:"), "screen was:\n{}", screen);
+}
+
+#[test]
+fn test_ansi_colors_in_preview() {
+    let fixture = project("ansi").file("output.txt", "\x1b[1;31merror:\x1b[0m build failed\x1b[K\n");
+    let mut driver = Driver::new(&fixture);
+    driver.press(KeyCode::End);
+    assert_eq!(driver.selected_name().as_deref(), Some("output.txt"));
+    let screen = driver.screen();
+    assert!(screen.contains("error: build failed") && !screen.contains("[1;31m"), "screen was:\n{}", screen);
+}
+
+#[test]
+fn test_scratch_directory() {
+    let fixture = project("scratch");
+    let scratch_dir = fixture.path("later/triage").display().to_string();
+    let mut driver = Driver::with_config(&fixture, Settings { scratch_dir, ..Settings::default() });
+
+    // Marked entries are moved there, creating it on the way
+    driver.type_text("al").press(KeyCode::Esc).type_text("  M");
+    driver.wait_for("the move", |app| app.job_manager().jobs().is_empty());
+    assert!(fixture.path("later/triage/alpha.txt").exists() && fixture.path("later/triage/beta.txt").exists());
+
+    driver.type_text("S");
+    assert_eq!(driver.current_dir(), fixture.path("later/triage"));
+    assert_eq!(driver.entry_names(), ["alpha.txt", "beta.txt"]);
+}
+
+#[test]
+fn test_export_and_import_bookmarks() {
+    let fixture = Fixture::new("bookmark-files").dir("docs");
+    let mut driver = Driver::new(&fixture);
+    let answer_prompt = |driver: &mut Driver, path: &std::path::Path| {
+        for _ in 0..browse::bookmarks::BOOKMARKS_FILE.len() {
+            driver.press(KeyCode::Backspace);
+        }
+        driver.type_text(&path.to_string_lossy()).press(KeyCode::Enter);
+    };
+
+    driver.type_text("ma").type_text("Be");
+    answer_prompt(&mut driver, &fixture.path("bookmarks.txt"));
+    let exported = std::fs::read_to_string(fixture.path("bookmarks.txt")).unwrap();
+    assert_eq!(exported, format!("a {}\n", fixture.root().display()));
+
+    // Merging keeps the bookmark already set and adds the new one
+    let more = format!(r#"{{"a": "/elsewhere", "d": "{}"}}"#, fixture.path("docs").display());
+    std::fs::write(fixture.path("more.json"), more).unwrap();
+    driver.type_text("Bi");
+    answer_prompt(&mut driver, &fixture.path("more.json"));
+    assert_eq!(driver.app.config().bookmarks.get(&'a').map(|path| path.as_path()), Some(fixture.root()));
+    assert_eq!(driver.app.config().bookmarks.get(&'d'), Some(&fixture.path("docs")));
+    let status_bar = driver.status_bar();
+    assert!(status_bar.contains("Imported 1 bookmark; kept the current ones for 'a'"), "status bar was: {}", status_bar);
+}
+
+#[test]
+fn test_confirmation_levels() {
+    let fixture = project("confirm");
+    let scratch_dir = fixture.path("later").display().to_string();
+    let config = Settings { scratch_dir, confirm: ConfirmLevel::Everything, ..Settings::default() };
+    let mut driver = Driver::with_config(&fixture, config);
+
+    // Moves to the scratch directory ask first when everything is confirmed
+    driver.type_text("al").press(KeyCode::Esc).type_text("M");
+    assert!(driver.app.dialog().is_some());
+    driver.type_text("n");
+    assert!(fixture.path("alpha.txt").exists());
+    driver.type_text("M").type_text("y");
+    driver.wait_for("the move", |app| app.job_manager().jobs().is_empty());
+    assert!(fixture.path("later/alpha.txt").exists());
+
+    // Nothing asks at all, not even a permanent delete
+    let config = Settings { delete_to_trash: false, confirm: ConfirmLevel::Never, ..Settings::default() };
+    let mut driver = Driver::with_config(&fixture, config);
+    driver.type_text("be").press(KeyCode::Esc).press(KeyCode::Delete);
+    assert!(driver.app.dialog().is_none());
+    driver.wait_for("the delete", |app| app.job_manager().jobs().is_empty());
+    assert!(!fixture.path("beta.txt").exists());
+}
+
+#[test]
+fn test_delete_counts_the_entries_removed() {
+    let fixture = project("delete-tree");
+    let config = Settings { delete_to_trash: false, confirm: ConfirmLevel::Never, ..Settings::default() };
+    let mut driver = Driver::with_config(&fixture, config);
+    assert_eq!(driver.selected_name().as_deref(), Some("docs"));
+    driver.press(KeyCode::Delete);
+    driver.wait_for("the delete", |app| app.job_manager().jobs().is_empty());
+    assert!(!fixture.path("docs").exists());
+    let toast = driver.app.toast().map(|toast| toast.message.clone());
+    assert_eq!(toast.as_deref(), Some("Delete 'docs' finished: removed 4 entries"));
+}
+
+#[test]
+fn test_read_only_keeps_results_from_being_deleted() {
+    let fixture = Fixture::new("read-only-results").file("empty.txt", "").dir("hollow").file("full.txt", "text");
+    let config = Settings { read_only: true, delete_to_trash: false, confirm: ConfirmLevel::Never, ..Settings::default() };
+    let mut driver = Driver::with_config(&fixture, config);
+
+    // Deleting from the results panel gets no further than a command would
+    driver.type_text("E");
+    driver.wait_for("the search", |app| app.results_panel().is_some_and(|panel| panel.finished));
+    assert_eq!(driver.app.results_panel().map(|panel| panel.results.len()), Some(2));
+    driver.press(KeyCode::Delete);
+    assert!(driver.app.dialog().is_none());
+    assert!(!driver.app.job_manager().is_busy());
+    assert!(driver.status_bar().contains("Read-only: files can't be changed"));
+    assert!(fixture.path("empty.txt").exists());
+    assert!(fixture.path("hollow").exists());
+}
+
+#[test]
+fn test_program_help() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let fixture = Fixture::new("program-help")
+        .file("bin/greet", "#!/bin/sh\necho \"usage: greet [name]\" >&2\nexit 1\n")
+        .file("bin/notes.txt", "")
+        .file("downloads/greet", "#!/bin/sh\necho \"usage: greet [name]\" >&2\nexit 1\n");
+    for program in ["bin/greet", "downloads/greet"] {
+        std::fs::set_permissions(fixture.path(program), std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    let mut driver = Driver::new(&fixture);
+    driver.press(KeyCode::Right);
+
+    // Without a man page, what the program prints for --help is shown over the whole screen
+    assert_eq!(driver.selected_name().as_deref(), Some("greet"));
+    driver.type_text("K");
+    driver.wait_for("program help", |app| app.full_preview().is_some());
+    assert!(driver.screen().contains("usage: greet [name]"));
+    driver.press(KeyCode::Esc);
+
+    driver.press(KeyCode::Down).type_text("K");
+    assert!(driver.app.full_preview().is_none());
+    assert!(driver.status_bar().contains("notes.txt isn't a program"));
+
+    // Nor is a program outside the directories programs are run from
+    driver.press(KeyCode::Left).press(KeyCode::Down).press(KeyCode::Right).type_text("K");
+    driver.wait_for("program help", |app| !app.job_manager().is_busy());
+    assert!(driver.app.full_preview().is_none());
+    assert!(driver.status_bar().contains("No man page for greet"));
+
+    // Programs under an untrusted root are never run
+    let config = Settings { untrusted_roots: vec![fixture.root().display().to_string()], ..Settings::default() };
+    let mut driver = Driver::with_config(&fixture, config);
+    driver.press(KeyCode::Right).type_text("K");
+    driver.wait_for("program help", |app| !app.job_manager().is_busy());
+    assert!(driver.app.full_preview().is_none());
+    assert!(driver.status_bar().contains("No man page for greet"));
+}
+
+#[test]
+fn test_names_that_are_not_utf8() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let fixture = project("not-utf8");
+    let latin1 = fixture.root().join(std::ffi::OsStr::from_bytes(b"caf\xe9.txt"));
+    std::fs::write(&latin1, "bonjour\n").unwrap();
+    let mut driver = Driver::new(&fixture);
+
+    // Quick search matches the name as shown, with `�` for the bad byte
+    driver.type_text("caf").press(KeyCode::Esc);
+    assert_eq!(driver.selected_name().as_deref(), Some("caf\u{FFFD}.txt"));
+    driver.press(KeyCode::Home).press(KeyCode::End);
+    let screen = driver.screen();
+    assert!(screen.contains("caf\u{FFFD}.txt"), "screen was:\n{}", screen);
+    // The preview shows the bytes themselves, and the file's content
+    assert!(screen.contains("caf\\xE9.txt") && screen.contains("bonjour"), "screen was:\n{}", screen);
+
+    // Leaving the rename field as it was keeps the name's bytes
+    driver.type_text("r").press(KeyCode::Enter);
+    assert!(latin1.exists());
+
+    // Typing a new name fixes it
+    driver.type_text("r").press(KeyCode::Backspace).type_text("\u{E9}").press(KeyCode::Enter);
+    assert!(!latin1.exists() && fixture.path("caf\u{E9}.txt").exists());
+    assert_eq!(driver.selected_name().as_deref(), Some("caf\u{E9}.txt"));
+}
+
+#[test]
+fn test_adjust_widths_by_a_cell() {
+    let fixture = project("cell-widths");
+    let mut driver = Driver::new(&fixture);
+    driver.press(KeyCode::Right);
+    let widths = |driver: &Driver| {
+        let area = ratatui::layout::Rect::new(0, 1, common::SCREEN_WIDTH, 28);
+        let (columns, preview) = browse::browser::browser_layout(area, driver.browser(), driver.app.config());
+        (columns.last().unwrap().width, preview.unwrap().width)
+    };
+    let (column, preview) = widths(&driver);
+
+    driver.type_text(">>>(");
+    assert_eq!(widths(&driver), (column + 3, preview - 1));
+    driver.type_text("<");
+    assert_eq!(widths(&driver), (column + 2, preview - 1));
+
+    // Narrowing stops at a minimum, and turning back starts right away
+    driver.type_text(&"(".repeat(200));
+    assert_eq!(widths(&driver).1, 8);
+    driver.type_text(")");
+    assert_eq!(widths(&driver).1, 9);
+
+    driver.type_text("=");
+    assert_eq!(widths(&driver), (column, preview));
+
+    // With a single column, the column and the preview share one border
+    driver.press(KeyCode::Left).type_text(">>");
+    let (column, preview) = widths(&driver);
+    driver.type_text("=");
+    assert_eq!(widths(&driver), (column - 2, preview + 2));
+}
+
+#[test]
+fn test_closing_a_running_search_is_not_an_error() {
+    // Enough directories that the walks are likely still going when they are stopped
+    let mut fixture = project("close-search");
+    for index in 0..300 {
+        fixture = fixture.file(&format!("many/dir{}/file.txt", index), "answer\n");
+    }
+    let mut driver = Driver::new(&fixture);
+
+    driver.ctrl('p').press(KeyCode::Esc);
+    driver.type_text("D").press(KeyCode::Esc);
+    // Searching again stops the search before it
+    driver.ctrl('g').type_text("answer").press(KeyCode::Enter);
+    driver.ctrl('g').type_text("answer").press(KeyCode::Enter).press(KeyCode::Esc);
+    driver.wait_for("the stopped jobs", |app| !app.job_manager().is_busy());
+    driver.app.tick();
+
+    let errors: Vec<&str> = driver.app.error_log().entries().iter().map(|entry| entry.message.as_str()).collect();
+    assert!(errors.is_empty(), "error log had {:?}", errors);
+    assert!(!driver.status_bar().contains("errors"), "status bar was: {}", driver.status_bar());
+}
+
+#[test]
+fn test_reload_keeps_selection_by_name() {
+    let fixture = project("reload");
+    let mut driver = Driver::new(&fixture);
+    driver.press(KeyCode::End);
+
+    // A new entry sorting before the selection shifts every index down by one
+    fs::write(fixture.path("aardvark.txt"), "").unwrap();
+    driver.ctrl('r');
+    assert_eq!(driver.selected_name().as_deref(), Some("beta.txt"));
+
+    // When the selected entry disappears, the cursor stays at the same position
+    fs::remove_file(fixture.path("beta.txt")).unwrap();
+    driver.ctrl('r');
+    assert_eq!(driver.selected_name().as_deref(), Some("alpha.txt"));
+}
+
+#[test]
+fn test_marks_are_kept_by_name_across_reloads() {
+    let fixture = project("marks");
+    let mut driver = Driver::new(&fixture);
+    let marked = |driver: &Driver| driver.browser().active_column().marked.iter().map(|name| name.to_string_lossy().to_string()).collect::<Vec<_>>();
+
+    driver.type_text("v").press(KeyCode::End).press(KeyCode::Esc);
+    assert_eq!(marked(&driver), ["alpha.txt", "beta.txt", "docs", "src"]);
+
+    // Marks on entries that vanished are dropped
+    fs::remove_file(fixture.path("beta.txt")).unwrap();
+    driver.ctrl('r');
+    assert_eq!(marked(&driver), ["alpha.txt", "docs", "src"]);
+}
+
+#[test]
+fn test_horizontal_column_scroll() {
+    let fixture = Fixture::new("column-scroll").dir("docs/level0/level1/level2/level3/level4/level5");
+    let mut driver = Driver::new(&fixture);
+    for _ in 0..7 {
+        driver.press(KeyCode::Right);
+    }
+    assert_eq!(driver.browser().columns().len(), 8);
+    assert_eq!(driver.browser().visible_columns(), 3..8);
+
+    // Scrolling stops once the root column is in view, and hides the preview
+    for _ in 0..5 {
+        driver.mouse(MouseEventKind::ScrollLeft, 10, 5);
+    }
+    assert_eq!(driver.browser().visible_columns(), 0..5);
+    assert!(driver.browser().visible_preview().is_none());
+
+    driver.mouse(MouseEventKind::ScrollRight, 10, 5);
+    assert_eq!(driver.browser().visible_columns(), 1..6);
+
+    // Navigating brings the active column back into view
+    driver.press(KeyCode::Left);
+    assert_eq!(driver.browser().visible_columns(), 2..7);
+}
+
+#[test]
+fn test_large_directory_prompt() {
+    let fixture = Fixture::new("large").dir("docs").file("alpha.txt", "").file("beta.txt", "");
+    let config = Settings { show_icons: false, large_directory_entries: 2, ..Settings::default() };
+    let mut driver = Driver::with_config(&fixture, config);
+    assert_eq!(driver.entry_names(), ["docs", "alpha.txt"]);
+
+    driver.app.tick();
+    let screen = driver.screen();
+    assert!(screen.contains("has 3 entries. Load them all?"), "screen was:\n{}", screen);
+    assert!(screen.contains("(2 of 3 items)"));
+    driver.type_text("y");
+    assert_eq!(driver.entry_names(), ["docs", "alpha.txt", "beta.txt"]);
+
+    // The answer is remembered, so the directory isn't asked about again
+    driver.app.tick();
+    assert!(!driver.screen().contains("Load them all?"));
+}
+
+#[test]
+fn test_one_tab_per_path() {
+    let fixture = project("tabs");
+    let driver = Driver::with_paths(&fixture, &["docs", "beta.txt"], Settings::default());
+    let tabs = driver.app.tab_manager();
+    assert_eq!(tabs.tab_count(), 2);
+    assert_eq!(tabs.active_index(), 0);
+    assert_eq!(driver.current_dir(), fixture.path("docs"));
+
+    // A file opens its directory with the file selected
+    let second = tabs.tabs()[1].browser.active_column();
+    assert_eq!(second.path, fixture.root());
+    assert_eq!(second.selected_entry().unwrap().file_name(), "beta.txt");
+}
+
+#[test]
+fn test_content_search_ignores_case_and_binary_files() {
+    let fixture = project("grep").file("docs/readme.md", "intro\nsay Hello there\n");
+    fs::write(fixture.path("binary.bin"), b"hello\0world").unwrap();
+    let mut driver = Driver::new(&fixture);
+
+    driver.ctrl('g').type_text("hello").press(KeyCode::Enter);
+    driver.wait_for("the content search", |app| app.grep_panel().is_some_and(|panel| panel.finished));
+    let mut found: Vec<(PathBuf, u64)> = driver.app.grep_panel().unwrap().matches.iter()
+        .map(|found| (found.path.clone(), found.line_number))
+        .collect();
+    found.sort();
+    assert_eq!(found, [(fixture.path("alpha.txt"), 1), (fixture.path("docs/readme.md"), 2), (fixture.path("src/main.rs"), 2)]);
+    let screen = driver.screen();
+    assert!(screen.contains("docs/readme.md:2: say Hello there"), "screen was:\n{}", screen);
+}
+
+#[test]
+fn test_root_lock_stops_left_navigation() {
+    let fixture = project("lock");
+    let mut driver = Driver::new(&fixture);
+
+    driver.press(KeyCode::Right).type_text("L");
+    assert_eq!(driver.browser().root_lock(), Some(fixture.path("docs").as_path()));
+    assert_eq!(driver.browser().columns().len(), 1);
+    driver.press(KeyCode::Left);
+    assert_eq!(driver.current_dir(), fixture.path("docs"));
+
+    // Unlocking lets Left climb again
+    driver.type_text("L").press(KeyCode::Left);
+    assert_eq!(driver.current_dir(), fixture.root());
+}
+
+#[test]
+fn test_bookmarks() {
+    let fixture = project("bookmarks");
+    let mut driver = Driver::new(&fixture);
+
+    // Bookmark docs as 'd', then jump back to it from the parent
+    driver.press(KeyCode::Right).type_text("md");
+    driver.press(KeyCode::Left).press(KeyCode::Left).type_text("'d");
+    assert_eq!(driver.current_dir(), fixture.path("docs"));
+
+    // Bookmarks are saved with the settings
+    let saved: Settings = serde_json::from_str(&serde_json::to_string(driver.app.config()).unwrap()).unwrap();
+    assert_eq!(saved.bookmarks.get(&'d'), Some(&fixture.path("docs")));
+
+    driver.type_text("B");
+    let screen = driver.screen();
+    assert!(screen.contains(&format!("d  {}", fixture.path("docs").display())), "screen was:\n{}", screen);
+    driver.type_text("d").press(KeyCode::Esc);
+    assert!(driver.app.config().bookmarks.is_empty());
+}
+
+#[test]
+fn test_alternate_directory_rebuilds_columns() {
+    let fixture = project("alternate");
+    let mut driver = Driver::new(&fixture);
+    assert_eq!(driver.browser().alternate_directory(), None);
+
+    driver.press(KeyCode::Right).press(KeyCode::Right).press(KeyCode::Left).press(KeyCode::Left);
+    assert_eq!(driver.browser().alternate_directory(), Some(fixture.path("docs").as_path()));
+
+    // Swapping back rebuilds the columns down to the directory, and swaps again
+    driver.type_text("-");
+    let paths: Vec<PathBuf> = driver.browser().columns().iter().map(|column| column.path.clone()).collect();
+    assert_eq!(paths, [fixture.root().to_path_buf(), fixture.path("docs")]);
+    assert_eq!(driver.browser().alternate_directory(), Some(fixture.root()));
+    driver.type_text("-");
+    assert_eq!(driver.current_dir(), fixture.root());
+    assert_eq!(driver.browser().history(), [fixture.path("docs/notes"), fixture.path("docs"), fixture.root().to_path_buf()]);
+}
+
+#[test]
+fn test_restore_session() {
+    let fixture = project("session");
+    let session = Session {
+        tabs: vec![
+            SessionTab { path: fixture.root().to_path_buf(), selected: Some("beta.txt".to_string()), accent: None },
+            SessionTab { path: fixture.path("missing"), selected: None, accent: None },
+            SessionTab { path: fixture.path("docs"), selected: Some("readme.md".to_string()), accent: Some("magenta".to_string()) },
+        ],
+        active: 2,
+    };
+    let mut driver = Driver::new(&fixture);
+    assert!(session.differs_from(fixture.root()));
+
+    driver.app.offer_session(session);
+    assert!(driver.screen().contains("Reopen the 3 tabs"));
+    driver.type_text("y");
+
+    // The missing directory is skipped and the saved selections come back
+    assert_eq!(driver.app.tab_manager().tab_count(), 2);
+    assert_eq!(driver.app.tab_manager().active_index(), 1);
+    let saved = driver.app.session();
+    assert_eq!(saved.tabs[0].selected.as_deref(), Some("beta.txt"));
+    assert_eq!(saved.tabs[1].path, fixture.path("docs"));
+    assert_eq!(saved.tabs[1].selected.as_deref(), Some("readme.md"));
+    assert_eq!(saved.tabs[1].accent.as_deref(), Some("Magenta"));
+}
+
+#[test]
+fn test_file_types_table_sorts_filters_and_scrolls() {
+    let fixture = project("file-types");
+    let mut driver = Driver::new(&fixture);
+    driver.type_text("?").press(KeyCode::Down).press(KeyCode::Right);
+
+    // The last rule scrolls into view in a short table
+    for _ in 0..20 {
+        driver.press(KeyCode::Down);
+    }
+    let screen = render_to_string(&mut driver.app, 100, 24).unwrap();
+    assert!(screen.contains("video"));
+    assert!(!screen.contains("application "));
+
+    // Sorting by preview lists the previewed rules first
+    driver.type_text("s");
+    let screen = render_to_string(&mut driver.app, 100, 40).unwrap();
+    let position = |name: &str| screen.find(name).unwrap();
+    assert!(screen.contains("Preview ▲"));
+    assert!(position("text/x-rust") < position("audio"));
+
+    driver.type_text("/dir").press(KeyCode::Enter);
+    let screen = render_to_string(&mut driver.app, 100, 40).unwrap();
+    assert!(screen.contains("empty-directory"));
+    assert!(!screen.contains("text/x-rust"));
+}
+
+#[test]
+fn test_choose_file_prints_marked_or_selected() {
+    let fixture = project("choose");
+    let mut driver = Driver::with_config(&fixture, Settings { pick: Some(PickMode::Files), ..Settings::default() });
+
+    // Enter on a directory opens it rather than choosing it
+    driver.press(KeyCode::Enter);
+    assert_eq!(driver.current_dir(), fixture.path("docs"));
+    assert!(!driver.app.should_quit());
+    driver.press(KeyCode::Down).press(KeyCode::Enter);
+    assert!(driver.app.should_quit());
+    assert_eq!(driver.app.chosen_paths(), [fixture.path("docs/readme.md")]);
+
+    // Marked entries are chosen together, leaving out directories
+    let mut driver = Driver::with_config(&fixture, Settings { pick: Some(PickMode::Files), ..Settings::default() });
+    driver.type_text("    ").press(KeyCode::Enter);
+    assert_eq!(driver.app.chosen_paths(), [fixture.path("alpha.txt"), fixture.path("beta.txt")]);
+}
+
+#[test]
+fn test_error_log_jumps_to_path() {
+    let fixture = project("error-jump");
+    let mut driver = Driver::new(&fixture);
+    driver.press(KeyCode::Down).press(KeyCode::Right);
+    assert_eq!(driver.current_dir(), fixture.path("src"));
+
+    // Reloading a directory that's gone logs an error about it
+    fs::rename(fixture.path("src"), fixture.path("src.moved")).unwrap();
+    driver.ctrl('r');
+    fs::rename(fixture.path("src.moved"), fixture.path("src")).unwrap();
+
+    driver.ctrl('e').press(KeyCode::End);
+    assert_eq!(driver.app.error_log().selected_path(), Some(fixture.path("src").as_path()));
+    driver.type_text("g");
+    assert!(!driver.app.error_log().is_visible());
+    assert_eq!(driver.current_dir(), fixture.root());
+    assert_eq!(driver.selected_name().as_deref(), Some("src"));
+}
+
+#[test]
+fn test_open_runs_configured_command() {
+    let fixture = project("open");
+    let mut config = Settings::default();
+    config.mime_types.primary.get_mut("text").unwrap().open_with = Some("false".to_string());
+    let mut driver = Driver::with_config(&fixture, config);
+
+    // Directories open in the browser
+    driver.press(KeyCode::Enter);
+    assert_eq!(driver.current_dir(), fixture.path("docs"));
+    assert!(driver.app.take_external_command().is_none());
+
+    // Files are handed to the main loop to run with the terminal
+    driver.press(KeyCode::Left).press(KeyCode::End).press(KeyCode::Up).type_text("o");
+    let command = driver.app.take_external_command().unwrap();
+    assert_eq!(command.path, fixture.path("alpha.txt"));
+
+    // e hands the same file to the editor
+    driver.type_text("e");
+    let edit = driver.app.take_external_command().unwrap();
+    assert_eq!((edit.operation, edit.path), ("edit", fixture.path("alpha.txt")));
+
+    // A command that fails is logged against the file
+    let result = command.run(None);
+    driver.app.finish_external_command(&command, result);
+    assert_eq!(driver.app.error_log().entries().len(), 1);
+    driver.ctrl('e');
+    assert_eq!(driver.app.error_log().selected_path(), Some(fixture.path("alpha.txt").as_path()));
+}
+
+#[test]
+fn test_shell_command_with_placeholders() {
+    let fixture = project("shell");
+    let mut driver = Driver::new(&fixture);
+    let run = |driver: &mut Driver, command: &str| {
+        driver.type_text("!").type_text(command).press(KeyCode::Enter);
+        driver.wait_for("the shell command", |app| app.job_manager().jobs().is_empty());
+    };
+
+    // The new file shows up once the command is done
+    driver.press(KeyCode::End).press(KeyCode::Up);
+    run(&mut driver, "cp %f %d/gamma.txt");
+    assert_eq!(fs::read_to_string(fixture.path("gamma.txt")).unwrap(), "hello from alpha\n");
+    assert!(driver.entry_names().contains(&"gamma.txt".to_string()));
+
+    // A failure is logged with its exit status and stderr
+    run(&mut driver, "echo no such thing >&2; exit 2");
+    let entry = driver.app.error_log().entries().back().unwrap();
+    assert!(entry.message.contains("exit status: 2: no such thing"), "{}", entry.message);
+}
+
+#[test]
+fn test_startup_commands() {
+    let fixture = project("startup");
+    let config = Settings {
+        startup_commands: ["tab docs", "prev_tab", "filter beta", "open_sesame", "cd missing"]
+            .map(str::to_string)
+            .to_vec(),
+        ..Settings::default()
+    };
+    let mut driver = Driver::with_config(&fixture, config);
+    driver.app.run_startup_commands();
+
+    assert_eq!(driver.app.tab_manager().tab_count(), 2);
+    assert_eq!(driver.app.tab_manager().tabs()[1].browser.active_column().path, fixture.path("docs"));
+    assert_eq!(driver.app.tab_manager().active_index(), 0);
+    assert_eq!(driver.entry_names(), ["beta.txt"]);
+
+    // Commands that fail are logged and the rest still run
+    let messages: Vec<_> = driver.app.error_log().entries().iter().map(|entry| entry.message.as_str()).collect();
+    assert_eq!(messages.len(), 2);
+    assert!(messages[0].starts_with("Startup command 'open_sesame' failed"));
+    assert!(messages[1].starts_with("Startup command 'cd missing' failed"));
+}
+
+#[test]
+fn test_record_and_replay_macro() {
+    let fixture = project("macro");
+    let mut driver = Driver::new(&fixture);
+
+    // Record marking docs, which moves down to src, and moving down again
+    driver.type_text("q");
+    assert!(driver.app.is_recording_macro());
+    let status_bar = driver.status_bar();
+    assert!(status_bar.contains("recording macr"), "status bar was: {}", status_bar);
+    driver.type_text(" ").press(KeyCode::Down).type_text("q");
+    assert!(!driver.app.is_recording_macro());
+    assert_eq!(driver.selected_name().as_deref(), Some("alpha.txt"));
+
+    // Replaying repeats the commands rather than the keys
+    driver.type_text("@");
+    let mut marked = driver.browser().active_column().marked_paths();
+    marked.sort();
+    assert_eq!(marked, [fixture.path("alpha.txt"), fixture.path("docs")]);
+}
+
+#[test]
+fn test_git_status_badges() {
+    let fixture = project("git");
+    let git = |args: &[&str]| {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(fixture.root())
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    };
+    git(&["init", "-q", "-b", "main"]);
+    fs::write(fixture.path(".gitignore"), "*.log\n").unwrap();
+    git(&["add", "alpha.txt", "beta.txt", ".gitignore"]);
+    git(&["commit", "-q", "-m", "initial"]);
+    fs::write(fixture.path("alpha.txt"), "changed\n").unwrap();
+    fs::write(fixture.path("debug.log"), "").unwrap();
+
+    let mut driver = Driver::new(&fixture);
+    let screen = driver.screen();
+    assert!(screen.contains("alpha.txt M"), "screen was:\n{}", screen);
+    assert!(screen.contains("docs ?"), "screen was:\n{}", screen);
+    assert!(screen.contains("debug.log !"), "screen was:\n{}", screen);
+    // Clean files have no badge
+    assert!(screen.contains("beta.txt  "), "screen was:\n{}", screen);
+
+    // The branch shows up in the status bar once git has been asked in the background
+    driver.wait_for("the repository info", |app| app.repo_info().is_some());
+    let status_bar = driver.status_bar();
+    assert!(status_bar.contains("⎇ main *"), "status bar was: {}", status_bar);
+}
@@ -0,0 +1,60 @@
+use browse::icons::IconTheme;
+use browse::{render_to_string, App, Settings};
+use std::fs;
+use std::path::PathBuf;
+
+fn fixture_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("browse-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("docs")).unwrap();
+    fs::write(dir.join("alpha.txt"), "hello from alpha\n").unwrap();
+    fs::write(dir.join("beta.txt"), "").unwrap();
+    dir
+}
+
+#[test]
+fn test_render_to_string_shows_directory_listing() {
+    let dir = fixture_dir("snapshot");
+    let config = Settings {
+        show_icons: false,
+        ..Settings::default()
+    };
+    let mut app = App::with_config(dir.clone(), config).unwrap();
+
+    let screen = render_to_string(&mut app, 100, 24).unwrap();
+    let lines: Vec<&str> = screen.lines().collect();
+
+    assert_eq!(lines.len(), 24);
+    assert!(screen.contains("docs"));
+    assert!(screen.contains("alpha.txt"));
+    assert!(screen.contains("beta.txt"));
+    // Directories sort first, so the status bar reports the first of three entries
+    assert!(lines[23].contains("3 items (1/3)"), "status bar was: {}", lines[23]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_icon_themes() {
+    let dir = fixture_dir("icon-themes");
+    fs::write(dir.join("main.rs"), "fn main() {}\n").unwrap();
+    let config = Settings {
+        icon_theme: IconTheme::Ascii,
+        ..Settings::default()
+    };
+    let mut app = App::with_config(dir.clone(), config).unwrap();
+    let screen = render_to_string(&mut app, 100, 24).unwrap();
+    assert!(screen.contains("/ docs") && screen.contains("$ main.rs") && screen.contains("- alpha.txt"), "screen was:\n{}", screen);
+
+    // A changed icon shows in every theme, the default ones follow the theme
+    let mut config = Settings {
+        icon_theme: IconTheme::NerdFont,
+        ..Settings::default()
+    };
+    config.mime_types.primary.get_mut("text").unwrap().icon = "T".to_string();
+    let mut app = App::with_config(dir.clone(), config).unwrap();
+    let screen = render_to_string(&mut app, 100, 24).unwrap();
+    assert!(screen.contains("T alpha.txt") && screen.contains("\u{e7a8} main.rs"), "screen was:\n{}", screen);
+
+    fs::remove_dir_all(&dir).unwrap();
+}